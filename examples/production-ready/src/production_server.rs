@@ -17,7 +17,10 @@ use tracing::{error, info, instrument};
 #[derive(Tool)]
 #[tool(
     name = "secure_calculator",
-    description = "Production calculator with enhanced security and validation"
+    description = "Production calculator with enhanced security and validation",
+    timeout_ms = 5000,
+    max_concurrency = 16,
+    max_queue = 64
 )]
 pub struct SecureCalculator;
 
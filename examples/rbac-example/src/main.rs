@@ -34,6 +34,8 @@ async fn main() -> mocopr_core::Result<()> {
     let _rbac = RbacMiddleware::builder()
         .with_default_roles() // Creates: guest, user, power_user, admin
         .with_audit_logging(true)
+        // Demo only: stdio clients name their own subject, see below
+        .with_message_subjects(true)
         // Add custom roles
         .with_role(
             "calculator_user",
@@ -230,6 +232,7 @@ impl ToolHandler for SimpleTool {
         Ok(ToolsCallResponse {
             content,
             is_error: Some(false),
+            structured_content: None,
             meta: ResponseMetadata::new(),
         })
     }
@@ -0,0 +1,73 @@
+//! Handler traits for requests the server sends to the client
+//!
+//! Servers with the matching client capability may ask the client to sample
+//! a message from its language model (`sampling/createMessage`) or to list
+//! the roots it exposes (`roots/list`). Register implementations with
+//! [`McpClientBuilder::with_sampling_handler`](crate::McpClientBuilder::with_sampling_handler)
+//! and [`McpClientBuilder::with_roots_provider`](crate::McpClientBuilder::with_roots_provider).
+//!
+//! Requests from the server are answered one at a time, in the order they
+//! arrive, so a handler should not wait on other requests to the same server.
+
+use async_trait::async_trait;
+use mocopr_core::prelude::*;
+use std::sync::Arc;
+
+/// Trait for answering `sampling/createMessage` requests from the server
+#[async_trait]
+pub trait SamplingHandler: Send + Sync {
+    /// Generate the next message for the conversation in `request`
+    async fn create_message(&self, request: CreateMessageRequest) -> Result<CreateMessageResponse>;
+}
+
+/// Trait for answering `roots/list` requests from the server
+#[async_trait]
+pub trait RootsProvider: Send + Sync {
+    /// Get the roots the server may operate on
+    async fn roots(&self) -> Result<Vec<Root>>;
+}
+
+/// A fixed list of roots
+#[async_trait]
+impl RootsProvider for Vec<Root> {
+    async fn roots(&self) -> Result<Vec<Root>> {
+        Ok(self.clone())
+    }
+}
+
+/// Message handler for the client side of a session
+///
+/// Requests without a registered handler are answered with a method not
+/// found error.
+#[derive(Default)]
+pub(crate) struct ClientMessageHandler {
+    pub(crate) sampling: Option<Arc<dyn SamplingHandler>>,
+    pub(crate) roots: Option<Arc<dyn RootsProvider>>,
+}
+
+#[async_trait]
+impl MessageHandler for ClientMessageHandler {
+    async fn handle_initialize(&self, _request: InitializeRequest) -> Result<InitializeResponse> {
+        Err(Error::MethodNotFound("initialize".to_string()))
+    }
+
+    async fn handle_sampling_create_message(
+        &self,
+        request: CreateMessageRequest,
+    ) -> Result<CreateMessageResponse> {
+        match &self.sampling {
+            Some(handler) => handler.create_message(request).await,
+            None => Err(Error::MethodNotFound("sampling/createMessage".to_string())),
+        }
+    }
+
+    async fn handle_roots_list(&self, _request: RootsListRequest) -> Result<RootsListResponse> {
+        match &self.roots {
+            Some(provider) => Ok(RootsListResponse {
+                roots: provider.roots().await?,
+                meta: ResponseMetadata { _meta: None },
+            }),
+            None => Err(Error::MethodNotFound("roots/list".to_string())),
+        }
+    }
+}
@@ -55,9 +55,21 @@
 //! - Comprehensive logging and debugging support
 
 use mocopr_core::prelude::*;
+use mocopr_core::transport::websocket::{WebSocketOptions, WebSocketTransport};
 use mocopr_core::transport::{TransportConfig, TransportFactory};
 use mocopr_core::utils::Utils;
+use serde::de::DeserializeOwned;
 use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+use tracing::debug;
+
+pub mod handlers;
+pub mod notifications;
+pub mod oauth;
+
+use handlers::{ClientMessageHandler, RootsProvider, SamplingHandler};
+use notifications::{NotificationKind, NotificationStream};
+use oauth::OAuthClient;
 
 /// High-level MCP client for connecting to and interacting with MCP servers.
 ///
@@ -118,6 +130,7 @@ pub struct McpClient {
     session: Arc<Session>,
     info: Implementation,
     capabilities: ClientCapabilities,
+    connected: watch::Receiver<bool>,
 }
 
 impl McpClient {
@@ -176,27 +189,13 @@ impl McpClient {
         client_capabilities: ClientCapabilities,
     ) -> Result<Self> {
         let transport = TransportFactory::create(transport_config).await?;
-        let handler = Arc::new(DefaultMessageHandler::new(
-            Implementation {
-                name: "MoCoPr Client".to_string(),
-                version: "1.0.0".to_string(),
-            },
-            ServerCapabilities::default(),
-        ));
-
-        let (session, _events) = Session::new(transport, handler);
-        let session = Arc::new(session);
-
-        // Initialize the session
-        session
-            .initialize(client_info.clone(), client_capabilities.clone())
-            .await?;
-
-        Ok(Self {
-            session,
-            info: client_info,
-            capabilities: client_capabilities,
-        })
+        Self::start(
+            transport,
+            client_info,
+            client_capabilities,
+            ClientMessageHandler::default(),
+        )
+        .await
     }
 
     /// Connect to an MCP server via stdio (process communication).
@@ -264,28 +263,13 @@ impl McpClient {
         capabilities: ClientCapabilities,
     ) -> Result<Self> {
         let transport = mocopr_core::transport::stdio::StdioTransport::spawn(command, args).await?;
-
-        let handler = Arc::new(DefaultMessageHandler::new(
-            Implementation {
-                name: "MoCoPr Client".to_string(),
-                version: "1.0.0".to_string(),
-            },
-            ServerCapabilities::default(),
-        ));
-
-        let (session, _events) = Session::new(Box::new(transport), handler);
-        let session = Arc::new(session);
-
-        // Initialize the session
-        session
-            .initialize(client_info.clone(), capabilities.clone())
-            .await?;
-
-        Ok(Self {
-            session,
-            info: client_info,
+        Self::start(
+            Box::new(transport),
+            client_info,
             capabilities,
-        })
+            ClientMessageHandler::default(),
+        )
+        .await
     }
 
     /// Connect to an MCP server via WebSocket
@@ -336,28 +320,51 @@ impl McpClient {
         client_info: Implementation,
         capabilities: ClientCapabilities,
     ) -> Result<Self> {
-        let transport = mocopr_core::transport::websocket::WebSocketTransport::new(url).await?;
-
-        let handler = Arc::new(DefaultMessageHandler::new(
-            Implementation {
-                name: "MoCoPr Client".to_string(),
-                version: "1.0.0".to_string(),
-            },
-            ServerCapabilities::default(),
-        ));
+        let transport = WebSocketTransport::new(url).await?;
+        Self::start(
+            Box::new(transport),
+            client_info,
+            capabilities,
+            ClientMessageHandler::default(),
+        )
+        .await
+    }
 
-        let (session, _events) = Session::new(Box::new(transport), handler);
+    /// Run the session message loop in the background and perform the handshake
+    async fn start(
+        transport: Box<dyn Transport>,
+        client_info: Implementation,
+        capabilities: ClientCapabilities,
+        handler: ClientMessageHandler,
+    ) -> Result<Self> {
+        let (session, _events) = Session::new(transport, Arc::new(handler));
         let session = Arc::new(session);
 
+        let (connected_sender, connected) = watch::channel(true);
+        tokio::spawn({
+            let session = session.clone();
+            async move {
+                if let Err(e) = session.run().await {
+                    debug!("Client session ended with error: {}", e);
+                }
+                let _ = connected_sender.send(false);
+            }
+        });
+
         // Initialize the session
-        session
+        if let Err(e) = session
             .initialize(client_info.clone(), capabilities.clone())
-            .await?;
+            .await
+        {
+            session.stop();
+            return Err(e);
+        }
 
         Ok(Self {
             session,
             info: client_info,
             capabilities,
+            connected,
         })
     }
 
@@ -397,23 +404,11 @@ impl McpClient {
     /// # }
     /// ```
     pub async fn list_resources(&self) -> Result<ResourcesListResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "resources/list".to_string(),
-            params: Some(Utils::to_json_value(&ResourcesListRequest::new())?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "resources/list",
+            Utils::to_json_value(&ResourcesListRequest::new())?,
+        )
+        .await
     }
 
     /// Read a resource
@@ -455,23 +450,11 @@ impl McpClient {
     /// # }
     /// ```
     pub async fn read_resource(&self, uri: url::Url) -> Result<ResourcesReadResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "resources/read".to_string(),
-            params: Some(Utils::to_json_value(&ResourcesReadRequest { uri })?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "resources/read",
+            Utils::to_json_value(&ResourcesReadRequest { uri })?,
+        )
+        .await
     }
 
     /// List available tools
@@ -510,23 +493,11 @@ impl McpClient {
     /// # }
     /// ```
     pub async fn list_tools(&self) -> Result<ToolsListResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "tools/list".to_string(),
-            params: Some(Utils::to_json_value(&ToolsListRequest::new())?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "tools/list",
+            Utils::to_json_value(&ToolsListRequest::new())?,
+        )
+        .await
     }
 
     /// Call a tool
@@ -578,23 +549,11 @@ impl McpClient {
         name: String,
         arguments: Option<serde_json::Value>,
     ) -> Result<ToolsCallResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "tools/call".to_string(),
-            params: Some(Utils::to_json_value(&ToolsCallRequest { name, arguments })?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "tools/call",
+            Utils::to_json_value(&ToolsCallRequest { name, arguments })?,
+        )
+        .await
     }
 
     /// List available prompts
@@ -634,23 +593,11 @@ impl McpClient {
     /// # }
     /// ```
     pub async fn list_prompts(&self) -> Result<PromptsListResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "prompts/list".to_string(),
-            params: Some(Utils::to_json_value(&PromptsListRequest::new())?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "prompts/list",
+            Utils::to_json_value(&PromptsListRequest::new())?,
+        )
+        .await
     }
 
     /// Get a prompt
@@ -705,26 +652,11 @@ impl McpClient {
         name: String,
         arguments: Option<std::collections::HashMap<String, String>>,
     ) -> Result<PromptsGetResponse> {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "prompts/get".to_string(),
-            params: Some(Utils::to_json_value(&PromptsGetRequest {
-                name,
-                arguments,
-            })?),
-        };
-
-        let response = self.session.send_request(request).await?;
-        if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
-        }
-
-        let result = response
-            .result
-            .ok_or_else(|| Error::Server("Missing result in response".to_string()))?;
-
-        Utils::from_json_value(result)
+        self.request(
+            "prompts/get",
+            Utils::to_json_value(&PromptsGetRequest { name, arguments })?,
+        )
+        .await
     }
 
     /// Send a ping to the server
@@ -765,16 +697,274 @@ impl McpClient {
     /// # }
     /// ```
     pub async fn ping(&self, message: Option<String>) -> Result<PingResponse> {
+        self.request("ping", Utils::to_json_value(&PingRequest { message })?)
+            .await
+    }
+
+    /// Call a tool and receive its progress notifications
+    ///
+    /// A progress token is attached to the request and every
+    /// `notifications/progress` the server sends for it is passed to
+    /// `on_progress` before the result is returned. If the returned future is
+    /// dropped before the server responds, the server is sent a
+    /// `notifications/cancelled` for the request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mocopr_client::McpClient;
+    /// # use mocopr_core::prelude::*;
+    /// # use serde_json::json;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// # let client = McpClient::connect_stdio("python", &["server.py"],
+    /// #     Implementation { name: "My Client".to_string(), version: "1.0.0".to_string() },
+    /// #     ClientCapabilities::default()).await?;
+    /// let result = client
+    ///     .call_tool_with_progress("index".to_string(), Some(json!({})), |progress| {
+    ///         println!("{} of {:?}", progress.progress, progress.total);
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_with_progress<F>(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        mut on_progress: F,
+    ) -> Result<ToolsCallResponse>
+    where
+        F: FnMut(ProgressNotification) + Send,
+    {
+        let token = ProgressToken::String(uuid::Uuid::new_v4().to_string());
+        let mut params = Utils::to_json_value(&ToolsCallRequest { name, arguments })?;
+        params["_meta"] = serde_json::json!({ "progressToken": token });
+
+        // Subscribe before sending so no notification can be missed
+        let mut notifications = self.session.subscribe_notifications();
+        let response = self.request::<ToolsCallResponse>("tools/call", params);
+        tokio::pin!(response);
+
+        loop {
+            tokio::select! {
+                biased;
+                notification = notifications.recv() => match notification {
+                    Ok(notification) if notification.method == "notifications/progress" => {
+                        if let Some(progress) = notification
+                            .params
+                            .and_then(|p| serde_json::from_value::<ProgressNotification>(p).ok())
+                            .filter(|p| p.progress_token == token)
+                        {
+                            on_progress(progress);
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return response.await,
+                },
+                result = &mut response => return result,
+            }
+        }
+    }
+
+    /// List all tools, following pagination cursors until the last page
+    pub async fn list_all_tools(&self) -> Result<Vec<Tool>> {
+        let mut tools = Vec::new();
+        let mut request = ToolsListRequest::new();
+        loop {
+            let page: ToolsListResponse = self
+                .request("tools/list", Utils::to_json_value(&request)?)
+                .await?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(cursor) => request = ToolsListRequest::new().with_cursor(cursor),
+                None => return Ok(tools),
+            }
+        }
+    }
+
+    /// List all resources, following pagination cursors until the last page
+    pub async fn list_all_resources(&self) -> Result<Vec<Resource>> {
+        let mut resources = Vec::new();
+        let mut request = ResourcesListRequest::new();
+        loop {
+            let page: ResourcesListResponse = self
+                .request("resources/list", Utils::to_json_value(&request)?)
+                .await?;
+            resources.extend(page.resources);
+            match page.next_cursor {
+                Some(cursor) => request = ResourcesListRequest::new().with_cursor(cursor),
+                None => return Ok(resources),
+            }
+        }
+    }
+
+    /// List all resource templates, following pagination cursors until the last page
+    pub async fn list_all_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        let mut templates = Vec::new();
+        let mut request = ResourcesTemplatesListRequest::new();
+        loop {
+            let page: ResourcesTemplatesListResponse = self
+                .request("resources/templates/list", Utils::to_json_value(&request)?)
+                .await?;
+            templates.extend(page.resource_templates);
+            match page.next_cursor {
+                Some(cursor) => request = ResourcesTemplatesListRequest::new().with_cursor(cursor),
+                None => return Ok(templates),
+            }
+        }
+    }
+
+    /// List all prompts, following pagination cursors until the last page
+    pub async fn list_all_prompts(&self) -> Result<Vec<Prompt>> {
+        let mut prompts = Vec::new();
+        let mut request = PromptsListRequest::new();
+        loop {
+            let page: PromptsListResponse = self
+                .request("prompts/list", Utils::to_json_value(&request)?)
+                .await?;
+            prompts.extend(page.prompts);
+            match page.next_cursor {
+                Some(cursor) => request = PromptsListRequest::new().with_cursor(cursor),
+                None => return Ok(prompts),
+            }
+        }
+    }
+
+    /// Subscribe to update notifications for a resource
+    pub async fn subscribe_resource(&self, uri: url::Url) -> Result<ResourcesSubscribeResponse> {
+        self.request(
+            "resources/subscribe",
+            Utils::to_json_value(&ResourcesSubscribeRequest { uri })?,
+        )
+        .await
+    }
+
+    /// Unsubscribe from update notifications for a resource
+    pub async fn unsubscribe_resource(
+        &self,
+        uri: url::Url,
+    ) -> Result<ResourcesUnsubscribeResponse> {
+        self.request(
+            "resources/unsubscribe",
+            Utils::to_json_value(&ResourcesUnsubscribeRequest { uri })?,
+        )
+        .await
+    }
+
+    /// Subscribe to notifications sent by the server
+    ///
+    /// Every subscriber receives each notification that arrives after it
+    /// subscribed, including `notifications/progress` and the various
+    /// `list_changed` notifications.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.session.subscribe_notifications()
+    }
+
+    /// Stream the notifications sent by the server
+    ///
+    /// The stream yields each notification that arrives after this call as a
+    /// typed [`ServerNotification`](notifications::ServerNotification) and
+    /// ends when the connection is closed. Every stream receives all
+    /// notifications independently of the others.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mocopr_client::McpClientBuilder;
+    /// # use mocopr_client::notifications::ServerNotification;
+    /// # use mocopr_core::prelude::*;
+    /// use futures::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// # let client = McpClientBuilder::new()
+    /// #     .with_info("My Client".to_string(), "1.0.0".to_string())
+    /// #     .connect_websocket("ws://localhost:8080/mcp").await?;
+    /// let mut notifications = client.notifications();
+    /// while let Some(notification) = notifications.next().await {
+    ///     if let ServerNotification::LogMessage(log) = notification {
+    ///         println!("{:?}: {}", log.level, log.data);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn notifications(&self) -> NotificationStream {
+        NotificationStream::new(
+            self.session.subscribe_notifications(),
+            self.connected.clone(),
+            None,
+        )
+    }
+
+    /// Stream the notifications of the given kinds sent by the server
+    ///
+    /// Like [`notifications`](Self::notifications), but other notifications
+    /// are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mocopr_client::McpClientBuilder;
+    /// # use mocopr_core::prelude::*;
+    /// use futures::StreamExt;
+    /// use mocopr_client::notifications::NotificationKind;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// # let client = McpClientBuilder::new()
+    /// #     .with_info("My Client".to_string(), "1.0.0".to_string())
+    /// #     .connect_websocket("ws://localhost:8080/mcp").await?;
+    /// let mut changes = client.notifications_of([NotificationKind::ToolsListChanged]);
+    /// while changes.next().await.is_some() {
+    ///     let tools = client.list_all_tools().await?;
+    ///     println!("{} tools available", tools.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn notifications_of(
+        &self,
+        kinds: impl IntoIterator<Item = NotificationKind>,
+    ) -> NotificationStream {
+        NotificationStream::new(
+            self.session.subscribe_notifications(),
+            self.connected.clone(),
+            Some(kinds.into_iter().collect()),
+        )
+    }
+
+    /// Wait until the connection to the server is closed
+    pub async fn closed(&self) {
+        let mut connected = self.connected.clone();
+        let _ = connected.wait_for(|connected| !connected).await;
+    }
+
+    /// Send a request, telling the server to cancel it if dropped before the response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let id = Protocol::generate_request_id();
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: Some(Protocol::generate_request_id()),
-            method: "ping".to_string(),
-            params: Some(Utils::to_json_value(&PingRequest { message })?),
+            id: Some(id.clone()),
+            method: method.to_string(),
+            params: Some(params),
         };
 
-        let response = self.session.send_request(request).await?;
+        let mut guard = CancelOnDrop {
+            session: self.session.clone(),
+            request_id: Some(id),
+        };
+        let response = self.session.send_request(request).await;
+        guard.request_id = None;
+
+        let response = response?;
         if let Some(error) = response.error {
-            return Err(Error::Server(error.message));
+            return Err(Protocol::jsonrpc_to_error(error));
         }
 
         let result = response
@@ -935,10 +1125,44 @@ impl McpClient {
     }
 }
 
+impl Drop for McpClient {
+    fn drop(&mut self) {
+        self.session.stop();
+    }
+}
+
+/// Cancels an outstanding request when the future awaiting it is dropped
+struct CancelOnDrop {
+    session: Arc<Session>,
+    request_id: Option<RequestId>,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let Some(request_id) = self.request_id.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let session = self.session.clone();
+        runtime.spawn(async move {
+            debug!("Cancelling request {}", request_id);
+            let _ = session
+                .cancel_request(&request_id, Some("Request cancelled by client".to_string()))
+                .await;
+        });
+    }
+}
+
 /// Builder for creating MCP clients
 pub struct McpClientBuilder {
     client_info: Option<Implementation>,
     capabilities: ClientCapabilities,
+    oauth: Option<OAuthClient>,
+    websocket_options: WebSocketOptions,
+    handler: ClientMessageHandler,
 }
 
 impl McpClientBuilder {
@@ -963,6 +1187,9 @@ impl McpClientBuilder {
         Self {
             client_info: None,
             capabilities: ClientCapabilities::default(),
+            oauth: None,
+            websocket_options: WebSocketOptions::new(),
+            handler: ClientMessageHandler::default(),
         }
     }
 
@@ -998,7 +1225,9 @@ impl McpClientBuilder {
     ///
     /// This method enables the sampling capability for the client, which allows
     /// the client to request sampled data from the server. Sampling is useful
-    /// for reducing the amount of data transferred over the network.
+    /// for reducing the amount of data transferred over the network. Register
+    /// a handler with [`with_sampling_handler`](Self::with_sampling_handler)
+    /// to answer the sampling requests.
     ///
     /// # Returns
     ///
@@ -1020,7 +1249,9 @@ impl McpClientBuilder {
     ///
     /// This method enables the roots capability for the client, which allows
     /// the client to request the list of available roots from the server. Roots
-    /// are the top-level elements in the MCP hierarchy.
+    /// are the top-level elements in the MCP hierarchy. Register a provider
+    /// with [`with_roots_provider`](Self::with_roots_provider) to answer the
+    /// roots requests.
     ///
     /// # Arguments
     ///
@@ -1043,6 +1274,89 @@ impl McpClientBuilder {
         self
     }
 
+    /// Answer `sampling/createMessage` requests from the server.
+    ///
+    /// This method registers the handler that generates messages when the
+    /// server asks the client to sample its language model, and enables the
+    /// sampling capability so the server knows it may do so. Without a
+    /// handler, sampling requests are answered with a method not found error.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler that generates the messages
+    ///
+    /// # Returns
+    ///
+    /// Returns the updated `McpClientBuilder` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_client::McpClientBuilder;
+    /// use mocopr_client::handlers::SamplingHandler;
+    /// use mocopr_core::prelude::*;
+    ///
+    /// struct Echo;
+    ///
+    /// #[async_trait::async_trait]
+    /// impl SamplingHandler for Echo {
+    ///     async fn create_message(
+    ///         &self,
+    ///         request: CreateMessageRequest,
+    ///     ) -> Result<CreateMessageResponse> {
+    ///         let last = request.messages.last();
+    ///         Ok(CreateMessageResponse {
+    ///             content: last.map(|message| message.content.clone())
+    ///                 .unwrap_or_else(|| Content::Text(TextContent::new(""))),
+    ///             model: "echo".to_string(),
+    ///             stop_reason: Some(StopReason::EndTurn),
+    ///             role: MessageRole::Assistant,
+    ///             meta: ResponseMetadata { _meta: None },
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let builder = McpClientBuilder::new().with_sampling_handler(Echo);
+    /// ```
+    pub fn with_sampling_handler(mut self, handler: impl SamplingHandler + 'static) -> Self {
+        self.handler.sampling = Some(Arc::new(handler));
+        self.with_sampling()
+    }
+
+    /// Answer `roots/list` requests from the server.
+    ///
+    /// This method registers the provider that lists the roots the server may
+    /// operate on, and enables the roots capability unless it was already
+    /// enabled with [`with_roots`](Self::with_roots). Without a provider,
+    /// roots requests are answered with a method not found error.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider of the roots, such as a `Vec<Root>`
+    ///
+    /// # Returns
+    ///
+    /// Returns the updated `McpClientBuilder` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_client::McpClientBuilder;
+    /// use mocopr_core::prelude::*;
+    ///
+    /// let builder = McpClientBuilder::new().with_roots_provider(vec![Root {
+    ///     uri: url::Url::parse("file:///home/user/project").unwrap(),
+    ///     name: Some("project".to_string()),
+    /// }]);
+    /// ```
+    pub fn with_roots_provider(mut self, provider: impl RootsProvider + 'static) -> Self {
+        self.handler.roots = Some(Arc::new(provider));
+        if self.capabilities.roots.is_none() {
+            self.capabilities = self.capabilities.with_roots(false);
+        }
+        self
+    }
+
     /// Enable experimental features.
     ///
     /// This method enables experimental features for the client. Experimental
@@ -1072,6 +1386,70 @@ impl McpClientBuilder {
         self
     }
 
+    /// Authorize with OAuth when connecting to protected servers.
+    ///
+    /// When a server rejects the WebSocket upgrade with `401 Unauthorized`,
+    /// the client discovers its authorization server, obtains an access token
+    /// through `oauth` and connects again with the token. Stored tokens are
+    /// sent right away and refreshed when they expire. See [`oauth`] for the
+    /// details of the flow.
+    ///
+    /// # Arguments
+    ///
+    /// * `oauth` - The OAuth client obtaining and storing tokens
+    ///
+    /// # Returns
+    ///
+    /// Returns the updated `McpClientBuilder` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_client::McpClientBuilder;
+    /// use mocopr_client::oauth::OAuthClient;
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), url::ParseError> {
+    /// let oauth = OAuthClient::new(
+    ///     Url::parse("http://127.0.0.1:8976/callback")?,
+    ///     |authorization_url: Url| async move {
+    ///         // Open the URL in a browser and wait for the redirect
+    ///         # let _ = authorization_url;
+    ///         # Err(mocopr_core::Error::internal("not implemented"))
+    ///     },
+    /// );
+    /// let builder = McpClientBuilder::new().with_oauth(oauth);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_oauth(mut self, oauth: OAuthClient) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Set the TLS settings and extra headers for WebSocket connections
+    ///
+    /// Use this to connect to `wss://` servers whose certificates are issued
+    /// by a private authority, or to servers that authenticate clients by
+    /// certificate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_client::McpClientBuilder;
+    /// use mocopr_core::transport::websocket::WebSocketOptions;
+    ///
+    /// let builder = McpClientBuilder::new().with_websocket_options(
+    ///     WebSocketOptions::new()
+    ///         .with_root_certificates("certs/ca.pem")
+    ///         .with_client_certificate("certs/client.pem", "certs/client.key"),
+    /// );
+    /// ```
+    pub fn with_websocket_options(mut self, options: WebSocketOptions) -> Self {
+        self.websocket_options = options;
+        self
+    }
+
     /// Connect to an MCP server via stdio (process communication).
     ///
     /// This is a convenience method for connecting to MCP servers that run as
@@ -1133,7 +1511,14 @@ impl McpClientBuilder {
             .client_info
             .ok_or_else(|| Error::InvalidRequest("Client info is required".to_string()))?;
 
-        McpClient::connect_stdio(command, args, client_info, self.capabilities).await
+        let transport = mocopr_core::transport::stdio::StdioTransport::spawn(command, args).await?;
+        McpClient::start(
+            Box::new(transport),
+            client_info,
+            self.capabilities,
+            self.handler,
+        )
+        .await
     }
 
     /// Connect to an MCP server via WebSocket
@@ -1182,7 +1567,17 @@ impl McpClientBuilder {
             .client_info
             .ok_or_else(|| Error::InvalidRequest("Client info is required".to_string()))?;
 
-        McpClient::connect_websocket(url, client_info, self.capabilities).await
+        let transport = match self.oauth {
+            Some(oauth) => oauth.connect_websocket(url, self.websocket_options).await?,
+            None => WebSocketTransport::with_options(url, self.websocket_options).await?,
+        };
+        McpClient::start(
+            Box::new(transport),
+            client_info,
+            self.capabilities,
+            self.handler,
+        )
+        .await
     }
 }
 
@@ -0,0 +1,161 @@
+//! Typed notifications received from the server
+//!
+//! [`McpClient::notifications`](crate::McpClient::notifications) returns a
+//! [`NotificationStream`] of every notification the server sends after the
+//! call, and [`McpClient::notifications_of`](crate::McpClient::notifications_of)
+//! one limited to some [`NotificationKind`]s. Each stream has its own buffer
+//! of received notifications, so any number of subscribers see every
+//! notification; a subscriber that falls more than the buffer size behind
+//! skips the oldest ones and a warning is logged.
+
+use futures::Stream;
+use futures::stream::BoxStream;
+use mocopr_core::prelude::*;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast, watch};
+use tracing::warn;
+
+/// A notification sent by the server
+#[derive(Debug, Clone)]
+pub enum ServerNotification {
+    /// The list of resources has changed
+    ResourcesListChanged,
+    /// The content of a subscribed resource has changed
+    ResourceUpdated(ResourcesUpdatedNotification),
+    /// The list of tools has changed
+    ToolsListChanged,
+    /// The list of prompts has changed
+    PromptsListChanged,
+    /// A log message (`notifications/message`)
+    LogMessage(LoggingNotification),
+    /// Progress of a request
+    Progress(ProgressNotification),
+    /// The server cancelled a request
+    Cancelled(CancelledNotification),
+    /// Any other notification, or one whose parameters could not be parsed
+    Other(JsonRpcNotification),
+}
+
+/// The kind of a [`ServerNotification`], used to filter notification streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    /// [`ServerNotification::ResourcesListChanged`]
+    ResourcesListChanged,
+    /// [`ServerNotification::ResourceUpdated`]
+    ResourceUpdated,
+    /// [`ServerNotification::ToolsListChanged`]
+    ToolsListChanged,
+    /// [`ServerNotification::PromptsListChanged`]
+    PromptsListChanged,
+    /// [`ServerNotification::LogMessage`]
+    LogMessage,
+    /// [`ServerNotification::Progress`]
+    Progress,
+    /// [`ServerNotification::Cancelled`]
+    Cancelled,
+    /// [`ServerNotification::Other`]
+    Other,
+}
+
+impl ServerNotification {
+    /// Get the kind of this notification
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Self::ResourcesListChanged => NotificationKind::ResourcesListChanged,
+            Self::ResourceUpdated(_) => NotificationKind::ResourceUpdated,
+            Self::ToolsListChanged => NotificationKind::ToolsListChanged,
+            Self::PromptsListChanged => NotificationKind::PromptsListChanged,
+            Self::LogMessage(_) => NotificationKind::LogMessage,
+            Self::Progress(_) => NotificationKind::Progress,
+            Self::Cancelled(_) => NotificationKind::Cancelled,
+            Self::Other(_) => NotificationKind::Other,
+        }
+    }
+}
+
+impl From<JsonRpcNotification> for ServerNotification {
+    fn from(notification: JsonRpcNotification) -> Self {
+        fn params<T: serde::de::DeserializeOwned>(notification: &JsonRpcNotification) -> Option<T> {
+            let params = notification.params.clone().unwrap_or(serde_json::json!({}));
+            serde_json::from_value(params).ok()
+        }
+
+        let parsed = match notification.method.as_str() {
+            "notifications/resources/list_changed" => Some(Self::ResourcesListChanged),
+            "notifications/resources/updated" => params(&notification).map(Self::ResourceUpdated),
+            "notifications/tools/list_changed" | "notifications/tools/updated" => {
+                Some(Self::ToolsListChanged)
+            }
+            "notifications/prompts/list_changed" | "notifications/prompts/updated" => {
+                Some(Self::PromptsListChanged)
+            }
+            "notifications/message" => params(&notification).map(Self::LogMessage),
+            "notifications/progress" => params(&notification).map(Self::Progress),
+            "notifications/cancelled" => params(&notification).map(Self::Cancelled),
+            _ => None,
+        };
+        parsed.unwrap_or(Self::Other(notification))
+    }
+}
+
+/// Stream of notifications received from the server
+///
+/// The stream ends once the connection to the server is closed and every
+/// notification received before that has been yielded.
+pub struct NotificationStream {
+    inner: BoxStream<'static, ServerNotification>,
+}
+
+impl NotificationStream {
+    pub(crate) fn new(
+        receiver: broadcast::Receiver<JsonRpcNotification>,
+        connected: watch::Receiver<bool>,
+        kinds: Option<Vec<NotificationKind>>,
+    ) -> Self {
+        let inner = futures::stream::unfold(
+            (receiver, connected),
+            move |(mut receiver, mut connected)| {
+                let kinds = kinds.clone();
+                async move {
+                    loop {
+                        // Received notifications are published before the
+                        // connection is marked closed, so none are left behind
+                        let notification = tokio::select! {
+                            biased;
+                            received = receiver.recv() => match received {
+                                Ok(notification) => ServerNotification::from(notification),
+                                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                                    warn!(
+                                        "Notification stream fell behind, skipped {} notifications",
+                                        skipped
+                                    );
+                                    continue;
+                                }
+                                Err(broadcast::error::RecvError::Closed) => return None,
+                            },
+                            _ = connected.wait_for(|connected| !connected) => return None,
+                        };
+                        if kinds
+                            .as_ref()
+                            .is_none_or(|kinds| kinds.contains(&notification.kind()))
+                        {
+                            return Some((notification, (receiver, connected)));
+                        }
+                    }
+                }
+            },
+        );
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl Stream for NotificationStream {
+    type Item = ServerNotification;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
@@ -0,0 +1,866 @@
+//! OAuth 2.1 authorization for connections to protected servers
+//!
+//! Servers acting as OAuth protected resources reject connections without a
+//! valid access token with `401 Unauthorized` and a `WWW-Authenticate`
+//! challenge. An [`OAuthClient`] answers the challenge the way the MCP
+//! authorization specification describes:
+//!
+//! 1. The protected resource metadata (RFC 9728) named by the challenge, or
+//!    published under `/.well-known/oauth-protected-resource`, tells which
+//!    authorization server issues tokens for the server.
+//! 2. The authorization server's metadata (RFC 8414) gives its endpoints.
+//! 3. Without a configured client ID, the client registers itself
+//!    dynamically (RFC 7591).
+//! 4. The user is sent through the authorization code flow with PKCE by an
+//!    [`AuthorizationCallback`], which opens the authorization URL in a
+//!    browser and returns the URL the authorization server redirected to.
+//! 5. The code is exchanged for tokens, which are kept in a [`TokenStore`]
+//!    and refreshed when they expire.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_client::McpClientBuilder;
+//! use mocopr_client::oauth::{FileTokenStore, OAuthClient};
+//! use mocopr_core::prelude::*;
+//! use url::Url;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let oauth = OAuthClient::new(
+//!     Url::parse("http://127.0.0.1:8976/callback")?,
+//!     |authorization_url: Url| async move {
+//!         println!("Open {authorization_url} and paste the URL you are redirected to:");
+//!         let mut line = String::new();
+//!         std::io::stdin().read_line(&mut line)?;
+//!         Ok(Url::parse(line.trim())?)
+//!     },
+//! )
+//! .with_scopes(["mcp:tools"])
+//! .with_token_store(FileTokenStore::open("tokens.json")?);
+//!
+//! let client = McpClientBuilder::new()
+//!     .with_info("My Client".to_string(), "1.0.0".to_string())
+//!     .with_oauth(oauth)
+//!     .connect_websocket("wss://mcp.example.com/mcp")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use mocopr_core::error::TransportError;
+use mocopr_core::prelude::*;
+use mocopr_core::transport::websocket::{WebSocketOptions, WebSocketTransport};
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+use url::Url;
+
+/// Path of OpenID Connect discovery documents, tried after RFC 8414 metadata
+const OPENID_CONFIGURATION_PATH: &str = "/.well-known/openid-configuration";
+
+/// How long before its expiry a token is treated as expired
+const EXPIRY_MARGIN: ChronoDuration = ChronoDuration::seconds(30);
+
+/// Tokens issued for a protected server
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthToken {
+    /// Access token sent as `Authorization: Bearer <token>`
+    pub access_token: String,
+    /// Token used to get a new access token once this one expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// When the access token expires, if the authorization server said
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Scopes granted to the access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Client the tokens were issued to, needed to refresh them
+    pub client_id: String,
+    /// Secret of the client, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+impl OAuthToken {
+    /// Check whether the access token has expired or is about to
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now() + EXPIRY_MARGIN)
+    }
+}
+
+// Keep tokens out of logs
+impl fmt::Debug for OAuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthToken")
+            .field("access_token", &"<redacted>")
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("expires_at", &self.expires_at)
+            .field("scope", &self.scope)
+            .field("client_id", &self.client_id)
+            .finish()
+    }
+}
+
+/// Storage for tokens, keyed by the URL of the server they were issued for
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Load the tokens for `resource`
+    async fn load(&self, resource: &str) -> Result<Option<OAuthToken>>;
+
+    /// Store the tokens for `resource`, replacing any previous ones
+    async fn save(&self, resource: &str, token: &OAuthToken) -> Result<()>;
+
+    /// Forget the tokens for `resource`
+    async fn remove(&self, resource: &str) -> Result<()>;
+}
+
+/// Token store keeping tokens for the lifetime of the process
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<HashMap<String, OAuthToken>>,
+}
+
+impl MemoryTokenStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn load(&self, resource: &str) -> Result<Option<OAuthToken>> {
+        Ok(self.tokens.lock().unwrap().get(resource).cloned())
+    }
+
+    async fn save(&self, resource: &str, token: &OAuthToken) -> Result<()> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(resource.to_string(), token.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, resource: &str) -> Result<()> {
+        self.tokens.lock().unwrap().remove(resource);
+        Ok(())
+    }
+}
+
+/// Token store persisting tokens to a JSON file
+///
+/// On Unix the file is only readable by its owner.
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    tokens: tokio::sync::Mutex<HashMap<String, OAuthToken>>,
+}
+
+impl FileTokenStore {
+    /// Open the store at `path`, starting empty if the file does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let tokens = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source).map_err(|e| {
+                Error::Configuration(format!("Invalid token file {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            tokens: tokio::sync::Mutex::new(tokens),
+        })
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the tokens, replacing the file in one step
+    async fn write(&self, tokens: &HashMap<String, OAuthToken>) -> Result<()> {
+        let temporary = self.path.with_extension("tmp");
+        tokio::fs::write(&temporary, serde_json::to_vec_pretty(tokens)?).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&temporary, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        tokio::fs::rename(&temporary, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn load(&self, resource: &str) -> Result<Option<OAuthToken>> {
+        Ok(self.tokens.lock().await.get(resource).cloned())
+    }
+
+    async fn save(&self, resource: &str, token: &OAuthToken) -> Result<()> {
+        let mut tokens = self.tokens.lock().await;
+        tokens.insert(resource.to_string(), token.clone());
+        self.write(&tokens).await
+    }
+
+    async fn remove(&self, resource: &str) -> Result<()> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.remove(resource).is_some() {
+            self.write(&tokens).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Takes the user through the authorization server's login and consent
+///
+/// Implementations typically open the authorization URL in a browser and
+/// listen on the redirect URI for the authorization server's redirect.
+/// Closures taking the authorization URL and returning a future of the
+/// redirect URL implement this trait.
+#[async_trait]
+pub trait AuthorizationCallback: Send + Sync {
+    /// Send the user to `authorization_url`
+    ///
+    /// Returns the URL, including its query, that the authorization server
+    /// redirected the user to.
+    async fn authorize(&self, authorization_url: Url) -> Result<Url>;
+}
+
+#[async_trait]
+impl<F, Fut> AuthorizationCallback for F
+where
+    F: Fn(Url) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Url>> + Send,
+{
+    async fn authorize(&self, authorization_url: Url) -> Result<Url> {
+        self(authorization_url).await
+    }
+}
+
+/// Endpoints of a protected server and of its authorization server
+struct Discovery {
+    resource: ProtectedResourceMetadata,
+    server: AuthorizationServerMetadata,
+}
+
+/// Client credentials issued by dynamic registration
+#[derive(Deserialize)]
+struct ClientRegistration {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+/// Successful response of the token endpoint
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Error response of the token and registration endpoints
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Obtains and refreshes access tokens for protected servers
+pub struct OAuthClient {
+    redirect_uri: Url,
+    callback: Arc<dyn AuthorizationCallback>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    client_name: String,
+    scopes: Vec<String>,
+    store: Arc<dyn TokenStore>,
+    http: reqwest::Client,
+}
+
+impl OAuthClient {
+    /// Create a client redirecting users to `redirect_uri` after authorization
+    ///
+    /// `callback` takes users through authorization. Tokens are kept in
+    /// memory unless a store is set with
+    /// [`with_token_store`](Self::with_token_store), and the client registers
+    /// itself unless given a client ID.
+    pub fn new(redirect_uri: Url, callback: impl AuthorizationCallback + 'static) -> Self {
+        Self {
+            redirect_uri,
+            callback: Arc::new(callback),
+            client_id: None,
+            client_secret: None,
+            client_name: "MoCoPr Client".to_string(),
+            scopes: Vec::new(),
+            store: Arc::new(MemoryTokenStore::new()),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a pre-registered client instead of registering dynamically
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Authenticate the pre-registered client with a secret
+    pub fn with_client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Set the name the client registers under, shown to users on consent screens
+    pub fn with_client_name(mut self, client_name: impl Into<String>) -> Self {
+        self.client_name = client_name.into();
+        self
+    }
+
+    /// Request these scopes
+    ///
+    /// Without scopes, the client requests the scopes named by the server's
+    /// challenge or, failing that, all scopes the server supports.
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keep tokens in `store`
+    pub fn with_token_store(mut self, store: impl TokenStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Use `client` for requests to the servers
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Get an access token for the server at `resource`
+    ///
+    /// Without `challenge`, a stored token is returned as long as it has not
+    /// expired. `challenge` is the `WWW-Authenticate` header with which the
+    /// server rejected the current token, or an empty string if it sent none;
+    /// it makes the client refresh the token or, failing that, authorize
+    /// again. Tokens are saved to the token store.
+    pub async fn access_token(&self, resource: &str, challenge: Option<&str>) -> Result<String> {
+        let stored = self.store.load(resource).await?;
+        if challenge.is_none()
+            && let Some(token) = &stored
+            && !token.is_expired()
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let params = challenge.map(challenge_params).unwrap_or_default();
+        let discovery = self
+            .discover(&Url::parse(resource)?, params.get("resource_metadata"))
+            .await?;
+
+        let insufficient_scope =
+            params.get("error").map(String::as_str) == Some("insufficient_scope");
+        if let Some(token) = &stored
+            && !insufficient_scope
+            && token.refresh_token.is_some()
+        {
+            match self.refresh(&discovery, token).await {
+                Ok(token) => {
+                    self.store.save(resource, &token).await?;
+                    return Ok(token.access_token);
+                }
+                Err(e) => debug!("Failed to refresh the token for {}: {}", resource, e),
+            }
+        }
+
+        let token = self
+            .authorize(&discovery, self.scope(&params, &discovery), stored.as_ref())
+            .await?;
+        self.store.save(resource, &token).await?;
+        Ok(token.access_token)
+    }
+
+    /// Connect to a WebSocket server, authorizing if it demands a token
+    pub(crate) async fn connect_websocket(
+        &self,
+        url: &str,
+        options: WebSocketOptions,
+    ) -> Result<WebSocketTransport> {
+        let mut first = options.clone();
+        if self.store.load(url).await?.is_some() {
+            first = bearer(first, self.access_token(url, None).await?);
+        }
+
+        match WebSocketTransport::with_options(url, first).await {
+            Err(e)
+                if matches!(
+                    e.inner(),
+                    Error::Transport(TransportError::AuthenticationFailed(_))
+                ) =>
+            {
+                let challenge = e
+                    .data()
+                    .and_then(|data| data["wwwAuthenticate"].as_str())
+                    .unwrap_or_default();
+                debug!("{} demands authorization: {}", url, challenge);
+                let token = self.access_token(url, Some(challenge)).await?;
+                WebSocketTransport::with_options(url, bearer(options, token)).await
+            }
+            result => result,
+        }
+    }
+
+    /// Find the authorization server of the server at `resource`
+    async fn discover(&self, resource: &Url, metadata_url: Option<&String>) -> Result<Discovery> {
+        let resource = http_url(resource)?;
+        let candidates = match metadata_url {
+            Some(url) => vec![Url::parse(url)?],
+            None => well_known_urls(&resource, PROTECTED_RESOURCE_METADATA_PATH)?,
+        };
+        let metadata: ProtectedResourceMetadata = self.fetch_first(&candidates).await?;
+
+        // Tokens must only be requested for the server that asked for them
+        let advertised = Url::parse(&metadata.resource)?;
+        if advertised.origin() != resource.origin()
+            || !resource
+                .path()
+                .starts_with(advertised.path().trim_end_matches('/'))
+        {
+            return Err(Error::security(format!(
+                "Protected resource metadata is for {}, not {}",
+                metadata.resource, resource
+            )));
+        }
+
+        let issuer = metadata.authorization_servers.first().ok_or_else(|| {
+            Error::Configuration(format!(
+                "{} does not name an authorization server",
+                metadata.resource
+            ))
+        })?;
+        let issuer = Url::parse(issuer)?;
+        let mut candidates = well_known_urls(&issuer, AUTHORIZATION_SERVER_METADATA_PATH)?;
+        candidates.extend(well_known_urls(&issuer, OPENID_CONFIGURATION_PATH)?);
+        let server = self.fetch_first(&candidates).await?;
+
+        Ok(Discovery {
+            resource: metadata,
+            server,
+        })
+    }
+
+    /// Fetch the first of `urls` that answers with a JSON document
+    async fn fetch_first<T: DeserializeOwned>(&self, urls: &[Url]) -> Result<T> {
+        let mut last_error = None;
+        for url in urls {
+            debug!("Fetching metadata from {}", url);
+            let response = self
+                .http
+                .get(url.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match response {
+                Ok(response) => {
+                    return response.json().await.map_err(|e| {
+                        Error::Configuration(format!("Invalid metadata from {}: {}", url, e))
+                    });
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(Error::Configuration(match last_error {
+            Some(e) => format!("Failed to fetch metadata: {}", e),
+            None => "No metadata location".to_string(),
+        }))
+    }
+
+    /// Scopes to request, space separated
+    fn scope(&self, params: &HashMap<String, String>, discovery: &Discovery) -> Option<String> {
+        let mut scopes = self.scopes.clone();
+        if let Some(challenged) = params.get("scope") {
+            for scope in challenged.split_whitespace() {
+                if !scopes.iter().any(|s| s == scope) {
+                    scopes.push(scope.to_string());
+                }
+            }
+        }
+        if scopes.is_empty() {
+            scopes = discovery.resource.scopes_supported.clone();
+        }
+        (!scopes.is_empty()).then(|| scopes.join(" "))
+    }
+
+    /// Register with the authorization server
+    async fn register(&self, server: &AuthorizationServerMetadata) -> Result<ClientRegistration> {
+        let endpoint = server.registration_endpoint.as_ref().ok_or_else(|| {
+            Error::Configuration(format!(
+                "{} does not support dynamic client registration; configure a client ID",
+                server.issuer
+            ))
+        })?;
+        debug!("Registering with {}", endpoint);
+        let request = serde_json::json!({
+            "client_name": self.client_name,
+            "redirect_uris": [self.redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        });
+        let response = self
+            .http
+            .post(endpoint)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| Error::internal(format!("Client registration failed: {}", e)))?;
+        parse_response(response, "Client registration").await
+    }
+
+    /// Take the user through the authorization code flow
+    async fn authorize(
+        &self,
+        discovery: &Discovery,
+        scope: Option<String>,
+        previous: Option<&OAuthToken>,
+    ) -> Result<OAuthToken> {
+        let server = &discovery.server;
+        if !server.code_challenge_methods_supported.is_empty()
+            && !server
+                .code_challenge_methods_supported
+                .iter()
+                .any(|method| method == "S256")
+        {
+            return Err(Error::security(format!(
+                "{} does not support PKCE with S256",
+                server.issuer
+            )));
+        }
+
+        let (client_id, client_secret) = match (&self.client_id, previous) {
+            (Some(client_id), _) => (client_id.clone(), self.client_secret.clone()),
+            (None, Some(previous)) => (previous.client_id.clone(), previous.client_secret.clone()),
+            (None, None) => {
+                let registration = self.register(server).await?;
+                (registration.client_id, registration.client_secret)
+            }
+        };
+
+        let verifier = random_string();
+        let state = random_string();
+        let mut authorization_url = Url::parse(&server.authorization_endpoint)?;
+        {
+            let mut query = authorization_url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &client_id)
+                .append_pair("redirect_uri", self.redirect_uri.as_str())
+                .append_pair("code_challenge", &code_challenge(&verifier))
+                .append_pair("code_challenge_method", "S256")
+                .append_pair("state", &state)
+                .append_pair("resource", &discovery.resource.resource);
+            if let Some(scope) = &scope {
+                query.append_pair("scope", scope);
+            }
+        }
+
+        let redirect = self.callback.authorize(authorization_url).await?;
+        let params: HashMap<String, String> = redirect.query_pairs().into_owned().collect();
+        if params.get("state") != Some(&state) {
+            return Err(Error::security(
+                "Authorization response state does not match",
+            ));
+        }
+        if let Some(error) = params.get("error") {
+            return Err(Error::security(match params.get("error_description") {
+                Some(description) => format!("Authorization failed: {}: {}", error, description),
+                None => format!("Authorization failed: {}", error),
+            }));
+        }
+        let code = params
+            .get("code")
+            .ok_or_else(|| Error::security("Authorization response carries no code"))?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", &client_id),
+            ("code_verifier", &verifier),
+            ("resource", &discovery.resource.resource),
+        ];
+        if let Some(secret) = &client_secret {
+            form.push(("client_secret", secret));
+        }
+        let response = self.request_token(&server.token_endpoint, &form).await?;
+        Ok(token(response, client_id, client_secret, None))
+    }
+
+    /// Exchange a refresh token for a new access token
+    async fn refresh(&self, discovery: &Discovery, previous: &OAuthToken) -> Result<OAuthToken> {
+        let refresh_token = previous
+            .refresh_token
+            .as_deref()
+            .ok_or_else(|| Error::security("No refresh token"))?;
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &previous.client_id),
+            ("resource", &discovery.resource.resource),
+        ];
+        if let Some(secret) = &previous.client_secret {
+            form.push(("client_secret", secret));
+        }
+        let response = self
+            .request_token(&discovery.server.token_endpoint, &form)
+            .await?;
+        Ok(token(
+            response,
+            previous.client_id.clone(),
+            previous.client_secret.clone(),
+            previous.refresh_token.clone(),
+        ))
+    }
+
+    /// Send a request to the token endpoint
+    async fn request_token(&self, endpoint: &str, form: &[(&str, &str)]) -> Result<TokenResponse> {
+        let response = self
+            .http
+            .post(endpoint)
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| Error::internal(format!("Token request failed: {}", e)))?;
+        let response: TokenResponse = parse_response(response, "Token request").await?;
+        if !response.token_type.eq_ignore_ascii_case("bearer") {
+            return Err(Error::security(format!(
+                "Unsupported token type {}",
+                response.token_type
+            )));
+        }
+        Ok(response)
+    }
+}
+
+impl fmt::Debug for OAuthClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthClient")
+            .field("redirect_uri", &self.redirect_uri)
+            .field("client_id", &self.client_id)
+            .field("client_name", &self.client_name)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+/// Build tokens from a token endpoint response
+fn token(
+    response: TokenResponse,
+    client_id: String,
+    client_secret: Option<String>,
+    refresh_token: Option<String>,
+) -> OAuthToken {
+    OAuthToken {
+        access_token: response.access_token,
+        // Authorization servers may keep the refresh token the same
+        refresh_token: response.refresh_token.or(refresh_token),
+        expires_at: response
+            .expires_in
+            .map(|seconds| Utc::now() + ChronoDuration::seconds(seconds)),
+        scope: response.scope,
+        client_id,
+        client_secret,
+    }
+}
+
+/// Parse a JSON response, turning OAuth error responses into errors
+async fn parse_response<T: DeserializeOwned>(
+    response: reqwest::Response,
+    operation: &str,
+) -> Result<T> {
+    let status = response.status();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| Error::internal(format!("{} failed: {}", operation, e)))?;
+    if !status.is_success() {
+        let message = match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(ErrorResponse {
+                error,
+                error_description: Some(description),
+            }) => format!("{}: {}", error, description),
+            Ok(ErrorResponse { error, .. }) => error,
+            Err(_) => status.to_string(),
+        };
+        warn!("{} failed: {}", operation, message);
+        return Err(Error::security(format!(
+            "{} failed: {}",
+            operation, message
+        )));
+    }
+    serde_json::from_slice(&body)
+        .map_err(|e| Error::internal(format!("Invalid {} response: {}", operation, e)))
+}
+
+/// Options sending a bearer token
+fn bearer(options: WebSocketOptions, token: String) -> WebSocketOptions {
+    options.with_header("Authorization", format!("Bearer {}", token))
+}
+
+/// Random URL-safe string for PKCE verifiers and states
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// PKCE `S256` challenge of a verifier
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// The `http`/`https` URL of a server reached over `ws`/`wss`
+fn http_url(url: &Url) -> Result<Url> {
+    let mut url = url.clone();
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        _ => return Ok(url),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| Error::Configuration(format!("Cannot use {} over HTTP", url)))?;
+    Ok(url)
+}
+
+/// Locations of a well-known document for `url`, most specific first
+///
+/// The well-known path is inserted between the origin and the path of `url`
+/// (RFC 8414, RFC 9728), falling back to the root.
+fn well_known_urls(url: &Url, well_known: &str) -> Result<Vec<Url>> {
+    let url = http_url(url)?;
+    let path = url.path().trim_end_matches('/');
+    let mut urls = Vec::new();
+    if !path.is_empty() {
+        urls.push(url.join(&format!("{}{}", well_known, path))?);
+    }
+    urls.push(url.join(well_known)?);
+    Ok(urls)
+}
+
+/// Parameters of a `WWW-Authenticate` challenge, keyed by lowercase name
+fn challenge_params(challenge: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let Some((_scheme, mut rest)) = challenge.trim().split_once(' ') else {
+        return params;
+    };
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        let Some((name, after)) = rest.split_once('=') else {
+            break;
+        };
+        let (value, remaining) = match after.trim_start().strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => match after.find(',') {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            },
+        };
+        params.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        rest = remaining;
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_params() {
+        let params = challenge_params(
+            r#"Bearer resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource/mcp", error="insufficient_scope", scope="mcp:tools profile""#,
+        );
+        assert_eq!(
+            params["resource_metadata"],
+            "https://mcp.example.com/.well-known/oauth-protected-resource/mcp"
+        );
+        assert_eq!(params["error"], "insufficient_scope");
+        assert_eq!(params["scope"], "mcp:tools profile");
+
+        let params = challenge_params("Bearer realm=mcp, Error=invalid_token");
+        assert_eq!(params["realm"], "mcp");
+        assert_eq!(params["error"], "invalid_token");
+        assert!(challenge_params("Bearer").is_empty());
+    }
+
+    #[test]
+    fn test_well_known_urls() {
+        let urls = well_known_urls(
+            &Url::parse("wss://mcp.example.com/tenant/mcp").unwrap(),
+            PROTECTED_RESOURCE_METADATA_PATH,
+        )
+        .unwrap();
+        assert_eq!(
+            urls.iter().map(Url::as_str).collect::<Vec<_>>(),
+            [
+                "https://mcp.example.com/.well-known/oauth-protected-resource/tenant/mcp",
+                "https://mcp.example.com/.well-known/oauth-protected-resource",
+            ]
+        );
+
+        let urls = well_known_urls(
+            &Url::parse("https://auth.example.com").unwrap(),
+            AUTHORIZATION_SERVER_METADATA_PATH,
+        )
+        .unwrap();
+        assert_eq!(
+            urls.iter().map(Url::as_str).collect::<Vec<_>>(),
+            ["https://auth.example.com/.well-known/oauth-authorization-server"]
+        );
+    }
+
+    #[test]
+    fn test_code_challenge() {
+        // Unpadded base64url of the SHA-256 digest
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mJ0kzLE6r0aTuA2Ap3O2WUIbA4sDkw"),
+            "YHaLuGfNv4Xqb8pmD-y6OwAhWls88BzLbuB5My6ld70"
+        );
+
+        let verifier = random_string();
+        assert_eq!(verifier.len(), 43);
+        assert_ne!(verifier, random_string());
+    }
+}
@@ -43,6 +43,10 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// - `InvalidParams` → -32602
 /// - `Internal` → -32603
 /// - `Parse` → -32700
+/// - `Remote` → the code sent by the peer
+///
+/// The enum is `#[non_exhaustive]`: variants may be added in minor releases,
+/// so matches outside this crate need a wildcard arm.
 ///
 /// # Examples
 ///
@@ -55,6 +59,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// let validation_err = Error::InvalidParams("Missing required parameter 'path'".to_string());
 /// ```
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Transport layer error (connection, send/receive failures, etc.).
     #[error("Transport error: {0}")]
@@ -135,6 +140,29 @@ pub enum Error {
     /// Catch-all for other error types.
     #[error("Other error: {0}")]
     Other(#[from] anyhow::Error),
+
+    /// An error response received from the peer, kept with its JSON-RPC code.
+    ///
+    /// Relaying it, e.g. from a proxy, sends the peer's code and message on
+    /// unchanged.
+    #[error("{message}")]
+    Remote {
+        /// JSON-RPC error code sent by the peer
+        code: i32,
+        /// Error message sent by the peer
+        message: String,
+    },
+
+    /// An error carrying structured details for the JSON-RPC `error.data` field.
+    ///
+    /// The JSON-RPC code and classification are those of the wrapped error.
+    #[error("{error}")]
+    WithData {
+        /// The underlying error
+        error: Box<Error>,
+        /// Structured details sent to the peer as `error.data`
+        data: serde_json::Value,
+    },
 }
 
 /// Transport-specific errors.
@@ -238,6 +266,10 @@ pub enum ProtocolError {
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    /// The server has no capacity left for the request right now.
+    #[error("Server busy: {0}")]
+    ServerBusy(String),
+
     /// The protocol version is not supported.
     #[error("Unsupported protocol version: {0}")]
     UnsupportedProtocolVersion(String),
@@ -403,6 +435,52 @@ impl Error {
         Self::ResourceAccess(msg.into())
     }
 
+    /// Attach structured details to this error.
+    ///
+    /// The details are sent to the peer in the `data` member of the JSON-RPC
+    /// error object.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_core::error::Error;
+    /// use serde_json::json;
+    ///
+    /// let error = Error::invalid_params("Invalid arguments")
+    ///     .with_data(json!({"violations": [{"path": "/count", "message": "not an integer"}]}));
+    /// assert_eq!(error.json_rpc_code(), -32602);
+    /// assert!(error.data().is_some());
+    /// ```
+    pub fn with_data(self, data: serde_json::Value) -> Self {
+        Self::WithData {
+            error: Box::new(self.into_inner()),
+            data,
+        }
+    }
+
+    /// Get the structured details attached with [`Error::with_data`], if any.
+    pub fn data(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::WithData { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Get the underlying error, looking through any attached details.
+    pub fn inner(&self) -> &Error {
+        match self {
+            Self::WithData { error, .. } => error.inner(),
+            _ => self,
+        }
+    }
+
+    fn into_inner(self) -> Error {
+        match self {
+            Self::WithData { error, .. } => error.into_inner(),
+            other => other,
+        }
+    }
+
     /// Check if the error is recoverable.
     ///
     /// Recoverable errors are those that might succeed if retried,
@@ -420,7 +498,7 @@ impl Error {
     /// assert!(!closed.is_recoverable());
     /// ```
     pub fn is_recoverable(&self) -> bool {
-        match self {
+        match self.inner() {
             Self::Transport(TransportError::NotReady) => true,
             Self::Transport(TransportError::Closed) => false,
             Self::Transport(TransportError::NetworkError(_)) => true,
@@ -447,7 +525,7 @@ impl Error {
     /// assert_eq!(error.json_rpc_code(), -32600);
     /// ```
     pub fn json_rpc_code(&self) -> i32 {
-        match self {
+        match self.inner() {
             Self::Parse(_) => -32700,
             Self::InvalidRequest(_) => -32600,
             Self::MethodNotFound(_) => -32601,
@@ -458,8 +536,10 @@ impl Error {
             Self::Protocol(ProtocolError::PromptNotFound(_)) => -32601,
             Self::Security(_) | Self::Protocol(ProtocolError::PermissionDenied) => -32000,
             Self::Protocol(ProtocolError::RateLimitExceeded) => -32001,
+            Self::Protocol(ProtocolError::ServerBusy(_)) => -32006,
             Self::Timeout => -32002,
             Self::ConnectionClosed => -32003,
+            Self::Remote { code, .. } => *code,
             _ => -32000, // Generic server error
         }
     }
@@ -481,7 +561,7 @@ impl Error {
     /// ```
     pub fn is_client_error(&self) -> bool {
         matches!(
-            self,
+            self.inner(),
             Self::InvalidRequest(_)
                 | Self::MethodNotFound(_)
                 | Self::InvalidParams(_)
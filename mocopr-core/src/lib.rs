@@ -243,7 +243,10 @@ pub mod prelude {
     pub use crate::ResourceReader;
     pub use crate::ToolExecutor;
     pub use crate::error::{Error, Result};
-    pub use crate::monitoring::{HealthCheck, HealthStatus, MonitoringSystem, PerformanceMetrics};
+    pub use crate::monitoring::{
+        HealthCheck, HealthStatus, MethodMetrics, MonitoringSystem, PerformanceMetrics,
+        ToolExecutionMetrics,
+    };
     pub use crate::protocol::*;
     pub use crate::security::{ErrorRecoverySystem, SecurityValidator};
     pub use crate::transport::{Transport, TransportConfig, TransportFactory};
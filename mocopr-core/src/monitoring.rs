@@ -2,6 +2,7 @@
 // This provides production-ready monitoring capabilities
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
@@ -98,7 +99,68 @@ pub struct RequestMetrics {
     pub error_message: Option<String>,
 }
 
+/// Execution counters for a single tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ToolExecutionMetrics {
+    /// Invocations currently waiting for a free execution slot
+    pub queue_depth: u64,
+    /// Highest queue depth observed
+    pub max_queue_depth: u64,
+    /// Invocations that exceeded their time limit
+    pub timeouts: u64,
+    /// Invocations rejected because the queue was full
+    pub rejections: u64,
+}
+
+/// Upper bounds, in seconds, of the request latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Request counters and latency histogram for a single method
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MethodMetrics {
+    /// Number of requests handled
+    pub requests: u64,
+    /// Number of requests that failed
+    pub errors: u64,
+    /// Number of requests per latency bucket, see [`LATENCY_BUCKETS`]
+    ///
+    /// Counts are not cumulative; the last entry counts requests slower than
+    /// the largest bucket bound.
+    pub latency_buckets: Vec<u64>,
+    /// Sum of all request latencies in seconds
+    pub latency_sum_seconds: f64,
+    /// Number of requests whose handler panicked
+    #[serde(default)]
+    pub panics: u64,
+}
+
+impl MethodMetrics {
+    fn record(&mut self, success: bool, response_time: Duration) {
+        if self.latency_buckets.is_empty() {
+            self.latency_buckets = vec![0; LATENCY_BUCKETS.len() + 1];
+        }
+
+        let seconds = response_time.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.requests += 1;
+        if !success {
+            self.errors += 1;
+        }
+        self.latency_buckets[bucket] += 1;
+        self.latency_sum_seconds += seconds;
+    }
+}
+
 /// Comprehensive monitoring system
+///
+/// Cloning is cheap; clones share the same metrics and health checks.
+#[derive(Clone)]
 pub struct MonitoringSystem {
     /// Registered health checks
     health_checks: Arc<RwLock<Vec<Box<dyn HealthCheck>>>>,
@@ -106,6 +168,10 @@ pub struct MonitoringSystem {
     metrics: Arc<RwLock<PerformanceMetrics>>,
     /// Recent response times for percentile calculations
     response_times: Arc<RwLock<Vec<Duration>>>,
+    /// Per-tool execution counters
+    tool_metrics: Arc<RwLock<HashMap<String, ToolExecutionMetrics>>>,
+    /// Per-method request counters
+    method_metrics: Arc<RwLock<HashMap<String, MethodMetrics>>>,
     /// Configuration
     config: MonitoringConfig,
 }
@@ -138,6 +204,8 @@ impl MonitoringSystem {
             health_checks: Arc::new(RwLock::new(Vec::new())),
             metrics: Arc::new(RwLock::new(PerformanceMetrics::default())),
             response_times: Arc::new(RwLock::new(Vec::new())),
+            tool_metrics: Arc::new(RwLock::new(HashMap::new())),
+            method_metrics: Arc::new(RwLock::new(HashMap::new())),
             config,
         }
     }
@@ -200,6 +268,13 @@ impl MonitoringSystem {
             metrics.failed_requests += 1;
         }
 
+        self.method_metrics
+            .write()
+            .await
+            .entry(request.method.clone())
+            .or_default()
+            .record(request.success, request.response_time);
+
         // Update response times
         response_times.push(request.response_time);
 
@@ -260,6 +335,46 @@ impl MonitoringSystem {
         self.metrics.read().await.clone()
     }
 
+    /// Get request counters and latency histograms per method
+    pub async fn get_method_metrics(&self) -> HashMap<String, MethodMetrics> {
+        self.method_metrics.read().await.clone()
+    }
+
+    /// Record the current number of queued invocations of a tool
+    pub async fn record_tool_queue_depth(&self, tool: &str, depth: usize) {
+        let mut tool_metrics = self.tool_metrics.write().await;
+        let entry = tool_metrics.entry(tool.to_string()).or_default();
+        entry.queue_depth = depth as u64;
+        entry.max_queue_depth = entry.max_queue_depth.max(depth as u64);
+    }
+
+    /// Record a request whose handler panicked
+    ///
+    /// The panic is counted in addition to the request itself, which is
+    /// recorded with [`record_request`](Self::record_request) when a response
+    /// was produced for it.
+    pub async fn record_panic(&self, method: &str) {
+        let mut method_metrics = self.method_metrics.write().await;
+        method_metrics.entry(method.to_string()).or_default().panics += 1;
+    }
+
+    /// Record a tool invocation that exceeded its time limit
+    pub async fn record_tool_timeout(&self, tool: &str) {
+        let mut tool_metrics = self.tool_metrics.write().await;
+        tool_metrics.entry(tool.to_string()).or_default().timeouts += 1;
+    }
+
+    /// Record a tool invocation rejected because its queue was full
+    pub async fn record_tool_rejection(&self, tool: &str) {
+        let mut tool_metrics = self.tool_metrics.write().await;
+        tool_metrics.entry(tool.to_string()).or_default().rejections += 1;
+    }
+
+    /// Get execution counters for all tools
+    pub async fn get_tool_metrics(&self) -> HashMap<String, ToolExecutionMetrics> {
+        self.tool_metrics.read().await.clone()
+    }
+
     /// Start periodic health checks
     pub async fn start_periodic_health_checks(&self) {
         let health_checks = self.health_checks.clone();
@@ -496,6 +611,13 @@ mod tests {
         assert_eq!(metrics.total_requests, 1);
         assert_eq!(metrics.successful_requests, 1);
         assert_eq!(metrics.failed_requests, 0);
+
+        let method_metrics = &monitoring.get_method_metrics().await["test_method"];
+        assert_eq!(method_metrics.requests, 1);
+        assert_eq!(method_metrics.errors, 0);
+        // 100ms falls into the 0.1s bucket
+        assert_eq!(method_metrics.latency_buckets[4], 1);
+        assert_eq!(method_metrics.latency_buckets.iter().sum::<u64>(), 1);
     }
 
     #[tokio::test]
@@ -0,0 +1,235 @@
+//! Typed per-session state
+//!
+//! [`Extensions`] is a map keyed by type, similar to `http::Extensions`, that
+//! lives as long as a session. Handlers and middleware use it to keep state
+//! for the connected client, such as a token obtained by one tool call and
+//! used by the next. It is cleared when the session ends.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_core::protocol::Extensions;
+//!
+//! #[derive(Clone)]
+//! struct WorkingDirectory(String);
+//!
+//! let extensions = Extensions::new();
+//! extensions.insert(WorkingDirectory("/tmp".to_string()));
+//!
+//! assert_eq!(extensions.get::<WorkingDirectory>().unwrap().0, "/tmp");
+//! ```
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+tokio::task_local! {
+    static CURRENT: Extensions;
+}
+
+type AnyMap = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+/// Type-keyed storage scoped to a session
+///
+/// Clones share the same storage, so a handle can be kept while the session
+/// stays alive. At most one value of each type is stored; use a newtype to
+/// store several values of the same underlying type.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: Arc<RwLock<AnyMap>>,
+}
+
+impl Extensions {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the extensions of the session whose message is being handled
+    ///
+    /// Returns `None` outside of message handling.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run a future with these extensions as the current ones
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Insert a value, returning the previous value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<T> {
+        self.write()
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok().map(|boxed| *boxed))
+    }
+
+    /// Get a clone of the value of type `T`
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.with(T::clone)
+    }
+
+    /// Get the value of type `T`, inserting the result of `init` if there is none
+    pub fn get_or_insert_with<T, F>(&self, init: F) -> T
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        self.with_mut_or_insert_with(init, |value| value.clone())
+    }
+
+    /// Call `f` with a reference to the value of type `T`
+    pub fn with<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.read()
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+            .map(f)
+    }
+
+    /// Call `f` with a mutable reference to the value of type `T`
+    pub fn with_mut<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.write()
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+            .map(f)
+    }
+
+    /// Call `f` with a mutable reference to the value of type `T`, inserting
+    /// the result of `init` first if there is none
+    pub fn with_mut_or_insert_with<T, R>(
+        &self,
+        init: impl FnOnce() -> T,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> R
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut map = self.write();
+        let value = map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(init()));
+        f(value
+            .downcast_mut()
+            .expect("extension stored under the TypeId of another type"))
+    }
+
+    /// Remove and return the value of type `T`
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<T> {
+        self.write()
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok().map(|boxed| *boxed))
+    }
+
+    /// Check whether a value of type `T` is stored
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.read().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Get the number of stored values
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Check whether no values are stored
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Drop every stored value
+    pub fn clear(&self) {
+        // Take the values out first so their destructors run without the lock
+        let values = std::mem::take(&mut *self.write());
+        drop(values);
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, AnyMap> {
+        self.map.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, AnyMap> {
+        self.map.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Token(String);
+
+    #[test]
+    fn test_insert_get_remove() {
+        let extensions = Extensions::new();
+        assert!(extensions.is_empty());
+
+        assert_eq!(extensions.insert(Token("a".to_string())), None);
+        assert_eq!(
+            extensions.insert(Token("b".to_string())),
+            Some(Token("a".to_string()))
+        );
+        extensions.insert(42u32);
+
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions.get::<Token>(), Some(Token("b".to_string())));
+        assert_eq!(extensions.with(|n: &u32| n + 1), Some(43));
+
+        extensions.with_mut(|n: &mut u32| *n = 7);
+        assert_eq!(extensions.get::<u32>(), Some(7));
+        assert_eq!(
+            extensions.with_mut_or_insert_with(
+                || 0u64,
+                |n| {
+                    *n += 1;
+                    *n
+                }
+            ),
+            1
+        );
+
+        assert_eq!(extensions.remove::<Token>(), Some(Token("b".to_string())));
+        assert!(!extensions.contains::<Token>());
+        assert_eq!(
+            extensions.get_or_insert_with(|| Token("c".to_string())).0,
+            "c"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_current_and_clear() {
+        struct DropFlag(Arc<std::sync::atomic::AtomicBool>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        assert!(Extensions::current().is_none());
+
+        let extensions = Extensions::new();
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        extensions
+            .clone()
+            .scope(async {
+                Extensions::current()
+                    .unwrap()
+                    .insert(DropFlag(dropped.clone()));
+            })
+            .await;
+
+        assert!(extensions.contains::<DropFlag>());
+        extensions.clear();
+        assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(extensions.is_empty());
+    }
+}
@@ -7,6 +7,17 @@ use async_trait::async_trait;
 /// Trait for handling MCP protocol messages
 #[async_trait]
 pub trait MessageHandler: Send + Sync {
+    /// Handle a request as a whole, before it is dispatched by method
+    ///
+    /// Called for every incoming request. Returning a response answers the
+    /// request without calling the method handlers below, which lets a server
+    /// run requests from every transport through one pipeline, e.g. its
+    /// middleware. The default returns `None`, dispatching the request by
+    /// method.
+    async fn handle_request(&self, _request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+        None
+    }
+
     /// Handle an initialize request
     async fn handle_initialize(&self, request: InitializeRequest) -> Result<InitializeResponse>;
 
@@ -38,6 +49,16 @@ pub trait MessageHandler: Send + Sync {
         Err(Error::MethodNotFound("resources/read".to_string()))
     }
 
+    /// Handle resources/templates/list request
+    async fn handle_resources_templates_list(
+        &self,
+        _request: ResourcesTemplatesListRequest,
+    ) -> Result<ResourcesTemplatesListResponse> {
+        Err(Error::MethodNotFound(
+            "resources/templates/list".to_string(),
+        ))
+    }
+
     /// Handle resources/subscribe request
     async fn handle_resources_subscribe(
         &self,
@@ -7,6 +7,7 @@ use crate::{Error, Result, types::*};
 use serde_json::Value;
 use uuid::Uuid;
 
+pub mod extensions;
 pub mod handler;
 pub mod router;
 pub mod session;
@@ -14,6 +15,7 @@ pub mod session;
 #[cfg(test)]
 mod tests;
 
+pub use extensions::Extensions;
 pub use handler::*;
 pub use router::*;
 pub use session::*;
@@ -49,6 +51,8 @@ pub mod error_codes {
     pub const PERMISSION_DENIED: i32 = -32004;
     /// The client has been rate limited
     pub const RATE_LIMITED: i32 = -32005;
+    /// The server has no capacity left for the request; it may be retried later
+    pub const SERVER_BUSY: i32 = -32006;
 }
 
 /// Protocol utilities
@@ -155,6 +159,10 @@ impl Protocol {
     /// Convert an error to a JSON-RPC error
     pub fn error_to_jsonrpc(error: &Error) -> JsonRpcError {
         match error {
+            Error::WithData { error, data } => JsonRpcError {
+                data: Some(data.clone()),
+                ..Self::error_to_jsonrpc(error)
+            },
             Error::InvalidRequest(msg) => {
                 Self::create_error(error_codes::INVALID_REQUEST, msg, None)
             }
@@ -180,11 +188,30 @@ impl Protocol {
             Error::Protocol(crate::error::ProtocolError::RateLimitExceeded) => {
                 Self::create_error(error_codes::RATE_LIMITED, "Rate limit exceeded", None)
             }
+            Error::Protocol(crate::error::ProtocolError::ServerBusy(msg)) => {
+                Self::create_error(error_codes::SERVER_BUSY, msg, None)
+            }
             Error::Parse(msg) => Self::create_error(error_codes::PARSE_ERROR, msg, None),
+            Error::Remote { code, message } => Self::create_error(*code, message, None),
             _ => Self::create_error(error_codes::INTERNAL_ERROR, &error.to_string(), None),
         }
     }
 
+    /// Convert a JSON-RPC error received from the peer to an error
+    ///
+    /// The code, message and data are kept, so [`Protocol::error_to_jsonrpc`]
+    /// gives back the same error.
+    pub fn jsonrpc_to_error(error: JsonRpcError) -> Error {
+        let remote = Error::Remote {
+            code: error.code,
+            message: error.message,
+        };
+        match error.data {
+            Some(data) => remote.with_data(data),
+            None => remote,
+        }
+    }
+
     /// Validate method name format
     pub fn validate_method_name(method: &str) -> bool {
         !method.is_empty()
@@ -35,6 +35,10 @@ impl MessageRouter {
 
     /// Route a request message
     async fn route_request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+        if let Some(response) = self.handler.handle_request(&request).await {
+            return Ok(response);
+        }
+
         let result = self.dispatch_request(&request).await;
 
         match result {
@@ -82,6 +86,12 @@ impl MessageRouter {
                 let response = self.handler.handle_resources_read(req).await?;
                 Utils::to_json_value(&response)
             }
+            "resources/templates/list" => {
+                let req: ResourcesTemplatesListRequest =
+                    self.deserialize_params(request.params.as_ref())?;
+                let response = self.handler.handle_resources_templates_list(req).await?;
+                Utils::to_json_value(&response)
+            }
             "resources/subscribe" => {
                 let req: ResourcesSubscribeRequest =
                     self.deserialize_params(request.params.as_ref())?;
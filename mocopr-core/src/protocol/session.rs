@@ -2,9 +2,12 @@
 
 use super::*;
 use crate::{Error, Result, transport::Transport, utils::Utils};
+use futures::FutureExt;
 use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, RwLock, broadcast, mpsc};
 use uuid::Uuid;
 
 /// Represents an active MCP session
@@ -15,8 +18,23 @@ pub struct Session {
     router: MessageRouter,
     pending_requests: Arc<Mutex<HashMap<RequestId, PendingRequest>>>,
     event_sender: mpsc::UnboundedSender<SessionEvent>,
+    outbound_sender: mpsc::UnboundedSender<String>,
+    outbound_receiver: Mutex<Option<mpsc::UnboundedReceiver<String>>>,
+    running: AtomicBool,
+    stop: tokio::sync::Notify,
+    abort: tokio::sync::Notify,
+    notifications: broadcast::Sender<JsonRpcNotification>,
+    extensions: Extensions,
+    panic_message: String,
 }
 
+/// Number of received notifications buffered per subscriber
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 256;
+
+/// Error message sent in place of the response to a request whose handler
+/// panicked, unless another is configured
+pub const DEFAULT_PANIC_MESSAGE: &str = "Internal error";
+
 /// Session state information
 #[derive(Debug, Clone)]
 pub struct SessionState {
@@ -76,6 +94,18 @@ pub enum SessionEvent {
         /// The error message
         error: String,
     },
+    /// Event triggered when the handler panicked while processing a message
+    ///
+    /// A request that panicked has been answered with an internal error and
+    /// the session keeps running.
+    Panicked {
+        /// Method of the request or notification being handled
+        method: String,
+        /// ID of the request, absent for notifications
+        request_id: Option<RequestId>,
+        /// The panic message
+        message: String,
+    },
 }
 
 impl Session {
@@ -87,6 +117,7 @@ impl Session {
         let id = Uuid::new_v4().to_string();
         let router = MessageRouter::new(handler);
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let (outbound_sender, outbound_receiver) = mpsc::unbounded_channel();
 
         let session = Self {
             id,
@@ -104,11 +135,29 @@ impl Session {
             router,
             pending_requests: Arc::new(Mutex::new(HashMap::new())),
             event_sender,
+            outbound_sender,
+            outbound_receiver: Mutex::new(Some(outbound_receiver)),
+            running: AtomicBool::new(false),
+            stop: tokio::sync::Notify::new(),
+            abort: tokio::sync::Notify::new(),
+            notifications: broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY).0,
+            extensions: Extensions::new(),
+            panic_message: DEFAULT_PANIC_MESSAGE.to_string(),
         };
 
         (session, event_receiver)
     }
 
+    /// Answer requests whose handler panics with `message` instead of
+    /// [`DEFAULT_PANIC_MESSAGE`]
+    ///
+    /// The panic itself is only logged and reported as
+    /// [`SessionEvent::Panicked`], so its details never reach the peer.
+    pub fn with_panic_message(mut self, message: impl Into<String>) -> Self {
+        self.panic_message = message.into();
+        self
+    }
+
     /// Get session ID
     pub fn id(&self) -> &str {
         &self.id
@@ -119,6 +168,25 @@ impl Session {
         self.state.read().await.clone()
     }
 
+    /// Get the typed state stored for this session
+    ///
+    /// While the session handles an incoming message the same map is
+    /// available through [`Extensions::current`]. It is cleared when the
+    /// message loop ends.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Get a sender that queues raw messages for the message loop
+    ///
+    /// Messages are written in order with the responses the session sends, so
+    /// a request handler can use it to deliver notifications, such as progress,
+    /// before its response. They are only written while [`Session::run`] is
+    /// running.
+    pub fn outbound(&self) -> mpsc::UnboundedSender<String> {
+        self.outbound_sender.clone()
+    }
+
     /// Check if session is initialized
     pub async fn is_initialized(&self) -> bool {
         self.state.read().await.initialized
@@ -167,10 +235,22 @@ impl Session {
     }
 
     /// Send a raw message
+    ///
+    /// While the message loop is running it owns the transport, so outgoing
+    /// messages are queued and written by [`Session::run`] between reads.
     async fn send_message(&self, message: &str) -> Result<()> {
         {
-            let mut transport = self.transport.lock().await;
-            transport.send(message).await?;
+            // The loop takes the receiver before locking the transport, so
+            // holding this lock while writing directly cannot deadlock with it
+            let outbound = self.outbound_receiver.lock().await;
+            if outbound.is_some() {
+                let mut transport = self.transport.lock().await;
+                transport.send(message).await?;
+            } else {
+                self.outbound_sender
+                    .send(message.to_string())
+                    .map_err(|_| Error::internal("Session message loop has stopped"))?;
+            }
         }
 
         // Update last activity
@@ -188,14 +268,70 @@ impl Session {
     }
 
     /// Start the session message loop
+    ///
+    /// The loop owns the transport until the connection closes. Messages sent
+    /// through the session in the meantime, including notifications from other
+    /// tasks, are written between reads.
     pub async fn run(&self) -> Result<()> {
+        let mut outbound = self
+            .outbound_receiver
+            .lock()
+            .await
+            .take()
+            .ok_or_else(|| Error::internal("Session message loop is already running"))?;
+
+        let mut transport = self.transport.lock().await;
+        self.running.store(true, Ordering::Release);
         let _ = self.event_sender.send(SessionEvent::Connected);
 
+        let result = self.run_loop(&mut transport, &mut outbound).await;
+
+        self.running.store(false, Ordering::Release);
+
+        // Flush anything queued after the last read, holding the receiver slot
+        // so that later messages are written directly
+        let mut slot = self.outbound_receiver.lock().await;
+        while let Ok(message) = outbound.try_recv() {
+            if transport.send(&message).await.is_err() {
+                break;
+            }
+        }
+        *slot = Some(outbound);
+        drop(slot);
+        drop(transport);
+
+        // Nothing will answer requests that are still waiting
+        for (_, pending) in self.pending_requests.lock().await.drain() {
+            let _ = pending.sender.send(Err(Error::ConnectionClosed));
+        }
+
+        self.extensions.clear();
+
+        result
+    }
+
+    async fn run_loop(
+        &self,
+        transport: &mut Box<dyn Transport>,
+        outbound: &mut mpsc::UnboundedReceiver<String>,
+    ) -> Result<()> {
         loop {
-            // Receive message from transport
-            let message = {
-                let mut transport = self.transport.lock().await;
-                transport.receive().await?
+            // Write queued messages, otherwise wait for the next incoming one
+            let message = tokio::select! {
+                biased;
+                Some(message) = outbound.recv() => {
+                    transport.send(&message).await?;
+                    continue;
+                }
+                _ = self.stop.notified() => {
+                    let _ = self.event_sender.send(SessionEvent::Disconnected);
+                    break;
+                }
+                _ = self.abort.notified() => {
+                    let _ = self.event_sender.send(SessionEvent::Disconnected);
+                    break;
+                }
+                message = transport.receive() => message?,
             };
 
             let message = match message {
@@ -218,8 +354,16 @@ impl Session {
                 message: message.clone(),
             });
 
-            // Process message
-            if let Err(e) = self.process_message(&message).await {
+            // Process message, unless the session is aborted first
+            let processed = tokio::select! {
+                result = self.extensions.clone().scope(self.process_message(&message)) => result,
+                _ = self.abort.notified() => {
+                    self.answer_aborted(&message).await;
+                    let _ = self.event_sender.send(SessionEvent::Disconnected);
+                    break;
+                }
+            };
+            if let Err(e) = processed {
                 let _ = self.event_sender.send(SessionEvent::Error {
                     error: e.to_string(),
                 });
@@ -229,6 +373,72 @@ impl Session {
         Ok(())
     }
 
+    /// Subscribe to notifications received from the peer
+    ///
+    /// Each notification is published before the next incoming message is
+    /// processed, so a notification always reaches subscribers before the
+    /// response to a request that the peer sent after it.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Stop waiting for the response to a request and tell the peer to cancel it
+    pub async fn cancel_request(
+        &self,
+        request_id: &RequestId,
+        reason: Option<String>,
+    ) -> Result<()> {
+        self.pending_requests.lock().await.remove(request_id);
+
+        let notification = JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/cancelled".to_string(),
+            params: Some(Utils::to_json_value(&CancelledNotification {
+                request_id: request_id.clone(),
+                reason,
+            })?),
+        };
+        self.send_notification(notification).await
+    }
+
+    /// Ask the message loop to stop
+    ///
+    /// The loop finishes the message it is currently processing, writes any
+    /// queued outgoing messages and then returns from [`Session::run`]. If the
+    /// loop is not running yet, the next call to `run` returns immediately.
+    pub fn stop(&self) {
+        self.stop.notify_one();
+    }
+
+    /// Stop the message loop without waiting for the message being processed
+    ///
+    /// Unlike [`stop`](Self::stop), a request that is still being handled is
+    /// dropped and answered with an internal error. The loop then writes any
+    /// queued outgoing messages and returns from [`Session::run`] as usual.
+    pub fn abort(&self) {
+        self.abort.notify_one();
+    }
+
+    /// Answer a request dropped by [`abort`](Self::abort)
+    async fn answer_aborted(&self, message: &str) {
+        let Ok(JsonRpcMessage::Request(request)) = Protocol::parse_message(message) else {
+            return;
+        };
+        let response = JsonRpcMessage::Response(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: request.id,
+            result: None,
+            error: Some(Protocol::create_error(
+                error_codes::INTERNAL_ERROR,
+                "Request cancelled: the session is closing",
+                None,
+            )),
+        });
+        if let Ok(response) = Protocol::serialize_message(&response) {
+            let _ = self.send_message(&response).await;
+        }
+    }
+
     /// Process an incoming message
     async fn process_message(&self, message: &str) -> Result<()> {
         let jsonrpc_message = Protocol::parse_message(message)?;
@@ -237,9 +447,13 @@ impl Session {
             JsonRpcMessage::Response(response) => {
                 self.handle_response(response).await?;
             }
-            JsonRpcMessage::Request(_) | JsonRpcMessage::Notification(_) => {
+            JsonRpcMessage::Notification(notification) => {
+                let _ = self.notifications.send(notification.clone());
+                self.route_isolated(jsonrpc_message).await?;
+            }
+            JsonRpcMessage::Request(_) => {
                 // Route to handler
-                if let Some(response_message) = self.router.route_message(jsonrpc_message).await? {
+                if let Some(response_message) = self.route_isolated(jsonrpc_message).await? {
                     let response_str = Protocol::serialize_message(&response_message)?;
                     self.send_message(&response_str).await?;
                 }
@@ -249,6 +463,53 @@ impl Session {
         Ok(())
     }
 
+    /// Route a message to the handler, answering a request whose handler
+    /// panics with an internal error
+    async fn route_isolated(&self, message: JsonRpcMessage) -> Result<Option<JsonRpcMessage>> {
+        let (method, request_id) = match &message {
+            JsonRpcMessage::Request(request) => (request.method.clone(), request.id.clone()),
+            JsonRpcMessage::Notification(notification) => (notification.method.clone(), None),
+            JsonRpcMessage::Response(_) => (String::new(), None),
+        };
+        let is_request = matches!(message, JsonRpcMessage::Request(_));
+
+        let payload = match AssertUnwindSafe(self.router.route_message(message))
+            .catch_unwind()
+            .await
+        {
+            Ok(routed) => return routed,
+            Err(payload) => payload,
+        };
+
+        let panic_message = Utils::panic_message(payload.as_ref());
+        tracing::error!(
+            session = %self.id,
+            method = %method,
+            request_id = ?request_id,
+            "Handler panicked: {}",
+            panic_message
+        );
+        let _ = self.event_sender.send(SessionEvent::Panicked {
+            method,
+            request_id: request_id.clone(),
+            message: panic_message,
+        });
+
+        if !is_request {
+            return Ok(None);
+        }
+        Ok(Some(JsonRpcMessage::Response(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: request_id,
+            result: None,
+            error: Some(Protocol::create_error(
+                error_codes::INTERNAL_ERROR,
+                &self.panic_message,
+                None,
+            )),
+        })))
+    }
+
     /// Handle incoming response
     async fn handle_response(&self, response: &JsonRpcResponse) -> Result<()> {
         if let Some(ref response_id) = response.id {
@@ -321,7 +582,12 @@ impl Session {
     }
 
     /// Close the session
+    ///
+    /// A running message loop is stopped first.
     pub async fn close(&self) -> Result<()> {
+        if self.running.load(Ordering::Acquire) {
+            self.stop();
+        }
         let mut transport = self.transport.lock().await;
         transport.close().await?;
         let _ = self.event_sender.send(SessionEvent::Disconnected);
@@ -330,6 +596,10 @@ impl Session {
 
     /// Check if transport is connected
     pub async fn is_connected(&self) -> bool {
+        // The running loop holds the transport until the connection closes
+        if self.running.load(Ordering::Acquire) {
+            return true;
+        }
         let transport = self.transport.lock().await;
         transport.is_connected()
     }
@@ -10,6 +10,7 @@ use tracing::warn;
 use url::Url;
 
 /// Comprehensive security validator for MCP operations
+#[derive(Debug, Clone)]
 pub struct SecurityValidator {
     /// Allowed URI schemes
     pub allowed_schemes: Vec<String>,
@@ -60,6 +61,8 @@ impl SecurityValidator {
     }
 
     /// Set allowed file extensions
+    ///
+    /// An entry of `*` allows every extension.
     pub fn with_allowed_extensions(mut self, extensions: Vec<String>) -> Self {
         self.allowed_extensions = extensions;
         self
@@ -117,10 +120,87 @@ impl SecurityValidator {
             }
         }
 
-        // Check file extension
+        self.validate_extension(path)
+    }
+
+    /// Resolve `path` inside the root directory and validate it
+    ///
+    /// Relative paths are resolved against the root directory, or the
+    /// current directory without one. Unlike
+    /// [`validate_file_path`](Self::validate_file_path), `..` components are
+    /// rejected rather than normalized away, symbolic links in the existing
+    /// part of the path are followed before checking that it stays inside the
+    /// root, and paths that do not exist yet are accepted, so they can name
+    /// outputs. Returns the resolved absolute path.
+    pub fn resolve_path(&self, path: &Path) -> Result<PathBuf> {
+        if path
+            .components()
+            .any(|component| component == std::path::Component::ParentDir)
+        {
+            return Err(Error::security(format!(
+                "'{}' must not contain '..' components",
+                path.display()
+            ))
+            .into());
+        }
+
+        let base = match &self.root_directory {
+            Some(root) => root.clone(),
+            None => std::env::current_dir()?,
+        };
+        let root = fs::canonicalize(&base).map_err(|e| {
+            Error::security(format!(
+                "Failed to canonicalize root directory '{}': {}",
+                base.display(),
+                e
+            ))
+        })?;
+
+        // Canonicalize the existing part so symbolic links cannot lead
+        // outside the root; the rest may not exist yet
+        let joined = root.join(path);
+        let mut existing = joined.as_path();
+        let mut missing = Vec::new();
+        let mut resolved = loop {
+            match fs::canonicalize(existing) {
+                Ok(canonical) => break canonical,
+                Err(_) => {
+                    let (Some(name), Some(parent)) = (existing.file_name(), existing.parent())
+                    else {
+                        return Err(Error::security(format!(
+                            "Cannot resolve '{}'",
+                            path.display()
+                        ))
+                        .into());
+                    };
+                    missing.push(name);
+                    existing = parent;
+                }
+            }
+        };
+        resolved.extend(missing.iter().rev());
+
+        if !resolved.starts_with(&root) {
+            return Err(Error::security(format!(
+                "'{}' is outside of allowed directory '{}'",
+                path.display(),
+                root.display()
+            ))
+            .into());
+        }
+        self.validate_extension(&resolved)?;
+        Ok(resolved)
+    }
+
+    /// Check the extension of `path` against the allowed extensions
+    fn validate_extension(&self, path: &Path) -> Result<()> {
         if let Some(extension) = path.extension() {
             let ext_str = extension.to_string_lossy().to_lowercase();
-            if !self.allowed_extensions.contains(&ext_str) {
+            if !self
+                .allowed_extensions
+                .iter()
+                .any(|allowed| allowed == "*" || *allowed == ext_str)
+            {
                 warn!(
                     "File extension '{}' is not in allowed list: {:?}",
                     ext_str, self.allowed_extensions
@@ -354,6 +434,26 @@ mod tests {
         // Test disallowed extensions
         assert!(validator.validate_file_path(Path::new("test.exe")).is_err());
         assert!(validator.validate_file_path(Path::new("test.sh")).is_err());
+
+        let validator = SecurityValidator::new().with_allowed_extensions(vec!["*".to_string()]);
+        assert!(validator.validate_file_path(Path::new("test.exe")).is_ok());
+    }
+
+    #[test]
+    fn test_security_validator_resolve_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        let validator = SecurityValidator::new().with_root_directory(root.clone());
+
+        assert_eq!(
+            validator.resolve_path(Path::new("docs/new.txt")).unwrap(),
+            root.join("docs/new.txt")
+        );
+        assert_eq!(validator.resolve_path(Path::new(".")).unwrap(), root);
+        assert!(validator.resolve_path(Path::new("docs/../a.txt")).is_err());
+        assert!(validator.resolve_path(Path::new("/etc/passwd")).is_err());
+        assert!(validator.resolve_path(Path::new("run.sh")).is_err());
     }
 
     #[tokio::test]
@@ -20,6 +20,9 @@ pub trait Transport: Send + Sync {
     async fn send(&mut self, message: &str) -> Result<()>;
 
     /// Receive a message
+    ///
+    /// Implementations should be cancellation safe: the session loop may drop
+    /// a pending receive to write an outgoing message and call it again later.
     async fn receive(&mut self) -> Result<Option<String>>;
 
     /// Close the transport
@@ -25,6 +25,8 @@ pub struct StdioTransport {
     io: StdioIO,
     child: Option<Child>,
     stats: TransportStats,
+    /// Bytes of a partially received line, kept so `receive` is cancellation safe
+    line_buffer: Vec<u8>,
 }
 
 /// Enum to handle different I/O types
@@ -50,6 +52,7 @@ impl StdioTransport {
             io: StdioIO::None,
             child: None,
             stats: TransportStats::default(),
+            line_buffer: Vec::new(),
         }
     }
 
@@ -62,6 +65,7 @@ impl StdioTransport {
             },
             child: Some(child),
             stats: TransportStats::default(),
+            line_buffer: Vec::new(),
         }
     }
 
@@ -101,6 +105,7 @@ impl StdioTransport {
             io: StdioIO::Current { stdin, stdout },
             child: None,
             stats: TransportStats::default(),
+            line_buffer: Vec::new(),
         }
     }
 
@@ -191,68 +196,53 @@ impl Transport for StdioTransport {
     async fn receive(&mut self) -> Result<Option<String>> {
         trace!("Receiving message via stdio");
 
-        match &mut self.io {
-            StdioIO::Child { stdout, .. } => {
-                let mut line = String::new();
-                match stdout.read_line(&mut line).await {
-                    Ok(0) => {
-                        // EOF - connection closed
-                        Ok(None)
-                    }
-                    Ok(_) => {
-                        // Remove trailing newline
-                        if line.ends_with('\n') {
-                            line.pop();
-                            if line.ends_with('\r') {
-                                line.pop();
-                            }
-                        }
-
-                        self.stats.messages_received += 1;
-                        self.stats.bytes_received += line.len() as u64;
-
-                        trace!("Received message: {}", line);
-                        Ok(Some(line))
-                    }
-                    Err(e) => {
-                        warn!("Failed to read from stdout: {}", e);
-                        Err(Error::Transport(TransportError::ReceiveFailed(format!(
-                            "Failed to read from stdout: {e}"
-                        ))))
-                    }
-                }
+        // `read_until` keeps partially read bytes in the buffer, so a cancelled
+        // receive resumes where it left off instead of dropping half a line.
+        let (read, source) = match &mut self.io {
+            StdioIO::Child { stdout, .. } => (
+                stdout.read_until(b'\n', &mut self.line_buffer).await,
+                "stdout",
+            ),
+            StdioIO::Current { stdin, .. } => (
+                stdin.read_until(b'\n', &mut self.line_buffer).await,
+                "stdin",
+            ),
+            StdioIO::None => return Err(Error::Transport(TransportError::NotReady)),
+        };
+
+        match read {
+            Ok(0) if self.line_buffer.is_empty() => {
+                // EOF - connection closed
+                Ok(None)
             }
-            StdioIO::Current { stdin, .. } => {
-                let mut line = String::new();
-                match stdin.read_line(&mut line).await {
-                    Ok(0) => {
-                        // EOF - connection closed
-                        Ok(None)
-                    }
-                    Ok(_) => {
-                        // Remove trailing newline
-                        if line.ends_with('\n') {
-                            line.pop();
-                            if line.ends_with('\r') {
-                                line.pop();
-                            }
-                        }
-
-                        self.stats.messages_received += 1;
-                        self.stats.bytes_received += line.len() as u64;
-
-                        trace!("Received message: {}", line);
-                        Ok(Some(line))
-                    }
-                    Err(e) => {
-                        warn!("Failed to read from stdin: {}", e);
-                        Err(Error::Transport(TransportError::ReceiveFailed(format!(
-                            "Failed to read from stdin: {e}"
-                        ))))
+            Ok(_) => {
+                let bytes = std::mem::take(&mut self.line_buffer);
+                let mut line = String::from_utf8(bytes).map_err(|e| {
+                    Error::Transport(TransportError::ReceiveFailed(format!(
+                        "Invalid UTF-8 read from {source}: {e}"
+                    )))
+                })?;
+
+                // Remove trailing newline
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
                     }
                 }
+
+                self.stats.messages_received += 1;
+                self.stats.bytes_received += line.len() as u64;
+
+                trace!("Received message: {}", line);
+                Ok(Some(line))
+            }
+            Err(e) => {
+                warn!("Failed to read from {}: {}", source, e);
+                Err(Error::Transport(TransportError::ReceiveFailed(format!(
+                    "Failed to read from {source}: {e}"
+                ))))
             }
-            StdioIO::None => Err(Error::Transport(TransportError::NotReady)),
         }
     }
 
@@ -4,25 +4,193 @@ use super::*;
 use crate::error::TransportError;
 use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ClientConfig, RootCertStore};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::{
+    self, Message,
+    client::IntoClientRequest,
+    http::{HeaderName, HeaderValue},
+};
+use tokio_tungstenite::{
+    Connector, MaybeTlsStream, WebSocketStream, connect_async_tls_with_config,
+};
 use tracing::{debug, error, trace};
 
+/// Connection settings for a [`WebSocketTransport`]
+///
+/// By default `wss://` servers must present a certificate issued by one of
+/// the publicly trusted authorities bundled with the crate. Servers using a
+/// private authority, or a self-signed certificate, can be trusted with
+/// [`with_root_certificates`](Self::with_root_certificates).
+///
+/// # Examples
+///
+/// ```rust
+/// use mocopr_core::transport::websocket::WebSocketOptions;
+///
+/// let options = WebSocketOptions::new()
+///     .with_root_certificates("certs/ca.pem")
+///     .with_client_certificate("certs/client.pem", "certs/client.key");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketOptions {
+    headers: Vec<(String, String)>,
+    root_certificates: Vec<PathBuf>,
+    client_certificate: Option<(PathBuf, PathBuf)>,
+}
+
+impl WebSocketOptions {
+    /// Create options with no extra headers and the bundled root certificates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send an extra header with the upgrade request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trust the authorities in the PEM file `path` for `wss://` servers
+    ///
+    /// Once any file is given, only the authorities in the given files are
+    /// trusted, not the bundled ones.
+    pub fn with_root_certificates(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificates.push(path.into());
+        self
+    }
+
+    /// Present the certificate chain in the PEM file `cert`, with the private
+    /// key in the PEM file `key`, to servers asking for a client certificate
+    pub fn with_client_certificate(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_certificate = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Get the extra headers sent with the upgrade request
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Create the TLS connector, or `None` for the default one
+    fn connector(&self) -> Result<Option<Connector>> {
+        if self.root_certificates.is_empty() && self.client_certificate.is_none() {
+            return Ok(None);
+        }
+
+        let mut roots = RootCertStore::empty();
+        if self.root_certificates.is_empty() {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for path in &self.root_certificates {
+            for cert in load_certificates(path)? {
+                roots.add(cert).map_err(|e| {
+                    TransportError::InvalidConfiguration(format!(
+                        "Invalid root certificate in '{}': {e}",
+                        path.display()
+                    ))
+                })?;
+            }
+        }
+
+        let builder =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|e| {
+                    TransportError::InvalidConfiguration(format!("Invalid TLS settings: {e}"))
+                })?
+                .with_root_certificates(roots);
+        let config = match &self.client_certificate {
+            Some((cert, key)) => {
+                let chain = load_certificates(cert)?;
+                let key = PrivateKeyDer::from_pem_file(key).map_err(|e| {
+                    TransportError::InvalidConfiguration(format!(
+                        "Failed to read private key from '{}': {e}",
+                        key.display()
+                    ))
+                })?;
+                builder.with_client_auth_cert(chain, key).map_err(|e| {
+                    TransportError::InvalidConfiguration(format!(
+                        "Invalid client certificate '{}': {e}",
+                        cert.display()
+                    ))
+                })?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        Ok(Some(Connector::Rustls(Arc::new(config))))
+    }
+}
+
+/// Read every certificate in the PEM file at `path`
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|e| {
+            TransportError::InvalidConfiguration(format!(
+                "Failed to read certificates from '{}': {e}",
+                path.display()
+            ))
+        })?;
+    if certs.is_empty() {
+        return Err(TransportError::InvalidConfiguration(format!(
+            "No certificates found in '{}'",
+            path.display()
+        ))
+        .into());
+    }
+    Ok(certs)
+}
+
 /// WebSocket transport for MCP communication
 pub struct WebSocketTransport {
     sink: Option<SplitSink<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>, Message>>,
     stream: Option<SplitStream<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>>>,
     url: String,
+    headers: Vec<(String, String)>,
+    connector: Option<Connector>,
     stats: TransportStats,
 }
 
 impl WebSocketTransport {
     /// Create a new WebSocket transport
     pub async fn new(url: &str) -> Result<Self> {
-        let (ws_stream, _) = connect_async(url).await.map_err(|e| {
-            TransportError::ConnectionFailed(format!("Failed to connect to WebSocket: {e}"))
-        })?;
+        Self::with_options(url, WebSocketOptions::new()).await
+    }
+
+    /// Create a new WebSocket transport sending extra headers with the upgrade request
+    ///
+    /// The headers are sent again on [`reconnect`](Self::reconnect). Use this
+    /// to pass credentials such as `Authorization: Bearer <token>`. A server
+    /// rejecting the upgrade with `401` or `403` fails with
+    /// [`TransportError::AuthenticationFailed`], carrying the status and the
+    /// `WWW-Authenticate` challenge as `{"status", "wwwAuthenticate"}` in
+    /// [`Error::data`].
+    pub async fn with_headers(url: &str, headers: Vec<(String, String)>) -> Result<Self> {
+        let options = headers
+            .into_iter()
+            .fold(WebSocketOptions::new(), |options, (name, value)| {
+                options.with_header(name, value)
+            });
+        Self::with_options(url, options).await
+    }
 
-        let (sink, stream) = ws_stream.split();
+    /// Create a new WebSocket transport with the given connection settings
+    ///
+    /// Use this to trust a private authority for `wss://` or to present a
+    /// client certificate; see [`WebSocketOptions`]. Certificate files that
+    /// cannot be read fail with [`TransportError::InvalidConfiguration`].
+    pub async fn with_options(url: &str, options: WebSocketOptions) -> Result<Self> {
+        let connector = options.connector()?;
+        let headers = options.headers;
+        let (sink, stream) = Self::open(url, &headers, connector.clone()).await?;
 
         let stats = TransportStats {
             connection_time: Some(chrono::Utc::now()),
@@ -33,10 +201,62 @@ impl WebSocketTransport {
             sink: Some(sink),
             stream: Some(stream),
             url: url.to_string(),
+            headers,
+            connector,
             stats,
         })
     }
 
+    /// Perform the WebSocket handshake
+    async fn open(
+        url: &str,
+        headers: &[(String, String)],
+        connector: Option<Connector>,
+    ) -> Result<(
+        SplitSink<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>, Message>,
+        SplitStream<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>>,
+    )> {
+        let mut request = url.into_client_request().map_err(|e| {
+            TransportError::InvalidConfiguration(format!("Invalid WebSocket URL: {e}"))
+        })?;
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                TransportError::InvalidConfiguration(format!("Invalid header name: {e}"))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                TransportError::InvalidConfiguration(format!("Invalid header value: {e}"))
+            })?;
+            request.headers_mut().insert(name, value);
+        }
+
+        let (ws_stream, _) = connect_async_tls_with_config(request, None, false, connector)
+            .await
+            .map_err(|e| match e {
+                tungstenite::Error::Http(response)
+                    if matches!(response.status().as_u16(), 401 | 403) =>
+                {
+                    let challenge = response
+                        .headers()
+                        .get("www-authenticate")
+                        .and_then(|value| value.to_str().ok());
+                    Error::from(TransportError::AuthenticationFailed(format!(
+                        "WebSocket upgrade rejected with status {}",
+                        response.status()
+                    )))
+                    .with_data(serde_json::json!({
+                        "status": response.status().as_u16(),
+                        "wwwAuthenticate": challenge,
+                    }))
+                }
+                e => {
+                    TransportError::ConnectionFailed(format!("Failed to connect to WebSocket: {e}"))
+                        .into()
+                }
+            })?;
+
+        Ok(ws_stream.split())
+    }
+
     /// Get the WebSocket URL
     pub fn url(&self) -> &str {
         &self.url
@@ -53,11 +273,7 @@ impl WebSocketTransport {
 
         self.close().await?;
 
-        let (ws_stream, _) = connect_async(&self.url).await.map_err(|e| {
-            TransportError::ConnectionFailed(format!("Failed to reconnect to WebSocket: {e}"))
-        })?;
-
-        let (sink, stream) = ws_stream.split();
+        let (sink, stream) = Self::open(&self.url, &self.headers, self.connector.clone()).await?;
 
         self.sink = Some(sink);
         self.stream = Some(stream);
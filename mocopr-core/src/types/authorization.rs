@@ -0,0 +1,80 @@
+//! Authorization-related types (OAuth 2.1 discovery)
+
+use super::*;
+
+/// Path under which protected resource metadata is published
+pub const PROTECTED_RESOURCE_METADATA_PATH: &str = "/.well-known/oauth-protected-resource";
+
+/// OAuth 2.0 protected resource metadata (RFC 9728)
+///
+/// Served by an MCP server so clients can discover which authorization
+/// servers issue tokens for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedResourceMetadata {
+    /// Identifier of the protected resource, the URL clients connect to
+    pub resource: String,
+    /// Issuer identifiers of the authorization servers that issue tokens for the resource
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authorization_servers: Vec<String>,
+    /// Scopes used in authorization requests for the resource
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes_supported: Vec<String>,
+    /// Ways of presenting bearer tokens, e.g. `header`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bearer_methods_supported: Vec<String>,
+    /// Human-readable name of the resource
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_name: Option<String>,
+    /// URL of documentation for developers using the resource
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_documentation: Option<String>,
+}
+
+impl ProtectedResourceMetadata {
+    /// Create metadata for the resource at `resource`
+    pub fn new(resource: impl Into<String>) -> Self {
+        Self {
+            resource: resource.into(),
+            authorization_servers: Vec::new(),
+            scopes_supported: Vec::new(),
+            bearer_methods_supported: vec!["header".to_string()],
+            resource_name: None,
+            resource_documentation: None,
+        }
+    }
+}
+
+/// Path under which authorization server metadata is published
+pub const AUTHORIZATION_SERVER_METADATA_PATH: &str = "/.well-known/oauth-authorization-server";
+
+/// OAuth 2.0 authorization server metadata (RFC 8414)
+///
+/// Clients fetch it to find the endpoints of an authorization server listed
+/// in [`ProtectedResourceMetadata::authorization_servers`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    /// Issuer identifier of the authorization server
+    pub issuer: String,
+    /// URL of the authorization endpoint
+    pub authorization_endpoint: String,
+    /// URL of the token endpoint
+    pub token_endpoint: String,
+    /// URL of the dynamic client registration endpoint (RFC 7591)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_endpoint: Option<String>,
+    /// URL of the authorization server's JWK set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks_uri: Option<String>,
+    /// Scopes the authorization server supports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes_supported: Vec<String>,
+    /// Supported `response_type` values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_types_supported: Vec<String>,
+    /// Supported grant types
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grant_types_supported: Vec<String>,
+    /// Supported PKCE code challenge methods, e.g. `S256`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub code_challenge_methods_supported: Vec<String>,
+}
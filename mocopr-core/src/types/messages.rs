@@ -89,6 +89,9 @@ pub struct ProgressNotification {
     /// Optional total value for progress calculation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total: Option<f64>,
+    /// Optional human-readable description of the current progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 /// Log message levels
@@ -137,6 +140,9 @@ pub enum McpMessage {
     /// Read a specific resource
     #[serde(rename = "resources/read")]
     ResourcesRead(ResourcesReadRequest),
+    /// List available resource templates
+    #[serde(rename = "resources/templates/list")]
+    ResourcesTemplatesList(ResourcesTemplatesListRequest),
     /// Subscribe to a resource for updates
     #[serde(rename = "resources/subscribe")]
     ResourcesSubscribe(ResourcesSubscribeRequest),
@@ -211,6 +217,8 @@ pub enum McpResponse {
     ResourcesList(ResourcesListResponse),
     /// Response to resources read request
     ResourcesRead(ResourcesReadResponse),
+    /// Response to resources templates list request
+    ResourcesTemplatesList(ResourcesTemplatesListResponse),
     /// Response to resources subscribe request
     ResourcesSubscribe(ResourcesSubscribeResponse),
     /// Response to resources unsubscribe request
@@ -218,7 +226,7 @@ pub enum McpResponse {
     /// Response to tools list request
     ToolsList(ToolsListResponse),
     /// Response to tools call request
-    ToolsCall(ToolsCallResponse),
+    ToolsCall(Box<ToolsCallResponse>),
     /// Response to prompts list request
     PromptsList(PromptsListResponse),
     /// Response to prompts get request
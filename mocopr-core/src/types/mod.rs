@@ -8,6 +8,7 @@ use std::collections::HashMap;
 use url::Url;
 use uuid::Uuid;
 
+pub mod authorization;
 pub mod capabilities;
 pub mod messages;
 pub mod prompts;
@@ -16,6 +17,7 @@ pub mod roots;
 pub mod sampling;
 pub mod tools;
 
+pub use authorization::*;
 pub use capabilities::*;
 pub use messages::*;
 pub use prompts::*;
@@ -119,7 +121,7 @@ impl std::fmt::Display for RequestId {
 }
 
 /// Progress token for tracking long-running operations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ProgressToken {
     /// String-based progress token
@@ -323,6 +325,89 @@ impl ImageContent {
     }
 }
 
+/// A resource embedded directly in a message.
+///
+/// Lets a prompt message or tool result carry the contents of a resource
+/// inline, so the client does not have to read it separately. Exactly one of
+/// `text` or `blob` is expected to be set.
+///
+/// # Examples
+///
+/// ```rust
+/// use mocopr_core::types::EmbeddedResource;
+///
+/// let uri = "file:///notes/todo.md".parse().unwrap();
+/// let resource = EmbeddedResource::text(uri, "- write docs").with_mime_type("text/markdown");
+/// assert_eq!(resource.resource.text.as_deref(), Some("- write docs"));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedResource {
+    /// The embedded resource contents
+    pub resource: EmbeddedResourceContents,
+    /// Optional annotations for the resource
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<Annotation>>,
+}
+
+/// Contents of an [`EmbeddedResource`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedResourceContents {
+    /// URI of the resource
+    pub uri: Url,
+    /// MIME type of the resource
+    #[serde(rename = "mimeType")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Text of the resource, for textual resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64 encoded data of the resource, for binary resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+impl EmbeddedResource {
+    /// Creates an embedded textual resource
+    ///
+    /// # Arguments
+    /// * `uri` - URI of the resource
+    /// * `text` - Text of the resource
+    pub fn text(uri: Url, text: impl Into<String>) -> Self {
+        Self {
+            resource: EmbeddedResourceContents {
+                uri,
+                mime_type: None,
+                text: Some(text.into()),
+                blob: None,
+            },
+            annotations: None,
+        }
+    }
+
+    /// Creates an embedded binary resource
+    ///
+    /// # Arguments
+    /// * `uri` - URI of the resource
+    /// * `blob` - Base64 encoded data of the resource
+    pub fn blob(uri: Url, blob: impl Into<String>) -> Self {
+        Self {
+            resource: EmbeddedResourceContents {
+                uri,
+                mime_type: None,
+                text: None,
+                blob: Some(blob.into()),
+            },
+            annotations: None,
+        }
+    }
+
+    /// Sets the MIME type of the resource
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.resource.mime_type = Some(mime_type.into());
+        self
+    }
+}
+
 /// Content types that can be sent in messages or included in resources.
 ///
 /// The MCP specification supports multiple content types to handle different
@@ -332,6 +417,7 @@ impl ImageContent {
 ///
 /// - `Text`: Plain text content, potentially with annotations
 /// - `Image`: Image content in base64-encoded format with a specific MIME type
+/// - `Resource`: A resource embedded in the message with its contents
 ///
 /// # MCP Specification Compliance
 ///
@@ -380,6 +466,14 @@ pub enum Content {
     /// other visual elements that need to be included in messages or resources.
     #[serde(rename = "image")]
     Image(ImageContent),
+
+    /// Embedded resource variant, containing the URI and contents of a
+    /// resource.
+    ///
+    /// This content type is used to include a resource inline in a prompt
+    /// message or tool result.
+    #[serde(rename = "resource")]
+    Resource(EmbeddedResource),
 }
 
 impl From<TextContent> for Content {
@@ -394,6 +488,12 @@ impl From<ImageContent> for Content {
     }
 }
 
+impl From<EmbeddedResource> for Content {
+    fn from(resource: EmbeddedResource) -> Self {
+        Content::Resource(resource)
+    }
+}
+
 impl From<String> for Content {
     fn from(text: String) -> Self {
         Content::Text(TextContent::new(text))
@@ -574,6 +574,124 @@ pub struct ResourcesUpdatedNotification {
     pub uri: Url,
 }
 
+/// A parameterized URI pattern describing a family of resources.
+///
+/// Resource templates correspond to the `ResourceTemplate` type in the MCP
+/// specification. Instead of enumerating every resource, a server can advertise
+/// an RFC 6570 URI template (for example `file:///srv/docs/{path}`) that clients
+/// expand and then pass to `resources/read`.
+///
+/// # Example
+///
+/// ```rust
+/// use mocopr_core::types::ResourceTemplate;
+///
+/// let template = ResourceTemplate::new("file:///srv/docs/{path}", "Documentation")
+///     .with_description("Any file below /srv/docs");
+///
+/// assert_eq!(template.uri_template, "file:///srv/docs/{path}");
+/// ```
+///
+/// # JSON Representation
+///
+/// ```json
+/// {
+///   "uriTemplate": "file:///srv/docs/{path}",
+///   "name": "Documentation",
+///   "description": "Any file below /srv/docs"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResourceTemplate {
+    /// RFC 6570 URI template used to construct resource URIs
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+
+    /// Human-readable name for the template
+    pub name: String,
+
+    /// Optional description of the resources matched by this template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// MIME type shared by all matching resources, if they share one
+    #[serde(rename = "mimeType")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Request to list the resource templates offered by a server.
+///
+/// This request corresponds to the `resources/templates/list` method in the MCP specification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesTemplatesListRequest {
+    /// Pagination parameters to control the number of templates returned
+    #[serde(flatten)]
+    pub pagination: PaginationParams,
+}
+
+/// Response returned by the `resources/templates/list` method.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesTemplatesListResponse {
+    /// The list of available resource templates
+    #[serde(rename = "resourceTemplates")]
+    pub resource_templates: Vec<ResourceTemplate>,
+
+    /// Optional pagination token for retrieving the next set of results
+    #[serde(rename = "nextCursor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+
+    /// Additional metadata associated with the response
+    #[serde(flatten)]
+    pub meta: ResponseMetadata,
+}
+
+impl ResourceTemplate {
+    /// Create a new resource template with the given URI template and name.
+    pub fn new(uri_template: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            uri_template: uri_template.into(),
+            name: name.into(),
+            description: None,
+            mime_type: None,
+        }
+    }
+
+    /// Add a description to the template.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the MIME type shared by resources matching the template.
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+}
+
+impl ResourcesTemplatesListRequest {
+    /// Create a new templates list request without a cursor.
+    pub fn new() -> Self {
+        Self {
+            pagination: PaginationParams { cursor: None },
+        }
+    }
+
+    /// Continue listing from the cursor returned with a previous page.
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.pagination.cursor = Some(cursor.into());
+        self
+    }
+}
+
+impl Default for ResourcesTemplatesListRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Resource {
     /// Create a new resource with validation.
     ///
@@ -216,6 +216,7 @@ pub struct ToolsCallRequest {
 ///         Content::Text(TextContent::new("File contents here"))
 ///     ].into(),
 ///     is_error: Some(false),
+///     structured_content: None,
 ///     meta: ResponseMetadata::default(),
 /// };
 /// ```
@@ -228,6 +229,10 @@ pub struct ToolsCallResponse {
     #[serde(rename = "isError")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    /// Machine-readable result of the tool, alongside its content.
+    #[serde(rename = "structuredContent")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<serde_json::Value>,
     /// Response metadata including protocol version and other information.
     #[serde(flatten)]
     pub meta: ResponseMetadata,
@@ -386,6 +391,7 @@ impl ToolsCallResponse {
         Self {
             content: content.into(),
             is_error: Some(false),
+            structured_content: None,
             meta: ResponseMetadata { _meta: None },
         }
     }
@@ -405,6 +411,7 @@ impl ToolsCallResponse {
         Self {
             content: content.into(),
             is_error: Some(true),
+            structured_content: None,
             meta: ResponseMetadata { _meta: None },
         }
     }
@@ -429,9 +436,31 @@ impl ToolsCallResponse {
         Self {
             content: result,
             is_error: Some(false),
+            structured_content: None,
             meta: ResponseMetadata { _meta: None },
         }
     }
+
+    /// Attach a machine-readable result to the response.
+    ///
+    /// Clients that understand structured results read it instead of parsing
+    /// the content; the content should still describe the result for those
+    /// that do not.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_core::types::{ToolsCallResponse, Content};
+    /// use serde_json::json;
+    ///
+    /// let response = ToolsCallResponse::success(vec![Content::from("42")])
+    ///     .with_structured_content(json!({"answer": 42}));
+    /// assert_eq!(response.structured_content.unwrap()["answer"], 42);
+    /// ```
+    pub fn with_structured_content(mut self, structured_content: serde_json::Value) -> Self {
+        self.structured_content = Some(structured_content);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -447,6 +447,33 @@ impl Utils {
         }
     }
 
+    /// Get the message of a caught panic.
+    ///
+    /// Panics raised with `panic!` carry either a `&str` or a `String`; any
+    /// other payload is described generically.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload returned by `catch_unwind`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_core::utils::Utils;
+    ///
+    /// let payload = std::panic::catch_unwind(|| panic!("index {} out of range", 3)).unwrap_err();
+    /// assert_eq!(Utils::panic_message(payload.as_ref()), "index 3 out of range");
+    /// ```
+    pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+        if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "non-string panic payload".to_string()
+        }
+    }
+
     /// Merge two JSON values recursively.
     ///
     /// This utility method merges the contents of one JSON value into another,
@@ -18,6 +18,7 @@ pub fn derive_tool_impl(input: DeriveInput) -> Result<TokenStream> {
     // Extract tool attributes using proper AST parsing
     let mut tool_name = None;
     let mut tool_description = None;
+    let mut limits = ExecutionLimits::default();
 
     for attr in &input.attrs {
         if attr.path().is_ident("tool") {
@@ -33,6 +34,8 @@ pub fn derive_tool_impl(input: DeriveInput) -> Result<TokenStream> {
                     let lit_str: syn::LitStr = value.parse()?;
                     tool_description = Some(lit_str.value());
                     Ok(())
+                } else if limits.parse(&meta)? {
+                    Ok(())
                 } else {
                     Err(meta.error("unsupported tool attribute"))
                 }
@@ -43,6 +46,7 @@ pub fn derive_tool_impl(input: DeriveInput) -> Result<TokenStream> {
     let default_name = name.to_string().to_lowercase();
     let tool_name_str = tool_name.as_deref().unwrap_or(&default_name);
     let tool_description_str = tool_description.as_deref().unwrap_or("Auto-generated tool");
+    let execution_policy = limits.to_tokens();
 
     let expanded = quote! {
         #[::async_trait::async_trait]
@@ -68,6 +72,8 @@ pub fn derive_tool_impl(input: DeriveInput) -> Result<TokenStream> {
                     Err(e) => Err(::mocopr_core::Error::Internal(e.to_string()))
                 }
             }
+
+            #execution_policy
         }
 
         // Compile-time assertion to ensure ToolExecutor is implemented
@@ -93,6 +99,7 @@ pub fn mcp_tool_impl(args: Meta, input: ItemFn) -> Result<TokenStream> {
     // Extract tool name and description from attributes using proper AST parsing
     let mut tool_name = fn_name.to_string();
     let mut tool_description = "Auto-generated tool".to_string();
+    let mut limits = ExecutionLimits::default();
 
     // Parse attributes using syn's built-in attribute parsing
     if let syn::Meta::List(meta_list) = args {
@@ -108,6 +115,8 @@ pub fn mcp_tool_impl(args: Meta, input: ItemFn) -> Result<TokenStream> {
                 let lit_str: syn::LitStr = value.parse()?;
                 tool_description = lit_str.value();
                 Ok(())
+            } else if limits.parse(&meta)? {
+                Ok(())
             } else {
                 Err(meta.error("unsupported tool attribute"))
             }
@@ -115,6 +124,7 @@ pub fn mcp_tool_impl(args: Meta, input: ItemFn) -> Result<TokenStream> {
     }
 
     let struct_name = syn::Ident::new(&format!("{}Tool", fn_name), fn_name.span());
+    let execution_policy = limits.to_tokens();
 
     let expanded = quote! {
         #fn_vis struct #struct_name;
@@ -146,6 +156,8 @@ pub fn mcp_tool_impl(args: Meta, input: ItemFn) -> Result<TokenStream> {
                     Err(e) => Err(::mocopr_core::Error::Internal(e.to_string()))
                 }
             }
+
+            #execution_policy
         }
 
         #[::async_trait::async_trait]
@@ -171,3 +183,53 @@ pub fn mcp_tool_impl(args: Meta, input: ItemFn) -> Result<TokenStream> {
 
     Ok(expanded)
 }
+
+/// Execution limits declared with `timeout_ms`, `max_concurrency` and `max_queue`
+#[derive(Default)]
+struct ExecutionLimits {
+    timeout_ms: Option<u64>,
+    max_concurrency: Option<usize>,
+    max_queue: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// Parse a limit attribute, returning `false` if the attribute is not a limit
+    fn parse(&mut self, meta: &syn::meta::ParseNestedMeta) -> Result<bool> {
+        if meta.path.is_ident("timeout_ms") {
+            self.timeout_ms = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("max_concurrency") {
+            self.max_concurrency = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("max_queue") {
+            self.max_queue = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Generate the `execution_policy` method, or nothing if no limits were declared
+    fn to_tokens(&self) -> TokenStream {
+        if self.timeout_ms.is_none() && self.max_concurrency.is_none() && self.max_queue.is_none() {
+            return TokenStream::new();
+        }
+
+        let timeout = self.timeout_ms.map(|ms| {
+            quote! { .with_timeout(::std::time::Duration::from_millis(#ms)) }
+        });
+        let max_concurrency = self
+            .max_concurrency
+            .map(|max| quote! { .with_max_concurrency(#max) });
+        let max_queue = self.max_queue.map(|max| quote! { .with_max_queue(#max) });
+
+        quote! {
+            fn execution_policy(&self) -> Option<::mocopr_server::ToolExecutionPolicy> {
+                Some(
+                    ::mocopr_server::ToolExecutionPolicy::new()
+                        #timeout
+                        #max_concurrency
+                        #max_queue
+                )
+            }
+        }
+    }
+}
@@ -65,6 +65,7 @@
 pub mod config;
 pub mod context;
 pub mod error;
+pub mod manifest;
 pub mod middleware;
 pub mod permissions;
 pub mod subjects;
@@ -0,0 +1,60 @@
+//! Server manifests with role-based access control
+//!
+//! [`McpServerBuilder::from_manifest`] cannot apply the `[middleware.rbac]`
+//! section of a manifest because this crate builds on `mocopr-server`.
+//! [`builder_from_manifest`] loads such manifests: it configures the server
+//! as the manifest describes and adds [`RbacMiddleware`] built from the RBAC
+//! configuration file the manifest names, after the other middleware.
+//! With `audit_log` set, access decisions are also written to a
+//! [`JsonlAuditSink`], rotated at `audit_log_max_bytes` when given.
+//!
+//! ```toml
+//! [middleware.rbac]
+//! config = "rbac.json"
+//! audit_log = "audit/access.jsonl"
+//! audit_log_max_bytes = 10485760
+//! ```
+
+use crate::error::RbacError;
+use crate::middleware::RbacMiddleware;
+use mocopr_core::{Error, Result};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::audit::JsonlAuditSink;
+use mocopr_server::manifest::ServerManifest;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Create a server builder from a manifest that may configure RBAC
+pub async fn builder_from_manifest(path: impl AsRef<Path>) -> Result<McpServerBuilder> {
+    let mut manifest = ServerManifest::from_file(path)?;
+    let rbac = manifest.middleware.rbac.take();
+    let config = rbac.as_ref().map(|rbac| manifest.resolve(&rbac.config));
+    let audit_log = rbac
+        .as_ref()
+        .and_then(|rbac| rbac.audit_log.as_ref())
+        .map(|log| manifest.resolve(log));
+    let builder = manifest.into_builder()?;
+
+    let Some(config) = config else {
+        return Ok(builder);
+    };
+    let load_error = |e: RbacError| {
+        Error::Configuration(format!(
+            "Failed to load RBAC configuration '{}': {}",
+            config.display(),
+            e
+        ))
+    };
+    let mut middleware = RbacMiddleware::builder()
+        .with_config_file(&config.to_string_lossy())
+        .map_err(load_error)?;
+    if let Some(audit_log) = audit_log {
+        let mut sink = JsonlAuditSink::open(&audit_log)?;
+        if let Some(max_bytes) = rbac.and_then(|rbac| rbac.audit_log_max_bytes) {
+            sink = sink.with_max_bytes(max_bytes);
+        }
+        middleware = middleware.with_audit_sink(Arc::new(sink));
+    }
+    let middleware = middleware.build().await.map_err(load_error)?;
+    Ok(builder.with_middleware(middleware))
+}
@@ -2,19 +2,34 @@
 
 use crate::prelude::*;
 use async_trait::async_trait;
+use chrono::Utc;
 use mocopr_core::prelude::*;
+use mocopr_server::audit::{AuditDecision, AuditOutcome, AuditRecord, AuditSink, hash_arguments};
+use mocopr_server::context::Principal;
 use mocopr_server::middleware::Middleware;
 use role_system::async_support::{AsyncRoleSystem, AsyncRoleSystemBuilder};
 use role_system::storage::MemoryStorage;
 use role_system::{Permission, Resource, Role, Subject as RoleSubject};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
 // Use fully qualified Result to avoid ambiguity
 type RbacResult<T> = std::result::Result<T, RbacError>;
 
+/// Inheritance of the roles registered by
+/// [`RbacMiddlewareBuilder::with_default_roles`], as (role, parent) pairs
+const DEFAULT_ROLE_INHERITANCE: [(&str, &str); 3] = [
+    ("user", "guest"),
+    ("power_user", "user"),
+    ("admin", "power_user"),
+];
+
+/// Prefix of subject IDs that are never accepted from clients or tokens
+const RESERVED_SUBJECT_PREFIX: &str = "role:";
+
 /// Parsed permission components
 #[derive(Debug, Clone)]
 struct ParsedPermission {
@@ -23,13 +38,56 @@ struct ParsedPermission {
     pattern: String,
 }
 
+/// Audit record of an allowed request, waiting for its outcome
+struct PendingAudit {
+    record: AuditRecord,
+    started: Instant,
+}
+
+/// Allowed requests of a session waiting for their outcome, by request ID
+#[derive(Clone, Default)]
+struct PendingAudits(Arc<Mutex<HashMap<String, PendingAudit>>>);
+
+impl PendingAudits {
+    /// Get the pending requests of the current session, or `fallback` outside
+    /// of a session
+    fn current(fallback: &Self) -> Self {
+        Extensions::current()
+            .map(|extensions| extensions.get_or_insert_with(Self::default))
+            .unwrap_or_else(|| fallback.clone())
+    }
+
+    fn insert(&self, id: &RequestId, pending: PendingAudit) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(audit_key(id), pending);
+    }
+
+    fn remove(&self, id: Option<&RequestId>) -> Option<PendingAudit> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&audit_key(id?))
+    }
+}
+
+/// Key telling numeric and string request IDs apart
+fn audit_key(id: &RequestId) -> String {
+    serde_json::to_string(id).unwrap_or_default()
+}
+
 /// RBAC middleware for MCP servers using the role-system crate
 pub struct RbacMiddleware {
     role_system: Arc<AsyncRoleSystem<MemoryStorage>>,
     context_extractor: Box<dyn ContextExtractor + Send + Sync>,
     audit_enabled: bool,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    pending_audits: PendingAudits,
+    message_subjects: bool,
     // Store patterns separately for pattern matching
     role_patterns: Arc<HashMap<String, Vec<String>>>, // role_name -> list of pattern permissions
+    role_parents: Arc<HashMap<String, Vec<String>>>,  // role_name -> roles it inherits from
 }
 
 impl RbacMiddleware {
@@ -38,6 +96,11 @@ impl RbacMiddleware {
         RbacMiddlewareBuilder::new()
     }
 
+    /// Create RBAC middleware from a JSON configuration file
+    pub async fn from_config_file(path: &str) -> RbacResult<Self> {
+        Self::builder().with_config_file(path)?.build().await
+    }
+
     /// Check if a subject has permission for a specific action on a resource
     pub async fn check_permission(
         &self,
@@ -142,6 +205,60 @@ impl RbacMiddleware {
         Ok(false)
     }
 
+    /// Check whether a pattern permission of the role matches the request
+    fn role_patterns_match(&self, role: &str, action: &str, resource: &MocoPrResource) -> bool {
+        let Some(patterns) = self.role_patterns.get(role) else {
+            return false;
+        };
+        patterns.iter().any(|pattern| {
+            self.parse_permission_string(pattern).is_ok_and(|parsed| {
+                parsed.action == action
+                    && parsed.resource_type == resource.resource_type
+                    && self.matches_pattern(&parsed.pattern, &resource.id)
+            })
+        })
+    }
+
+    /// Check whether any of the roles, or a role they inherit from, grants
+    /// the action on the resource
+    ///
+    /// Used for roles granted by the principal's token, which are not
+    /// assigned to any subject.
+    async fn check_role_permission(
+        &self,
+        roles: &[String],
+        action: &str,
+        resource: &MocoPrResource,
+        context: &HashMap<String, String>,
+    ) -> RbacResult<bool> {
+        let mut pending = roles.to_vec();
+        let mut checked = HashSet::new();
+
+        while let Some(role_name) = pending.pop() {
+            if !checked.insert(role_name.clone()) {
+                continue;
+            }
+
+            let role = self
+                .role_system
+                .get_role(&role_name)
+                .await
+                .map_err(|e| RbacError::PermissionCheck(e.to_string()))?;
+            if role
+                .is_some_and(|role| role.has_permission(action, &resource.resource_type, context))
+                || self.role_patterns_match(&role_name, action, resource)
+            {
+                return Ok(true);
+            }
+
+            if let Some(parents) = self.role_parents.get(&role_name) {
+                pending.extend(parents.iter().cloned());
+            }
+        }
+
+        Ok(false)
+    }
+
     /// Parse a permission string into components
     fn parse_permission_string(&self, perm_str: &str) -> RbacResult<ParsedPermission> {
         let parts: Vec<&str> = perm_str.split(':').collect();
@@ -188,9 +305,22 @@ impl RbacMiddleware {
     }
 
     /// Extract the subject from the request
+    ///
+    /// The principal authenticated at the transport takes precedence over
+    /// `params.auth.subject_id`, which is only read when
+    /// [`with_message_subjects`](RbacMiddlewareBuilder::with_message_subjects)
+    /// is enabled.
     fn extract_subject(&self, request: &JsonRpcRequest) -> RbacResult<MocoPrSubject> {
+        if let Some(principal) = Principal::current() {
+            return Ok(MocoPrSubject {
+                id: principal.subject().to_string(),
+                subject_type: SubjectType::User,
+            });
+        }
+
         // Try to extract subject from auth parameters
-        if let Some(params) = &request.params
+        if self.message_subjects
+            && let Some(params) = &request.params
             && let Some(auth) = params.get("auth")
             && let Some(subject_id) = auth.get("subject_id")
             && let Some(id) = subject_id.as_str()
@@ -217,6 +347,13 @@ impl RbacMiddleware {
         })
     }
 
+    /// Extract the roles granted to the session's principal
+    fn extract_roles(&self) -> Vec<String> {
+        Principal::current()
+            .map(|principal| principal.roles().to_vec())
+            .unwrap_or_default()
+    }
+
     /// Extract the resource being accessed from the request
     fn extract_resource(&self, request: &JsonRpcRequest) -> RbacResult<MocoPrResource> {
         match request.method.as_str() {
@@ -306,11 +443,92 @@ impl RbacMiddleware {
             _ => "unknown",
         }
     }
-}
 
-#[async_trait]
-impl Middleware for RbacMiddleware {
-    async fn before_request(&self, request: &JsonRpcRequest) -> mocopr_core::Result<()> {
+    /// Tool, resource or prompt named by the request parameters
+    fn audit_target(&self, request: &JsonRpcRequest) -> Option<String> {
+        let params = request.params.as_ref()?;
+        params
+            .get("name")
+            .or_else(|| params.get("uri"))
+            .and_then(|target| target.as_str())
+            .map(str::to_string)
+    }
+
+    /// Audit record of the access decision on `request`
+    fn audit_record(
+        &self,
+        request: &JsonRpcRequest,
+        decision: &mocopr_core::Result<()>,
+    ) -> AuditRecord {
+        // Only the transport principal is trusted; subjects named in the
+        // request are recorded as claims
+        let principal = Principal::current()
+            .map(|principal| principal.subject().to_string())
+            .unwrap_or_else(|| "anonymous".to_string());
+        let claimed_subject = request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("auth")?.get("subject_id")?.as_str())
+            .map(str::to_string);
+        let (decision, outcome, error) = match decision {
+            Ok(()) => (AuditDecision::Allowed, AuditOutcome::Success, None),
+            Err(e) => (
+                AuditDecision::Denied,
+                AuditOutcome::Rejected,
+                Some(e.to_string()),
+            ),
+        };
+        AuditRecord {
+            timestamp: Utc::now(),
+            principal,
+            claimed_subject,
+            method: request.method.clone(),
+            target: self.audit_target(request),
+            request_id: request.id.clone(),
+            arguments_hash: request.params.as_ref().map(hash_arguments),
+            decision,
+            outcome,
+            error,
+            latency_us: 0,
+        }
+    }
+
+    /// Write an audit record, logging failures
+    async fn write_audit(
+        &self,
+        sink: &Arc<dyn AuditSink>,
+        mut record: AuditRecord,
+        started: Instant,
+    ) {
+        record.latency_us = started.elapsed().as_micros() as u64;
+        if let Err(e) = sink.record(record).await {
+            error!("Failed to write audit record: {}", e);
+        }
+    }
+
+    /// Write the audit record of an allowed request once it has ended
+    async fn finish_audit(
+        &self,
+        request: &JsonRpcRequest,
+        outcome: AuditOutcome,
+        error: Option<String>,
+    ) {
+        let Some(sink) = &self.audit_sink else {
+            return;
+        };
+        let Some(pending) =
+            PendingAudits::current(&self.pending_audits).remove(request.id.as_ref())
+        else {
+            return;
+        };
+        let mut record = pending.record;
+        record.outcome = outcome;
+        record.error = error;
+        self.write_audit(sink, record, pending.started).await;
+    }
+
+    /// Decide whether the request's subject may perform it
+    async fn authorize(&self, request: &JsonRpcRequest) -> mocopr_core::Result<()> {
         debug!("RBAC middleware checking request: {}", request.method);
 
         // Extract request components
@@ -322,6 +540,13 @@ impl Middleware for RbacMiddleware {
             mocopr_core::Error::Protocol(mocopr_core::error::ProtocolError::PermissionDenied)
         })?;
 
+        if subject.id.starts_with(RESERVED_SUBJECT_PREFIX) {
+            warn!(subject = %subject.id, "Rejected reserved subject ID");
+            return Err(mocopr_core::Error::Protocol(
+                mocopr_core::error::ProtocolError::PermissionDenied,
+            ));
+        }
+
         let action = self.extract_action(request);
 
         // Extract context
@@ -334,13 +559,26 @@ impl Middleware for RbacMiddleware {
             })?;
 
         // Check permission
-        let has_permission = self
+        let mut has_permission = self
             .check_permission(&subject, action, &resource, &context)
             .await
             .map_err(|_e| {
                 mocopr_core::Error::Protocol(mocopr_core::error::ProtocolError::PermissionDenied)
             })?;
 
+        // Roles granted by the principal's token act like assignments
+        let roles = self.extract_roles();
+        if !has_permission && !roles.is_empty() {
+            has_permission = self
+                .check_role_permission(&roles, action, &resource, &context)
+                .await
+                .map_err(|_e| {
+                    mocopr_core::Error::Protocol(
+                        mocopr_core::error::ProtocolError::PermissionDenied,
+                    )
+                })?;
+        }
+
         if !has_permission {
             error!(
                 subject = %subject.id,
@@ -362,20 +600,56 @@ impl Middleware for RbacMiddleware {
 
         Ok(())
     }
+}
+
+#[async_trait]
+impl Middleware for RbacMiddleware {
+    async fn before_request(&self, request: &JsonRpcRequest) -> mocopr_core::Result<()> {
+        let started = Instant::now();
+        let decision = self.authorize(request).await;
+
+        if let Some(sink) = &self.audit_sink {
+            let record = self.audit_record(request, &decision);
+            match (&decision, &request.id) {
+                (Ok(()), Some(id)) => PendingAudits::current(&self.pending_audits)
+                    .insert(id, PendingAudit { record, started }),
+                _ => self.write_audit(sink, record, started).await,
+            }
+        }
+        decision
+    }
 
     async fn after_response(
         &self,
-        _request: &JsonRpcRequest,
-        _response: &JsonRpcResponse,
+        request: &JsonRpcRequest,
+        response: &JsonRpcResponse,
     ) -> mocopr_core::Result<()> {
+        match &response.error {
+            Some(error) => {
+                self.finish_audit(request, AuditOutcome::Error, Some(error.message.clone()))
+                    .await
+            }
+            None => {
+                self.finish_audit(request, AuditOutcome::Success, None)
+                    .await
+            }
+        }
         Ok(())
     }
 
     async fn on_error(
         &self,
-        _request: &JsonRpcRequest,
-        _error: &mocopr_core::Error,
+        request: &JsonRpcRequest,
+        error: &mocopr_core::Error,
     ) -> mocopr_core::Result<()> {
+        self.finish_audit(request, AuditOutcome::Error, Some(error.to_string()))
+            .await;
+        Ok(())
+    }
+
+    async fn on_cancel(&self, request: &JsonRpcRequest) -> mocopr_core::Result<()> {
+        self.finish_audit(request, AuditOutcome::Cancelled, None)
+            .await;
         Ok(())
     }
 }
@@ -383,20 +657,28 @@ impl Middleware for RbacMiddleware {
 /// Builder for RBAC middleware
 pub struct RbacMiddlewareBuilder {
     roles: Vec<(String, Vec<String>)>,
+    inheritance: Vec<(String, String)>,
+    assignments: Vec<(String, String)>,
     conditional_permissions: Vec<ConditionalPermissionConfig>,
     context_extractor: Option<Box<dyn ContextExtractor + Send + Sync>>,
     audit_enabled: bool,
+    audit_sink: Option<Arc<dyn AuditSink>>,
     default_roles: bool,
+    message_subjects: bool,
 }
 
 impl RbacMiddlewareBuilder {
     pub fn new() -> Self {
         Self {
             roles: Vec::new(),
+            inheritance: Vec::new(),
+            assignments: Vec::new(),
             conditional_permissions: Vec::new(),
             context_extractor: None,
             audit_enabled: false,
+            audit_sink: None,
             default_roles: false,
+            message_subjects: false,
         }
     }
 
@@ -409,6 +691,63 @@ impl RbacMiddlewareBuilder {
         self
     }
 
+    /// Make `role_name` inherit the permissions of `parent`
+    pub fn with_role_inheritance(mut self, role_name: &str, parent: &str) -> Self {
+        self.inheritance
+            .push((role_name.to_string(), parent.to_string()));
+        self
+    }
+
+    /// Assign a role to the subject with the given ID
+    pub fn with_assignment(mut self, subject_id: &str, role_name: &str) -> Self {
+        self.assignments
+            .push((subject_id.to_string(), role_name.to_string()));
+        self
+    }
+
+    /// Apply the roles, inheritance and assignments of an [`RbacConfig`]
+    ///
+    /// Conditional permissions in the configuration are written as
+    /// expressions that cannot be evaluated yet; they are skipped with a
+    /// warning, so the permissions they would grant stay denied. Use
+    /// [`with_conditional_permission`](Self::with_conditional_permission)
+    /// for those instead.
+    pub fn with_config(mut self, config: &RbacConfig) -> Self {
+        self.default_roles |= config.default_roles;
+        self.audit_enabled |= config.audit_enabled;
+
+        for role in &config.roles {
+            self.roles
+                .push((role.name.clone(), role.permissions.clone()));
+            for parent in &role.inherits_from {
+                self.inheritance.push((role.name.clone(), parent.clone()));
+            }
+            for conditional in &role.conditional_permissions {
+                warn!(
+                    "Skipping conditional permission '{}' of role '{}': expression conditions are not supported",
+                    conditional.permission, role.name
+                );
+            }
+        }
+
+        for assignment in &config.assignments {
+            for role_name in &assignment.roles {
+                self.assignments
+                    .push((assignment.subject_id.clone(), role_name.clone()));
+            }
+        }
+        self
+    }
+
+    /// Load, validate and apply a JSON configuration file
+    ///
+    /// See [`with_config`](Self::with_config).
+    pub fn with_config_file(self, path: &str) -> RbacResult<Self> {
+        let config = RbacConfig::from_file(path)?;
+        config.validate()?;
+        Ok(self.with_config(&config))
+    }
+
     /// Add default MCP roles
     pub fn with_default_roles(mut self) -> Self {
         self.default_roles = true;
@@ -440,6 +779,29 @@ impl RbacMiddlewareBuilder {
         self
     }
 
+    /// Record every access decision, with the request's outcome and
+    /// latency, in `sink`
+    ///
+    /// Requests without an ID are recorded when they are allowed. Failures to
+    /// write a record are logged and do not fail the request.
+    pub fn with_audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Take the subject of requests without a transport principal from
+    /// `params.auth.subject_id` and `params.auth.subject_type`
+    ///
+    /// **Warning:** this lets any client claim any subject, and with it that
+    /// subject's roles, simply by naming it in a request. Only enable it when
+    /// every client is trusted to identify itself honestly, for example in
+    /// tests or behind a gateway that rewrites the field. It is disabled by
+    /// default, so such requests are checked as the `anonymous` subject.
+    pub fn with_message_subjects(mut self, enabled: bool) -> Self {
+        self.message_subjects = enabled;
+        self
+    }
+
     /// Set custom context extractor
     pub fn with_context_extractor<T>(mut self, extractor: T) -> Self
     where
@@ -458,9 +820,18 @@ impl RbacMiddlewareBuilder {
         // Collect role patterns for pattern matching
         let mut role_patterns: HashMap<String, Vec<String>> = HashMap::new();
 
+        // Inheritance of all roles, to check roles granted by tokens
+        let mut role_parents: HashMap<String, Vec<String>> = HashMap::new();
+        let mut inheritance: Vec<(String, String)> = self.inheritance.clone();
+
         // Add default roles if requested
         if self.default_roles {
             self.add_default_roles(&role_system).await?;
+            inheritance.extend(
+                DEFAULT_ROLE_INHERITANCE
+                    .iter()
+                    .map(|(role, parent)| (role.to_string(), parent.to_string())),
+            );
         }
 
         // Add custom roles and assign subjects
@@ -529,6 +900,24 @@ impl RbacMiddlewareBuilder {
             }
         }
 
+        for (role_name, parent) in &self.inheritance {
+            role_system
+                .add_role_inheritance(role_name, parent)
+                .await
+                .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
+        }
+
+        for (subject_id, role_name) in &self.assignments {
+            role_system
+                .assign_role(&RoleSubject::new(subject_id), role_name)
+                .await
+                .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
+        }
+
+        for (role_name, parent) in inheritance {
+            role_parents.entry(role_name).or_default().push(parent);
+        }
+
         let context_extractor = self
             .context_extractor
             .unwrap_or_else(|| Box::new(DefaultContextExtractor));
@@ -537,7 +926,11 @@ impl RbacMiddlewareBuilder {
             role_system: Arc::new(role_system),
             context_extractor,
             audit_enabled: self.audit_enabled,
+            audit_sink: self.audit_sink,
+            pending_audits: PendingAudits::default(),
+            message_subjects: self.message_subjects,
             role_patterns: Arc::new(role_patterns),
+            role_parents: Arc::new(role_parents),
         })
     }
 
@@ -588,18 +981,12 @@ impl RbacMiddlewareBuilder {
             .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
 
         // Set up role hierarchy
-        role_system
-            .add_role_inheritance("user", "guest")
-            .await
-            .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
-        role_system
-            .add_role_inheritance("power_user", "user")
-            .await
-            .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
-        role_system
-            .add_role_inheritance("admin", "power_user")
-            .await
-            .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
+        for (role_name, parent) in DEFAULT_ROLE_INHERITANCE {
+            role_system
+                .add_role_inheritance(role_name, parent)
+                .await
+                .map_err(|e| RbacError::RoleRegistration(e.to_string()))?;
+        }
 
         Ok(())
     }
@@ -746,6 +1133,20 @@ mod tests {
         assert_eq!(subject.id, "anonymous");
         assert_eq!(subject.subject_type, SubjectType::User);
 
+        // Claimed subjects are ignored unless enabled
+        let request = create_test_request("tools/list", None, Some("admin"), Some("user"));
+        let subject = rbac.extract_subject(&request).unwrap();
+        assert_eq!(subject.id, "anonymous");
+
+        let rbac = RbacMiddleware::builder()
+            .with_default_roles()
+            .with_message_subjects(true)
+            .build()
+            .await
+            .unwrap();
+        let subject = rbac.extract_subject(&request).unwrap();
+        assert_eq!(subject.id, "admin");
+
         // Test empty subject_id
         let request = create_test_request(
             "tools/list",
@@ -777,6 +1178,25 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_transport_principal_is_the_subject() {
+        let rbac = RbacMiddleware::builder()
+            .with_default_roles()
+            .build()
+            .await
+            .unwrap();
+        let request = create_test_request("tools/list", None, Some("mallory"), None);
+
+        let extensions = mocopr_core::protocol::Extensions::new();
+        extensions.insert(Principal::new("alice"));
+        let subject = extensions
+            .scope(async { rbac.extract_subject(&request) })
+            .await
+            .unwrap();
+        assert_eq!(subject.id, "alice");
+        assert_eq!(subject.subject_type, SubjectType::User);
+    }
+
     #[tokio::test]
     async fn test_resource_extraction_edge_cases() {
         let rbac = RbacMiddleware::builder()
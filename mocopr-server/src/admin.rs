@@ -0,0 +1,573 @@
+//! Introspection and control of live sessions
+//!
+//! Every running server keeps a [`SessionRegistry`] of its connected
+//! sessions, available from [`McpServer::sessions`](crate::McpServer::sessions).
+//! For each session it knows the client's address, principal and
+//! implementation, the negotiated protocol version and capabilities, when it
+//! was last active and which requests are in flight. Requests can be
+//! cancelled and sessions disconnected through it.
+//!
+//! [`AdminApi`] serves the registry over HTTP next to the MCP endpoint,
+//! protected by an [`Authenticator`] of its own rather than the one guarding
+//! MCP clients:
+//!
+//! - `GET /admin/sessions` lists the sessions as JSON
+//! - `GET /admin/sessions/{id}` returns a single session
+//! - `DELETE /admin/sessions/{id}` disconnects a session
+//! - `DELETE /admin/sessions/{id}/requests/{request_id}` cancels a request
+//!   like a `notifications/cancelled` from the client would, and answers it
+//!   with an error so the client does not wait for it
+//!
+//! Stdio sessions handle one request at a time. The request being handled is
+//! listed, but cannot be cancelled.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::admin::AdminApi;
+//! use mocopr_server::auth::ApiKeyAuthenticator;
+//! use mocopr_server::prelude::*;
+//!
+//! let builder = McpServerBuilder::new()
+//!     .with_info("Observable Server", "1.0.0")
+//!     .with_admin_api(AdminApi::new(
+//!         ApiKeyAuthenticator::new().with_key("admin-secret", "operator"),
+//!     ))
+//!     .with_websocket_transport();
+//! ```
+
+use crate::auth::{Authenticator, Credentials};
+use crate::context::{ClientAddr, Principal};
+use axum::Router;
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get};
+use chrono::{DateTime, Utc};
+use mocopr_core::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{Notify, mpsc, oneshot};
+use tokio::task::AbortHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// What a client told the server when initializing its session
+#[derive(Debug, Clone)]
+pub(crate) struct ClientHello {
+    pub(crate) client_info: Implementation,
+    pub(crate) protocol_version: String,
+    pub(crate) capabilities: ClientCapabilities,
+}
+
+/// Snapshot of a connected session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    /// Identifier of the session, assigned when it connected
+    pub id: String,
+    /// Transport the session is served over, `websocket` or `stdio`
+    pub transport: String,
+    /// Address of the client, for network transports
+    pub client_addr: Option<SocketAddr>,
+    /// Subject of the authenticated principal, if any
+    pub principal: Option<String>,
+    /// Name and version of the client, once initialized
+    pub client_info: Option<Implementation>,
+    /// Negotiated protocol version, once initialized
+    pub protocol_version: Option<String>,
+    /// Capabilities the client announced, once initialized
+    pub capabilities: Option<ClientCapabilities>,
+    /// When the session connected
+    pub connected_at: DateTime<Utc>,
+    /// When the session last received a message
+    pub last_activity: DateTime<Utc>,
+    /// Requests being handled
+    pub requests: Vec<RequestInfo>,
+}
+
+/// Snapshot of a request being handled
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestInfo {
+    /// ID of the request
+    pub id: RequestId,
+    /// Method of the request
+    pub method: String,
+    /// Name of the tool called, for `tools/call`
+    pub tool: Option<String>,
+    /// When handling started
+    pub started_at: DateTime<Utc>,
+    /// How long the request has been running, in milliseconds
+    pub duration_ms: u64,
+}
+
+struct RequestEntry {
+    method: String,
+    tool: Option<String>,
+    started_at: DateTime<Utc>,
+    started: Instant,
+    abort: Option<AbortHandle>,
+}
+
+impl RequestEntry {
+    fn is_finished(&self) -> bool {
+        self.abort.as_ref().is_some_and(AbortHandle::is_finished)
+    }
+}
+
+/// Request to cancel a request in flight, answered with whether it was
+pub(crate) type CancelRequest = (RequestId, oneshot::Sender<bool>);
+
+struct SessionEntry {
+    transport: &'static str,
+    extensions: Extensions,
+    connected_at: DateTime<Utc>,
+    last_activity: DateTime<Utc>,
+    requests: HashMap<RequestId, RequestEntry>,
+    disconnect: Arc<Notify>,
+    cancel: Option<mpsc::UnboundedSender<CancelRequest>>,
+}
+
+impl SessionEntry {
+    fn info(&self, id: &str) -> SessionInfo {
+        let hello = self.extensions.get::<ClientHello>();
+        let mut requests: Vec<_> = self
+            .requests
+            .iter()
+            .filter(|(_, request)| !request.is_finished())
+            .map(|(id, request)| RequestInfo {
+                id: id.clone(),
+                method: request.method.clone(),
+                tool: request.tool.clone(),
+                started_at: request.started_at,
+                duration_ms: request.started.elapsed().as_millis() as u64,
+            })
+            .collect();
+        requests.sort_by_key(|request| request.started_at);
+
+        SessionInfo {
+            id: id.to_string(),
+            transport: self.transport.to_string(),
+            client_addr: self.extensions.get::<ClientAddr>().map(|addr| addr.0),
+            principal: self
+                .extensions
+                .get::<Principal>()
+                .map(|principal| principal.subject().to_string()),
+            client_info: hello.as_ref().map(|hello| hello.client_info.clone()),
+            protocol_version: hello.as_ref().map(|hello| hello.protocol_version.clone()),
+            capabilities: hello.map(|hello| hello.capabilities),
+            connected_at: self.connected_at,
+            last_activity: self.last_activity,
+            requests,
+        }
+    }
+}
+
+/// Sessions connected to a server
+///
+/// Cloning is cheap; clones share the same sessions.
+#[derive(Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<HashMap<String, SessionEntry>>>,
+}
+
+impl SessionRegistry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SessionEntry>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// List the connected sessions, oldest first
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|(id, entry)| entry.info(id))
+            .collect();
+        sessions.sort_by_key(|session| session.connected_at);
+        sessions
+    }
+
+    /// Get a connected session
+    pub fn get(&self, session_id: &str) -> Option<SessionInfo> {
+        self.lock()
+            .get(session_id)
+            .map(|entry| entry.info(session_id))
+    }
+
+    /// Get the number of connected sessions
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check whether no session is connected
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Stop handling a request and answer it with an error
+    ///
+    /// The session cancels the request as if the client had sent
+    /// `notifications/cancelled`, so middleware hear of it and the client
+    /// gets at most one response. Returns `false` if the session has no such
+    /// request in flight or cannot cancel requests.
+    pub async fn cancel_request(&self, session_id: &str, request_id: &RequestId) -> bool {
+        let (reply, cancelled) = oneshot::channel();
+        {
+            let sessions = self.lock();
+            let Some(session) = sessions.get(session_id) else {
+                return false;
+            };
+            let Some(cancel) = &session.cancel else {
+                return false;
+            };
+            if !session.requests.contains_key(request_id)
+                || cancel.send((request_id.clone(), reply)).is_err()
+            {
+                return false;
+            }
+        }
+
+        let cancelled = cancelled.await.unwrap_or(false);
+        if cancelled {
+            info!("Cancelled request {} of session {}", request_id, session_id);
+        }
+        cancelled
+    }
+
+    /// Close a session
+    ///
+    /// Its requests in flight are cancelled. Returns `false` if no such
+    /// session is connected.
+    pub fn disconnect(&self, session_id: &str) -> bool {
+        match self.lock().get(session_id) {
+            Some(session) => {
+                info!("Disconnecting session {}", session_id);
+                session.disconnect.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add a session, which stays registered until the returned handle is
+    /// dropped
+    ///
+    /// Without a `cancel` channel, the session's requests cannot be cancelled.
+    pub(crate) fn register(
+        &self,
+        transport: &'static str,
+        extensions: Extensions,
+        cancel: Option<mpsc::UnboundedSender<CancelRequest>>,
+    ) -> RegisteredSession {
+        let id = Uuid::new_v4().to_string();
+        let disconnect = Arc::new(Notify::new());
+        let now = Utc::now();
+        self.lock().insert(
+            id.clone(),
+            SessionEntry {
+                transport,
+                extensions,
+                connected_at: now,
+                last_activity: now,
+                requests: HashMap::new(),
+                disconnect: disconnect.clone(),
+                cancel,
+            },
+        );
+        RegisteredSession {
+            id,
+            registry: self.clone(),
+            disconnect,
+        }
+    }
+
+    /// Record that a session received a message
+    pub(crate) fn touch(&self, session_id: &str) {
+        if let Some(session) = self.lock().get_mut(session_id) {
+            session.last_activity = Utc::now();
+        }
+    }
+
+    /// Track a request a session is handling
+    ///
+    /// With an `abort` handle, the request is forgotten once its task has
+    /// finished; otherwise [`request_finished`](Self::request_finished) must
+    /// be called.
+    pub(crate) fn request_started(
+        &self,
+        session_id: &str,
+        request_id: RequestId,
+        json_msg: &serde_json::Value,
+        abort: Option<AbortHandle>,
+    ) {
+        let method = json_msg["method"].as_str().unwrap_or_default().to_string();
+        let tool = match method.as_str() {
+            "tools/call" => json_msg
+                .pointer("/params/name")
+                .and_then(|name| name.as_str())
+                .map(str::to_string),
+            _ => None,
+        };
+        if let Some(session) = self.lock().get_mut(session_id) {
+            session.requests.retain(|_, request| !request.is_finished());
+            session.requests.insert(
+                request_id,
+                RequestEntry {
+                    method,
+                    tool,
+                    started_at: Utc::now(),
+                    started: Instant::now(),
+                    abort,
+                },
+            );
+        }
+    }
+
+    /// Forget a request once the session has answered or cancelled it
+    pub(crate) fn request_finished(&self, session_id: &str, request_id: &RequestId) {
+        if let Some(session) = self.lock().get_mut(session_id) {
+            session.requests.remove(request_id);
+        }
+    }
+}
+
+/// Registration of a session, removed when dropped
+pub(crate) struct RegisteredSession {
+    id: String,
+    registry: SessionRegistry,
+    disconnect: Arc<Notify>,
+}
+
+impl RegisteredSession {
+    /// Get the ID of the session
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Record that the session received a message
+    pub(crate) fn touch(&self) {
+        self.registry.touch(&self.id);
+    }
+
+    /// Track a request being handled by the task behind `abort`
+    ///
+    /// The request is forgotten once the task has finished.
+    pub(crate) fn request_started(
+        &self,
+        request_id: RequestId,
+        json_msg: &serde_json::Value,
+        abort: AbortHandle,
+    ) {
+        self.registry
+            .request_started(&self.id, request_id, json_msg, Some(abort));
+    }
+
+    /// Forget a request the session has cancelled
+    pub(crate) fn request_finished(&self, request_id: &RequestId) {
+        self.registry.request_finished(&self.id, request_id);
+    }
+
+    /// Wait until the session is asked to disconnect
+    pub(crate) async fn disconnected(&self) {
+        self.disconnect.notified().await
+    }
+}
+
+impl Drop for RegisteredSession {
+    fn drop(&mut self) {
+        self.registry.lock().remove(&self.id);
+    }
+}
+
+/// HTTP endpoints for inspecting and controlling sessions
+#[derive(Clone)]
+pub struct AdminApi {
+    path: String,
+    authenticator: Arc<dyn Authenticator>,
+}
+
+impl AdminApi {
+    /// Serve the admin endpoints under `/admin` to clients that `authenticator`
+    /// accepts
+    ///
+    /// Every request must carry credentials of its own; a TLS client
+    /// certificate accepted for MCP does not grant access.
+    pub fn new<A>(authenticator: A) -> Self
+    where
+        A: Authenticator + 'static,
+    {
+        Self {
+            path: "/admin".to_string(),
+            authenticator: Arc::new(authenticator),
+        }
+    }
+
+    /// Serve the endpoints under `path` instead of `/admin`
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Get the path the endpoints are served under
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Routes of the admin endpoints, serving `sessions`
+    pub(crate) fn routes(&self, sessions: SessionRegistry) -> Router {
+        let base = format!("{}/sessions", self.path);
+        Router::new()
+            .route(&base, get(list_sessions))
+            .route(&format!("{base}/:id"), get(get_session).delete(disconnect))
+            .route(
+                &format!("{base}/:id/requests/:request_id"),
+                delete(cancel_request),
+            )
+            .with_state(sessions)
+            .layer(axum::middleware::from_fn_with_state(
+                self.authenticator.clone(),
+                authorize,
+            ))
+    }
+}
+
+/// Reject admin requests whose credentials the admin authenticator refuses
+async fn authorize(
+    State(authenticator): State<Arc<dyn Authenticator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let credentials = Credentials::from_headers(request.headers());
+    match authenticator.authenticate(credentials.as_ref()).await {
+        Ok(principal) => {
+            info!(
+                "Admin request {} {} by {}",
+                request.method(),
+                request.uri().path(),
+                principal.subject()
+            );
+            next.run(request).await
+        }
+        Err(e) => {
+            warn!("Rejected admin request to {}: {}", request.uri(), e);
+            let mut response = (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+            if let Ok(challenge) = HeaderValue::from_str(&authenticator.challenge(&e)) {
+                response
+                    .headers_mut()
+                    .insert(header::WWW_AUTHENTICATE, challenge);
+            }
+            response
+        }
+    }
+}
+
+async fn list_sessions(State(sessions): State<SessionRegistry>) -> Response {
+    axum::Json(sessions.list()).into_response()
+}
+
+async fn get_session(State(sessions): State<SessionRegistry>, Path(id): Path<String>) -> Response {
+    match sessions.get(&id) {
+        Some(session) => axum::Json(session).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn disconnect(State(sessions): State<SessionRegistry>, Path(id): Path<String>) -> StatusCode {
+    if sessions.disconnect(&id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Cancel a request, taking a numeric `request_id` for a numeric ID first
+async fn cancel_request(
+    State(sessions): State<SessionRegistry>,
+    Path((id, request_id)): Path<(String, String)>,
+) -> StatusCode {
+    let numeric = request_id.parse().ok().map(RequestId::Number);
+    let cancelled = match numeric {
+        Some(number) if sessions.cancel_request(&id, &number).await => true,
+        _ => {
+            sessions
+                .cancel_request(&id, &RequestId::String(request_id))
+                .await
+        }
+    };
+    if cancelled {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_registry_tracks_sessions_and_requests() {
+        let registry = SessionRegistry::default();
+        let extensions = Extensions::new();
+        extensions.insert(Principal::new("alice"));
+        let (cancel, mut cancels) = mpsc::unbounded_channel::<CancelRequest>();
+        let session = registry.register("websocket", extensions, Some(cancel));
+
+        let task = tokio::spawn(std::future::pending::<()>());
+        session.request_started(
+            RequestId::Number(1),
+            &json!({"method": "tools/call", "params": {"name": "search"}}),
+            task.abort_handle(),
+        );
+
+        let info = registry.get(session.id()).unwrap();
+        assert_eq!(info.principal.as_deref(), Some("alice"));
+        assert_eq!(info.requests.len(), 1);
+        assert_eq!(info.requests[0].tool.as_deref(), Some("search"));
+
+        // The session's loop does the cancelling
+        let session_loop = tokio::spawn(async move {
+            let (request_id, reply) = cancels.recv().await.unwrap();
+            assert_eq!(request_id, RequestId::Number(1));
+            reply.send(true).unwrap();
+        });
+        assert!(
+            registry
+                .cancel_request(session.id(), &RequestId::Number(1))
+                .await
+        );
+        session_loop.await.unwrap();
+        session.request_finished(&RequestId::Number(1));
+        assert!(
+            !registry
+                .cancel_request(session.id(), &RequestId::Number(1))
+                .await
+        );
+        task.abort();
+
+        // Requests of sessions without a cancel channel are listed only
+        let stdio = registry.register("stdio", Extensions::new(), None);
+        registry.request_started(
+            stdio.id(),
+            RequestId::Number(7),
+            &json!({"method": "resources/read"}),
+            None,
+        );
+        assert_eq!(registry.get(stdio.id()).unwrap().requests.len(), 1);
+        assert!(
+            !registry
+                .cancel_request(stdio.id(), &RequestId::Number(7))
+                .await
+        );
+        registry.request_finished(stdio.id(), &RequestId::Number(7));
+        assert!(registry.get(stdio.id()).unwrap().requests.is_empty());
+
+        drop(session);
+        drop(stdio);
+        assert!(registry.is_empty());
+    }
+}
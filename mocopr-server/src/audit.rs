@@ -0,0 +1,557 @@
+//! Tamper-evident audit logging
+//!
+//! An [`AuditSink`] receives an [`AuditRecord`] for every request an access
+//! control middleware such as `RbacMiddleware` decides on: who made it, what
+//! it targeted, a hash of its parameters, whether it was allowed, how it
+//! ended and how long it took.
+//!
+//! Records name the principal authenticated at the transport, never a
+//! subject the client claims in the request body; such claims are kept in a
+//! separate `claimed_subject` field. Requests on every transport are
+//! recorded, stdio included, where sessions have no principal and are
+//! recorded as `anonymous`.
+//!
+//! [`JsonlAuditSink`] appends the records to a file, one JSON object per
+//! line. Each line carries a `sequence` number, the `previous_hash` of the
+//! line before it and its own `hash`, the SHA-256 of the previous hash and
+//! the line's other fields. Changing, removing or reordering lines breaks the
+//! chain, which [`verify_audit_log`] and [`verify_audit_logs`] detect; removing
+//! lines from the end is detected given a hash the chain is known to contain.
+//! Files can be rotated by size; the chain continues across rotated files.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::audit::{JsonlAuditSink, verify_audit_logs};
+//!
+//! # async fn example() -> mocopr_core::Result<()> {
+//! let sink = JsonlAuditSink::open("audit/mcp.jsonl")?.with_max_bytes(64 * 1024 * 1024);
+//!
+//! // Later, e.g. in a compliance job
+//! let verification = verify_audit_logs(&sink.files()?, None)?;
+//! println!("{} records intact", verification.records);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mocopr_core::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+
+/// `previous_hash` of the first record of a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Whether access control let a request through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    /// The request was passed on to the handler
+    Allowed,
+    /// The request was rejected
+    Denied,
+}
+
+/// How a request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The handler returned a result
+    Success,
+    /// The handler, or a later middleware, returned an error
+    Error,
+    /// The request was denied and never handled
+    Rejected,
+    /// The request was dropped before it ended, e.g. because the client
+    /// cancelled it, disconnected or the server shut down
+    Cancelled,
+}
+
+/// What happened to one request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the request was received
+    pub timestamp: DateTime<Utc>,
+    /// Subject of the principal authenticated at the transport, or
+    /// `anonymous`
+    pub principal: String,
+    /// Subject the client claimed in the request body, if any
+    ///
+    /// Clients can put anything here, so it is kept apart from `principal`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_subject: Option<String>,
+    /// JSON-RPC method
+    pub method: String,
+    /// Tool, resource or prompt the request targeted
+    pub target: Option<String>,
+    /// ID of the request
+    pub request_id: Option<RequestId>,
+    /// SHA-256 of the request parameters, see [`hash_arguments`]
+    pub arguments_hash: Option<String>,
+    /// Access control decision
+    pub decision: AuditDecision,
+    /// How the request ended
+    pub outcome: AuditOutcome,
+    /// Error message for denied and failed requests
+    pub error: Option<String>,
+    /// Microseconds from receiving the request to its outcome
+    pub latency_us: u64,
+}
+
+/// Hex-encoded SHA-256 of request parameters
+///
+/// The parameters are hashed rather than logged so the audit log does not
+/// hold the data clients send, while identical arguments can still be
+/// matched.
+pub fn hash_arguments(params: &serde_json::Value) -> String {
+    format!("{:x}", Sha256::digest(params.to_string().as_bytes()))
+}
+
+/// Destination for audit records
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    /// Store a record
+    async fn record(&self, record: AuditRecord) -> Result<()>;
+}
+
+/// Sink keeping records in memory, for tests and inspection
+#[derive(Debug, Default)]
+pub struct MemoryAuditSink {
+    records: Mutex<Vec<AuditRecord>>,
+}
+
+impl MemoryAuditSink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the records stored so far
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl AuditSink for MemoryAuditSink {
+    async fn record(&self, record: AuditRecord) -> Result<()> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record);
+        Ok(())
+    }
+}
+
+/// Sink appending hash-chained records to a JSON Lines file
+///
+/// With [`with_max_bytes`](Self::with_max_bytes), a file that has reached
+/// the limit is renamed to `<file>.1`, `<file>.2` and so on, oldest first,
+/// and a new file is started. Reopening a log continues its chain.
+#[derive(Debug)]
+pub struct JsonlAuditSink {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    chain: tokio::sync::Mutex<Chain>,
+}
+
+/// Position of the next record in the chain
+#[derive(Debug)]
+struct Chain {
+    sequence: u64,
+    previous_hash: String,
+    size: u64,
+}
+
+impl JsonlAuditSink {
+    /// Open the log at `path`, creating it if it does not exist
+    ///
+    /// Fails if the last record of an existing log cannot be read.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut chain = Chain {
+            sequence: 0,
+            previous_hash: GENESIS_HASH.to_string(),
+            size: 0,
+        };
+
+        // The newest file with records holds the end of the chain
+        let mut files = rotated_files(&path);
+        files.push(path.clone());
+        for file in files.iter().rev() {
+            let source = match std::fs::read_to_string(file) {
+                Ok(source) => source,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if *file == path {
+                chain.size = source.len() as u64;
+            }
+            if let Some(line) = source.lines().rev().find(|line| !line.trim().is_empty()) {
+                let entry: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+                    Error::Configuration(format!(
+                        "Invalid last record in audit log {}: {}",
+                        file.display(),
+                        e
+                    ))
+                })?;
+                match (entry["sequence"].as_u64(), entry["hash"].as_str()) {
+                    (Some(sequence), Some(hash)) => {
+                        chain.sequence = sequence;
+                        chain.previous_hash = hash.to_string();
+                    }
+                    _ => {
+                        return Err(Error::Configuration(format!(
+                            "Last record in audit log {} has no sequence or hash",
+                            file.display()
+                        )));
+                    }
+                }
+                break;
+            }
+        }
+
+        Ok(Self {
+            path,
+            max_bytes: None,
+            chain: tokio::sync::Mutex::new(chain),
+        })
+    }
+
+    /// Start a new file once the current one has reached `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Get the path of the current file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the rotated files followed by the current one, in chain order
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = rotated_files(&self.path);
+        if self.path.exists() {
+            files.push(self.path.clone());
+        }
+        Ok(files)
+    }
+
+    /// Move the current file aside as the newest rotated file
+    async fn rotate(&self) -> Result<()> {
+        let next = rotated_files(&self.path).len() + 1;
+        tokio::fs::rename(&self.path, rotated_path(&self.path, next)).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditSink for JsonlAuditSink {
+    async fn record(&self, record: AuditRecord) -> Result<()> {
+        let mut chain = self.chain.lock().await;
+        if self
+            .max_bytes
+            .is_some_and(|max_bytes| chain.size >= max_bytes)
+        {
+            self.rotate().await?;
+            chain.size = 0;
+        }
+
+        let mut entry = json!({
+            "sequence": chain.sequence + 1,
+            "previous_hash": chain.previous_hash,
+        });
+        if let (Some(entry), serde_json::Value::Object(fields)) =
+            (entry.as_object_mut(), serde_json::to_value(&record)?)
+        {
+            entry.extend(fields);
+        }
+        let hash = entry_hash(&entry);
+        entry["hash"] = json!(hash);
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        chain.sequence += 1;
+        chain.previous_hash = hash;
+        chain.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Path of the `index`th rotated file of the log at `path`
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", index));
+    path.with_file_name(name)
+}
+
+/// Rotated files of the log at `path`, oldest first
+fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    while rotated_path(path, files.len() + 1).exists() {
+        files.push(rotated_path(path, files.len() + 1));
+    }
+    files
+}
+
+/// Hash of an entry's fields other than `hash`, chained to `previous_hash`
+fn entry_hash(entry: &serde_json::Value) -> String {
+    let mut fields = entry.clone();
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("hash");
+    }
+    let previous_hash = entry["previous_hash"].as_str().unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(fields.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Summary of an intact audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditVerification {
+    /// Number of records checked
+    pub records: u64,
+    /// Sequence number of the first record, 0 for an empty log
+    pub first_sequence: u64,
+    /// Sequence number of the last record, 0 for an empty log
+    pub last_sequence: u64,
+    /// Hash of the last record, which the next record must link to
+    pub last_hash: String,
+}
+
+/// Check that a single audit log file is intact
+///
+/// The first record may continue a chain from a rotated file; its link to
+/// that file is not checked. Use [`verify_audit_logs`] to check rotated files
+/// together.
+///
+/// Fails with [`Error::Security`] naming the first line that does not fit the
+/// chain, with `{"file", "line"}` in [`Error::data`].
+pub fn verify_audit_log(path: impl AsRef<Path>) -> Result<AuditVerification> {
+    verify_file(path.as_ref(), None)
+}
+
+/// Check that audit log files, given oldest first, form one intact chain
+///
+/// The first file must start the chain, and each further file must continue
+/// where the one before it ended. Records removed from the end of the chain
+/// leave it intact; pass the `last_hash` of an earlier verification, or one
+/// kept elsewhere, as `expected_last_hash` to detect that too. The chain may
+/// have grown since that hash was taken, but must still contain it.
+pub fn verify_audit_logs(
+    paths: &[PathBuf],
+    expected_last_hash: Option<&str>,
+) -> Result<AuditVerification> {
+    let mut verification = AuditVerification {
+        records: 0,
+        first_sequence: 0,
+        last_sequence: 0,
+        last_hash: GENESIS_HASH.to_string(),
+    };
+    for path in paths {
+        let file = verify_file(
+            path,
+            Some((verification.last_sequence, &verification.last_hash)),
+        )?;
+        if verification.records == 0 {
+            verification.first_sequence = file.first_sequence;
+        }
+        if file.records > 0 {
+            verification.last_sequence = file.last_sequence;
+            verification.last_hash = file.last_hash;
+        }
+        verification.records += file.records;
+    }
+    if let Some(expected) = expected_last_hash
+        && expected != verification.last_hash
+        && !paths
+            .iter()
+            .any(|path| file_contains_hash(path, expected).unwrap_or(false))
+    {
+        return Err(Error::security(format!(
+            "Audit log is missing record {}: it was truncated",
+            expected
+        ))
+        .with_data(json!({"expected_hash": expected, "last_hash": verification.last_hash})));
+    }
+    Ok(verification)
+}
+
+/// Check whether a verified file has a record with the given hash
+fn file_contains_hash(path: &Path, hash: &str) -> Result<bool> {
+    let source = std::fs::read_to_string(path)?;
+    Ok(source
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .any(|entry| entry["hash"] == hash))
+}
+
+/// Check one file, optionally requiring it to continue the given chain
+fn verify_file(path: &Path, continues: Option<(u64, &str)>) -> Result<AuditVerification> {
+    let source = std::fs::read_to_string(path)?;
+    let mut verification = AuditVerification {
+        records: 0,
+        first_sequence: 0,
+        last_sequence: 0,
+        last_hash: String::new(),
+    };
+    let mut expected = continues.map(|(sequence, hash)| (sequence, hash.to_string()));
+
+    for (index, line) in source.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let broken = |reason: &str| {
+            Error::security(format!(
+                "Audit log {} is broken at line {}: {}",
+                path.display(),
+                index + 1,
+                reason
+            ))
+            .with_data(json!({"file": path.display().to_string(), "line": index + 1}))
+        };
+
+        let entry: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| broken(&e.to_string()))?;
+        let sequence = entry["sequence"]
+            .as_u64()
+            .ok_or_else(|| broken("missing sequence"))?;
+        let previous_hash = entry["previous_hash"]
+            .as_str()
+            .ok_or_else(|| broken("missing previous hash"))?;
+        let hash = entry["hash"]
+            .as_str()
+            .ok_or_else(|| broken("missing hash"))?;
+
+        if let Some((expected_sequence, expected_hash)) = &expected {
+            if sequence != expected_sequence + 1 {
+                return Err(broken(&format!(
+                    "expected sequence {}, found {}",
+                    expected_sequence + 1,
+                    sequence
+                )));
+            }
+            if previous_hash != expected_hash {
+                return Err(broken("does not link to the previous record"));
+            }
+        }
+        if entry_hash(&entry) != hash {
+            return Err(broken("hash does not match the record"));
+        }
+
+        if verification.records == 0 {
+            verification.first_sequence = sequence;
+        }
+        verification.records += 1;
+        verification.last_sequence = sequence;
+        verification.last_hash = hash.to_string();
+        expected = Some((sequence, hash.to_string()));
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(method: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now(),
+            principal: "alice".to_string(),
+            claimed_subject: None,
+            method: method.to_string(),
+            target: Some("search".to_string()),
+            request_id: Some(RequestId::Number(1)),
+            arguments_hash: Some(hash_arguments(&json!({"query": "x"}))),
+            decision: AuditDecision::Allowed,
+            outcome: AuditOutcome::Success,
+            error: None,
+            latency_us: 1200,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let sink = JsonlAuditSink::open(&path).unwrap();
+        for method in ["tools/call", "resources/read", "prompts/get"] {
+            sink.record(record(method)).await.unwrap();
+        }
+        let verification = verify_audit_log(&path).unwrap();
+        assert_eq!(verification.records, 3);
+        assert_eq!(verification.last_sequence, 3);
+
+        // Reopening continues the chain
+        let sink = JsonlAuditSink::open(&path).unwrap();
+        sink.record(record("tools/call")).await.unwrap();
+        assert_eq!(verify_audit_log(&path).unwrap().records, 4);
+
+        let source = std::fs::read_to_string(&path).unwrap();
+        let edited = source.replacen("\"principal\":\"alice\"", "\"principal\":\"bob\"", 1);
+        std::fs::write(&path, edited).unwrap();
+        let error = verify_audit_log(&path).unwrap_err();
+        assert_eq!(error.data().unwrap()["line"], 1);
+
+        let lines: Vec<&str> = source.lines().collect();
+        let removed = [lines[0], lines[2], lines[3]].join("\n");
+        std::fs::write(&path, removed).unwrap();
+        let error = verify_audit_log(&path).unwrap_err();
+        assert_eq!(error.data().unwrap()["line"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_one_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let sink = JsonlAuditSink::open(&path).unwrap().with_max_bytes(1);
+        for _ in 0..3 {
+            sink.record(record("tools/call")).await.unwrap();
+        }
+
+        let files = sink.files().unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("audit.jsonl.1"),
+                dir.path().join("audit.jsonl.2"),
+                path.clone(),
+            ]
+        );
+        let verification = verify_audit_logs(&files, None).unwrap();
+        assert_eq!(verification.records, 3);
+        assert_eq!(verification.first_sequence, 1);
+
+        // A missing file breaks the chain
+        assert!(verify_audit_logs(&[files[0].clone(), files[2].clone()], None).is_err());
+        assert!(verify_audit_logs(&files[1..], None).is_err());
+        // Dropping the newest file leaves an intact chain without the known head
+        assert!(verify_audit_logs(&files[..2], None).is_ok());
+        assert!(verify_audit_logs(&files[..2], Some(&verification.last_hash)).is_err());
+
+        let sink = JsonlAuditSink::open(&path).unwrap();
+        sink.record(record("tools/call")).await.unwrap();
+        // A known head still verifies after the chain grew
+        let grown = verify_audit_logs(&sink.files().unwrap(), Some(&verification.last_hash));
+        assert_eq!(grown.unwrap().records, 4);
+    }
+}
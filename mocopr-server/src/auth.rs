@@ -0,0 +1,291 @@
+//! Transport-level authentication
+//!
+//! HTTP requests and WebSocket upgrade requests are authenticated from their
+//! `Authorization` header before any MCP message is read. An
+//! [`Authenticator`] resolves the credentials into a [`Principal`] once per
+//! connection, and the principal is stored in the session's
+//! [`Extensions`](mocopr_core::protocol::Extensions), where middleware and
+//! handlers find it through [`Principal::current`]. Requests with missing or
+//! rejected credentials get `401 Unauthorized` and never reach the server.
+//!
+//! Credentials are accepted as `Authorization: Bearer <token>`,
+//! `Authorization: ApiKey <key>` or an `X-API-Key: <key>` header.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::auth::ApiKeyAuthenticator;
+//!
+//! # fn main() -> Result<()> {
+//! let server = McpServerBuilder::new()
+//!     .with_info("Private Server", "1.0.0")
+//!     .with_authenticator(
+//!         ApiKeyAuthenticator::new()
+//!             .with_key("k-3f9a", "alice")
+//!             .with_key("k-77c1", "ci-bot"),
+//!     )
+//!     .with_websocket_transport()
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::context::Principal;
+use async_trait::async_trait;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use mocopr_core::error::ProtocolError;
+use mocopr_core::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Header carrying an API key as an alternative to `Authorization`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Credentials presented by a client
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credentials {
+    /// A bearer token from `Authorization: Bearer <token>`
+    Bearer(String),
+    /// An API key from `Authorization: ApiKey <key>` or `X-API-Key`
+    ApiKey(String),
+}
+
+impl Credentials {
+    /// Read credentials from request headers
+    ///
+    /// Returns `None` when the headers carry no credentials in a known form.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        if let Some(value) = headers.get(header::AUTHORIZATION) {
+            let value = value.to_str().ok()?.trim();
+            let (scheme, secret) = value.split_once(' ')?;
+            let secret = secret.trim();
+            if secret.is_empty() {
+                return None;
+            }
+            return if scheme.eq_ignore_ascii_case("bearer") {
+                Some(Self::Bearer(secret.to_string()))
+            } else if scheme.eq_ignore_ascii_case("apikey") {
+                Some(Self::ApiKey(secret.to_string()))
+            } else {
+                None
+            };
+        }
+
+        let key = headers.get(API_KEY_HEADER)?.to_str().ok()?.trim();
+        (!key.is_empty()).then(|| Self::ApiKey(key.to_string()))
+    }
+
+    /// Get the token or key
+    pub fn secret(&self) -> &str {
+        match self {
+            Self::Bearer(secret) | Self::ApiKey(secret) => secret,
+        }
+    }
+}
+
+// Keep secrets out of logs
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Self::ApiKey(_) => f.write_str("ApiKey(<redacted>)"),
+        }
+    }
+}
+
+/// Resolves transport credentials into a principal
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Authenticate a client
+    ///
+    /// `credentials` is `None` when the request carried none. Returning an
+    /// error rejects the connection with `401 Unauthorized`, or with
+    /// `403 Forbidden` for [`ProtocolError::PermissionDenied`], which signals
+    /// valid credentials that lack a required scope.
+    ///
+    /// [`ProtocolError::PermissionDenied`]: mocopr_core::error::ProtocolError::PermissionDenied
+    async fn authenticate(&self, credentials: Option<&Credentials>) -> Result<Principal>;
+
+    /// Value of the `WWW-Authenticate` header sent when rejecting with `error`
+    fn challenge(&self, _error: &Error) -> String {
+        "Bearer".to_string()
+    }
+}
+
+/// Authenticates clients by a fixed set of keys
+///
+/// Each key maps to the subject of the principal it authenticates. Keys are
+/// accepted both as API keys and as bearer tokens.
+#[derive(Clone, Default)]
+pub struct ApiKeyAuthenticator {
+    keys: HashMap<String, String>,
+    anonymous: bool,
+}
+
+impl ApiKeyAuthenticator {
+    /// Create an authenticator that accepts no keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `key` as the credentials of `subject`
+    pub fn with_key(mut self, key: impl Into<String>, subject: impl Into<String>) -> Self {
+        self.keys.insert(key.into(), subject.into());
+        self
+    }
+
+    /// Accept each key as the credentials of a subject named after the key's position
+    ///
+    /// The subjects are `key-1`, `key-2` and so on, so the keys themselves
+    /// never appear in logs or authorization decisions.
+    pub fn with_keys<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let offset = self.keys.len();
+        for (index, key) in keys.into_iter().enumerate() {
+            self.keys.insert(key, format!("key-{}", offset + index + 1));
+        }
+        self
+    }
+
+    /// Let clients without credentials in as the `anonymous` subject
+    ///
+    /// Clients presenting an unknown key are still rejected.
+    pub fn allow_anonymous(mut self) -> Self {
+        self.anonymous = true;
+        self
+    }
+}
+
+impl fmt::Debug for ApiKeyAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyAuthenticator")
+            .field("keys", &self.keys.len())
+            .field("anonymous", &self.anonymous)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Authenticator for ApiKeyAuthenticator {
+    async fn authenticate(&self, credentials: Option<&Credentials>) -> Result<Principal> {
+        match credentials {
+            Some(credentials) => self
+                .keys
+                .get(credentials.secret())
+                .map(Principal::new)
+                .ok_or_else(|| Error::security("Unknown API key")),
+            None if self.anonymous => Ok(Principal::anonymous()),
+            None => Err(Error::security("Missing credentials")),
+        }
+    }
+}
+
+/// Axum middleware authenticating a request and attaching its principal
+pub(crate) async fn authenticate(
+    State(authenticator): State<Arc<dyn Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // Clients authenticated by TLS client certificate need no credentials
+    if request.extensions().get::<Principal>().is_some() {
+        return next.run(request).await;
+    }
+
+    let credentials = Credentials::from_headers(request.headers());
+    match authenticator.authenticate(credentials.as_ref()).await {
+        Ok(principal) => {
+            debug!("Authenticated {} as {}", request.uri(), principal.subject());
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(e) => {
+            warn!("Rejected request to {}: {}", request.uri(), e);
+            let mut response = match e.inner() {
+                Error::Protocol(ProtocolError::PermissionDenied) => {
+                    (StatusCode::FORBIDDEN, "Forbidden").into_response()
+                }
+                _ => (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
+            };
+            if let Ok(challenge) = HeaderValue::from_str(&authenticator.challenge(&e)) {
+                response
+                    .headers_mut()
+                    .insert(header::WWW_AUTHENTICATE, challenge);
+            }
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_credentials_from_headers() {
+        assert_eq!(
+            Credentials::from_headers(&headers("authorization", "Bearer abc")),
+            Some(Credentials::Bearer("abc".to_string()))
+        );
+        assert_eq!(
+            Credentials::from_headers(&headers("authorization", "apikey  k1 ")),
+            Some(Credentials::ApiKey("k1".to_string()))
+        );
+        assert_eq!(
+            Credentials::from_headers(&headers("x-api-key", "k2")),
+            Some(Credentials::ApiKey("k2".to_string()))
+        );
+        assert_eq!(
+            Credentials::from_headers(&headers("authorization", "Basic dXNlcg==")),
+            None
+        );
+        assert_eq!(Credentials::from_headers(&HeaderMap::new()), None);
+
+        let debug = format!("{:?}", Credentials::Bearer("secret".to_string()));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authenticator() {
+        let authenticator = ApiKeyAuthenticator::new()
+            .with_key("k1", "alice")
+            .with_keys(["k2".to_string()]);
+
+        let principal = authenticator
+            .authenticate(Some(&Credentials::Bearer("k1".to_string())))
+            .await
+            .unwrap();
+        assert_eq!(principal.subject(), "alice");
+        let principal = authenticator
+            .authenticate(Some(&Credentials::ApiKey("k2".to_string())))
+            .await
+            .unwrap();
+        assert_eq!(principal.subject(), "key-2");
+
+        let unknown = Credentials::ApiKey("nope".to_string());
+        assert!(authenticator.authenticate(Some(&unknown)).await.is_err());
+        assert!(authenticator.authenticate(None).await.is_err());
+
+        let authenticator = authenticator.allow_anonymous();
+        let anonymous = authenticator.authenticate(None).await.unwrap();
+        assert_eq!(anonymous.subject(), "anonymous");
+        assert!(anonymous.is_anonymous());
+        assert!(authenticator.authenticate(Some(&unknown)).await.is_err());
+    }
+}
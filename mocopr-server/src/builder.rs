@@ -23,12 +23,30 @@
 //! }
 //! ```
 
+use crate::admin::AdminApi;
+use crate::auth::Authenticator;
+use crate::cache::ResourceCache;
+use crate::execution::ToolExecutionPolicy;
 use crate::handlers::*;
+use crate::manifest::ServerManifest;
+use crate::metrics::MonitoringEndpoints;
 use crate::middleware::Middleware;
+use crate::notifications::NotificationSender;
+use crate::oauth::ProtectedResource;
+use crate::openapi::OpenApiBridge;
+use crate::origin::OriginPolicy;
+use crate::pagination::{DEFAULT_PAGE_SIZE, Paginator};
+use crate::proxy::McpProxy;
 use crate::registry::*;
+use crate::schema::SchemaValidation;
 use crate::server::McpServer;
+use crate::shutdown::{DEFAULT_SHUTDOWN_TIMEOUT, ShutdownHandle};
+use crate::tls::TlsConfig;
 use mocopr_core::monitoring::MonitoringSystem;
 use mocopr_core::prelude::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Builder for creating MCP servers with a fluent API.
 ///
@@ -55,10 +73,21 @@ pub struct McpServerBuilder {
     prompt_registry: PromptRegistry,
     middleware_stack: Vec<Box<dyn Middleware>>,
     monitoring_system: Option<MonitoringSystem>,
+    monitoring_endpoints: MonitoringEndpoints,
+    page_size: usize,
+    cursor_secret: Option<Vec<u8>>,
+    schema_validation: SchemaValidation,
     bind_address: String,
     port: u16,
     enable_http: bool,
     enable_websocket: bool,
+    shutdown_timeout: Duration,
+    panic_message: Option<String>,
+    admin: Option<AdminApi>,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    protected_resource: Option<ProtectedResource>,
+    origin_policy: Option<OriginPolicy>,
+    tls: Option<TlsConfig>,
 }
 
 impl McpServerBuilder {
@@ -75,22 +104,53 @@ impl McpServerBuilder {
     /// let builder = McpServerBuilder::new();
     /// ```
     pub fn new() -> Self {
+        let notifications = NotificationSender::new();
         Self {
             name: None,
             version: None,
             capabilities: ServerCapabilities::default(),
-            resource_registry: ResourceRegistry::new(),
+            resource_registry: ResourceRegistry::with_notifications(notifications.clone()),
             tool_registry: ToolRegistry::new(),
-            prompt_registry: PromptRegistry::new(),
+            prompt_registry: PromptRegistry::with_notifications(notifications),
             middleware_stack: Vec::new(),
             monitoring_system: None,
+            monitoring_endpoints: MonitoringEndpoints::default(),
+            page_size: DEFAULT_PAGE_SIZE,
+            cursor_secret: None,
+            schema_validation: SchemaValidation::default(),
             bind_address: "127.0.0.1".to_string(),
             port: 8080,
             enable_http: false,
             enable_websocket: false,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            panic_message: None,
+            admin: None,
+            authenticator: None,
+            protected_resource: None,
+            origin_policy: None,
+            tls: None,
         }
     }
 
+    /// Create a builder configured by a TOML or JSON manifest file.
+    ///
+    /// See the [`manifest`](crate::manifest) module for the file format.
+    /// The returned builder can be customized further before building.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mocopr_server::McpServerBuilder;
+    ///
+    /// # fn main() -> mocopr_core::Result<()> {
+    /// let server = McpServerBuilder::from_manifest("server.toml")?.build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_manifest(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        ServerManifest::from_file(path)?.into_builder()
+    }
+
     /// Set server name and version.
     ///
     /// This information is sent to clients during the initialization handshake
@@ -204,6 +264,94 @@ impl McpServerBuilder {
         self
     }
 
+    /// Add a resource provider that serves a dynamic set of resources
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_resources()
+    ///     .with_resource_provider(DirectoryResourceProvider::new("./docs", "Docs").unwrap());
+    /// ```
+    pub fn with_resource_provider<P>(mut self, provider: P) -> Self
+    where
+        P: ResourceProvider + 'static,
+    {
+        self.resource_registry.register_provider(Box::new(provider));
+        self
+    }
+
+    /// Cache resource reads
+    ///
+    /// See [`ResourceCache`] for how entries expire and are invalidated.
+    pub fn with_resource_cache(mut self, cache: ResourceCache) -> Self {
+        self.resource_registry.set_cache(cache);
+        self
+    }
+
+    /// Add a tool provider that serves a dynamic set of tools
+    pub fn with_tool_provider<P>(mut self, provider: P) -> Self
+    where
+        P: ToolProvider + 'static,
+    {
+        self.tool_registry.register_provider(Box::new(provider));
+        self
+    }
+
+    /// Add a prompt provider that serves a dynamic set of prompts
+    pub fn with_prompt_provider<P>(mut self, provider: P) -> Self
+    where
+        P: PromptProvider + 'static,
+    {
+        self.prompt_registry.register_provider(Box::new(provider));
+        self
+    }
+
+    /// Serve the tools, prompts and resources of the proxy's upstream servers
+    ///
+    /// Enables the tools, prompts and resources capabilities and starts
+    /// relaying change notifications from the upstreams. Fails if the
+    /// namespaces of two upstreams overlap, see [`McpProxy::validate`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mocopr_client::McpClient;
+    /// use mocopr_server::prelude::*;
+    /// use mocopr_server::proxy::McpProxy;
+    ///
+    /// # async fn example(github: McpClient) -> Result<()> {
+    /// let server = McpServerBuilder::new()
+    ///     .with_info("Gateway", "1.0.0")
+    ///     .with_proxy(McpProxy::new().with_upstream("github", github))?
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_proxy(self, proxy: McpProxy) -> Result<Self> {
+        proxy.validate()?;
+        Ok(self
+            .with_tools()
+            .with_prompts()
+            .with_resources()
+            .with_tool_provider(proxy.clone())
+            .with_prompt_provider(proxy.clone())
+            .with_resource_provider(proxy))
+    }
+
+    /// Add one tool per operation of an OpenAPI document
+    ///
+    /// Enables the tools capability. Fails if the bridge has no base URL or
+    /// two operations map to the same tool name.
+    pub fn with_openapi(mut self, bridge: OpenApiBridge) -> Result<Self> {
+        for tool in bridge.tools()? {
+            self = self.with_tool(tool);
+        }
+        Ok(self.with_tools())
+    }
+
     /// Add a tool handler
     pub fn with_tool<T>(mut self, tool: T) -> Self
     where
@@ -213,6 +361,29 @@ impl McpServerBuilder {
         self
     }
 
+    /// Set execution limits for a tool
+    ///
+    /// Overrides any policy declared by the tool handler itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let builder = McpServerBuilder::new().with_tool_policy(
+    ///     "search",
+    ///     ToolExecutionPolicy::new()
+    ///         .with_timeout(Duration::from_secs(30))
+    ///         .with_max_concurrency(4)
+    ///         .with_max_queue(16),
+    /// );
+    /// ```
+    pub fn with_tool_policy(self, tool: impl Into<String>, policy: ToolExecutionPolicy) -> Self {
+        self.tool_registry.set_policy(tool, policy);
+        self
+    }
+
     /// Add a prompt handler
     pub fn with_prompt<P>(mut self, prompt: P) -> Self
     where
@@ -287,6 +458,11 @@ impl McpServerBuilder {
 
     /// Enable monitoring system
     ///
+    /// With HTTP or WebSocket transport enabled, the server also serves
+    /// `/healthz`, `/readyz` and `/metrics` (Prometheus text format). Use
+    /// [`with_monitoring_endpoints`](Self::with_monitoring_endpoints) to change
+    /// the paths.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -301,11 +477,85 @@ impl McpServerBuilder {
         self
     }
 
+    /// Enable monitoring with custom health, readiness and metrics paths
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new().with_monitoring_endpoints(
+    ///     MonitoringEndpoints::default()
+    ///         .with_health_path("/live")
+    ///         .with_metrics_path("/internal/metrics"),
+    /// );
+    /// ```
+    pub fn with_monitoring_endpoints(mut self, endpoints: MonitoringEndpoints) -> Self {
+        if self.monitoring_system.is_none() {
+            self = self.with_monitoring();
+        }
+        self.monitoring_endpoints = endpoints;
+        self
+    }
+
+    /// Set the number of items returned per page by list operations
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_page_size(100);
+    /// ```
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Set the secret used to sign pagination cursors
+    ///
+    /// By default a random secret is generated per server. Replicas behind a
+    /// load balancer should share a secret so their cursors are interchangeable.
+    pub fn with_cursor_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.cursor_secret = Some(secret.as_ref().to_vec());
+        self
+    }
+
+    /// Enable or disable JSON Schema validation of tool arguments
+    ///
+    /// Validation is enabled by default. Arguments that do not match a tool's
+    /// `input_schema` are rejected with `-32602` before the tool is called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_schema_validation(false)
+    ///     .with_tool_schema_validation("strict_tool", true);
+    /// ```
+    pub fn with_schema_validation(mut self, enabled: bool) -> Self {
+        self.schema_validation.set_enabled(enabled);
+        self
+    }
+
+    /// Enable or disable JSON Schema validation for a single tool
+    pub fn with_tool_schema_validation(mut self, tool: impl Into<String>, enabled: bool) -> Self {
+        self.schema_validation.set_tool_enabled(tool, enabled);
+        self
+    }
+
     /// Configure server address and port
     ///
+    /// The default is `127.0.0.1:8080`. Binding to `0.0.0.0` exposes the
+    /// server on every interface and is logged as a warning; clients then
+    /// reach it under names the [`OriginPolicy`] must allow.
+    ///
     /// # Arguments
     ///
-    /// * `address` - The IP address to bind to (e.g., "0.0.0.0", "127.0.0.1")
+    /// * `address` - The IP address to bind to (e.g., "127.0.0.1", "0.0.0.0")
     /// * `port` - The port number to bind to
     ///
     /// # Examples
@@ -314,7 +564,7 @@ impl McpServerBuilder {
     /// use mocopr_server::prelude::*;
     ///
     /// let builder = McpServerBuilder::new()
-    ///     .with_bind_address("0.0.0.0", 8080);
+    ///     .with_bind_address("127.0.0.1", 8080);
     /// ```
     pub fn with_bind_address(mut self, address: impl Into<String>, port: u16) -> Self {
         self.bind_address = address.into();
@@ -322,6 +572,67 @@ impl McpServerBuilder {
         self
     }
 
+    /// Restrict the origins and hosts HTTP and WebSocket clients may use
+    ///
+    /// Without a policy only loopback origins and hosts are allowed. See the
+    /// [`origin`](crate::origin) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use mocopr_server::origin::OriginPolicy;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_bind_address("0.0.0.0", 8080)
+    ///     .with_origin_policy(OriginPolicy::new().with_allowed_host("mcp.example.com"));
+    /// ```
+    pub fn with_origin_policy(mut self, policy: OriginPolicy) -> Self {
+        self.origin_policy = Some(policy);
+        self
+    }
+
+    /// Serve HTTP and WebSocket clients over TLS
+    ///
+    /// `cert` is a PEM file with the server's certificate chain and `key` a
+    /// PEM file with its private key. They are read by
+    /// [`build`](Self::build), which fails if they cannot be loaded. Use
+    /// [`with_tls_config`](Self::with_tls_config) to authenticate clients by
+    /// certificate as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_tls("certs/server.pem", "certs/server.key")
+    ///     .with_websocket_transport();
+    /// ```
+    pub fn with_tls(self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.with_tls_config(TlsConfig::new(cert, key))
+    }
+
+    /// Serve HTTP and WebSocket clients over TLS with the given settings
+    ///
+    /// See the [`tls`](crate::tls) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use mocopr_server::tls::TlsConfig;
+    ///
+    /// let builder = McpServerBuilder::new().with_tls_config(
+    ///     TlsConfig::new("certs/server.pem", "certs/server.key")
+    ///         .with_client_ca("certs/clients-ca.pem"),
+    /// );
+    /// ```
+    pub fn with_tls_config(mut self, config: TlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
     /// Enable HTTP transport
     ///
     /// # Examples
@@ -352,8 +663,124 @@ impl McpServerBuilder {
         self
     }
 
+    /// Authenticate HTTP and WebSocket clients from their `Authorization` header
+    ///
+    /// Each connection is authenticated once, and its [`Principal`] is kept in
+    /// the session's extensions for middleware and handlers. Clients that fail
+    /// authentication get `401 Unauthorized`. Stdio sessions are not affected.
+    ///
+    /// [`Principal`]: crate::context::Principal
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use mocopr_server::auth::ApiKeyAuthenticator;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_authenticator(ApiKeyAuthenticator::new().with_key("k-3f9a", "alice"));
+    /// ```
+    pub fn with_authenticator<A>(mut self, authenticator: A) -> Self
+    where
+        A: Authenticator + 'static,
+    {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Make the server an OAuth 2.1 protected resource
+    ///
+    /// HTTP and WebSocket clients must present a JWT access token accepted by
+    /// `resource`, and the protected resource metadata is served under
+    /// `/.well-known/oauth-protected-resource`. Replaces any authenticator set
+    /// with [`with_authenticator`](Self::with_authenticator).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use mocopr_server::oauth::ProtectedResource;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let builder = McpServerBuilder::new().with_protected_resource(
+    ///     ProtectedResource::new(Url::parse("https://mcp.example.com/mcp")?)
+    ///         .with_authorization_server(Url::parse("https://auth.example.com")?)
+    ///         .with_jwks_url(Url::parse("https://auth.example.com/jwks.json")?),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_protected_resource(mut self, resource: ProtectedResource) -> Self {
+        self.protected_resource = Some(resource);
+        self
+    }
+
+    /// Set how long in-flight requests may run after shutdown is requested
+    ///
+    /// Requests still running when the timeout elapses are cancelled. Defaults
+    /// to 30 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_shutdown_timeout(Duration::from_secs(10));
+    /// ```
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serve endpoints for listing sessions and their requests in flight,
+    /// cancelling requests and disconnecting sessions
+    ///
+    /// The endpoints are served next to the MCP endpoint over HTTP or
+    /// WebSocket transports and only accept clients that the admin API's own
+    /// authenticator lets in. See the [`admin`](crate::admin) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::admin::AdminApi;
+    /// use mocopr_server::auth::ApiKeyAuthenticator;
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new().with_admin_api(
+    ///     AdminApi::new(ApiKeyAuthenticator::new().with_key("admin-secret", "operator"))
+    ///         .with_path("/ops"),
+    /// );
+    /// ```
+    pub fn with_admin_api(mut self, admin: AdminApi) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    /// Set the error message sent to clients when a handler panics
+    ///
+    /// A panicking handler no longer takes its connection down: the request
+    /// is answered with an `INTERNAL_ERROR` carrying this message, which
+    /// defaults to "Internal error". The panic itself, with the request's
+    /// method, ID and principal, is only logged and counted as
+    /// `mcp_request_panics_total` when monitoring is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mocopr_server::prelude::*;
+    ///
+    /// let builder = McpServerBuilder::new()
+    ///     .with_panic_message("Something went wrong, please try again");
+    /// ```
+    pub fn with_panic_message(mut self, message: impl Into<String>) -> Self {
+        self.panic_message = Some(message.into());
+        self
+    }
+
     /// Build the MCP server
-    pub fn build(self) -> Result<McpServer> {
+    pub fn build(mut self) -> Result<McpServer> {
         let name = self
             .name
             .ok_or_else(|| Error::InvalidRequest("Server name is required".to_string()))?;
@@ -364,7 +791,20 @@ impl McpServerBuilder {
 
         let info = Implementation { name, version };
 
-        Ok(McpServer::new(
+        let mut paginator = Paginator::new(self.page_size);
+        if let Some(secret) = &self.cursor_secret {
+            paginator = paginator.with_secret(secret);
+        }
+        self.resource_registry.set_paginator(paginator.clone());
+        self.tool_registry.set_paginator(paginator.clone());
+        self.prompt_registry.set_paginator(paginator);
+        self.tool_registry
+            .set_schema_validation(self.schema_validation);
+        if let Some(monitoring) = &self.monitoring_system {
+            self.tool_registry.set_monitoring(monitoring.clone());
+        }
+
+        let mut server = McpServer::new(
             info,
             self.capabilities,
             self.resource_registry,
@@ -376,7 +816,30 @@ impl McpServerBuilder {
             self.port,
             self.enable_http,
             self.enable_websocket,
-        ))
+        );
+        server.set_shutdown_handle(ShutdownHandle::new(self.shutdown_timeout));
+        if let Some(message) = self.panic_message {
+            server.set_panic_message(message);
+        }
+        if let Some(admin) = self.admin {
+            server.set_admin_api(admin);
+        }
+        server.set_monitoring_endpoints(self.monitoring_endpoints);
+        if let Some(authenticator) = self.authenticator {
+            server.set_authenticator(authenticator);
+        }
+        if let Some(resource) = self.protected_resource {
+            server.set_protected_resource(resource);
+        }
+        if let Some(policy) = self.origin_policy {
+            server.set_origin_policy(policy);
+        }
+        if let Some(config) = self.tls {
+            let acceptor = config.acceptor()?;
+            server.set_tls(config, acceptor);
+        }
+
+        Ok(server)
     }
 }
 
@@ -445,6 +908,38 @@ mod tests {
         assert!(server.capabilities().tools.is_some());
     }
 
+    #[tokio::test]
+    async fn test_builder_page_size() {
+        let server = McpServerBuilder::new()
+            .with_info("Test Server", "1.0.0")
+            .with_tools()
+            .with_page_size(2)
+            .with_tools_from(["charlie", "alpha", "bravo"].map(|name| {
+                FunctionToolHandler::new(name, "test", serde_json::json!({}), |_| {
+                    Ok(ToolsCallResponse::success(vec![]))
+                })
+            }))
+            .build()
+            .unwrap();
+
+        let first = server
+            .tools()
+            .list_tools(ToolsListRequest::new())
+            .await
+            .unwrap();
+        let names: Vec<_> = first.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "bravo"]);
+
+        let second = server
+            .tools()
+            .list_tools(ToolsListRequest::new().with_cursor(first.next_cursor.unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(second.tools.len(), 1);
+        assert_eq!(second.tools[0].name, "charlie");
+        assert!(second.next_cursor.is_none());
+    }
+
     #[test]
     fn test_builder_validation() {
         let result = McpServerBuilder::new().with_resources().build();
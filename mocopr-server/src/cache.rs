@@ -0,0 +1,578 @@
+//! Caching of resource reads
+//!
+//! A [`ResourceCache`] keeps the contents returned by `resources/read` so
+//! expensive resources are not rebuilt for every request. Entries live for a
+//! time to live (TTL) chosen per resource, and the least recently used ones
+//! are evicted once the cache holds too many entries or bytes.
+//!
+//! Entries are kept per authenticated principal, so contents read for one
+//! client are never served to another. Resources whose contents are the same
+//! for everyone can share entries across principals with
+//! [`ResourceCache::with_shared_entries`].
+//!
+//! When a resource signals a change with
+//! [`NotificationSender::resource_updated`], its entry is dropped; a
+//! `resources/list_changed` notification drops all entries. Handlers that can
+//! tell cheaply whether their content changed implement
+//! [`ResourceHandler::etag`] or [`ResourceHandler::last_modified`]: an expired
+//! entry whose validator still matches is renewed without reading again.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::cache::ResourceCache;
+//! use mocopr_server::prelude::*;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<()> {
+//! let cache = ResourceCache::new()
+//!     .with_default_ttl(Duration::from_secs(60))
+//!     .with_ttl("db://dump", Duration::from_secs(600))
+//!     .with_max_bytes(16 * 1024 * 1024);
+//!
+//! let server = McpServerBuilder::new()
+//!     .with_info("Reports", "1.0.0")
+//!     .with_resources()
+//!     .with_resource_cache(cache)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::context::Principal;
+use crate::handlers::ResourceHandler;
+use crate::notifications::{NotificationSender, RESOURCES_LIST_CHANGED, RESOURCES_UPDATED};
+use mocopr_core::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+/// Cheap indicators of whether a resource changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    async fn of(handler: &dyn ResourceHandler) -> Result<Self> {
+        Ok(Self {
+            etag: handler.etag().await?,
+            last_modified: handler.last_modified().await?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Check whether the validators show the content is unchanged
+    fn confirm(&self, current: &Validators) -> bool {
+        match (&self.etag, &current.etag) {
+            (Some(cached), Some(current)) => return cached == current,
+            (None, None) => {}
+            _ => return false,
+        }
+        matches!(
+            (self.last_modified, current.last_modified),
+            (Some(cached), Some(current)) if cached == current
+        )
+    }
+}
+
+/// A cached read
+struct Entry {
+    contents: Vec<ResourceContent>,
+    validators: Validators,
+    expires_at: Instant,
+    size: usize,
+    last_used: u64,
+}
+
+/// Identifies a cached read
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    /// Subject of the principal the contents were read for, `None` for
+    /// anonymous clients and shared entries
+    principal: Option<String>,
+    uri: String,
+}
+
+/// How often a resource was invalidated, to tell whether a read that was
+/// running meanwhile is stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Generation {
+    /// Times the whole cache was cleared
+    epoch: u64,
+    /// Times the resource was invalidated since the last clear
+    uri: u64,
+}
+
+/// State of the cache guarded by one lock
+#[derive(Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    /// Keys by the tick they were last used at, oldest first
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+    bytes: usize,
+    invalidations: Option<broadcast::Receiver<JsonRpcNotification>>,
+    epoch: u64,
+    /// Invalidations per URI since the last clear
+    generations: HashMap<String, u64>,
+}
+
+impl State {
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.recency.insert(tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn generation(&self, uri: &str) -> Generation {
+        Generation {
+            epoch: self.epoch,
+            uri: self.generations.get(uri).copied().unwrap_or_default(),
+        }
+    }
+
+    /// Remove the entries of `uri` for every principal
+    fn remove_uri(&mut self, uri: &str) {
+        *self.generations.entry(uri.to_string()).or_default() += 1;
+        let keys: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|key| key.uri == uri)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.epoch += 1;
+        self.generations.clear();
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+
+    /// Apply the change notifications sent since the last lookup
+    fn invalidate(&mut self) {
+        let Some(receiver) = self.invalidations.as_mut() else {
+            return;
+        };
+
+        let mut stale = Vec::new();
+        let mut everything = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(notification) if notification.method == RESOURCES_UPDATED => {
+                    if let Some(uri) = notification
+                        .params
+                        .as_ref()
+                        .and_then(|params| params.get("uri"))
+                        .and_then(|uri| uri.as_str())
+                    {
+                        stale.push(uri.to_string());
+                    }
+                }
+                Ok(notification) if notification.method == RESOURCES_LIST_CHANGED => {
+                    everything = true;
+                }
+                Ok(_) => {}
+                // Missed notifications could have been updates
+                Err(TryRecvError::Lagged(_)) => everything = true,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+            }
+        }
+
+        if everything {
+            self.clear();
+        } else {
+            for uri in stale {
+                self.remove_uri(&uri);
+            }
+        }
+    }
+}
+
+/// What a lookup found
+enum Lookup {
+    Fresh(Vec<ResourceContent>),
+    Expired(Vec<ResourceContent>, Validators),
+    Missing,
+}
+
+/// Size-bounded cache of resource contents
+///
+/// Nothing is cached unless a TTL applies to the resource: the one set with
+/// [`with_ttl`](Self::with_ttl), else the one returned by
+/// [`ResourceHandler::cache_ttl`], else the default TTL.
+pub struct ResourceCache {
+    default_ttl: Option<Duration>,
+    shared: bool,
+    ttls: HashMap<String, Duration>,
+    max_entries: usize,
+    max_bytes: usize,
+    state: Mutex<State>,
+}
+
+impl ResourceCache {
+    /// Default maximum number of cached resources
+    pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+    /// Default maximum total size of cached contents
+    pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+    /// Create a cache without a default TTL
+    pub fn new() -> Self {
+        Self {
+            default_ttl: None,
+            shared: false,
+            ttls: HashMap::new(),
+            max_entries: Self::DEFAULT_MAX_ENTRIES,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Cache every resource for `ttl` unless told otherwise
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Cache the resource at `uri` for `ttl`
+    pub fn with_ttl(mut self, uri: impl Into<String>, ttl: Duration) -> Self {
+        self.ttls.insert(uri.into(), ttl);
+        self
+    }
+
+    /// Set the maximum number of cached resources
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Set the maximum total size of cached contents in bytes
+    ///
+    /// Contents larger than this are never cached.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Share entries between principals instead of keeping them per principal
+    ///
+    /// **Warning:** with shared entries, contents read for one client are
+    /// served to every other client until they expire, bypassing any access
+    /// checks or per-user filtering the resource applies when read. Only
+    /// enable this when every cached resource returns the same contents to
+    /// everyone who may read it.
+    pub fn with_shared_entries(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Drop cached entries when `notifications` reports a change
+    pub(crate) fn listen(&self, notifications: &NotificationSender) {
+        self.lock().invalidations = Some(notifications.subscribe());
+    }
+
+    /// Drop the cached contents of a resource for every principal
+    pub fn invalidate(&self, uri: &str) {
+        self.lock().remove_uri(uri);
+    }
+
+    /// Drop all cached contents
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Number of cached reads, counting each principal's separately
+    pub fn len(&self) -> usize {
+        let mut state = self.lock();
+        state.invalidate();
+        state.entries.len()
+    }
+
+    /// Check whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read a resource handler through the cache
+    pub(crate) async fn read_handler(
+        &self,
+        uri: &str,
+        handler: &dyn ResourceHandler,
+    ) -> Result<Vec<ResourceContent>> {
+        let Some(ttl) = self
+            .ttls
+            .get(uri)
+            .copied()
+            .or_else(|| handler.cache_ttl())
+            .or(self.default_ttl)
+        else {
+            return handler.read().await;
+        };
+        let key = self.key(uri);
+
+        // Validators are taken before reading, so a change during the read
+        // makes the entry look stale rather than fresh
+        let (lookup, generation) = self.lookup(&key);
+        let validators = match lookup {
+            Lookup::Fresh(contents) => return Ok(contents),
+            Lookup::Expired(contents, cached) => {
+                let current = Validators::of(handler).await?;
+                if cached.confirm(&current) {
+                    self.renew(&key, ttl);
+                    return Ok(contents);
+                }
+                current
+            }
+            Lookup::Missing => Validators::of(handler).await?,
+        };
+        let contents = handler.read().await?;
+        self.insert(key, &contents, validators, ttl, generation);
+        Ok(contents)
+    }
+
+    /// Read a provider resource through the cache
+    ///
+    /// Providers have no validators, so only configured TTLs apply.
+    pub(crate) async fn read_with<F>(&self, uri: &str, read: F) -> Result<Vec<ResourceContent>>
+    where
+        F: Future<Output = Result<Vec<ResourceContent>>>,
+    {
+        let Some(ttl) = self.ttls.get(uri).copied().or(self.default_ttl) else {
+            return read.await;
+        };
+        let key = self.key(uri);
+
+        let (lookup, generation) = self.lookup(&key);
+        if let Lookup::Fresh(contents) = lookup {
+            return Ok(contents);
+        }
+        let contents = read.await?;
+        self.insert(key, &contents, Validators::default(), ttl, generation);
+        Ok(contents)
+    }
+
+    /// Key of `uri` for the principal of the current request
+    fn key(&self, uri: &str) -> Key {
+        let principal = if self.shared {
+            None
+        } else {
+            Principal::current().map(|principal| principal.subject().to_string())
+        };
+        Key {
+            principal,
+            uri: uri.to_string(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Look up a key, along with the generation of its resource to read
+    /// it at
+    fn lookup(&self, key: &Key) -> (Lookup, Generation) {
+        let mut state = self.lock();
+        state.invalidate();
+        let generation = state.generation(&key.uri);
+
+        let Some(entry) = state.entries.get(key) else {
+            return (Lookup::Missing, generation);
+        };
+        let lookup = if entry.expires_at > Instant::now() {
+            Lookup::Fresh(entry.contents.clone())
+        } else if entry.validators.is_empty() {
+            Lookup::Missing
+        } else {
+            Lookup::Expired(entry.contents.clone(), entry.validators.clone())
+        };
+        state.touch(key);
+        (lookup, generation)
+    }
+
+    fn renew(&self, key: &Key, ttl: Duration) {
+        let mut state = self.lock();
+        if let Some(entry) = state.entries.get_mut(key) {
+            entry.expires_at = Instant::now() + ttl;
+        }
+    }
+
+    fn insert(
+        &self,
+        key: Key,
+        contents: &[ResourceContent],
+        validators: Validators,
+        ttl: Duration,
+        generation: Generation,
+    ) {
+        // An entry that expires at once is only useful for revalidation
+        if ttl.is_zero() && validators.is_empty() {
+            return;
+        }
+        let size = serde_json::to_vec(contents).map_or(usize::MAX, |bytes| bytes.len());
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        // Contents read before the resource was invalidated are stale
+        state.invalidate();
+        if state.generation(&key.uri) != generation {
+            return;
+        }
+        state.remove(&key);
+        while state.entries.len() >= self.max_entries || state.bytes + size > self.max_bytes {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.remove(&oldest);
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+        state.recency.insert(tick, key.clone());
+        state.bytes += size;
+        state.entries.insert(
+            key,
+            Entry {
+                contents: contents.to_vec(),
+                validators,
+                expires_at: Instant::now() + ttl,
+                size,
+                last_used: tick,
+            },
+        );
+    }
+}
+
+impl Default for ResourceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(uri: &str) -> Key {
+        Key {
+            principal: None,
+            uri: uri.to_string(),
+        }
+    }
+
+    /// Insert contents read at the current generation of their resource
+    fn put(cache: &ResourceCache, key: Key, contents: &[ResourceContent], ttl: Duration) {
+        let generation = cache.lock().generation(&key.uri);
+        cache.insert(key, contents, Validators::default(), ttl, generation);
+    }
+
+    fn contents(uri: &str, text: &str) -> Vec<ResourceContent> {
+        vec![ResourceContent {
+            uri: url::Url::parse(uri).unwrap(),
+            mime_type: None,
+            contents: vec![Content::from(text.to_string())],
+        }]
+    }
+
+    #[test]
+    fn test_least_recently_used_entries_are_evicted() {
+        let cache = ResourceCache::new().with_max_entries(2);
+        let ttl = Duration::from_secs(60);
+        for uri in ["test://a", "test://b"] {
+            put(&cache, key(uri), &contents(uri, "x"), ttl);
+        }
+
+        // Using `a` makes `b` the eviction candidate
+        assert!(matches!(cache.lookup(&key("test://a")).0, Lookup::Fresh(_)));
+        put(&cache, key("test://c"), &contents("test://c", "x"), ttl);
+
+        assert!(matches!(cache.lookup(&key("test://a")).0, Lookup::Fresh(_)));
+        assert!(matches!(cache.lookup(&key("test://b")).0, Lookup::Missing));
+        assert!(matches!(cache.lookup(&key("test://c")).0, Lookup::Fresh(_)));
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let small = contents("test://small", "x");
+        let limit = serde_json::to_vec(&small).unwrap().len() * 3 / 2;
+        let cache = ResourceCache::new().with_max_bytes(limit);
+        let ttl = Duration::from_secs(60);
+
+        put(
+            &cache,
+            key("test://big"),
+            &contents("test://big", &"x".repeat(limit)),
+            ttl,
+        );
+        assert!(cache.is_empty());
+
+        put(&cache, key("test://small"), &small, ttl);
+        put(
+            &cache,
+            key("test://other"),
+            &contents("test://other", "y"),
+            ttl,
+        );
+        assert_eq!(cache.len(), 1);
+        assert!(matches!(
+            cache.lookup(&key("test://other")).0,
+            Lookup::Fresh(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reads_invalidated_meanwhile_are_not_cached() {
+        let cache = ResourceCache::new().with_default_ttl(Duration::from_secs(60));
+        let notifications = NotificationSender::new();
+        cache.listen(&notifications);
+        let uri = url::Url::parse("test://report").unwrap();
+
+        let read = async {
+            cache.invalidate(uri.as_str());
+            Ok(contents(uri.as_str(), "old"))
+        };
+        cache.read_with(uri.as_str(), read).await.unwrap();
+        assert!(cache.is_empty());
+
+        // Nor when another lookup takes in the change announced meanwhile
+        let read = async {
+            notifications.resource_updated(&uri);
+            assert!(cache.is_empty());
+            Ok(contents(uri.as_str(), "old"))
+        };
+        cache.read_with(uri.as_str(), read).await.unwrap();
+        assert!(cache.is_empty());
+
+        cache
+            .read_with(uri.as_str(), async { Ok(contents(uri.as_str(), "new")) })
+            .await
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+}
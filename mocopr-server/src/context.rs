@@ -0,0 +1,235 @@
+//! Per-request context for handlers
+//!
+//! While a request is being handled, [`RequestContext::current`] gives the
+//! handler access to the request it is serving: the method, the request ID,
+//! the progress token the client attached, the [`Extensions`] of the client's
+//! session and a way to send notifications back to the client that made the
+//! request.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::context::RequestContext;
+//!
+//! async fn index_files(files: &[String]) {
+//!     let context = RequestContext::current();
+//!     for (done, _file) in files.iter().enumerate() {
+//!         // ... index the file ...
+//!         if let Some(context) = &context {
+//!             let _ = context.send_progress(done as f64 + 1.0, Some(files.len() as f64), None);
+//!         }
+//!     }
+//! }
+//! ```
+
+use mocopr_core::prelude::*;
+use std::future::Future;
+use tokio::sync::mpsc;
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// Information about the request currently being handled
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    method: String,
+    request_id: Option<RequestId>,
+    progress_token: Option<ProgressToken>,
+    extensions: Extensions,
+    outgoing: mpsc::UnboundedSender<String>,
+}
+
+impl RequestContext {
+    /// Create a context whose notifications are written to `outgoing`
+    pub(crate) fn new(
+        method: impl Into<String>,
+        request_id: Option<RequestId>,
+        progress_token: Option<ProgressToken>,
+        extensions: Extensions,
+        outgoing: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            request_id,
+            progress_token,
+            extensions,
+            outgoing,
+        }
+    }
+
+    /// Get the context of the request handled by the current task
+    ///
+    /// Returns `None` outside of request handling, and for transports that do
+    /// not provide a per-request context.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run a future with this context and its extensions as the current ones
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        let extensions = self.extensions.clone();
+        CURRENT.scope(self, extensions.scope(future)).await
+    }
+
+    /// Get the method of the request
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Get the ID of the request
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.request_id.as_ref()
+    }
+
+    /// Get the progress token the client attached to the request, if any
+    pub fn progress_token(&self) -> Option<&ProgressToken> {
+        self.progress_token.as_ref()
+    }
+
+    /// Get the typed state of the client's session
+    ///
+    /// The same map is returned by [`Extensions::current`] while the request
+    /// is handled, which is how middleware reaches it.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Send a notification to the client that made the request
+    pub fn notify(&self, notification: JsonRpcNotification) -> Result<()> {
+        let text = serde_json::to_string(&notification)?;
+        self.outgoing
+            .send(text)
+            .map_err(|_| Error::ConnectionClosed)
+    }
+
+    /// Report progress on the request
+    ///
+    /// Does nothing when the client did not ask for progress notifications.
+    pub fn send_progress(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    ) -> Result<()> {
+        let Some(progress_token) = self.progress_token.clone() else {
+            return Ok(());
+        };
+
+        let params = serde_json::to_value(ProgressNotification {
+            progress_token,
+            progress,
+            total,
+            message,
+        })?;
+        self.notify(Protocol::create_notification(
+            "notifications/progress",
+            Some(params),
+        ))
+    }
+}
+
+/// Network address of the client of a session
+///
+/// Network transports store it in the session's [`Extensions`] when the client
+/// connects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientAddr(pub std::net::SocketAddr);
+
+/// Authenticated identity of the client of a session
+///
+/// Stored in the session's [`Extensions`] by whatever authenticated the
+/// client, and used to attribute requests to a subject rather than to a
+/// connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    subject: String,
+    roles: Vec<String>,
+    scopes: Vec<String>,
+    claims: Option<serde_json::Value>,
+    anonymous: bool,
+}
+
+impl Principal {
+    /// Create a principal for the given subject
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            claims: None,
+            anonymous: false,
+        }
+    }
+
+    /// Create the principal of a client let in without credentials
+    ///
+    /// Its subject is `anonymous`.
+    pub fn anonymous() -> Self {
+        Self {
+            anonymous: true,
+            ..Self::new("anonymous")
+        }
+    }
+
+    /// Set the roles granted to the principal
+    pub fn with_roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the scopes the principal's credentials were issued for
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Attach the claims of the token the principal was authenticated with
+    pub fn with_claims(mut self, claims: serde_json::Value) -> Self {
+        self.claims = Some(claims);
+        self
+    }
+
+    /// Get the principal of the session whose message is being handled
+    pub fn current() -> Option<Self> {
+        Extensions::current()?.get()
+    }
+
+    /// Get the identifier of the subject, e.g. a user name or key ID
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Get the roles granted to the principal
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    /// Get the scopes the principal's credentials were issued for
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    /// Check whether the principal's credentials were issued for `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+
+    /// Check whether the client was let in without credentials
+    pub fn is_anonymous(&self) -> bool {
+        self.anonymous
+    }
+
+    /// Get the token claims, if the principal was authenticated with a token
+    pub fn claims(&self) -> Option<&serde_json::Value> {
+        self.claims.as_ref()
+    }
+}
@@ -0,0 +1,639 @@
+//! Directory-backed resource provider
+//!
+//! [`DirectoryResourceProvider`] exposes every file below a root directory as an
+//! MCP resource. Files are either enumerated in `resources/list` or advertised
+//! through a single `file://` resource template, MIME types are guessed from
+//! file extensions, and binary files are returned as base64 blob contents.
+//!
+//! All access is checked with a [`SecurityValidator`] whose root directory is
+//! pinned to the provider root, so path traversal and oversized files are
+//! rejected before any content is read. When watching is enabled, file system
+//! events (inotify on Linux) are turned into `notifications/resources/list_changed`
+//! and `notifications/resources/updated` notifications.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let docs = DirectoryResourceProvider::new("./docs", "Documentation")?
+//!     .with_description("Project documentation");
+//!
+//! let server = McpServerBuilder::new()
+//!     .with_info("Docs Server", "1.0.0")
+//!     .with_resources()
+//!     .with_resource_provider(docs)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::handlers::ResourceProvider;
+use crate::notifications::NotificationSender;
+use async_trait::async_trait;
+use base64::Engine;
+use mocopr_core::prelude::*;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// How a directory is exposed to clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectoryExposure {
+    /// Every file is listed individually in `resources/list`
+    Enumerated,
+    /// A single `file://` URI template is listed in `resources/templates/list`
+    Template,
+}
+
+/// Resource provider that exposes a directory tree
+pub struct DirectoryResourceProvider {
+    root: PathBuf,
+    root_uri: url::Url,
+    name: String,
+    description: Option<String>,
+    exposure: DirectoryExposure,
+    max_depth: Option<usize>,
+    include_hidden: bool,
+    watch_enabled: bool,
+    validator: Arc<SecurityValidator>,
+    subscriptions: Arc<RwLock<HashSet<String>>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl DirectoryResourceProvider {
+    /// Create a provider for the directory at `root`.
+    ///
+    /// The directory must exist. Files are enumerated individually and the
+    /// directory is watched for changes by default.
+    pub fn new(root: impl AsRef<Path>, name: impl Into<String>) -> Result<Self> {
+        let root = std::fs::canonicalize(root.as_ref()).map_err(|e| {
+            Error::resource_access(format!(
+                "Failed to open directory '{}': {}",
+                root.as_ref().display(),
+                e
+            ))
+        })?;
+
+        if !root.is_dir() {
+            return Err(Error::resource_access(format!(
+                "'{}' is not a directory",
+                root.display()
+            )));
+        }
+
+        let root_uri = url::Url::from_directory_path(&root).map_err(|_| {
+            Error::resource_access(format!("Cannot build a file URI for '{}'", root.display()))
+        })?;
+
+        Ok(Self {
+            validator: Arc::new(SecurityValidator::new().with_root_directory(root.clone())),
+            root,
+            root_uri,
+            name: name.into(),
+            description: None,
+            exposure: DirectoryExposure::Enumerated,
+            max_depth: None,
+            include_hidden: false,
+            watch_enabled: true,
+            subscriptions: Arc::new(RwLock::new(HashSet::new())),
+            watcher: Mutex::new(None),
+        })
+    }
+
+    /// Set a description used for the resource template
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Choose how the directory is exposed
+    pub fn with_exposure(mut self, exposure: DirectoryExposure) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Expose the directory as a `file://` URI template instead of enumerating files
+    pub fn as_template(self) -> Self {
+        self.with_exposure(DirectoryExposure::Template)
+    }
+
+    /// Limit how many directory levels below the root are enumerated
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Include files and directories whose names start with a dot
+    pub fn with_hidden_files(mut self, include: bool) -> Self {
+        self.include_hidden = include;
+        self
+    }
+
+    /// Enable or disable file system watching
+    pub fn with_watching(mut self, enabled: bool) -> Self {
+        self.watch_enabled = enabled;
+        self
+    }
+
+    /// Use a custom security validator
+    ///
+    /// The validator's root directory is always replaced with the provider root
+    /// so that files outside the directory can never be served.
+    pub fn with_security_validator(mut self, validator: SecurityValidator) -> Self {
+        self.validator = Arc::new(validator.with_root_directory(self.root.clone()));
+        self
+    }
+
+    /// Get the canonical root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the URI template advertised in template mode
+    pub fn uri_template(&self) -> String {
+        format!("{}{{path}}", self.root_uri)
+    }
+
+    fn is_hidden(&self, path: &Path) -> bool {
+        is_hidden(&self.root, path)
+    }
+
+    /// Check a path against the security validator, returning a MoCoPr error
+    fn validate(&self, path: &Path) -> Result<()> {
+        self.validator
+            .validate_file_path(path)
+            .map_err(|e| e.downcast::<Error>().unwrap_or_else(Error::Other))
+    }
+
+    /// Walk the directory tree on the blocking thread pool
+    async fn collect_files(&self) -> Result<Vec<PathBuf>> {
+        let root = self.root.clone();
+        let validator = self.validator.clone();
+        let max_depth = self.max_depth;
+        let include_hidden = self.include_hidden;
+
+        tokio::task::spawn_blocking(move || {
+            collect_files(&root, &validator, max_depth, include_hidden)
+        })
+        .await
+        .map_err(|e| Error::internal(format!("Directory listing failed: {}", e)))
+    }
+
+    fn describe(&self, path: &Path) -> Option<Resource> {
+        let uri = url::Url::from_file_path(path).ok()?;
+        let name = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        let mut resource = Resource::new(uri, name);
+        if let Some(mime) = guess_mime_type(path) {
+            resource = resource.with_mime_type(mime);
+        }
+        Some(resource)
+    }
+}
+
+#[async_trait]
+impl ResourceProvider for DirectoryResourceProvider {
+    async fn list(&self) -> Result<Vec<Resource>> {
+        if self.exposure == DirectoryExposure::Template {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .collect_files()
+            .await?
+            .iter()
+            .filter_map(|path| self.describe(path))
+            .collect())
+    }
+
+    async fn templates(&self) -> Vec<ResourceTemplate> {
+        if self.exposure != DirectoryExposure::Template {
+            return Vec::new();
+        }
+
+        let mut template = ResourceTemplate::new(self.uri_template(), self.name.clone());
+        if let Some(description) = &self.description {
+            template = template.with_description(description.clone());
+        }
+        vec![template]
+    }
+
+    fn handles(&self, uri: &url::Url) -> bool {
+        uri.scheme() == "file"
+            && uri
+                .to_file_path()
+                .map(|path| path.starts_with(&self.root))
+                .unwrap_or(false)
+    }
+
+    async fn read(&self, uri: &url::Url) -> Result<Vec<ResourceContent>> {
+        let path = uri
+            .to_file_path()
+            .map_err(|_| Error::invalid_params(format!("Not a file URI: {}", uri)))?;
+
+        if !self.include_hidden && self.is_hidden(&path) {
+            return Err(Error::Protocol(
+                mocopr_core::error::ProtocolError::ResourceNotFound(uri.to_string()),
+            ));
+        }
+
+        let metadata = tokio::fs::metadata(&path).await.map_err(|_| {
+            Error::Protocol(mocopr_core::error::ProtocolError::ResourceNotFound(
+                uri.to_string(),
+            ))
+        })?;
+
+        if !metadata.is_file() {
+            return Err(Error::Protocol(
+                mocopr_core::error::ProtocolError::ResourceNotFound(uri.to_string()),
+            ));
+        }
+
+        self.validate(&path)?;
+        self.validator
+            .validate_file_size(metadata.len())
+            .map_err(|e| e.downcast::<Error>().unwrap_or_else(Error::Other))?;
+
+        let bytes = tokio::fs::read(&path).await.map_err(|e| {
+            Error::resource_access(format!("Failed to read '{}': {}", path.display(), e))
+        })?;
+
+        let mime_type = guess_mime_type(&path);
+        let content = encode_content(uri, bytes, mime_type.as_deref());
+        let mime_type = mime_type.or_else(|| match &content {
+            Content::Text(_) => Some("text/plain".to_string()),
+            _ => Some("application/octet-stream".to_string()),
+        });
+
+        let mut resource_content = ResourceContent::new(uri.clone(), vec![content]);
+        resource_content.mime_type = mime_type;
+        Ok(vec![resource_content])
+    }
+
+    fn supports_subscription(&self) -> bool {
+        true
+    }
+
+    async fn subscribe(&self, uri: &url::Url) -> Result<()> {
+        self.subscriptions
+            .write()
+            .map_err(|_| Error::internal("Subscription lock poisoned"))?
+            .insert(uri.to_string());
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, uri: &url::Url) -> Result<()> {
+        self.subscriptions
+            .write()
+            .map_err(|_| Error::internal("Subscription lock poisoned"))?
+            .remove(uri.as_str());
+        Ok(())
+    }
+
+    fn watch(&self, notifications: NotificationSender) -> Result<()> {
+        if !self.watch_enabled {
+            return Ok(());
+        }
+
+        let root = self.root.clone();
+        let include_hidden = self.include_hidden;
+        let enumerated = self.exposure == DirectoryExposure::Enumerated;
+        let subscriptions = self.subscriptions.clone();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("File watcher error for '{}': {}", root.display(), e);
+                        return;
+                    }
+                };
+
+                let paths: Vec<&PathBuf> = event
+                    .paths
+                    .iter()
+                    .filter(|path| {
+                        include_hidden
+                            || !path.strip_prefix(&root).is_ok_and(|relative| {
+                                relative
+                                    .components()
+                                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                            })
+                    })
+                    .collect();
+
+                if paths.is_empty() {
+                    return;
+                }
+
+                debug!("File system event {:?} for {:?}", event.kind, paths);
+
+                let structural = matches!(
+                    event.kind,
+                    EventKind::Create(_)
+                        | EventKind::Remove(_)
+                        | EventKind::Modify(ModifyKind::Name(_))
+                );
+
+                if structural && enumerated {
+                    notifications.resource_list_changed();
+                }
+
+                if matches!(event.kind, EventKind::Modify(_) | EventKind::Remove(_)) {
+                    let Ok(subscribed) = subscriptions.read() else {
+                        return;
+                    };
+                    for path in paths {
+                        if let Ok(uri) = url::Url::from_file_path(path)
+                            && subscribed.contains(uri.as_str())
+                        {
+                            notifications.resource_updated(&uri);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| Error::internal(format!("Failed to create file watcher: {}", e)))?;
+
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .map_err(|e| {
+                Error::internal(format!(
+                    "Failed to watch directory '{}': {}",
+                    self.root.display(),
+                    e
+                ))
+            })?;
+
+        *self
+            .watcher
+            .lock()
+            .map_err(|_| Error::internal("Watcher lock poisoned"))? = Some(watcher);
+
+        Ok(())
+    }
+}
+
+/// Whether any component of `path` below `root` starts with a dot
+fn is_hidden(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .map(|relative| {
+            relative
+                .components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        })
+        .unwrap_or(false)
+}
+
+/// List the files below `root` that the validator allows, in sorted order
+fn collect_files(
+    root: &Path,
+    validator: &SecurityValidator,
+    max_depth: Option<usize>,
+    include_hidden: bool,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read directory '{}': {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !include_hidden && is_hidden(root, &path) {
+                continue;
+            }
+
+            // Do not follow directory symlinks to avoid cycles
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                if max_depth.is_none_or(|max| depth < max) {
+                    pending.push((path, depth + 1));
+                }
+            } else if validator.validate_file_path(&path).is_ok() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Guess the MIME type of a file from its extension
+fn guess_mime_type(path: &Path) -> Option<String> {
+    mime_guess::from_path(path)
+        .first()
+        .map(|mime| mime.essence_str().to_string())
+}
+
+/// Check whether a MIME type describes textual content
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/toml"
+                | "application/x-yaml"
+                | "application/yaml"
+                | "image/svg+xml"
+        )
+}
+
+/// Turn raw file bytes into text content when possible, otherwise base64
+/// encoded image content or an embedded binary resource
+fn encode_content(uri: &url::Url, bytes: Vec<u8>, mime_type: Option<&str>) -> Content {
+    let textual = mime_type.is_none_or(is_text_mime) && !bytes.contains(&0);
+
+    if textual {
+        match String::from_utf8(bytes) {
+            Ok(text) => return Content::Text(TextContent::new(text)),
+            Err(e) => return binary_content(uri, e.into_bytes(), mime_type),
+        }
+    }
+
+    binary_content(uri, bytes, mime_type)
+}
+
+fn binary_content(uri: &url::Url, bytes: Vec<u8>, mime_type: Option<&str>) -> Content {
+    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+    let mime_type = mime_type.unwrap_or("application/octet-stream");
+    if mime_type.starts_with("image/") {
+        Content::Image(ImageContent::new(data, mime_type))
+    } else {
+        Content::Resource(EmbeddedResource::blob(uri.clone(), data).with_mime_type(mime_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn validator() -> SecurityValidator {
+        SecurityValidator::new().with_allowed_extensions(vec![
+            "txt".to_string(),
+            "md".to_string(),
+            "png".to_string(),
+        ])
+    }
+
+    fn setup() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("readme.md"), "# Title").unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested/notes.txt"), "notes").unwrap();
+        std::fs::write(dir.path().join("image.png"), [0x89, b'P', b'N', b'G', 0, 1]).unwrap();
+        std::fs::write(dir.path().join(".secret.txt"), "hidden").unwrap();
+        std::fs::write(dir.path().join("script.sh"), "echo hi").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_enumerates_allowed_files() {
+        let dir = setup();
+        let provider = DirectoryResourceProvider::new(dir.path(), "files")
+            .unwrap()
+            .with_security_validator(validator())
+            .with_watching(false);
+
+        let names: Vec<String> = provider
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+
+        assert_eq!(names, vec!["image.png", "nested/notes.txt", "readme.md"]);
+        assert!(provider.templates().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reads_text_and_binary_files() {
+        let dir = setup();
+        let provider = DirectoryResourceProvider::new(dir.path(), "files")
+            .unwrap()
+            .with_security_validator(validator())
+            .with_watching(false);
+
+        let text_uri = url::Url::from_file_path(provider.root().join("readme.md")).unwrap();
+        let contents = provider.read(&text_uri).await.unwrap();
+        assert_eq!(contents[0].mime_type.as_deref(), Some("text/markdown"));
+        assert!(matches!(&contents[0].contents[0], Content::Text(t) if t.text == "# Title"));
+
+        let image_uri = url::Url::from_file_path(provider.root().join("image.png")).unwrap();
+        let contents = provider.read(&image_uri).await.unwrap();
+        match &contents[0].contents[0] {
+            Content::Image(image) => {
+                assert_eq!(image.data, "iVBORwAB");
+                assert_eq!(image.mime_type, "image/png");
+            }
+            other => panic!("Expected image content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_binary_contents_use_spec_content_types() {
+        let uri = url::Url::parse("file:///srv/archive.zip").unwrap();
+        let content = encode_content(&uri, vec![0x50, 0x4b, 0, 1], Some("application/zip"));
+        let json = serde_json::to_value(&content).unwrap();
+        assert_eq!(json["type"], "resource");
+        assert_eq!(json["resource"]["uri"], "file:///srv/archive.zip");
+        assert_eq!(json["resource"]["mimeType"], "application/zip");
+        assert_eq!(json["resource"]["blob"], "UEsAAQ==");
+
+        let content = encode_content(&uri, vec![0xff, 0xfe], None);
+        let json = serde_json::to_value(&content).unwrap();
+        assert_eq!(json["type"], "resource");
+        assert_eq!(json["resource"]["mimeType"], "application/octet-stream");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_files_outside_policy() {
+        let dir = setup();
+        let provider = DirectoryResourceProvider::new(dir.path(), "files")
+            .unwrap()
+            .with_security_validator(validator().with_max_file_size(4))
+            .with_watching(false);
+
+        let large = url::Url::from_file_path(provider.root().join("readme.md")).unwrap();
+        assert!(provider.read(&large).await.is_err());
+
+        let script = url::Url::from_file_path(provider.root().join("script.sh")).unwrap();
+        assert!(provider.read(&script).await.is_err());
+
+        let hidden = url::Url::from_file_path(provider.root().join(".secret.txt")).unwrap();
+        assert!(provider.read(&hidden).await.is_err());
+
+        let outside = url::Url::parse("file:///etc/passwd").unwrap();
+        assert!(!provider.handles(&outside));
+    }
+
+    #[tokio::test]
+    async fn test_template_exposure() {
+        let dir = setup();
+        let provider = DirectoryResourceProvider::new(dir.path(), "files")
+            .unwrap()
+            .as_template()
+            .with_watching(false);
+
+        assert!(provider.list().await.unwrap().is_empty());
+        let templates = provider.templates().await;
+        assert_eq!(templates.len(), 1);
+        assert!(templates[0].uri_template.ends_with("/{path}"));
+    }
+
+    #[tokio::test]
+    async fn test_watch_emits_notifications() {
+        let dir = setup();
+        let provider = DirectoryResourceProvider::new(dir.path(), "files")
+            .unwrap()
+            .with_security_validator(validator());
+
+        let notifications = NotificationSender::new();
+        let mut receiver = notifications.subscribe();
+        provider.watch(notifications).unwrap();
+
+        let notes = provider.root().join("nested/notes.txt");
+        let uri = url::Url::from_file_path(&notes).unwrap();
+        provider.subscribe(&uri).await.unwrap();
+
+        std::fs::write(provider.root().join("new.txt"), "new").unwrap();
+        std::fs::write(&notes, "changed").unwrap();
+
+        let mut methods = HashSet::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while methods.len() < 2 {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Ok(notification)) => {
+                    methods.insert(notification.method);
+                }
+                _ => break,
+            }
+        }
+
+        assert!(methods.contains(crate::notifications::RESOURCES_LIST_CHANGED));
+        assert!(methods.contains(crate::notifications::RESOURCES_UPDATED));
+    }
+}
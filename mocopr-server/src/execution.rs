@@ -0,0 +1,375 @@
+//! Per-tool execution policies
+//!
+//! A [`ToolExecutionPolicy`] bounds how a single tool may use the server: how
+//! long one invocation may run, how many invocations run at the same time, and
+//! how many more may wait for a free slot. Invocations beyond the queue limit
+//! are rejected immediately instead of piling up behind a slow tool.
+//!
+//! Rejected invocations fail with `-32006` (server busy). The `data` of the
+//! error names the `tool` and its `maxConcurrency` and `maxQueue`, and holds
+//! `retryAfter` and `retryAfterMs`, a hint in whole seconds and milliseconds
+//! for when to try again.
+//!
+//! Policies come from [`ToolHandler::execution_policy`](crate::handlers::ToolHandler::execution_policy)
+//! (for example via `#[tool(timeout_ms = 5000, max_concurrency = 2, max_queue = 8)]`)
+//! or from [`McpServerBuilder::with_tool_policy`](crate::builder::McpServerBuilder::with_tool_policy),
+//! which takes precedence.
+
+use mocopr_core::error::ProtocolError;
+use mocopr_core::monitoring::MonitoringSystem;
+use mocopr_core::prelude::*;
+use serde_json::json;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+/// How long rejected callers are asked to wait before retrying
+const RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Limits applied to invocations of a single tool
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolExecutionPolicy {
+    /// Maximum time a single invocation may run
+    pub timeout: Option<Duration>,
+    /// Maximum number of invocations running at the same time
+    pub max_concurrency: Option<usize>,
+    /// Maximum number of invocations waiting for a free slot
+    ///
+    /// Only meaningful together with `max_concurrency`. Defaults to zero, so
+    /// invocations beyond the concurrency limit are rejected right away.
+    pub max_queue: Option<usize>,
+}
+
+impl ToolExecutionPolicy {
+    /// Create a policy without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum execution time
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum number of concurrent invocations
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+
+    /// Set the maximum number of queued invocations
+    pub fn with_max_queue(mut self, max_queue: usize) -> Self {
+        self.max_queue = Some(max_queue);
+        self
+    }
+}
+
+/// Enforces a [`ToolExecutionPolicy`] for one tool
+pub(crate) struct ToolLimiter {
+    tool: String,
+    policy: ToolExecutionPolicy,
+    permits: Option<Arc<Semaphore>>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl ToolLimiter {
+    pub(crate) fn new(tool: impl Into<String>, policy: ToolExecutionPolicy) -> Self {
+        Self {
+            tool: tool.into(),
+            permits: policy
+                .max_concurrency
+                .map(|max| Arc::new(Semaphore::new(max))),
+            policy,
+            queued: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Run an invocation under the policy
+    ///
+    /// Returns a server busy error when the queue is full and a tool error
+    /// result when the invocation exceeds its time limit.
+    pub(crate) async fn run<F>(
+        &self,
+        monitoring: Option<&MonitoringSystem>,
+        invocation: F,
+    ) -> Result<ToolsCallResponse>
+    where
+        F: Future<Output = Result<ToolsCallResponse>>,
+    {
+        let _permit = self.acquire(monitoring).await?;
+
+        let Some(timeout) = self.policy.timeout else {
+            return invocation.await;
+        };
+
+        match tokio::time::timeout(timeout, invocation).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Tool '{}' timed out after {:?}", self.tool, timeout);
+                if let Some(monitoring) = monitoring {
+                    monitoring.record_tool_timeout(&self.tool).await;
+                }
+                Ok(ToolsCallResponse::error(vec![Content::Text(
+                    TextContent::new(format!(
+                        "Tool '{}' timed out after {} ms",
+                        self.tool,
+                        timeout.as_millis()
+                    )),
+                )]))
+            }
+        }
+    }
+
+    async fn acquire(
+        &self,
+        monitoring: Option<&MonitoringSystem>,
+    ) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(permits) = &self.permits else {
+            return Ok(None);
+        };
+
+        if let Ok(permit) = permits.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+
+        let max_queue = self.policy.max_queue.unwrap_or(0);
+        let reserved = self
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < max_queue).then_some(queued + 1)
+            });
+
+        let reservation = match reserved {
+            Ok(previous) => QueueSlot {
+                tool: self.tool.clone(),
+                queued: self.queued.clone(),
+                monitoring: monitoring.cloned(),
+                depth: previous + 1,
+                left: false,
+            },
+            Err(_) => {
+                if let Some(monitoring) = monitoring {
+                    monitoring.record_tool_rejection(&self.tool).await;
+                }
+                let retry_after = RETRY_AFTER.as_millis() as u64;
+                return Err(Error::Protocol(ProtocolError::ServerBusy(format!(
+                    "Tool '{}' is at capacity, try again later",
+                    self.tool
+                )))
+                .with_data(json!({
+                    "tool": self.tool,
+                    "maxConcurrency": self.policy.max_concurrency,
+                    "maxQueue": max_queue,
+                    "retryAfter": retry_after.div_ceil(1000),
+                    "retryAfterMs": retry_after,
+                })));
+            }
+        };
+
+        if let Some(monitoring) = monitoring {
+            monitoring
+                .record_tool_queue_depth(&self.tool, reservation.depth)
+                .await;
+        }
+
+        // A caller cancelled while waiting gives up its slot when dropped
+        let permit = permits.clone().acquire_owned().await;
+        reservation.leave().await;
+
+        permit
+            .map(Some)
+            .map_err(|_| Error::internal(format!("Tool '{}' limiter closed", self.tool)))
+    }
+}
+
+/// A place in the queue of a [`ToolLimiter`], given up when dropped
+struct QueueSlot {
+    tool: String,
+    queued: Arc<AtomicUsize>,
+    monitoring: Option<MonitoringSystem>,
+    depth: usize,
+    left: bool,
+}
+
+impl QueueSlot {
+    /// Leave the queue after getting a permit
+    async fn leave(mut self) {
+        self.left = true;
+        let depth = self.queued.fetch_sub(1, Ordering::AcqRel) - 1;
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_tool_queue_depth(&self.tool, depth).await;
+        }
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        if self.left {
+            return;
+        }
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+        if let (Some(monitoring), Ok(runtime)) = (
+            self.monitoring.take(),
+            tokio::runtime::Handle::try_current(),
+        ) {
+            let (tool, queued) = (std::mem::take(&mut self.tool), self.queued.clone());
+            runtime.spawn(async move {
+                let depth = queued.load(Ordering::Acquire);
+                monitoring.record_tool_queue_depth(&tool, depth).await;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mocopr_core::monitoring::MonitoringConfig;
+
+    fn ok() -> Result<ToolsCallResponse> {
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_error_result() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        let limiter = ToolLimiter::new(
+            "slow",
+            ToolExecutionPolicy::new().with_timeout(Duration::from_millis(10)),
+        );
+
+        let response = limiter
+            .run(Some(&monitoring), async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                ok()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.is_error, Some(true));
+        assert_eq!(monitoring.get_tool_metrics().await["slow"].timeouts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_rejects_when_full() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        let limiter = Arc::new(ToolLimiter::new(
+            "busy",
+            ToolExecutionPolicy::new()
+                .with_max_concurrency(1)
+                .with_max_queue(1),
+        ));
+        let release = Arc::new(tokio::sync::Notify::new());
+
+        let running = {
+            let (limiter, release, monitoring) =
+                (limiter.clone(), release.clone(), monitoring.clone());
+            tokio::spawn(async move {
+                limiter
+                    .run(Some(&monitoring), async {
+                        release.notified().await;
+                        ok()
+                    })
+                    .await
+            })
+        };
+        while limiter.permits.as_ref().unwrap().available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let queued = {
+            let (limiter, monitoring) = (limiter.clone(), monitoring.clone());
+            tokio::spawn(async move { limiter.run(Some(&monitoring), async { ok() }).await })
+        };
+        while limiter.queued.load(Ordering::Acquire) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let rejected = limiter
+            .run(Some(&monitoring), async { ok() })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejected.inner(),
+            Error::Protocol(ProtocolError::ServerBusy(_))
+        ));
+        assert_eq!(
+            Protocol::error_to_jsonrpc(&rejected).code,
+            mocopr_core::protocol::error_codes::SERVER_BUSY
+        );
+        let data = rejected.data().unwrap();
+        assert_eq!(data["retryAfter"], 1);
+        assert_eq!(data["maxQueue"], 1);
+
+        release.notify_one();
+        assert!(running.await.unwrap().is_ok());
+        assert!(queued.await.unwrap().is_ok());
+
+        let metrics = &monitoring.get_tool_metrics().await["busy"];
+        assert_eq!(metrics.rejections, 1);
+        assert_eq!(metrics.max_queue_depth, 1);
+        assert_eq!(metrics.queue_depth, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_gives_up_its_queue_slot() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        let limiter = Arc::new(ToolLimiter::new(
+            "busy",
+            ToolExecutionPolicy::new()
+                .with_max_concurrency(1)
+                .with_max_queue(1),
+        ));
+        let release = Arc::new(tokio::sync::Notify::new());
+
+        let running = {
+            let (limiter, release, monitoring) =
+                (limiter.clone(), release.clone(), monitoring.clone());
+            tokio::spawn(async move {
+                limiter
+                    .run(Some(&monitoring), async {
+                        release.notified().await;
+                        ok()
+                    })
+                    .await
+            })
+        };
+        while limiter.permits.as_ref().unwrap().available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let queue = || {
+            let (limiter, monitoring) = (limiter.clone(), monitoring.clone());
+            tokio::spawn(async move { limiter.run(Some(&monitoring), async { ok() }).await })
+        };
+        let cancelled = queue();
+        while limiter.queued.load(Ordering::Acquire) == 0 {
+            tokio::task::yield_now().await;
+        }
+        cancelled.abort();
+        assert!(cancelled.await.unwrap_err().is_cancelled());
+        assert_eq!(limiter.queued.load(Ordering::Acquire), 0);
+
+        // The slot is free again for the next caller
+        let queued = queue();
+        while limiter.queued.load(Ordering::Acquire) == 0 {
+            tokio::task::yield_now().await;
+        }
+        release.notify_one();
+        assert!(running.await.unwrap().is_ok());
+        assert!(queued.await.unwrap().is_ok());
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while monitoring.get_tool_metrics().await["busy"].queue_depth != 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("queue depth was not brought back to zero");
+        assert_eq!(monitoring.get_tool_metrics().await["busy"].rejections, 0);
+    }
+}
@@ -1,8 +1,14 @@
 //! Handler traits and implementations for MCP server features
 
+use crate::execution::ToolExecutionPolicy;
+use crate::notifications::NotificationSender;
 use async_trait::async_trait;
 use mocopr_core::prelude::*;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
 /// Trait for handling resource operations
 #[async_trait]
@@ -27,6 +33,73 @@ pub trait ResourceHandler: Send + Sync {
     async fn unsubscribe(&self) -> Result<()> {
         Err(Error::MethodNotFound("unsubscribe".to_string()))
     }
+
+    /// Get how long reads of this resource may be cached
+    ///
+    /// Only used when the server has a [`ResourceCache`](crate::cache::ResourceCache);
+    /// a TTL configured on the cache for this URI takes precedence.
+    fn cache_ttl(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Get an entity tag that changes whenever the content changes
+    ///
+    /// Lets the cache keep an expired entry without reading the resource again.
+    async fn etag(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Get the time the content last changed
+    ///
+    /// Used like [`etag`](Self::etag) by handlers that have no entity tag.
+    async fn last_modified(&self) -> Result<Option<std::time::SystemTime>> {
+        Ok(None)
+    }
+}
+
+/// Trait for handling a dynamic set of resources
+///
+/// Unlike [`ResourceHandler`], which serves exactly one URI, a provider owns a
+/// whole family of resources (for example every file below a directory). The
+/// registry consults providers for any URI that no single handler claims.
+#[async_trait]
+pub trait ResourceProvider: Send + Sync {
+    /// List the resources currently offered by this provider
+    async fn list(&self) -> Result<Vec<Resource>>;
+
+    /// List the resource templates offered by this provider
+    async fn templates(&self) -> Vec<ResourceTemplate> {
+        Vec::new()
+    }
+
+    /// Check whether this provider is responsible for the given URI
+    fn handles(&self, uri: &url::Url) -> bool;
+
+    /// Read the content of a resource owned by this provider
+    async fn read(&self, uri: &url::Url) -> Result<Vec<ResourceContent>>;
+
+    /// Check if the provider supports subscriptions
+    fn supports_subscription(&self) -> bool {
+        false
+    }
+
+    /// Subscribe to updates of a resource owned by this provider
+    async fn subscribe(&self, _uri: &url::Url) -> Result<()> {
+        Err(Error::MethodNotFound("subscribe".to_string()))
+    }
+
+    /// Unsubscribe from updates of a resource owned by this provider
+    async fn unsubscribe(&self, _uri: &url::Url) -> Result<()> {
+        Err(Error::MethodNotFound("unsubscribe".to_string()))
+    }
+
+    /// Start emitting change notifications through the given sender
+    ///
+    /// Called once when the provider is registered. Providers that cannot
+    /// detect changes keep the default no-op implementation.
+    fn watch(&self, _notifications: NotificationSender) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Trait for handling tool operations
@@ -37,6 +110,44 @@ pub trait ToolHandler: Send + Sync {
 
     /// Execute the tool with given arguments
     async fn call(&self, arguments: Option<serde_json::Value>) -> Result<ToolsCallResponse>;
+
+    /// Get the execution limits for this tool
+    ///
+    /// A policy configured on the server builder takes precedence.
+    fn execution_policy(&self) -> Option<ToolExecutionPolicy> {
+        None
+    }
+}
+
+/// Trait for handling a dynamic set of tools
+///
+/// The registry consults providers for any tool name that no single
+/// [`ToolHandler`] claims. Execution policies configured on the server builder
+/// apply to provider tools as well.
+#[async_trait]
+pub trait ToolProvider: Send + Sync {
+    /// List the tools currently offered by this provider
+    async fn list(&self) -> Result<Vec<Tool>>;
+
+    /// Check whether this provider is responsible for the given tool name
+    fn handles(&self, name: &str) -> bool;
+
+    /// Get the definition of one of the provider's tools, used to validate
+    /// call arguments against its input schema
+    async fn tool(&self, name: &str) -> Result<Option<Tool>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|tool| tool.name == name))
+    }
+
+    /// Execute a tool owned by this provider
+    async fn call(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<ToolsCallResponse>;
 }
 
 /// Trait for handling prompt operations
@@ -52,6 +163,34 @@ pub trait PromptHandler: Send + Sync {
     ) -> Result<PromptsGetResponse>;
 }
 
+/// Trait for handling a dynamic set of prompts
+///
+/// The registry consults providers for any prompt name that no single
+/// [`PromptHandler`] claims.
+#[async_trait]
+pub trait PromptProvider: Send + Sync {
+    /// List the prompts currently offered by this provider
+    async fn list(&self) -> Result<Vec<Prompt>>;
+
+    /// Check whether this provider is responsible for the given prompt name
+    fn handles(&self, name: &str) -> bool;
+
+    /// Generate a prompt owned by this provider
+    async fn generate(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, String>>,
+    ) -> Result<PromptsGetResponse>;
+
+    /// Start emitting change notifications through the given sender
+    ///
+    /// Called once when the provider is registered. Providers whose prompts
+    /// never change keep the default no-op implementation.
+    fn watch(&self, _notifications: NotificationSender) -> Result<()> {
+        Ok(())
+    }
+}
+
 /// File-based resource handler
 pub struct FileResourceHandler {
     uri: url::Url,
@@ -117,6 +256,13 @@ impl ResourceHandler for FileResourceHandler {
 
         Ok(vec![resource_content])
     }
+
+    async fn last_modified(&self) -> Result<Option<std::time::SystemTime>> {
+        Ok(tokio::fs::metadata(&self.file_path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok())
+    }
 }
 
 /// Simple function-based tool handler
@@ -155,18 +301,561 @@ impl ToolHandler for FunctionToolHandler {
     }
 }
 
+/// Tool handler that runs a program
+///
+/// The program is started directly rather than through a shell. Each
+/// argument is a Jinja template rendered with the tool arguments, so
+/// `{{ path }}` always becomes exactly one argument regardless of the spaces
+/// or quotes it contains. Referencing an argument the client did not send is
+/// an [`Error::InvalidParams`]; use `default` or `{% if %}` for optional ones.
+///
+/// The program runs with a cleared environment: only the variables on the
+/// allowlist (`PATH` by default) are passed through, plus any set with
+/// [`with_env`](Self::with_env). Runs are killed after the timeout (30
+/// seconds by default), and each output stream is capped (1 MiB by default);
+/// anything beyond the cap is discarded. On Unix the program runs in its own
+/// process group, and the whole group is killed on timeout or when the call
+/// is cancelled. When the client asked for progress, the lines the program
+/// writes to its standard output are reported as progress notifications, at
+/// most one per [`with_progress_interval`](Self::with_progress_interval)
+/// (100 milliseconds by default) plus the last line.
+///
+/// The result carries the standard output and, if not empty, the standard
+/// error as text content. The structured content holds `stdout`, `stderr`,
+/// `exitCode`, `timedOut` and `truncated`. A non-zero exit status or a
+/// timeout makes the result an error result.
+///
+/// Arguments declared with [`with_path_argument`](Self::with_path_argument)
+/// are resolved with a [`SecurityValidator`], by default one confined to the
+/// working directory that accepts any file extension: relative paths are
+/// resolved against it, `..` components are rejected, and symbolic links are
+/// followed before the check; the program receives the resolved absolute
+/// path. Paths that do not exist yet are accepted, so they can name outputs.
+///
+/// An argument template that is a single placeholder, such as
+/// `{{ files }}`, expands an array argument into one program argument per
+/// element. Values of other arguments may not start with `-` where they
+/// begin a program argument, so clients cannot pass options the template
+/// does not contain.
+pub struct CommandToolHandler {
+    tool_info: Tool,
+    program: String,
+    args: Vec<String>,
+    working_dir: Option<PathBuf>,
+    env_allowlist: Vec<String>,
+    env: Vec<(String, String)>,
+    timeout: Duration,
+    max_output: usize,
+    path_arguments: Vec<String>,
+    validator: Option<Arc<SecurityValidator>>,
+    progress_interval: Duration,
+}
+
+impl CommandToolHandler {
+    /// Default time a run may take
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Default number of bytes kept of each output stream
+    pub const DEFAULT_MAX_OUTPUT: usize = 1024 * 1024;
+
+    /// Default minimum time between two progress notifications
+    pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Create a tool named `name` that runs `program` with `args`
+    pub fn new(
+        name: impl Into<String>,
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            tool_info: Tool::new(name, serde_json::json!({"type": "object"})),
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            working_dir: None,
+            env_allowlist: vec!["PATH".to_string()],
+            env: Vec::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+            max_output: Self::DEFAULT_MAX_OUTPUT,
+            path_arguments: Vec::new(),
+            validator: None,
+            progress_interval: Self::DEFAULT_PROGRESS_INTERVAL,
+        }
+    }
+
+    /// Set the description of the tool
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.tool_info = self.tool_info.with_description(description);
+        self
+    }
+
+    /// Set the JSON schema of the tool arguments
+    pub fn with_input_schema(mut self, input_schema: serde_json::Value) -> Self {
+        self.tool_info.input_schema = input_schema;
+        self
+    }
+
+    /// Set the working directory of the program
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Replace the environment variables passed through from the server
+    pub fn with_env_allowlist(
+        mut self,
+        names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.env_allowlist = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Pass an environment variable of the server through to the program
+    pub fn allow_env(mut self, name: impl Into<String>) -> Self {
+        self.env_allowlist.push(name.into());
+        self
+    }
+
+    /// Set an environment variable for the program
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set the time after which the program is killed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the number of bytes kept of each output stream
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.max_output = bytes;
+        self
+    }
+
+    /// Set the minimum time between two progress notifications
+    pub fn with_progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Require the named argument to be a path inside the working directory
+    ///
+    /// The argument may be a string or an array of strings.
+    pub fn with_path_argument(mut self, name: impl Into<String>) -> Self {
+        self.path_arguments.push(name.into());
+        self
+    }
+
+    /// Resolve path arguments with `validator`, e.g. to limit extensions
+    ///
+    /// Replaces the default validator. A validator without a root directory
+    /// is confined to the working directory.
+    pub fn with_security_validator(mut self, validator: SecurityValidator) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Render the argument templates with the tool arguments
+    fn render_args(&self, arguments: &serde_json::Value) -> Result<Vec<String>> {
+        let mut env = minijinja::Environment::new();
+        env.set_undefined_behavior(minijinja::UndefinedBehavior::SemiStrict);
+        let mut rendered = Vec::new();
+        for arg in &self.args {
+            // A lone placeholder of an array expands to one argument per item
+            if let Some(serde_json::Value::Array(items)) =
+                placeholder(arg).and_then(|name| arguments.get(name))
+            {
+                for item in items {
+                    rendered.push(self.check_option(arg, scalar_arg(item))?);
+                }
+                continue;
+            }
+            let value = env.render_str(arg, arguments).map_err(|e| {
+                Error::InvalidParams(format!(
+                    "Cannot build arguments for tool '{}': {}",
+                    self.tool_info.name, e
+                ))
+            })?;
+            rendered.push(self.check_option(arg, value)?);
+        }
+        Ok(rendered)
+    }
+
+    /// Reject `value`, rendered from `template`, if an argument value made it
+    /// look like an option
+    fn check_option(&self, template: &str, value: String) -> Result<String> {
+        if value.starts_with('-') && !template.starts_with('-') {
+            return Err(Error::InvalidParams(format!(
+                "Arguments of tool '{}' cannot start with '-': '{}'",
+                self.tool_info.name, value
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Resolve the path arguments with the security validator, returning the
+    /// arguments with each path replaced by its resolved form
+    fn resolve_paths(&self, mut arguments: serde_json::Value) -> Result<serde_json::Value> {
+        if self.path_arguments.is_empty() {
+            return Ok(arguments);
+        }
+
+        let root = match &self.working_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir()?,
+        };
+        let validator = match &self.validator {
+            Some(validator) if validator.root_directory.is_some() => validator.as_ref().clone(),
+            Some(validator) => validator.as_ref().clone().with_root_directory(root),
+            None => SecurityValidator::new()
+                .with_root_directory(root)
+                .with_allowed_extensions(vec!["*".to_string()]),
+        };
+
+        for name in &self.path_arguments {
+            let resolve = |value: &mut serde_json::Value| -> Result<()> {
+                let serde_json::Value::String(path) = value else {
+                    return Err(Error::InvalidParams(format!(
+                        "Argument '{name}' must contain paths"
+                    )));
+                };
+                let resolved = validator
+                    .resolve_path(Path::new(path))
+                    .map_err(|e| Error::security(format!("Argument '{name}': {e}")))?;
+                *path = resolved.to_string_lossy().into_owned();
+                Ok(())
+            };
+
+            match arguments.get_mut(name) {
+                None | Some(serde_json::Value::Null) => {}
+                Some(serde_json::Value::Array(items)) => {
+                    items.iter_mut().try_for_each(resolve)?;
+                }
+                Some(value @ serde_json::Value::String(_)) => resolve(value)?,
+                Some(_) => {
+                    return Err(Error::InvalidParams(format!(
+                        "Argument '{name}' must be a path"
+                    )));
+                }
+            }
+        }
+        Ok(arguments)
+    }
+}
+
+/// Name of the variable if `template` is nothing but one placeholder, as in
+/// `{{ files }}`
+fn placeholder(template: &str) -> Option<&str> {
+    let name = template
+        .trim()
+        .strip_prefix("{{")?
+        .strip_suffix("}}")?
+        .trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(name)
+}
+
+/// A JSON value as a program argument, with strings unquoted
+fn scalar_arg(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Process group of a running command, killed when dropped
+///
+/// The program's own children share its group, so they do not outlive the
+/// call, whether it ends normally, times out or is cancelled.
+struct ProcessGroup {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    id: Option<u32>,
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(id) = self.id {
+            // SAFETY: killpg has no memory safety requirements; the group was
+            // created for the program and is led by its process ID
+            unsafe {
+                libc::killpg(id as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// Output of one stream of a command, capped at a number of bytes
+struct CapturedOutput {
+    data: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl CapturedOutput {
+    fn new(limit: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            limit,
+            truncated: false,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let room = self.limit.saturating_sub(self.data.len());
+        if bytes.len() > room {
+            self.truncated = true;
+        }
+        self.data.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+
+    /// Read a stream to its end, calling `on_line` for every line
+    async fn read_lines(
+        &mut self,
+        stream: impl tokio::io::AsyncRead + Unpin,
+        mut on_line: impl FnMut(&[u8]),
+    ) -> std::io::Result<()> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+        let mut reader = tokio::io::BufReader::new(stream);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            // Bound the line buffer as well, so a program that never writes a
+            // newline cannot exhaust memory
+            let read = (&mut reader)
+                .take(self.limit.max(1) as u64)
+                .read_until(b'\n', &mut line)
+                .await?;
+            if read == 0 {
+                return Ok(());
+            }
+            on_line(&line);
+            self.push(&line);
+        }
+    }
+}
+
+#[async_trait]
+impl ToolHandler for CommandToolHandler {
+    async fn tool(&self) -> Tool {
+        self.tool_info.clone()
+    }
+
+    async fn call(&self, arguments: Option<serde_json::Value>) -> Result<ToolsCallResponse> {
+        use std::process::Stdio;
+        use tokio::io::AsyncReadExt;
+
+        let arguments = self.resolve_paths(arguments.unwrap_or_else(|| serde_json::json!({})))?;
+        let args = self.render_args(&arguments)?;
+
+        let mut command = tokio::process::Command::new(&self.program);
+        command
+            .args(&args)
+            .env_clear()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+        for name in &self.env_allowlist {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| Error::Internal(format!("Failed to run '{}': {}", self.program, e)))?;
+        let group = ProcessGroup { id: child.id() };
+        let stdout_pipe = child.stdout.take();
+        let stderr_pipe = child.stderr.take();
+
+        let context = crate::context::RequestContext::current();
+        let mut lines = 0u64;
+        let mut last_progress: Option<std::time::Instant> = None;
+        let mut pending_progress: Option<String> = None;
+        let mut stdout = CapturedOutput::new(self.max_output);
+        let mut stderr = CapturedOutput::new(self.max_output);
+
+        let finished = tokio::time::timeout(self.timeout, async {
+            let read_stdout = async {
+                let Some(pipe) = stdout_pipe else {
+                    return Ok(());
+                };
+                let result = stdout
+                    .read_lines(pipe, |line| {
+                        lines += 1;
+                        let Some(context) = &context else {
+                            return;
+                        };
+                        let message = String::from_utf8_lossy(line).trim_end().to_string();
+                        if last_progress.is_some_and(|sent| sent.elapsed() < self.progress_interval)
+                        {
+                            pending_progress = Some(message);
+                            return;
+                        }
+                        last_progress = Some(std::time::Instant::now());
+                        pending_progress = None;
+                        let _ = context.send_progress(lines as f64, None, Some(message));
+                    })
+                    .await;
+                // Report the last line even if it came too soon after the previous one
+                if let (Some(context), Some(message)) = (&context, pending_progress.take()) {
+                    let _ = context.send_progress(lines as f64, None, Some(message));
+                }
+                result
+            };
+            let read_stderr = async {
+                let Some(mut pipe) = stderr_pipe else {
+                    return Ok(());
+                };
+                let mut buffer = [0u8; 8192];
+                loop {
+                    let read = pipe.read(&mut buffer).await?;
+                    if read == 0 {
+                        return Ok::<_, std::io::Error>(());
+                    }
+                    stderr.push(&buffer[..read]);
+                }
+            };
+            let (out, err, status) = tokio::join!(read_stdout, read_stderr, child.wait());
+            out?;
+            err?;
+            status
+        })
+        .await;
+
+        let (status, timed_out) = match finished {
+            Ok(status) => {
+                // Background children the program left behind end with the call
+                drop(group);
+                (
+                    Some(status.map_err(|e| {
+                        Error::Internal(format!("Failed to run '{}': {}", self.program, e))
+                    })?),
+                    false,
+                )
+            }
+            Err(_) => {
+                drop(group);
+                let _ = child.kill().await;
+                (None, true)
+            }
+        };
+
+        let exit_code = status.and_then(|status| status.code());
+        let stdout_text = stdout.text();
+        let stderr_text = stderr.text();
+        let structured = serde_json::json!({
+            "stdout": stdout_text,
+            "stderr": stderr_text,
+            "exitCode": exit_code,
+            "timedOut": timed_out,
+            "truncated": stdout.truncated || stderr.truncated,
+        });
+
+        let mut content = vec![Content::from(stdout_text)];
+        if !stderr_text.is_empty() {
+            content.push(Content::from(stderr_text));
+        }
+
+        let failure = if timed_out {
+            Some(format!(
+                "'{}' timed out after {:?}",
+                self.program, self.timeout
+            ))
+        } else {
+            status
+                .filter(|status| !status.success())
+                .map(|status| format!("'{}' failed with {}", self.program, status))
+        };
+
+        let response = match failure {
+            Some(failure) => {
+                content.push(Content::from(failure));
+                ToolsCallResponse::error(content)
+            }
+            None => ToolsCallResponse::success(content),
+        };
+        Ok(response.with_structured_content(structured))
+    }
+}
+
 /// Template-based prompt handler
+///
+/// Each message of the prompt is a [Jinja](https://docs.rs/minijinja) template
+/// rendered with the prompt arguments, so templates can use conditionals,
+/// loops and defaults:
+///
+/// ```text
+/// Review this {{ language | default("code") }}:
+/// {% if focus %}Focus on {{ focus }}.{% endif %}
+/// {% for rule in rules | split(",") %}- {{ rule | trim }}
+/// {% endfor %}
+/// ```
+///
+/// Arguments are strings; arguments that were not given are undefined, so
+/// they are false in conditionals and replaced by `default`. Literal braces
+/// are written as `{{ "{{" }}` or inside `{% raw %}...{% endraw %}`. A
+/// message that renders to nothing but whitespace is left out of the
+/// conversation, which lets a conditional drop a whole message.
+///
+/// Missing required arguments are rejected with [`Error::InvalidParams`].
+/// Placeholders in the older `{name}` syntax are sent to the client
+/// literally; adding a message that uses one for a declared argument logs a
+/// warning, and [`validate`](Self::validate) rejects it.
 pub struct TemplatePromptHandler {
     prompt_info: Prompt,
-    template: String,
+    messages: Vec<(MessageRole, MessageTemplate)>,
+}
+
+/// Template for the content of one prompt message
+enum MessageTemplate {
+    Text(String),
+    Resource {
+        uri: String,
+        text: String,
+        mime_type: Option<String>,
+    },
+}
+
+impl MessageTemplate {
+    fn sources(&self) -> Vec<&str> {
+        match self {
+            Self::Text(template) => vec![template],
+            Self::Resource { uri, text, .. } => vec![uri, text],
+        }
+    }
 }
 
 impl TemplatePromptHandler {
+    /// Create a prompt made of a single user message
     pub fn new(
         name: impl Into<String>,
         description: impl Into<String>,
         template: impl Into<String>,
         arguments: Vec<PromptArgument>,
+    ) -> Self {
+        Self::conversation(name, description, arguments).with_user_message(template)
+    }
+
+    /// Create a prompt without messages, to be added with `with_*_message`
+    pub fn conversation(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        arguments: Vec<PromptArgument>,
     ) -> Self {
         let prompt_info = Prompt::new(name)
             .with_description(description)
@@ -174,9 +863,152 @@ impl TemplatePromptHandler {
 
         Self {
             prompt_info,
-            template: template.into(),
+            messages: Vec::new(),
         }
     }
+
+    /// Get the name of the prompt
+    pub fn name(&self) -> &str {
+        &self.prompt_info.name
+    }
+
+    /// Append a message with the given role
+    pub fn with_message(self, role: MessageRole, template: impl Into<String>) -> Self {
+        self.push_message(role, MessageTemplate::Text(template.into()))
+    }
+
+    /// Append a system message
+    pub fn with_system_message(self, template: impl Into<String>) -> Self {
+        self.with_message(MessageRole::System, template)
+    }
+
+    /// Append a user message
+    pub fn with_user_message(self, template: impl Into<String>) -> Self {
+        self.with_message(MessageRole::User, template)
+    }
+
+    /// Append an assistant message
+    pub fn with_assistant_message(self, template: impl Into<String>) -> Self {
+        self.with_message(MessageRole::Assistant, template)
+    }
+
+    /// Append a message embedding a textual resource
+    ///
+    /// Both the URI and the text are templates.
+    pub fn with_resource_message(
+        self,
+        role: MessageRole,
+        uri: impl Into<String>,
+        text: impl Into<String>,
+        mime_type: Option<String>,
+    ) -> Self {
+        self.push_message(
+            role,
+            MessageTemplate::Resource {
+                uri: uri.into(),
+                text: text.into(),
+                mime_type,
+            },
+        )
+    }
+
+    /// Check that every message is a valid template
+    ///
+    /// Besides syntax errors, this rejects `{name}` placeholders for declared
+    /// arguments, which templates written before the move to Jinja still use.
+    pub fn validate(&self) -> Result<()> {
+        let env = minijinja::Environment::new();
+        for (_, message) in &self.messages {
+            for source in message.sources() {
+                env.template_from_str(source).map_err(|e| {
+                    Error::Configuration(format!(
+                        "Invalid template in prompt '{}': {}",
+                        self.prompt_info.name, e
+                    ))
+                })?;
+                if let Some(name) = self.legacy_placeholder(source) {
+                    return Err(Error::Configuration(format!(
+                        "Prompt '{}' uses the placeholder `{{{name}}}`, write `{{{{ {name} }}}}` instead",
+                        self.prompt_info.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push_message(mut self, role: MessageRole, message: MessageTemplate) -> Self {
+        for source in message.sources() {
+            if let Some(name) = self.legacy_placeholder(source) {
+                warn!(
+                    "Prompt '{}' uses the placeholder `{{{name}}}`, which is sent literally; write `{{{{ {name} }}}}` instead",
+                    self.prompt_info.name
+                );
+            }
+        }
+        self.messages.push((role, message));
+        self
+    }
+
+    /// Find a `{name}` placeholder naming a declared argument
+    fn legacy_placeholder<'a>(&self, template: &'a str) -> Option<&'a str> {
+        template.match_indices('{').find_map(|(start, _)| {
+            if template[..start].ends_with('{') {
+                return None;
+            }
+            let rest = &template[start + 1..];
+            if rest.starts_with(['{', '%', '#']) {
+                return None;
+            }
+            let end = rest.find('}')?;
+            if rest[end + 1..].starts_with('}') {
+                return None;
+            }
+            let name = rest[..end].trim();
+            self.prompt_info
+                .arguments
+                .iter()
+                .flatten()
+                .any(|argument| argument.name == name)
+                .then_some(name)
+        })
+    }
+
+    fn check_required(&self, arguments: &HashMap<String, String>) -> Result<()> {
+        let missing: Vec<_> = self
+            .prompt_info
+            .arguments
+            .iter()
+            .flatten()
+            .filter(|argument| argument.required == Some(true))
+            .filter(|argument| !arguments.contains_key(&argument.name))
+            .map(|argument| argument.name.as_str())
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidParams(format!(
+                "Missing required argument(s) for prompt '{}': {}",
+                self.prompt_info.name,
+                missing.join(", ")
+            )))
+        }
+    }
+
+    fn render(
+        &self,
+        env: &minijinja::Environment<'_>,
+        template: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<String> {
+        env.render_str(template, arguments).map_err(|e| {
+            Error::Internal(format!(
+                "Failed to render prompt '{}': {}",
+                self.prompt_info.name, e
+            ))
+        })
+    }
 }
 
 #[async_trait]
@@ -189,20 +1021,44 @@ impl PromptHandler for TemplatePromptHandler {
         &self,
         arguments: Option<HashMap<String, String>>,
     ) -> Result<PromptsGetResponse> {
-        let mut content = self.template.clone();
+        let arguments = arguments.unwrap_or_default();
+        self.check_required(&arguments)?;
 
-        if let Some(args) = arguments {
-            for (key, value) in args {
-                let placeholder = format!("{{{}}}", key);
-                content = content.replace(&placeholder, &value);
-            }
+        let env = minijinja::Environment::new();
+        let mut messages = Vec::with_capacity(self.messages.len());
+        for (role, template) in &self.messages {
+            let content = match template {
+                MessageTemplate::Text(template) => {
+                    let text = self.render(&env, template, &arguments)?;
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    Content::from(text)
+                }
+                MessageTemplate::Resource {
+                    uri,
+                    text,
+                    mime_type,
+                } => {
+                    let uri = self.render(&env, uri, &arguments)?;
+                    let uri = uri.trim().parse().map_err(|e| {
+                        Error::InvalidParams(format!("Invalid resource URI '{}': {}", uri, e))
+                    })?;
+                    let mut resource =
+                        EmbeddedResource::text(uri, self.render(&env, text, &arguments)?);
+                    resource.resource.mime_type = mime_type.clone();
+                    Content::from(resource)
+                }
+            };
+            messages.push(PromptMessage {
+                role: role.clone(),
+                content,
+            });
         }
 
-        let message = PromptMessage::user(content);
-
         Ok(PromptsGetResponse {
             description: self.prompt_info.description.clone(),
-            messages: vec![message],
+            messages,
             meta: ResponseMetadata { _meta: None },
         })
     }
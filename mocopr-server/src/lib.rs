@@ -23,23 +23,71 @@
 //! }
 //! ```
 
+pub mod admin;
+pub mod audit;
+pub mod auth;
 pub mod builder;
+pub mod cache;
+pub mod context;
+pub mod directory;
+pub mod execution;
 pub mod handlers;
+pub mod manifest;
+pub mod metrics;
 pub mod middleware;
+pub mod notifications;
+pub mod oauth;
+pub mod openapi;
+pub mod origin;
+pub mod pagination;
+pub mod prompt_directory;
+pub mod proxy;
+pub mod rate_limit;
 pub mod registry;
+pub mod schema;
 pub mod server;
+pub mod shutdown;
+pub mod tls;
 
 pub use builder::*;
+pub use cache::ResourceCache;
+pub use context::RequestContext;
+pub use directory::*;
+pub use execution::ToolExecutionPolicy;
 pub use handlers::*;
+pub use manifest::ServerManifest;
+pub use metrics::MonitoringEndpoints;
+pub use notifications::NotificationSender;
+pub use openapi::OpenApiBridge;
+pub use pagination::Paginator;
+pub use prompt_directory::DirectoryPromptProvider;
+pub use proxy::McpProxy;
+pub use rate_limit::RateLimitMiddleware;
 pub use registry::*;
+pub use schema::SchemaValidation;
 pub use server::*;
+pub use shutdown::{ShutdownHandle, shutdown_signal};
 
 /// Common imports for MCP server development
 pub mod prelude {
     pub use crate::builder::*;
+    pub use crate::cache::ResourceCache;
+    pub use crate::context::RequestContext;
+    pub use crate::directory::*;
+    pub use crate::execution::ToolExecutionPolicy;
     pub use crate::handlers::*;
+    pub use crate::manifest::ServerManifest;
+    pub use crate::metrics::MonitoringEndpoints;
+    pub use crate::notifications::NotificationSender;
+    pub use crate::openapi::OpenApiBridge;
+    pub use crate::pagination::Paginator;
+    pub use crate::prompt_directory::DirectoryPromptProvider;
+    pub use crate::proxy::McpProxy;
+    pub use crate::rate_limit::RateLimitMiddleware;
     pub use crate::registry::*;
+    pub use crate::schema::SchemaValidation;
     pub use crate::server::*;
+    pub use crate::shutdown::{ShutdownHandle, shutdown_signal};
     pub use mocopr_core::prelude::*;
     pub use mocopr_macros::*;
 
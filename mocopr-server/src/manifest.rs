@@ -0,0 +1,740 @@
+//! Declarative server manifests
+//!
+//! A [`ServerManifest`] describes a complete server in TOML or JSON: its
+//! info and capabilities, file and directory resources, prompt templates,
+//! command-backed tools, middleware and transports. It lets simple servers
+//! be stood up without writing Rust, using the handlers this crate already
+//! provides.
+//!
+//! ```toml
+//! [server]
+//! name = "Team Tools"
+//! version = "1.0.0"
+//!
+//! [[resources]]
+//! type = "directory"
+//! path = "./docs"
+//! name = "Documentation"
+//!
+//! [[prompts]]
+//! type = "template"
+//! name = "summarize"
+//! description = "Summarize text"
+//! template = "Summarize: {{ text }}"
+//! arguments = [{ name = "text", required = true }]
+//!
+//! [[prompts]]
+//! type = "directory"
+//! path = "./prompts"
+//!
+//! [[tools]]
+//! type = "command"
+//! name = "disk_usage"
+//! description = "Show disk usage of a directory"
+//! command = ["du", "-sh", "{{ path }}"]
+//! input_schema = { type = "object", properties = { path = { type = "string" } }, required = ["path"] }
+//! cwd = "./data"
+//! path_arguments = ["path"]
+//! timeout_secs = 10
+//!
+//! [middleware]
+//! logging = true
+//! rate_limit = { max_requests = 100, window_secs = 60, key = "client_ip" }
+//!
+//! [transport]
+//! websocket = true
+//! port = 8080
+//! ```
+//!
+//! Relative paths are resolved against the directory containing the
+//! manifest. Capabilities default to what the manifest declares: listing any
+//! resource, prompt or tool enables that capability. A manifest enabling
+//! neither `http` nor `websocket` is served over stdio; its middleware,
+//! including RBAC, applies there as well.
+//!
+//! The `[middleware.rbac]` section names an RBAC configuration file that
+//! this crate cannot load itself, because RBAC lives in `mocopr-rbac`.
+//! [`McpServerBuilder::from_manifest`] therefore rejects manifests that use
+//! it; load those with `mocopr_rbac::manifest::builder_from_manifest`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::prelude::*;
+//!
+//! # async fn run() -> Result<()> {
+//! let server = McpServerBuilder::from_manifest("server.toml")?.build()?;
+//! server.run().await
+//! # }
+//! ```
+
+use crate::auth::ApiKeyAuthenticator;
+use crate::builder::McpServerBuilder;
+use crate::directory::DirectoryResourceProvider;
+use crate::handlers::{CommandToolHandler, FileResourceHandler, TemplatePromptHandler};
+use crate::middleware::{AuthMiddleware, LoggingMiddleware};
+use crate::origin::OriginPolicy;
+use crate::prompt_directory::DirectoryPromptProvider;
+use crate::rate_limit::{RateLimit, RateLimitKey, RateLimitMiddleware};
+use crate::tls::TlsConfig;
+use mocopr_core::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File formats a manifest can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// TOML, for files ending in `.toml`
+    Toml,
+    /// JSON, for files ending in `.json`
+    Json,
+}
+
+impl ManifestFormat {
+    /// Pick the format from the extension of `path`
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(Self::Toml),
+            Some("json") => Ok(Self::Json),
+            _ => Err(Error::Configuration(format!(
+                "Cannot tell the format of manifest '{}': expected a .toml or .json file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Declarative description of a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerManifest {
+    /// Server name and version
+    pub server: ServerInfoManifest,
+    /// Capabilities to advertise
+    #[serde(default)]
+    pub capabilities: CapabilitiesManifest,
+    /// Resources to serve
+    #[serde(default)]
+    pub resources: Vec<ResourceManifest>,
+    /// Prompts to serve
+    #[serde(default)]
+    pub prompts: Vec<PromptManifest>,
+    /// Tools to serve
+    #[serde(default)]
+    pub tools: Vec<ToolManifest>,
+    /// Middleware applied to every request
+    #[serde(default)]
+    pub middleware: MiddlewareManifest,
+    /// Transports to serve on
+    #[serde(default)]
+    pub transport: TransportManifest,
+    /// Directory relative paths are resolved against
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+/// Server name and version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerInfoManifest {
+    /// Name reported to clients
+    pub name: String,
+    /// Version reported to clients
+    pub version: String,
+}
+
+/// Capabilities to advertise
+///
+/// A capability left out is enabled with default settings when the manifest
+/// declares anything that needs it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapabilitiesManifest {
+    /// Accept `logging/setLevel` and send log messages
+    #[serde(default)]
+    pub logging: bool,
+    /// Resource capability settings
+    pub resources: Option<ResourcesCapabilityManifest>,
+    /// Tool capability settings
+    pub tools: Option<ListChangedManifest>,
+    /// Prompt capability settings
+    pub prompts: Option<ListChangedManifest>,
+}
+
+/// Resource capability settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourcesCapabilityManifest {
+    /// Send `notifications/resources/list_changed`
+    #[serde(default = "default_true")]
+    pub list_changed: bool,
+    /// Accept resource subscriptions
+    #[serde(default = "default_true")]
+    pub subscribe: bool,
+}
+
+/// Settings of a capability that only has list change notifications
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListChangedManifest {
+    /// Send `list_changed` notifications
+    #[serde(default = "default_true")]
+    pub list_changed: bool,
+}
+
+/// A resource, or a set of resources, to serve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ResourceManifest {
+    /// A single file, served by [`FileResourceHandler`]
+    File {
+        /// URI clients read the file by
+        uri: url::Url,
+        /// Display name
+        name: String,
+        /// Path of the file
+        path: PathBuf,
+        /// Description of the file
+        description: Option<String>,
+        /// MIME type of the file
+        mime_type: Option<String>,
+    },
+    /// A directory tree, served by [`DirectoryResourceProvider`]
+    Directory {
+        /// Path of the directory
+        path: PathBuf,
+        /// Display name
+        name: String,
+        /// Description of the directory
+        description: Option<String>,
+        /// Advertise a URI template instead of listing every file
+        #[serde(default)]
+        template: bool,
+        /// Watch the directory for changes
+        #[serde(default = "default_true")]
+        watch: bool,
+        /// How many directory levels to list
+        max_depth: Option<usize>,
+        /// Include hidden files
+        #[serde(default)]
+        hidden: bool,
+    },
+}
+
+/// A prompt, or a set of prompts, to serve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PromptManifest {
+    /// A prompt built from templates, served by [`TemplatePromptHandler`]
+    Template {
+        /// Name of the prompt
+        name: String,
+        /// Description of the prompt
+        #[serde(default)]
+        description: String,
+        /// Template of a single user message
+        template: Option<String>,
+        /// Templates of the messages of a conversation, after `template`
+        #[serde(default)]
+        messages: Vec<PromptMessageManifest>,
+        /// Arguments the prompt accepts
+        #[serde(default)]
+        arguments: Vec<PromptArgument>,
+    },
+    /// A directory of Markdown prompts, served by [`DirectoryPromptProvider`]
+    Directory {
+        /// Path of the directory
+        path: PathBuf,
+        /// Reload prompts when files change
+        #[serde(default = "default_true")]
+        watch: bool,
+    },
+}
+
+/// One message of a templated prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptMessageManifest {
+    /// Role of the message
+    pub role: MessageRole,
+    /// Template of the message text
+    pub template: String,
+}
+
+/// A tool to serve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ToolManifest {
+    /// A program run for each call, served by [`CommandToolHandler`]
+    Command {
+        /// Name of the tool
+        name: String,
+        /// Description of the tool
+        description: Option<String>,
+        /// Program followed by its argument templates
+        command: Vec<String>,
+        /// JSON schema of the tool arguments
+        input_schema: Option<serde_json::Value>,
+        /// Working directory of the program
+        cwd: Option<PathBuf>,
+        /// Environment variables passed through, replacing the default `PATH`
+        env_allowlist: Option<Vec<String>>,
+        /// Environment variables set for the program
+        #[serde(default)]
+        env: HashMap<String, String>,
+        /// Seconds after which the program is killed
+        timeout_secs: Option<u64>,
+        /// Number of bytes kept of each output stream
+        max_output_bytes: Option<usize>,
+        /// Arguments validated as paths inside the working directory
+        #[serde(default)]
+        path_arguments: Vec<String>,
+    },
+}
+
+/// Middleware applied to every request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MiddlewareManifest {
+    /// Log requests and responses
+    #[serde(default)]
+    pub logging: bool,
+    /// Require an API key
+    pub auth: Option<AuthManifest>,
+    /// Limit the request rate
+    pub rate_limit: Option<RateLimitManifest>,
+    /// Enforce role-based access control
+    pub rbac: Option<RbacManifest>,
+}
+
+/// API key authentication settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthManifest {
+    /// Accepted API keys
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Environment variables holding further accepted API keys
+    #[serde(default)]
+    pub api_key_env: Vec<String>,
+    /// Also accept a key in `params.auth.api_key` of each request
+    ///
+    /// Off by default: keys in request bodies end up in logs. HTTP and
+    /// WebSocket clients send theirs in the `Authorization` or `X-API-Key`
+    /// header instead. See [`AuthMiddleware::with_message_keys`].
+    #[serde(default)]
+    pub message_keys: bool,
+}
+
+/// Rate limit settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitManifest {
+    /// Requests allowed per window
+    pub max_requests: u32,
+    /// Length of the window in seconds
+    pub window_secs: u64,
+    /// Requests allowed at once, `max_requests` when unset
+    pub burst: Option<u32>,
+    /// How clients are told apart, all requests share one limit when unset
+    #[serde(default)]
+    pub key: RateLimitKey,
+}
+
+/// Role-based access control settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RbacManifest {
+    /// Path of the RBAC configuration file
+    pub config: PathBuf,
+    /// Path of a hash-chained JSONL audit log of access decisions
+    pub audit_log: Option<PathBuf>,
+    /// Size in bytes at which the audit log is rotated
+    pub audit_log_max_bytes: Option<u64>,
+}
+
+/// Transports to serve on
+///
+/// Without HTTP or WebSocket the server is served over stdio.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransportManifest {
+    /// Serve over HTTP
+    #[serde(default)]
+    pub http: bool,
+    /// Serve over WebSocket
+    #[serde(default)]
+    pub websocket: bool,
+    /// Address to bind to
+    pub bind: Option<String>,
+    /// Port to listen on
+    pub port: Option<u16>,
+    /// Origins allowed besides loopback ones, `*` for any
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Refuse origins on loopback addresses, such as local development
+    /// servers, unless they are listed in `allowed_origins`
+    #[serde(default)]
+    pub deny_loopback_origins: bool,
+    /// Hosts allowed besides loopback ones, `*` for any
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// PEM certificate chain to serve TLS with, together with `tls_key`
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of `tls_cert`
+    pub tls_key: Option<PathBuf>,
+    /// PEM authorities client certificates must be issued by
+    pub tls_client_ca: Option<PathBuf>,
+    /// Seconds to wait for requests to finish when shutting down
+    pub shutdown_timeout_secs: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ServerManifest {
+    /// Read a manifest from a `.toml` or `.json` file
+    ///
+    /// Relative paths in the manifest are resolved against the directory
+    /// containing the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let format = ManifestFormat::from_path(path)?;
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Configuration(format!(
+                "Failed to read manifest '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let mut manifest = Self::parse(&source, format)?;
+        manifest.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(manifest)
+    }
+
+    /// Parse a manifest whose relative paths are relative to the working directory
+    pub fn parse(source: &str, format: ManifestFormat) -> Result<Self> {
+        match format {
+            ManifestFormat::Toml => toml::from_str(source)
+                .map_err(|e| Error::Configuration(format!("Invalid manifest: {}", e))),
+            ManifestFormat::Json => serde_json::from_str(source)
+                .map_err(|e| Error::Configuration(format!("Invalid manifest: {}", e))),
+        }
+    }
+
+    /// Resolve a path from the manifest against its directory
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+
+    /// Create a server builder configured as the manifest describes
+    ///
+    /// Fails if the manifest configures RBAC; see the [module
+    /// documentation](self).
+    pub fn into_builder(self) -> Result<McpServerBuilder> {
+        if let Some(rbac) = &self.middleware.rbac {
+            return Err(Error::Configuration(format!(
+                "The manifest configures RBAC ('{}'), which needs mocopr-rbac: \
+                 load it with mocopr_rbac::manifest::builder_from_manifest",
+                rbac.config.display()
+            )));
+        }
+
+        let mut builder = McpServerBuilder::new()
+            .with_info(self.server.name.clone(), self.server.version.clone());
+        builder = self.apply_capabilities(builder);
+
+        for resource in &self.resources {
+            builder = self.apply_resource(builder, resource)?;
+        }
+        for prompt in &self.prompts {
+            builder = self.apply_prompt(builder, prompt)?;
+        }
+        for tool in &self.tools {
+            builder = self.apply_tool(builder, tool)?;
+        }
+
+        builder = apply_middleware(builder, &self.middleware)?;
+        apply_transport(builder, &self)
+    }
+
+    fn apply_capabilities(&self, mut builder: McpServerBuilder) -> McpServerBuilder {
+        let capabilities = &self.capabilities;
+        if capabilities.logging {
+            builder = builder.with_logging();
+        }
+
+        match &capabilities.resources {
+            Some(resources) => {
+                builder = builder.with_resources_config(resources.list_changed, resources.subscribe)
+            }
+            None if !self.resources.is_empty() => builder = builder.with_resources(),
+            None => {}
+        }
+        match &capabilities.tools {
+            Some(tools) => builder = builder.with_tools_config(tools.list_changed),
+            None if !self.tools.is_empty() => builder = builder.with_tools(),
+            None => {}
+        }
+        match &capabilities.prompts {
+            Some(prompts) => builder = builder.with_prompts_config(prompts.list_changed),
+            None if !self.prompts.is_empty() => builder = builder.with_prompts(),
+            None => {}
+        }
+        builder
+    }
+
+    fn apply_resource(
+        &self,
+        builder: McpServerBuilder,
+        resource: &ResourceManifest,
+    ) -> Result<McpServerBuilder> {
+        Ok(match resource {
+            ResourceManifest::File {
+                uri,
+                name,
+                path,
+                description,
+                mime_type,
+            } => {
+                let mut handler =
+                    FileResourceHandler::new(uri.clone(), name.clone(), self.resolve(path));
+                if let Some(description) = description {
+                    handler = handler.with_description(description.clone());
+                }
+                if let Some(mime_type) = mime_type {
+                    handler = handler.with_mime_type(mime_type.clone());
+                }
+                builder.with_resource(handler)
+            }
+            ResourceManifest::Directory {
+                path,
+                name,
+                description,
+                template,
+                watch,
+                max_depth,
+                hidden,
+            } => {
+                let mut provider =
+                    DirectoryResourceProvider::new(self.resolve(path), name.clone())?
+                        .with_watching(*watch)
+                        .with_hidden_files(*hidden);
+                if let Some(description) = description {
+                    provider = provider.with_description(description.clone());
+                }
+                if *template {
+                    provider = provider.as_template();
+                }
+                if let Some(depth) = max_depth {
+                    provider = provider.with_max_depth(*depth);
+                }
+                builder.with_resource_provider(provider)
+            }
+        })
+    }
+
+    fn apply_prompt(
+        &self,
+        builder: McpServerBuilder,
+        prompt: &PromptManifest,
+    ) -> Result<McpServerBuilder> {
+        Ok(match prompt {
+            PromptManifest::Template {
+                name,
+                description,
+                template,
+                messages,
+                arguments,
+            } => {
+                if template.is_none() && messages.is_empty() {
+                    return Err(Error::Configuration(format!(
+                        "Prompt '{}' needs a template or messages",
+                        name
+                    )));
+                }
+
+                let mut handler = TemplatePromptHandler::conversation(
+                    name.clone(),
+                    description.clone(),
+                    arguments.clone(),
+                );
+                if let Some(template) = template {
+                    handler = handler.with_user_message(template.clone());
+                }
+                for message in messages {
+                    handler = handler.with_message(message.role.clone(), message.template.clone());
+                }
+                handler.validate()?;
+                builder.with_prompt(handler)
+            }
+            PromptManifest::Directory { path, watch } => builder.with_prompt_provider(
+                DirectoryPromptProvider::new(self.resolve(path))?.with_watching(*watch),
+            ),
+        })
+    }
+
+    fn apply_tool(
+        &self,
+        builder: McpServerBuilder,
+        tool: &ToolManifest,
+    ) -> Result<McpServerBuilder> {
+        Ok(match tool {
+            ToolManifest::Command {
+                name,
+                description,
+                command,
+                input_schema,
+                cwd,
+                env_allowlist,
+                env,
+                timeout_secs,
+                max_output_bytes,
+                path_arguments,
+            } => {
+                let Some((program, args)) = command.split_first() else {
+                    return Err(Error::Configuration(format!(
+                        "Tool '{}' has an empty command",
+                        name
+                    )));
+                };
+
+                let mut handler =
+                    CommandToolHandler::new(name.clone(), program.clone(), args.to_vec())
+                        .with_working_dir(self.resolve(cwd.as_deref().unwrap_or(Path::new("."))));
+                if let Some(description) = description {
+                    handler = handler.with_description(description.clone());
+                }
+                if let Some(input_schema) = input_schema {
+                    handler = handler.with_input_schema(input_schema.clone());
+                }
+                if let Some(names) = env_allowlist {
+                    handler = handler.with_env_allowlist(names.clone());
+                }
+                for (key, value) in env {
+                    handler = handler.with_env(key.clone(), value.clone());
+                }
+                if let Some(secs) = timeout_secs {
+                    handler = handler.with_timeout(Duration::from_secs(*secs));
+                }
+                if let Some(bytes) = max_output_bytes {
+                    handler = handler.with_max_output(*bytes);
+                }
+                for argument in path_arguments {
+                    handler = handler.with_path_argument(argument.clone());
+                }
+                builder.with_tool(handler)
+            }
+        })
+    }
+}
+
+fn apply_middleware(
+    mut builder: McpServerBuilder,
+    middleware: &MiddlewareManifest,
+) -> Result<McpServerBuilder> {
+    if middleware.logging {
+        builder = builder.with_middleware(LoggingMiddleware::new());
+    }
+
+    if let Some(auth) = &middleware.auth {
+        let mut keys = auth.api_keys.clone();
+        for variable in &auth.api_key_env {
+            keys.push(std::env::var(variable).map_err(|_| {
+                Error::Configuration(format!(
+                    "API key environment variable '{}' is not set",
+                    variable
+                ))
+            })?);
+        }
+        if keys.is_empty() {
+            return Err(Error::Configuration(
+                "Authentication is configured without any API keys".to_string(),
+            ));
+        }
+        let mut authenticator = ApiKeyAuthenticator::new().with_keys(keys.clone());
+        if auth.message_keys {
+            // Clients sending their key in the body connect without one
+            authenticator = authenticator.allow_anonymous();
+        }
+        builder = builder.with_authenticator(authenticator).with_middleware(
+            AuthMiddleware::new()
+                .with_api_keys(keys)
+                .with_message_keys(auth.message_keys),
+        );
+    }
+
+    if let Some(rate_limit) = &middleware.rate_limit {
+        let mut limit = RateLimit::new(
+            rate_limit.max_requests,
+            Duration::from_secs(rate_limit.window_secs),
+        );
+        if let Some(burst) = rate_limit.burst {
+            limit = limit.with_burst(burst);
+        }
+        builder =
+            builder.with_middleware(RateLimitMiddleware::keyed(rate_limit.key).with_limit(limit));
+    }
+
+    Ok(builder)
+}
+
+fn apply_transport(
+    mut builder: McpServerBuilder,
+    manifest: &ServerManifest,
+) -> Result<McpServerBuilder> {
+    let transport = &manifest.transport;
+    if transport.bind.is_some() || transport.port.is_some() {
+        builder = builder.with_bind_address(
+            transport
+                .bind
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string()),
+            transport.port.unwrap_or(8080),
+        );
+    }
+    if !transport.allowed_origins.is_empty()
+        || !transport.allowed_hosts.is_empty()
+        || transport.deny_loopback_origins
+    {
+        builder = builder.with_origin_policy(
+            OriginPolicy::new()
+                .with_allowed_origins(&transport.allowed_origins)
+                .with_loopback_origins(!transport.deny_loopback_origins)
+                .with_allowed_hosts(&transport.allowed_hosts),
+        );
+    }
+    if transport.http {
+        builder = builder.with_http_transport();
+    }
+    if transport.websocket {
+        builder = builder.with_websocket_transport();
+    }
+    if let Some(timeout) = transport.shutdown_timeout_secs {
+        builder = builder.with_shutdown_timeout(Duration::from_secs(timeout));
+    }
+    match (&transport.tls_cert, &transport.tls_key) {
+        (Some(cert), Some(key)) => {
+            let mut tls = TlsConfig::new(manifest.resolve(cert), manifest.resolve(key));
+            if let Some(ca) = &transport.tls_client_ca {
+                tls = tls.with_client_ca(manifest.resolve(ca));
+            }
+            builder = builder.with_tls_config(tls);
+        }
+        (None, None) if transport.tls_client_ca.is_none() => {}
+        _ => {
+            return Err(Error::Configuration(
+                "TLS needs both tls_cert and tls_key".to_string(),
+            ));
+        }
+    }
+    Ok(builder)
+}
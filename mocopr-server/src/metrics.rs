@@ -0,0 +1,396 @@
+//! Health, readiness and Prometheus metrics endpoints
+//!
+//! When monitoring is enabled and the server runs over HTTP or WebSocket,
+//! three extra routes are served next to the MCP endpoint:
+//!
+//! - `/healthz` runs the registered [`HealthCheck`](mocopr_core::monitoring::HealthCheck)s
+//!   and returns the report as JSON, with `503` when any check is unhealthy
+//! - `/readyz` returns `200` while the server accepts sessions and `503` once
+//!   it is shutting down or unhealthy
+//! - `/metrics` exposes request counters, latency histograms, active sessions,
+//!   tool execution counters and health check results in the Prometheus text
+//!   format
+//!
+//! The paths can be changed with [`MonitoringEndpoints`].
+
+use crate::admin::SessionRegistry;
+use crate::shutdown::ShutdownHandle;
+use axum::Router;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use mocopr_core::monitoring::{HealthReport, HealthStatus, LATENCY_BUCKETS, MonitoringSystem};
+use serde_json::json;
+use std::fmt::Write;
+
+/// Content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Paths of the monitoring endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitoringEndpoints {
+    /// Liveness endpoint, `/healthz` by default
+    pub health: String,
+    /// Readiness endpoint, `/readyz` by default
+    pub ready: String,
+    /// Prometheus metrics endpoint, `/metrics` by default
+    pub metrics: String,
+}
+
+impl MonitoringEndpoints {
+    /// Set the liveness endpoint path
+    pub fn with_health_path(mut self, path: impl Into<String>) -> Self {
+        self.health = path.into();
+        self
+    }
+
+    /// Set the readiness endpoint path
+    pub fn with_ready_path(mut self, path: impl Into<String>) -> Self {
+        self.ready = path.into();
+        self
+    }
+
+    /// Set the metrics endpoint path
+    pub fn with_metrics_path(mut self, path: impl Into<String>) -> Self {
+        self.metrics = path.into();
+        self
+    }
+}
+
+impl Default for MonitoringEndpoints {
+    fn default() -> Self {
+        Self {
+            health: "/healthz".to_string(),
+            ready: "/readyz".to_string(),
+            metrics: "/metrics".to_string(),
+        }
+    }
+}
+
+/// Build the router serving the monitoring endpoints
+pub(crate) fn routes(
+    monitoring: MonitoringSystem,
+    endpoints: &MonitoringEndpoints,
+    shutdown: ShutdownHandle,
+    sessions: SessionRegistry,
+) -> Router {
+    let health = {
+        let monitoring = monitoring.clone();
+        move || async move {
+            let report = monitoring.health_check().await;
+            (health_status_code(&report), axum::Json(report))
+        }
+    };
+
+    let ready = {
+        let monitoring = monitoring.clone();
+        let shutdown = shutdown.clone();
+        move || async move {
+            if shutdown.is_shutting_down() {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    axum::Json(json!({"status": "shutting_down"})),
+                );
+            }
+
+            let report = monitoring.health_check().await;
+            (
+                health_status_code(&report),
+                axum::Json(json!({"status": report.status})),
+            )
+        }
+    };
+
+    let metrics = move || async move {
+        monitoring
+            .update_system_metrics(sessions.len() as u64)
+            .await;
+        let report = monitoring.health_check().await;
+        (
+            [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+            render_prometheus(&monitoring, &report).await,
+        )
+            .into_response()
+    };
+
+    Router::new()
+        .route(&endpoints.health, get(health))
+        .route(&endpoints.ready, get(ready))
+        .route(&endpoints.metrics, get(metrics))
+}
+
+fn health_status_code(report: &HealthReport) -> StatusCode {
+    match report.status {
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    }
+}
+
+/// Render the current metrics and health report in the Prometheus text format
+pub async fn render_prometheus(monitoring: &MonitoringSystem, health: &HealthReport) -> String {
+    let mut out = String::new();
+    let metrics = monitoring.get_metrics().await;
+
+    let mut methods: Vec<_> = monitoring.get_method_metrics().await.into_iter().collect();
+    methods.sort_by(|a, b| a.0.cmp(&b.0));
+
+    header(
+        &mut out,
+        "mcp_requests_total",
+        "counter",
+        "Total MCP requests by method",
+    );
+    for (method, m) in &methods {
+        let _ = writeln!(
+            out,
+            "mcp_requests_total{{method=\"{}\"}} {}",
+            escape(method),
+            m.requests
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_request_errors_total",
+        "counter",
+        "Failed MCP requests by method",
+    );
+    for (method, m) in &methods {
+        let _ = writeln!(
+            out,
+            "mcp_request_errors_total{{method=\"{}\"}} {}",
+            escape(method),
+            m.errors
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_request_panics_total",
+        "counter",
+        "MCP requests whose handler panicked, by method",
+    );
+    for (method, m) in &methods {
+        let _ = writeln!(
+            out,
+            "mcp_request_panics_total{{method=\"{}\"}} {}",
+            escape(method),
+            m.panics
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_request_duration_seconds",
+        "histogram",
+        "MCP request latency by method",
+    );
+    for (method, m) in &methods {
+        let method = escape(method);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&m.latency_buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "mcp_request_duration_seconds_bucket{{method=\"{method}\",le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "mcp_request_duration_seconds_bucket{{method=\"{method}\",le=\"+Inf\"}} {}",
+            m.requests
+        );
+        let _ = writeln!(
+            out,
+            "mcp_request_duration_seconds_sum{{method=\"{method}\"}} {}",
+            m.latency_sum_seconds
+        );
+        let _ = writeln!(
+            out,
+            "mcp_request_duration_seconds_count{{method=\"{method}\"}} {}",
+            m.requests
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_active_sessions",
+        "gauge",
+        "Connected MCP sessions across all transports",
+    );
+    let _ = writeln!(out, "mcp_active_sessions {}", metrics.active_connections);
+
+    header(
+        &mut out,
+        "process_resident_memory_bytes",
+        "gauge",
+        "Resident memory size in bytes",
+    );
+    let _ = writeln!(
+        out,
+        "process_resident_memory_bytes {}",
+        metrics.memory_usage_bytes
+    );
+
+    let mut tools: Vec<_> = monitoring.get_tool_metrics().await.into_iter().collect();
+    tools.sort_by(|a, b| a.0.cmp(&b.0));
+
+    header(
+        &mut out,
+        "mcp_tool_queue_depth",
+        "gauge",
+        "Tool invocations waiting for an execution slot",
+    );
+    for (tool, t) in &tools {
+        let _ = writeln!(
+            out,
+            "mcp_tool_queue_depth{{tool=\"{}\"}} {}",
+            escape(tool),
+            t.queue_depth
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_tool_timeouts_total",
+        "counter",
+        "Tool invocations that exceeded their time limit",
+    );
+    for (tool, t) in &tools {
+        let _ = writeln!(
+            out,
+            "mcp_tool_timeouts_total{{tool=\"{}\"}} {}",
+            escape(tool),
+            t.timeouts
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_tool_rejections_total",
+        "counter",
+        "Tool invocations rejected because the queue was full",
+    );
+    for (tool, t) in &tools {
+        let _ = writeln!(
+            out,
+            "mcp_tool_rejections_total{{tool=\"{}\"}} {}",
+            escape(tool),
+            t.rejections
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_health_check_status",
+        "gauge",
+        "Health check result: 1 healthy, 0.5 degraded, 0 unhealthy or unknown",
+    );
+    for check in &health.checks {
+        let _ = writeln!(
+            out,
+            "mcp_health_check_status{{check=\"{}\"}} {}",
+            escape(&check.name),
+            status_value(&check.status)
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_health_check_duration_seconds",
+        "gauge",
+        "Time the last health check took",
+    );
+    for check in &health.checks {
+        let _ = writeln!(
+            out,
+            "mcp_health_check_duration_seconds{{check=\"{}\"}} {}",
+            escape(&check.name),
+            check.duration.as_secs_f64()
+        );
+    }
+
+    header(
+        &mut out,
+        "mcp_health_status",
+        "gauge",
+        "Overall health: 1 healthy, 0.5 degraded, 0 unhealthy or unknown",
+    );
+    let _ = writeln!(out, "mcp_health_status {}", status_value(&health.status));
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn status_value(status: &HealthStatus) -> f64 {
+    match status {
+        HealthStatus::Healthy => 1.0,
+        HealthStatus::Degraded => 0.5,
+        HealthStatus::Unhealthy | HealthStatus::Unknown => 0.0,
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mocopr_core::monitoring::{BasicHealthCheck, MonitoringConfig, RequestMetrics};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_render_prometheus() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig::default());
+        monitoring
+            .register_health_check(Box::new(BasicHealthCheck::new("basic".to_string())))
+            .await;
+
+        for (millis, success) in [(3, true), (30, true), (20_000, false)] {
+            monitoring
+                .record_request(RequestMetrics {
+                    start_time: Instant::now(),
+                    method: "tools/call".to_string(),
+                    success,
+                    response_time: Duration::from_millis(millis),
+                    error_message: None,
+                })
+                .await;
+        }
+        monitoring.record_tool_timeout("say \"hi\"").await;
+        monitoring.record_panic("tools/call").await;
+
+        let report = monitoring.health_check().await;
+        let text = render_prometheus(&monitoring, &report).await;
+
+        assert!(text.contains("# TYPE mcp_request_duration_seconds histogram"));
+        assert!(text.contains("mcp_requests_total{method=\"tools/call\"} 3"));
+        assert!(text.contains("mcp_request_errors_total{method=\"tools/call\"} 1"));
+        assert!(text.contains("mcp_request_panics_total{method=\"tools/call\"} 1"));
+        assert!(
+            text.contains(
+                "mcp_request_duration_seconds_bucket{method=\"tools/call\",le=\"0.005\"} 1"
+            )
+        );
+        assert!(
+            text.contains("mcp_request_duration_seconds_bucket{method=\"tools/call\",le=\"10\"} 2")
+        );
+        assert!(
+            text.contains(
+                "mcp_request_duration_seconds_bucket{method=\"tools/call\",le=\"+Inf\"} 3"
+            )
+        );
+        assert!(text.contains("mcp_tool_timeouts_total{tool=\"say \\\"hi\\\"\"} 1"));
+        assert!(text.contains("mcp_health_check_status{check=\"basic\"} 1"));
+        assert!(text.contains("mcp_health_status 1"));
+    }
+}
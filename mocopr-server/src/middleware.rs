@@ -1,9 +1,16 @@
 //! Middleware for MCP servers
 
+use crate::context::Principal;
 use mocopr_core::prelude::*;
 use tracing::{error, info, warn};
 
+pub use crate::rate_limit::RateLimitMiddleware;
+
 /// Middleware trait for processing requests
+///
+/// Middleware runs around every request received after initialization, on
+/// every transport: stdio, HTTP and WebSocket. The session's state is available through
+/// [`Extensions::current`] while it runs.
 #[async_trait::async_trait]
 pub trait Middleware: Send + Sync {
     /// Process a request before it reaches the handler
@@ -18,6 +25,15 @@ pub trait Middleware: Send + Sync {
 
     /// Handle errors that occur during processing
     async fn on_error(&self, request: &JsonRpcRequest, error: &Error) -> Result<()>;
+
+    /// Handle a request that was dropped before it ended
+    ///
+    /// Called when the client cancels the request, disconnects or the server
+    /// shuts down while the request is in flight. It runs on its own task
+    /// after the request was dropped, with the session's [`Extensions`].
+    async fn on_cancel(&self, _request: &JsonRpcRequest) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Logging middleware
@@ -94,55 +110,24 @@ impl Middleware for LoggingMiddleware {
     }
 }
 
-/// Rate limiting middleware
-pub struct RateLimitMiddleware {
-    rate_limiter: std::sync::Arc<tokio::sync::Mutex<mocopr_core::utils::RateLimiter>>,
-}
-
-impl RateLimitMiddleware {
-    pub fn new(max_requests: u32, window_duration: std::time::Duration) -> Self {
-        Self {
-            rate_limiter: std::sync::Arc::new(tokio::sync::Mutex::new(
-                mocopr_core::utils::RateLimiter::new(max_requests, window_duration),
-            )),
-        }
-    }
-}
-
-#[async_trait::async_trait]
-impl Middleware for RateLimitMiddleware {
-    async fn before_request(&self, _request: &JsonRpcRequest) -> Result<()> {
-        let mut limiter = self.rate_limiter.lock().await;
-        if !limiter.check_rate_limit() {
-            return Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::RateLimitExceeded,
-            ));
-        }
-        Ok(())
-    }
-
-    async fn after_response(
-        &self,
-        _request: &JsonRpcRequest,
-        _response: &JsonRpcResponse,
-    ) -> Result<()> {
-        Ok(())
-    }
-
-    async fn on_error(&self, _request: &JsonRpcRequest, _error: &Error) -> Result<()> {
-        Ok(())
-    }
-}
-
 /// Authentication middleware
+///
+/// Accepts requests from sessions with an authenticated [`Principal`], which
+/// transport-level authentication (see [`crate::auth`]) attaches to HTTP and
+/// WebSocket sessions. Requests of anonymous principals, and of sessions
+/// without one such as stdio sessions, are rejected while any key is
+/// configured, unless [`with_message_keys`](Self::with_message_keys) lets them
+/// carry a key in the request body.
 pub struct AuthMiddleware {
     api_keys: std::collections::HashSet<String>,
+    message_keys: bool,
 }
 
 impl AuthMiddleware {
     pub fn new() -> Self {
         Self {
             api_keys: std::collections::HashSet::new(),
+            message_keys: false,
         }
     }
 
@@ -158,6 +143,18 @@ impl AuthMiddleware {
         self.api_keys.extend(keys);
         self
     }
+
+    /// Accept one of the configured keys in `params.auth.api_key` from
+    /// requests without an authenticated principal
+    ///
+    /// **Warning:** keys sent in message bodies end up in request logs,
+    /// traces and audit trails, and every request has to repeat them. Prefer
+    /// an [`Authenticator`](crate::auth::Authenticator), which checks the
+    /// `Authorization` header once per connection. Disabled by default.
+    pub fn with_message_keys(mut self, enabled: bool) -> Self {
+        self.message_keys = enabled;
+        self
+    }
 }
 
 impl Default for AuthMiddleware {
@@ -173,8 +170,13 @@ impl Middleware for AuthMiddleware {
             return Ok(()); // No authentication required
         }
 
+        if Principal::current().is_some_and(|principal| !principal.is_anonymous()) {
+            return Ok(());
+        }
+
         // Check for API key in request params
-        if let Some(params) = &request.params
+        if self.message_keys
+            && let Some(params) = &request.params
             && let Some(auth) = params.get("auth")
             && let Some(api_key) = auth.get("api_key")
             && let Some(key_str) = api_key.as_str()
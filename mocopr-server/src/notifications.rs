@@ -0,0 +1,87 @@
+//! Server-initiated notifications
+//!
+//! MCP servers push notifications such as `notifications/resources/list_changed`
+//! or `notifications/resources/updated` to connected clients. The
+//! [`NotificationSender`] is shared between the registries, handlers and every
+//! transport connection so that any component can announce a change and each
+//! connected client receives it.
+
+use mocopr_core::prelude::*;
+use tokio::sync::broadcast;
+use tracing::trace;
+
+/// Number of notifications buffered per subscriber before older ones are dropped
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 256;
+
+/// Method name for resource list change notifications
+pub const RESOURCES_LIST_CHANGED: &str = "notifications/resources/list_changed";
+/// Method name for resource content update notifications
+pub const RESOURCES_UPDATED: &str = "notifications/resources/updated";
+/// Method name for tool list change notifications
+pub const TOOLS_LIST_CHANGED: &str = "notifications/tools/list_changed";
+/// Method name for prompt list change notifications
+pub const PROMPTS_LIST_CHANGED: &str = "notifications/prompts/list_changed";
+
+/// Broadcasts server notifications to every connected client.
+///
+/// Cloning a `NotificationSender` is cheap; all clones publish to the same
+/// set of subscribers.
+#[derive(Clone)]
+pub struct NotificationSender {
+    sender: broadcast::Sender<JsonRpcNotification>,
+}
+
+impl NotificationSender {
+    /// Create a new notification sender with no subscribers
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Subscribe to notifications sent after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.sender.subscribe()
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Send a notification to all subscribers
+    ///
+    /// Notifications sent while no client is connected are dropped.
+    pub fn send(&self, notification: JsonRpcNotification) {
+        trace!("Broadcasting notification: {}", notification.method);
+        let _ = self.sender.send(notification);
+    }
+
+    /// Notify clients that the list of resources has changed
+    pub fn resource_list_changed(&self) {
+        self.send(Protocol::create_notification(RESOURCES_LIST_CHANGED, None));
+    }
+
+    /// Notify clients that the content of a resource has changed
+    pub fn resource_updated(&self, uri: &url::Url) {
+        self.send(Protocol::create_notification(
+            RESOURCES_UPDATED,
+            Some(serde_json::json!({ "uri": uri })),
+        ));
+    }
+
+    /// Notify clients that the list of tools has changed
+    pub fn tool_list_changed(&self) {
+        self.send(Protocol::create_notification(TOOLS_LIST_CHANGED, None));
+    }
+
+    /// Notify clients that the list of prompts has changed
+    pub fn prompt_list_changed(&self) {
+        self.send(Protocol::create_notification(PROMPTS_LIST_CHANGED, None));
+    }
+}
+
+impl Default for NotificationSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
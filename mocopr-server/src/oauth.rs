@@ -0,0 +1,534 @@
+//! OAuth 2.1 protected resource support
+//!
+//! [`ProtectedResource`] makes an HTTP or WebSocket server an OAuth 2.1
+//! resource server as described by the MCP authorization specification:
+//!
+//! - Protected resource metadata (RFC 9728) is served at
+//!   `/.well-known/oauth-protected-resource`, telling clients which
+//!   authorization servers issue tokens for this server.
+//! - Requests without a valid token are rejected with `401 Unauthorized` and a
+//!   `WWW-Authenticate: Bearer` challenge pointing at the metadata. Tokens
+//!   lacking a required scope get `403 Forbidden` with an `insufficient_scope`
+//!   challenge.
+//! - Access tokens are JWTs whose signature is checked against a JSON Web Key
+//!   Set loaded from a file or fetched from a URL, along with their `aud`,
+//!   `exp`, `iss` and scopes.
+//!
+//! The claims of a valid token become the session's [`Principal`]: the
+//! subject comes from `sub`, roles from a configurable claim and scopes from
+//! `scope` or `scp`. Role-based access control uses both the subject and the
+//! roles.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::oauth::ProtectedResource;
+//!
+//! # fn main() -> Result<()> {
+//! let resource = ProtectedResource::new(Url::parse("https://mcp.example.com/mcp")?)
+//!     .with_authorization_server(Url::parse("https://auth.example.com")?)
+//!     .with_jwks_url(Url::parse("https://auth.example.com/.well-known/jwks.json")?)
+//!     .with_required_scopes(["mcp:tools"])
+//!     .with_roles_claim("realm_access.roles");
+//!
+//! let server = McpServerBuilder::new()
+//!     .with_info("Hosted Server", "1.0.0")
+//!     .with_protected_resource(resource)
+//!     .with_websocket_transport()
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth::{Authenticator, Credentials};
+use crate::context::Principal;
+use async_trait::async_trait;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet, KeyAlgorithm};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use mocopr_core::error::ProtocolError;
+use mocopr_core::prelude::*;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::debug;
+use url::Url;
+
+/// Minimum time between fetches of a remote key set
+pub const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default tolerance for clock differences when checking `exp` and `nbf`
+pub const DEFAULT_LEEWAY: Duration = Duration::from_secs(60);
+
+/// Signature algorithms accepted for access tokens
+///
+/// Symmetric algorithms are never accepted, as a key set is public.
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Where the keys verifying token signatures come from
+#[derive(Debug, Clone)]
+enum KeySource {
+    /// Keys given up front or loaded from a file
+    Static(JwkSet),
+    /// Keys fetched from a URL and cached
+    Remote(Url),
+}
+
+/// A key set fetched from a URL
+#[derive(Debug)]
+struct FetchedKeys {
+    keys: JwkSet,
+    fetched: Instant,
+}
+
+/// An OAuth 2.1 protected resource validating JWT access tokens
+///
+/// Install it with
+/// [`McpServerBuilder::with_protected_resource`](crate::McpServerBuilder::with_protected_resource).
+#[derive(Debug, Clone)]
+pub struct ProtectedResource {
+    metadata: ProtectedResourceMetadata,
+    required_scopes: Vec<String>,
+    audiences: Vec<String>,
+    issuers: Vec<String>,
+    keys: Option<KeySource>,
+    fetched: Arc<RwLock<Option<FetchedKeys>>>,
+    client: reqwest::Client,
+    subject_claim: String,
+    roles_claim: String,
+    leeway: Duration,
+}
+
+impl ProtectedResource {
+    /// Protect the resource identified by `resource`, the URL clients connect to
+    ///
+    /// Tokens must name `resource` in their audience unless other audiences
+    /// are set with [`with_audience`](Self::with_audience). The resource URL
+    /// is accepted with or without a trailing slash, since parsing
+    /// `https://mcp.example.com` adds one that authorization servers usually
+    /// leave out.
+    pub fn new(resource: Url) -> Self {
+        Self {
+            metadata: ProtectedResourceMetadata::new(resource.as_str()),
+            required_scopes: Vec::new(),
+            audiences: Vec::new(),
+            issuers: Vec::new(),
+            keys: None,
+            fetched: Arc::new(RwLock::new(None)),
+            client: reqwest::Client::new(),
+            subject_claim: "sub".to_string(),
+            roles_claim: "roles".to_string(),
+            leeway: DEFAULT_LEEWAY,
+        }
+    }
+
+    /// Advertise an authorization server issuing tokens for the resource
+    pub fn with_authorization_server(mut self, issuer: Url) -> Self {
+        self.metadata
+            .authorization_servers
+            .push(issuer.as_str().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Advertise the scopes clients can request for the resource
+    pub fn with_scopes_supported<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.metadata
+            .scopes_supported
+            .extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require every token to carry these scopes
+    ///
+    /// They are advertised as supported scopes as well.
+    pub fn with_required_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for scope in scopes {
+            let scope = scope.into();
+            if !self.metadata.scopes_supported.contains(&scope) {
+                self.metadata.scopes_supported.push(scope.clone());
+            }
+            self.required_scopes.push(scope);
+        }
+        self
+    }
+
+    /// Accept tokens issued for `audience`
+    ///
+    /// Replaces the default audience, the resource URL. Audiences set here
+    /// must match the token's `aud` claim exactly.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Only accept tokens issued by `issuer`
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuers.push(issuer.into());
+        self
+    }
+
+    /// Verify signatures with the keys of `keys`
+    pub fn with_jwks(mut self, keys: JwkSet) -> Self {
+        self.keys = Some(KeySource::Static(keys));
+        self
+    }
+
+    /// Verify signatures with the keys in a JSON Web Key Set file
+    pub fn with_jwks_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        let keys = serde_json::from_str(&source).map_err(|e| {
+            Error::Configuration(format!("Invalid key set {}: {}", path.display(), e))
+        })?;
+        Ok(self.with_jwks(keys))
+    }
+
+    /// Verify signatures with the keys published at `url`
+    ///
+    /// The key set is fetched on first use and again when a token names an
+    /// unknown key, at most once per [`JWKS_REFRESH_INTERVAL`].
+    pub fn with_jwks_url(mut self, url: Url) -> Self {
+        self.keys = Some(KeySource::Remote(url));
+        self
+    }
+
+    /// Use `client` to fetch a remote key set
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Take the subject from `claim` instead of `sub`
+    pub fn with_subject_claim(mut self, claim: impl Into<String>) -> Self {
+        self.subject_claim = claim.into();
+        self
+    }
+
+    /// Take roles from `claim` instead of `roles`
+    ///
+    /// Nested claims are addressed with dots, e.g. `realm_access.roles`. The
+    /// claim holds an array of strings or a space-separated string.
+    pub fn with_roles_claim(mut self, claim: impl Into<String>) -> Self {
+        self.roles_claim = claim.into();
+        self
+    }
+
+    /// Set the tolerance for clock differences when checking `exp` and `nbf`
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Set the human-readable name advertised for the resource
+    pub fn with_resource_name(mut self, name: impl Into<String>) -> Self {
+        self.metadata.resource_name = Some(name.into());
+        self
+    }
+
+    /// Set the documentation URL advertised for the resource
+    pub fn with_resource_documentation(mut self, url: Url) -> Self {
+        self.metadata.resource_documentation = Some(url.to_string());
+        self
+    }
+
+    /// Get the protected resource metadata
+    pub fn metadata(&self) -> &ProtectedResourceMetadata {
+        &self.metadata
+    }
+
+    /// Get the URL of the protected resource metadata
+    ///
+    /// Follows RFC 9728: the well-known path is inserted before the path of
+    /// the resource URL.
+    pub fn metadata_url(&self) -> String {
+        let Ok(resource) = Url::parse(&self.metadata.resource) else {
+            return PROTECTED_RESOURCE_METADATA_PATH.to_string();
+        };
+        let path = resource.path().trim_end_matches('/');
+        format!(
+            "{}{}{}",
+            resource.origin().ascii_serialization(),
+            PROTECTED_RESOURCE_METADATA_PATH,
+            path
+        )
+    }
+
+    /// Paths under which the metadata is served
+    pub(crate) fn metadata_paths(&self) -> Vec<String> {
+        let mut paths = vec![PROTECTED_RESOURCE_METADATA_PATH.to_string()];
+        if let Ok(resource) = Url::parse(&self.metadata.resource) {
+            let path = resource.path().trim_end_matches('/');
+            if !path.is_empty() {
+                paths.push(format!("{}{}", PROTECTED_RESOURCE_METADATA_PATH, path));
+            }
+        }
+        paths
+    }
+
+    /// Validate an access token and build the principal it authenticates
+    pub async fn validate(&self, token: &str) -> Result<Principal> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|e| invalid_token(format!("Malformed token: {}", e)))?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(invalid_token(format!(
+                "Unsupported algorithm {:?}",
+                header.alg
+            )));
+        }
+
+        let key = self.decoding_key(header.kid.as_deref(), header.alg).await?;
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.leeway.as_secs();
+        validation.validate_nbf = true;
+        validation.set_required_spec_claims(&["exp"]);
+        if self.audiences.is_empty() {
+            let resource = self.metadata.resource.as_str();
+            let trimmed = resource.trim_end_matches('/');
+            validation.set_audience(&[resource, trimmed, &format!("{trimmed}/")]);
+        } else {
+            validation.set_audience(&self.audiences);
+        }
+        if !self.issuers.is_empty() {
+            validation.set_issuer(&self.issuers);
+        }
+
+        let claims = jsonwebtoken::decode::<Value>(token, &key, &validation)
+            .map_err(|e| invalid_token(format!("Invalid token: {}", e)))?
+            .claims;
+
+        let subject = claim(&claims, &self.subject_claim)
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid_token(format!("Token has no '{}' claim", self.subject_claim)))?;
+        let scopes = claim(&claims, "scope")
+            .or_else(|| claim(&claims, "scp"))
+            .map(strings)
+            .unwrap_or_default();
+        let missing: Vec<&String> = self
+            .required_scopes
+            .iter()
+            .filter(|scope| !scopes.contains(scope))
+            .collect();
+        if !missing.is_empty() {
+            return Err(
+                Error::Protocol(ProtocolError::PermissionDenied).with_data(json!({
+                    "error": "insufficient_scope",
+                    "missingScopes": missing,
+                })),
+            );
+        }
+        let roles = claim(&claims, &self.roles_claim)
+            .map(strings)
+            .unwrap_or_default();
+
+        Ok(Principal::new(subject)
+            .with_roles(roles)
+            .with_scopes(scopes)
+            .with_claims(claims))
+    }
+
+    /// Find the key that signed a token
+    async fn decoding_key(&self, kid: Option<&str>, alg: Algorithm) -> Result<DecodingKey> {
+        let keys = match &self.keys {
+            Some(KeySource::Static(keys)) => keys.clone(),
+            Some(KeySource::Remote(url)) => {
+                let cached = self.fetched.read().await.as_ref().map(|f| f.keys.clone());
+                match cached {
+                    Some(keys) if find_key(&keys, kid, alg).is_some() => keys,
+                    _ => self.fetch_keys(url).await?,
+                }
+            }
+            None => {
+                return Err(Error::Configuration(
+                    "No key set configured for token validation".to_string(),
+                ));
+            }
+        };
+
+        let jwk = find_key(&keys, kid, alg)
+            .ok_or_else(|| invalid_token(format!("Unknown signing key {:?}", kid)))?;
+        DecodingKey::from_jwk(jwk).map_err(|e| invalid_token(format!("Unusable key: {}", e)))
+    }
+
+    /// Fetch the remote key set unless it was fetched recently
+    async fn fetch_keys(&self, url: &Url) -> Result<JwkSet> {
+        let mut fetched = self.fetched.write().await;
+        if let Some(current) = fetched.as_ref()
+            && current.fetched.elapsed() < JWKS_REFRESH_INTERVAL
+        {
+            return Ok(current.keys.clone());
+        }
+
+        debug!("Fetching key set from {}", url);
+        let keys: JwkSet = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::internal(format!("Failed to fetch key set: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| Error::internal(format!("Invalid key set from {}: {}", url, e)))?;
+        *fetched = Some(FetchedKeys {
+            keys: keys.clone(),
+            fetched: Instant::now(),
+        });
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl Authenticator for ProtectedResource {
+    async fn authenticate(&self, credentials: Option<&Credentials>) -> Result<Principal> {
+        match credentials {
+            Some(Credentials::Bearer(token)) => self.validate(token).await,
+            Some(Credentials::ApiKey(_)) => Err(invalid_token(
+                "Expected a bearer token, not an API key".to_string(),
+            )),
+            None => Err(Error::security("Missing bearer token")),
+        }
+    }
+
+    fn challenge(&self, error: &Error) -> String {
+        let mut challenge = format!("Bearer resource_metadata=\"{}\"", self.metadata_url());
+        match error.data().and_then(|data| data["error"].as_str()) {
+            Some("insufficient_scope") => {
+                challenge.push_str(&format!(
+                    ", error=\"insufficient_scope\", scope=\"{}\"",
+                    self.required_scopes.join(" ")
+                ));
+            }
+            Some(code) => challenge.push_str(&format!(", error=\"{}\"", code)),
+            None if !self.required_scopes.is_empty() => {
+                challenge.push_str(&format!(", scope=\"{}\"", self.required_scopes.join(" ")));
+            }
+            None => {}
+        }
+        challenge
+    }
+}
+
+/// Build the error for a token that is not acceptable
+fn invalid_token(message: String) -> Error {
+    Error::security(message).with_data(json!({"error": "invalid_token"}))
+}
+
+/// Find the key for a token's `kid` and algorithm
+///
+/// Without a `kid`, a key set with a single suitable key is used.
+fn find_key<'a>(keys: &'a JwkSet, kid: Option<&str>, alg: Algorithm) -> Option<&'a Jwk> {
+    let suitable = |jwk: &&Jwk| {
+        !matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_))
+            && jwk
+                .common
+                .key_algorithm
+                .is_none_or(|key_alg| signature_algorithm(key_alg) == Some(alg))
+    };
+    match kid {
+        Some(kid) => keys.find(kid).filter(suitable),
+        None => {
+            let mut candidates = keys.keys.iter().filter(suitable);
+            let key = candidates.next()?;
+            candidates.next().is_none().then_some(key)
+        }
+    }
+}
+
+/// Get the signature algorithm a key is meant for
+fn signature_algorithm(key_alg: KeyAlgorithm) -> Option<Algorithm> {
+    Some(match key_alg {
+        KeyAlgorithm::HS256 => Algorithm::HS256,
+        KeyAlgorithm::HS384 => Algorithm::HS384,
+        KeyAlgorithm::HS512 => Algorithm::HS512,
+        KeyAlgorithm::ES256 => Algorithm::ES256,
+        KeyAlgorithm::ES384 => Algorithm::ES384,
+        KeyAlgorithm::RS256 => Algorithm::RS256,
+        KeyAlgorithm::RS384 => Algorithm::RS384,
+        KeyAlgorithm::RS512 => Algorithm::RS512,
+        KeyAlgorithm::PS256 => Algorithm::PS256,
+        KeyAlgorithm::PS384 => Algorithm::PS384,
+        KeyAlgorithm::PS512 => Algorithm::PS512,
+        KeyAlgorithm::EdDSA => Algorithm::EdDSA,
+        _ => return None,
+    })
+}
+
+/// Look up a claim by a dotted path
+fn claim<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(claims, |value, segment| value.get(segment))
+}
+
+/// Read an array of strings or a space-separated string
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => text.split_whitespace().map(str::to_string).collect(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_url_and_paths() {
+        let resource = ProtectedResource::new(Url::parse("https://mcp.example.com/mcp").unwrap());
+        assert_eq!(
+            resource.metadata_url(),
+            "https://mcp.example.com/.well-known/oauth-protected-resource/mcp"
+        );
+        assert_eq!(
+            resource.metadata_paths(),
+            [
+                "/.well-known/oauth-protected-resource",
+                "/.well-known/oauth-protected-resource/mcp"
+            ]
+        );
+
+        let resource = ProtectedResource::new(Url::parse("https://mcp.example.com").unwrap());
+        assert_eq!(
+            resource.metadata_url(),
+            "https://mcp.example.com/.well-known/oauth-protected-resource"
+        );
+        assert_eq!(resource.metadata_paths().len(), 1);
+    }
+
+    #[test]
+    fn test_claims() {
+        let claims = json!({
+            "scope": "read  write",
+            "realm_access": {"roles": ["admin", "analyst"]},
+        });
+        assert_eq!(strings(&claims["scope"]), ["read", "write"]);
+        assert_eq!(
+            claim(&claims, "realm_access.roles").map(strings).unwrap(),
+            ["admin", "analyst"]
+        );
+        assert!(claim(&claims, "realm_access.groups").is_none());
+    }
+}
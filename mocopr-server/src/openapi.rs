@@ -0,0 +1,866 @@
+//! Tools generated from OpenAPI documents
+//!
+//! An [`OpenApiBridge`] reads an OpenAPI 3 document and turns every operation
+//! into an [`OpenApiTool`], so an existing REST service can be offered to
+//! clients without writing a handler per endpoint.
+//!
+//! The tool is named after the `operationId` of the operation, or after its
+//! method and path when it has none. Its input schema has one property per
+//! path, query and header parameter, plus a `body` property holding the
+//! request body. Local `$ref`s are inlined, so the schema stands on its own.
+//!
+//! A call fills the parameters into an HTTP request to the base URL, which
+//! defaults to the first absolute URL in `servers`. The response body is
+//! returned as text content; a JSON response is also returned as structured
+//! content. Responses with a status other than 2xx are error results.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::openapi::OpenApiBridge;
+//! use mocopr_server::prelude::*;
+//!
+//! # async fn run() -> Result<()> {
+//! let bridge = OpenApiBridge::from_file("petstore.yaml")?
+//!     .with_base_url(Url::parse("http://localhost:3000/api")?)
+//!     .with_bearer_token(std::env::var("PETSTORE_TOKEN").unwrap_or_default())
+//!     .with_tags(["pets"])
+//!     .without_operations(["deletePet"]);
+//!
+//! let server = McpServerBuilder::new()
+//!     .with_info("Petstore", "1.0.0")
+//!     .with_openapi(bridge)?
+//!     .build()?;
+//! server.run_stdio().await
+//! # }
+//! ```
+
+use crate::handlers::ToolHandler;
+use async_trait::async_trait;
+use mocopr_core::prelude::*;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value, json};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+/// HTTP methods that may hold an operation in a path item
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Generates tools from the operations of an OpenAPI 3 document
+#[derive(Debug, Clone)]
+pub struct OpenApiBridge {
+    document: Value,
+    base_url: Option<Url>,
+    headers: Vec<(String, String)>,
+    include: Option<HashSet<String>>,
+    exclude: HashSet<String>,
+    tags: Option<HashSet<String>>,
+    timeout: Duration,
+    max_response_bytes: usize,
+    client: Option<reqwest::Client>,
+}
+
+impl OpenApiBridge {
+    /// Default time a request may take
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Default size limit of a response body
+    pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+    /// Read a document from a YAML or JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Configuration(format!(
+                "Cannot read OpenAPI document {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        // JSON is valid YAML, so one parser reads both
+        let document: Value = serde_yaml::from_str(&source).map_err(|e| {
+            Error::Configuration(format!(
+                "Invalid OpenAPI document {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_value(document)
+    }
+
+    /// Use an already parsed document
+    pub fn from_value(document: Value) -> Result<Self> {
+        let version = document
+            .get("openapi")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !version.starts_with("3.") {
+            return Err(Error::Configuration(format!(
+                "Unsupported OpenAPI version '{}', expected 3.x",
+                version
+            )));
+        }
+
+        Ok(Self {
+            document,
+            base_url: None,
+            headers: Vec::new(),
+            include: None,
+            exclude: HashSet::new(),
+            tags: None,
+            timeout: Self::DEFAULT_TIMEOUT,
+            max_response_bytes: Self::DEFAULT_MAX_RESPONSE_BYTES,
+            client: None,
+        })
+    }
+
+    /// Set the URL the operation paths are appended to
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Send a header with every request, e.g. an API key
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send a bearer token with every request
+    pub fn with_bearer_token(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.with_header("Authorization", value)
+    }
+
+    /// Only generate tools for these operations
+    ///
+    /// Operations are named by `operationId` or by tool name.
+    pub fn with_operations(
+        mut self,
+        operations: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.include
+            .get_or_insert_with(HashSet::new)
+            .extend(operations.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skip these operations
+    pub fn without_operations(
+        mut self,
+        operations: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.exclude.extend(operations.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only generate tools for operations with at least one of these tags
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags
+            .get_or_insert_with(HashSet::new)
+            .extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the time after which a request fails
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the size of the largest response body a call returns
+    ///
+    /// Calls receiving a larger body fail with a tool error instead of
+    /// buffering it.
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. for proxies or certificates
+    ///
+    /// The timeout set with [`with_timeout`](Self::with_timeout) is applied to
+    /// each request on top of the client's own settings.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Generate the tools
+    ///
+    /// Operations whose request body cannot be sent (e.g. multipart uploads)
+    /// are skipped with a warning.
+    pub fn tools(&self) -> Result<Vec<OpenApiTool>> {
+        let connection = Arc::new(Connection {
+            client: match &self.client {
+                Some(client) => client.clone(),
+                None => reqwest::Client::new(),
+            },
+            base_url: self.base_url()?,
+            headers: self.header_map()?,
+            timeout: self.timeout,
+            max_response_bytes: self.max_response_bytes,
+        });
+
+        let mut tools = Vec::new();
+        let mut names = HashSet::new();
+        let empty = Map::new();
+        let paths = self
+            .document
+            .get("paths")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+
+        for (path, item) in paths {
+            let item = resolve_refs(&self.document, item, &mut Vec::new());
+            let shared_parameters = item.get("parameters").cloned().unwrap_or_default();
+
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let name = tool_name(operation, method, path);
+                if !self.selects(operation, &name) {
+                    continue;
+                }
+                if !names.insert(name.clone()) {
+                    return Err(Error::Configuration(format!(
+                        "OpenAPI operations {} {} and another one are both named '{}'",
+                        method.to_uppercase(),
+                        path,
+                        name
+                    )));
+                }
+
+                if let Some(tool) = OpenApiTool::from_operation(
+                    name,
+                    method,
+                    path,
+                    operation,
+                    &shared_parameters,
+                    connection.clone(),
+                )? {
+                    tools.push(tool);
+                }
+            }
+        }
+
+        Ok(tools)
+    }
+
+    /// Check the filters against an operation
+    fn selects(&self, operation: &Value, name: &str) -> bool {
+        let operation_id = operation.get("operationId").and_then(Value::as_str);
+        let named = |set: &HashSet<String>| {
+            set.contains(name) || operation_id.is_some_and(|id| set.contains(id))
+        };
+
+        if named(&self.exclude) {
+            return false;
+        }
+        if let Some(include) = &self.include
+            && !named(include)
+        {
+            return false;
+        }
+        if let Some(tags) = &self.tags {
+            let tagged = operation
+                .get("tags")
+                .and_then(Value::as_array)
+                .is_some_and(|list| {
+                    list.iter()
+                        .filter_map(Value::as_str)
+                        .any(|tag| tags.contains(tag))
+                });
+            if !tagged {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Get the configured base URL, or the first absolute server URL
+    fn base_url(&self) -> Result<Url> {
+        if let Some(base_url) = &self.base_url {
+            return Ok(base_url.clone());
+        }
+
+        self.document
+            .get("servers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server.get("url").and_then(Value::as_str))
+            .find_map(|url| Url::parse(url).ok())
+            .ok_or_else(|| {
+                Error::Configuration(
+                    "OpenAPI document has no absolute server URL, set a base URL".to_string(),
+                )
+            })
+    }
+
+    /// Parse the configured headers
+    fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                Error::Configuration(format!("Invalid header name '{}': {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                Error::Configuration(format!("Invalid value for header '{}': {}", name, e))
+            })?;
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// HTTP settings shared by the tools of one bridge
+#[derive(Debug)]
+struct Connection {
+    client: reqwest::Client,
+    base_url: Url,
+    headers: HeaderMap,
+    timeout: Duration,
+    max_response_bytes: usize,
+}
+
+/// Where a parameter goes in the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+}
+
+/// A parameter of an operation
+#[derive(Debug, Clone)]
+struct Parameter {
+    name: String,
+    location: ParameterLocation,
+    required: bool,
+}
+
+/// How the request body is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyEncoding {
+    Json,
+    Form,
+    Text,
+}
+
+/// The request body of an operation
+#[derive(Debug, Clone)]
+struct RequestBody {
+    content_type: String,
+    encoding: BodyEncoding,
+    required: bool,
+}
+
+/// Tool that calls one operation of a REST service
+#[derive(Debug)]
+pub struct OpenApiTool {
+    tool_info: Tool,
+    method: reqwest::Method,
+    path: String,
+    parameters: Vec<Parameter>,
+    body: Option<RequestBody>,
+    connection: Arc<Connection>,
+}
+
+impl OpenApiTool {
+    /// Get the HTTP method of the operation
+    pub fn method(&self) -> &str {
+        self.method.as_str()
+    }
+
+    /// Get the path template of the operation
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Build the tool for an operation, or `None` if its body is unsupported
+    fn from_operation(
+        name: String,
+        method: &str,
+        path: &str,
+        operation: &Value,
+        shared_parameters: &Value,
+        connection: Arc<Connection>,
+    ) -> Result<Option<Self>> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut parameters: Vec<Parameter> = Vec::new();
+
+        // Operation parameters override path item parameters of the same name
+        // and location
+        let declared = shared_parameters.as_array().into_iter().flatten().chain(
+            operation
+                .get("parameters")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten(),
+        );
+        let mut specs: Vec<&Value> = Vec::new();
+        for spec in declared {
+            specs.retain(|existing| {
+                existing.get("name") != spec.get("name") || existing.get("in") != spec.get("in")
+            });
+            specs.push(spec);
+        }
+
+        for spec in specs {
+            let Some(param_name) = spec.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let location = match spec.get("in").and_then(Value::as_str) {
+                Some("path") => ParameterLocation::Path,
+                Some("query") => ParameterLocation::Query,
+                Some("header") => ParameterLocation::Header,
+                other => {
+                    warn!(
+                        "Skipping parameter '{}' in {:?} of OpenAPI operation '{}'",
+                        param_name, other, name
+                    );
+                    continue;
+                }
+            };
+            if properties.contains_key(param_name) {
+                warn!(
+                    "Skipping duplicate parameter '{}' of OpenAPI operation '{}'",
+                    param_name, name
+                );
+                continue;
+            }
+
+            let is_required = location == ParameterLocation::Path
+                || spec
+                    .get("required")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+            let schema = spec
+                .get("schema")
+                .or_else(|| first_media_schema(spec.get("content")))
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            properties.insert(
+                param_name.to_string(),
+                with_description(schema, spec.get("description")),
+            );
+            if is_required {
+                required.push(Value::String(param_name.to_string()));
+            }
+            parameters.push(Parameter {
+                name: param_name.to_string(),
+                location,
+                required: is_required,
+            });
+        }
+
+        let mut body = None;
+        if let Some(request_body) = operation.get("requestBody") {
+            let content = request_body.get("content").and_then(Value::as_object);
+            let chosen = content.and_then(|content| {
+                let pick = |matches: &dyn Fn(&str) -> bool| {
+                    content.iter().find(|(media_type, _)| matches(media_type))
+                };
+                pick(&|t| t == "application/json" || t.ends_with("+json"))
+                    .map(|found| (found, BodyEncoding::Json))
+                    .or_else(|| {
+                        pick(&|t| t == "application/x-www-form-urlencoded")
+                            .map(|found| (found, BodyEncoding::Form))
+                    })
+                    .or_else(|| {
+                        pick(&|t| t.starts_with("text/")).map(|found| (found, BodyEncoding::Text))
+                    })
+            });
+            let Some(((content_type, media), encoding)) = chosen else {
+                warn!(
+                    "Skipping OpenAPI operation '{}': unsupported request body",
+                    name
+                );
+                return Ok(None);
+            };
+
+            if properties.contains_key("body") {
+                return Err(Error::Configuration(format!(
+                    "OpenAPI operation '{}' has both a parameter and a request body named 'body'",
+                    name
+                )));
+            }
+            let is_required = request_body
+                .get("required")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let schema = media.get("schema").cloned().unwrap_or_else(|| json!({}));
+            properties.insert(
+                "body".to_string(),
+                with_description(schema, request_body.get("description")),
+            );
+            if is_required {
+                required.push(Value::String("body".to_string()));
+            }
+            body = Some(RequestBody {
+                content_type: content_type.clone(),
+                encoding,
+                required: is_required,
+            });
+        }
+
+        let mut input_schema = json!({"type": "object", "properties": properties});
+        if !required.is_empty() {
+            input_schema["required"] = Value::Array(required);
+        }
+
+        let description = ["summary", "description"]
+            .iter()
+            .find_map(|key| operation.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|e| Error::Configuration(e.to_string()))?;
+
+        Ok(Some(Self {
+            tool_info: Tool::new(name, input_schema).with_description(description),
+            method,
+            path: path.to_string(),
+            parameters,
+            body,
+            connection,
+        }))
+    }
+
+    /// Build the request URL from the path template and arguments
+    fn url(&self, arguments: &Map<String, Value>) -> Result<Url> {
+        let mut url = self.connection.base_url.clone();
+        let mut query = Vec::new();
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| {
+                Error::Configuration(format!(
+                    "Base URL {} cannot have a path",
+                    self.connection.base_url
+                ))
+            })?;
+            segments.pop_if_empty();
+            for segment in self.path.split('/').filter(|segment| !segment.is_empty()) {
+                let mut rendered = segment.to_string();
+                for parameter in &self.parameters {
+                    if parameter.location == ParameterLocation::Path
+                        && let Some(value) = arguments.get(&parameter.name)
+                    {
+                        rendered =
+                            rendered.replace(&format!("{{{}}}", parameter.name), &scalar(value));
+                    }
+                }
+                // Pushing encodes the segment, so values cannot add path
+                // segments, but empty, `.` and `..` segments would still be
+                // dropped or resolved against the segment before
+                if rendered != segment && matches!(rendered.as_str(), "" | "." | "..") {
+                    return Err(Error::InvalidParams(format!(
+                        "Path segment '{}' of tool '{}' cannot be '{}'",
+                        segment, self.tool_info.name, rendered
+                    )));
+                }
+                segments.push(&rendered);
+            }
+        }
+
+        for parameter in &self.parameters {
+            if parameter.location != ParameterLocation::Query {
+                continue;
+            }
+            match arguments.get(&parameter.name) {
+                Some(Value::Array(items)) => query.extend(
+                    items
+                        .iter()
+                        .map(|item| (parameter.name.as_str(), scalar(item))),
+                ),
+                Some(Value::Null) | None => {}
+                Some(value) => query.push((parameter.name.as_str(), scalar(value))),
+            }
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl ToolHandler for OpenApiTool {
+    async fn tool(&self) -> Tool {
+        self.tool_info.clone()
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let arguments = match arguments {
+            Some(Value::Object(arguments)) => arguments,
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => {
+                return Err(Error::InvalidParams(
+                    "Tool arguments must be an object".to_string(),
+                ));
+            }
+        };
+
+        let missing: Vec<&str> = self
+            .parameters
+            .iter()
+            .filter(|parameter| parameter.required)
+            .map(|parameter| parameter.name.as_str())
+            .chain(
+                self.body
+                    .as_ref()
+                    .filter(|body| body.required)
+                    .map(|_| "body"),
+            )
+            .filter(|name| arguments.get(*name).is_none_or(Value::is_null))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::InvalidParams(format!(
+                "Missing required argument(s) for tool '{}': {}",
+                self.tool_info.name,
+                missing.join(", ")
+            )));
+        }
+
+        let url = self.url(&arguments)?;
+        let mut request = self
+            .connection
+            .client
+            .request(self.method.clone(), url.clone())
+            .timeout(self.connection.timeout)
+            .headers(self.connection.headers.clone());
+
+        for parameter in &self.parameters {
+            if parameter.location != ParameterLocation::Header {
+                continue;
+            }
+            if let Some(value) = arguments.get(&parameter.name).filter(|v| !v.is_null()) {
+                let value = HeaderValue::from_str(&scalar(value)).map_err(|e| {
+                    Error::InvalidParams(format!("Invalid header '{}': {}", parameter.name, e))
+                })?;
+                request = request.header(parameter.name.as_str(), value);
+            }
+        }
+
+        if let Some(body) = &self.body
+            && let Some(value) = arguments.get("body").filter(|v| !v.is_null())
+        {
+            request = match body.encoding {
+                BodyEncoding::Json => request
+                    .header(CONTENT_TYPE, body.content_type.as_str())
+                    .body(serde_json::to_vec(value)?),
+                BodyEncoding::Form => request.form(value),
+                BodyEncoding::Text => request
+                    .header(CONTENT_TYPE, body.content_type.as_str())
+                    .body(scalar(value)),
+            };
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(ToolsCallResponse::error(vec![Content::from(format!(
+                    "Request to {} failed: {}",
+                    url, e
+                ))]));
+            }
+        };
+
+        let status = response.status();
+        let is_json = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("json"));
+        let text = match read_body(response, self.connection.max_response_bytes).await {
+            Ok(text) => text,
+            Err(e) => {
+                return Ok(ToolsCallResponse::error(vec![Content::from(format!(
+                    "Cannot read response from {}: {}",
+                    url, e
+                ))]));
+            }
+        };
+
+        if !status.is_success() {
+            return Ok(ToolsCallResponse::error(vec![Content::from(format!(
+                "{} {} returned {}: {}",
+                self.method, self.path, status, text
+            ))]));
+        }
+
+        let structured = is_json
+            .then(|| serde_json::from_str::<Value>(&text).ok())
+            .flatten()
+            .map(|value| match value {
+                Value::Object(_) => value,
+                // Structured content has to be an object
+                other => json!({ "result": other }),
+            });
+        let text = if text.is_empty() {
+            status.to_string()
+        } else {
+            text
+        };
+
+        let response = ToolsCallResponse::success(vec![Content::from(text)]);
+        Ok(match structured {
+            Some(structured) => response.with_structured_content(structured),
+            None => response,
+        })
+    }
+}
+
+/// Read a response body of at most `limit` bytes as text
+async fn read_body(
+    mut response: reqwest::Response,
+    limit: usize,
+) -> std::result::Result<String, String> {
+    let too_large = || format!("body exceeds {} bytes", limit);
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Derive a tool name from the `operationId`, or the method and path
+fn tool_name(operation: &Value, method: &str, path: &str) -> String {
+    let raw = match operation.get("operationId").and_then(Value::as_str) {
+        Some(operation_id) => operation_id.to_string(),
+        None => format!("{}_{}", method, path),
+    };
+
+    let mut name = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        let c = if c.is_ascii_alphanumeric() || c == '-' {
+            c
+        } else {
+            '_'
+        };
+        if c != '_' || !name.ends_with('_') {
+            name.push(c);
+        }
+    }
+    let name = name.trim_matches('_');
+    name.chars().take(64).collect()
+}
+
+/// Get the schema of the first media type of a `content` map
+fn first_media_schema(content: Option<&Value>) -> Option<&Value> {
+    content?.as_object()?.values().next()?.get("schema")
+}
+
+/// Add a description to a schema that has none
+fn with_description(mut schema: Value, description: Option<&Value>) -> Value {
+    if let (Value::Object(map), Some(description)) = (&mut schema, description)
+        && !map.contains_key("description")
+    {
+        map.insert("description".to_string(), description.clone());
+    }
+    schema
+}
+
+/// Format an argument for a path, query or header
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Inline the local `$ref`s of a value
+///
+/// References that point back into a schema being inlined (recursive
+/// schemas) are replaced by an empty schema, which accepts anything.
+fn resolve_refs(document: &Value, value: &Value, stack: &mut Vec<String>) -> Value {
+    match value {
+        Value::Object(map) => {
+            if let Some(reference) = map.get("$ref").and_then(Value::as_str) {
+                let Some(pointer) = reference.strip_prefix('#') else {
+                    warn!("Ignoring external OpenAPI reference '{}'", reference);
+                    return json!({});
+                };
+                if stack.iter().any(|seen| seen == reference) {
+                    return json!({});
+                }
+                let Some(target) = document.pointer(pointer) else {
+                    warn!("Ignoring unresolved OpenAPI reference '{}'", reference);
+                    return json!({});
+                };
+                stack.push(reference.to_string());
+                let resolved = resolve_refs(document, target, stack);
+                stack.pop();
+                return resolved;
+            }
+            Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), resolve_refs(document, value, stack)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| resolve_refs(document, item, stack))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_names() {
+        let named = json!({"operationId": "listPets"});
+        assert_eq!(tool_name(&named, "get", "/pets"), "listPets");
+
+        let unnamed = json!({});
+        assert_eq!(
+            tool_name(&unnamed, "get", "/pets/{petId}/photos"),
+            "get_pets_petId_photos"
+        );
+    }
+
+    #[test]
+    fn test_recursive_refs_are_cut() {
+        let document = json!({
+            "components": {"schemas": {"Node": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "children": {"type": "array", "items": {"$ref": "#/components/schemas/Node"}}
+                }
+            }}}
+        });
+
+        let resolved = resolve_refs(
+            &document,
+            &json!({"$ref": "#/components/schemas/Node"}),
+            &mut Vec::new(),
+        );
+        assert_eq!(resolved["properties"]["name"]["type"], "string");
+        assert_eq!(resolved["properties"]["children"]["items"], json!({}));
+    }
+}
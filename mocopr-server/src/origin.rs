@@ -0,0 +1,378 @@
+//! Origin and Host validation for HTTP and WebSocket transports
+//!
+//! Browsers let any web page send requests to servers on the user's machine,
+//! and DNS rebinding lets a page on an attacker's domain reach a server bound
+//! to localhost under that domain's name. An [`OriginPolicy`] guards against
+//! both, as the MCP security guidance asks:
+//!
+//! - Requests carrying an `Origin` header are rejected with `403 Forbidden`
+//!   unless the origin is allowed. Requests without one, such as those of
+//!   non-browser clients, are not affected.
+//! - Requests whose `Host` header names a host that is not allowed are
+//!   rejected with `403 Forbidden`.
+//! - CORS responses only allow the allowed origins.
+//!
+//! The default policy allows loopback origins and hosts (`localhost`,
+//! `127.0.0.1` and `[::1]`, on any port), which suits the default bind
+//! address of `127.0.0.1`. Servers reachable under other names must list
+//! them. Loopback origins stay allowed when other origins are listed; turn
+//! that off with [`OriginPolicy::with_loopback_origins`] if untrusted pages
+//! may be served from the same machine.
+//!
+//! The policy covers every HTTP route of the server: the MCP endpoints, the
+//! health, readiness and metrics endpoints, OAuth protected resource metadata
+//! and the admin API. Probes and scrapers that address the server by another
+//! name, such as a pod IP, need that host allowed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::origin::OriginPolicy;
+//!
+//! # fn main() -> Result<()> {
+//! let server = McpServerBuilder::new()
+//!     .with_info("Shared Server", "1.0.0")
+//!     .with_bind_address("0.0.0.0", 8080)
+//!     .with_origin_policy(
+//!         OriginPolicy::new()
+//!             .with_allowed_host("mcp.example.com")
+//!             .with_allowed_origin("https://app.example.com"),
+//!     )
+//!     .with_websocket_transport()
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderName, HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
+use url::{Host, Url};
+
+/// Headers browsers may send on cross-origin requests by default
+const CORS_HEADERS: [&str; 4] = [
+    "authorization",
+    "content-type",
+    "x-api-key",
+    "mcp-protocol-version",
+];
+
+/// Which origins and hosts may reach the server
+#[derive(Debug, Clone)]
+pub struct OriginPolicy {
+    origins: Vec<String>,
+    any_origin: bool,
+    loopback_origins: bool,
+    hosts: Vec<String>,
+    any_host: bool,
+    cors_headers: Vec<String>,
+    cors_max_age: Option<Duration>,
+}
+
+impl Default for OriginPolicy {
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            any_origin: false,
+            loopback_origins: true,
+            hosts: Vec::new(),
+            any_host: false,
+            cors_headers: CORS_HEADERS.iter().map(|name| name.to_string()).collect(),
+            cors_max_age: None,
+        }
+    }
+}
+
+impl OriginPolicy {
+    /// Create a policy allowing loopback origins and hosts only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy allowing every origin and host
+    ///
+    /// This disables DNS rebinding protection and should only be used behind
+    /// a proxy that validates requests itself.
+    pub fn permissive() -> Self {
+        Self::new().allow_any_origin().allow_any_host()
+    }
+
+    /// Allow requests from `origin`, e.g. `https://app.example.com`
+    ///
+    /// `*` allows every origin. Origins that cannot be parsed are ignored
+    /// with a warning.
+    pub fn with_allowed_origin(mut self, origin: impl AsRef<str>) -> Self {
+        let origin = origin.as_ref().trim();
+        if origin == "*" {
+            self.any_origin = true;
+        } else {
+            match Url::parse(origin) {
+                Ok(url) if url.has_host() => self.origins.push(url.origin().ascii_serialization()),
+                _ => warn!("Ignoring invalid allowed origin '{}'", origin),
+            }
+        }
+        self
+    }
+
+    /// Allow requests from each of `origins`
+    pub fn with_allowed_origins<I, S>(self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        origins
+            .into_iter()
+            .fold(self, |policy, origin| policy.with_allowed_origin(origin))
+    }
+
+    /// Allow requests from every origin
+    pub fn allow_any_origin(mut self) -> Self {
+        self.any_origin = true;
+        self
+    }
+
+    /// Allow or refuse origins on loopback addresses, allowed by default
+    ///
+    /// Any page served from the user's machine, such as a local development
+    /// server, has a loopback origin. Refuse them when such pages are not
+    /// trusted, and list the local origins that should still be allowed.
+    pub fn with_loopback_origins(mut self, allowed: bool) -> Self {
+        self.loopback_origins = allowed;
+        self
+    }
+
+    /// Allow requests addressed to `host`, as named by the `Host` header
+    ///
+    /// A host without a port, such as `mcp.example.com`, is allowed on any
+    /// port; `mcp.example.com:8443` only on that port. `*` allows every host.
+    pub fn with_allowed_host(mut self, host: impl AsRef<str>) -> Self {
+        let host = host.as_ref().trim();
+        if host == "*" {
+            self.any_host = true;
+        } else {
+            self.hosts.push(host.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// Allow requests addressed to each of `hosts`
+    pub fn with_allowed_hosts<I, S>(self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        hosts
+            .into_iter()
+            .fold(self, |policy, host| policy.with_allowed_host(host))
+    }
+
+    /// Allow requests addressed to every host
+    pub fn allow_any_host(mut self) -> Self {
+        self.any_host = true;
+        self
+    }
+
+    /// Let browsers send `header` on cross-origin requests
+    ///
+    /// `Authorization`, `Content-Type`, `X-API-Key` and
+    /// `MCP-Protocol-Version` are allowed by default.
+    pub fn with_cors_header(mut self, header: impl Into<String>) -> Self {
+        self.cors_headers.push(header.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Let browsers cache CORS preflight responses for `max_age`
+    pub fn with_cors_max_age(mut self, max_age: Duration) -> Self {
+        self.cors_max_age = Some(max_age);
+        self
+    }
+
+    /// Check whether requests with the `Origin` header `origin` are allowed
+    ///
+    /// Loopback origins are allowed unless refused with
+    /// [`OriginPolicy::with_loopback_origins`], whether or not other origins
+    /// are listed.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        if self.any_origin {
+            return true;
+        }
+        let Ok(url) = Url::parse(origin) else {
+            // Includes the opaque origin `null`
+            return false;
+        };
+        let is_loopback = match url.host() {
+            Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+            Some(Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(Host::Ipv6(ip)) => ip.is_loopback(),
+            None => return false,
+        };
+        (is_loopback && self.loopback_origins)
+            || self.origins.contains(&url.origin().ascii_serialization())
+    }
+
+    /// Check whether requests with the `Host` header `host` are allowed
+    pub fn allows_host(&self, host: &str) -> bool {
+        if self.any_host {
+            return true;
+        }
+        let host = host.trim().to_ascii_lowercase();
+        let name = host_name(&host);
+        let is_loopback = name == "localhost"
+            || name
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        is_loopback
+            || self
+                .hosts
+                .iter()
+                .any(|allowed| *allowed == host || allowed == name)
+    }
+
+    /// CORS layer allowing the allowed origins
+    pub(crate) fn cors_layer(&self) -> CorsLayer {
+        let policy = self.clone();
+        let mut cors = CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| policy.allows_origin(origin))
+            }))
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers(
+                self.cors_headers
+                    .iter()
+                    .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+                    .collect::<Vec<_>>(),
+            )
+            .expose_headers([header::WWW_AUTHENTICATE]);
+        if let Some(max_age) = self.cors_max_age {
+            cors = cors.max_age(max_age);
+        }
+        cors
+    }
+}
+
+/// Host part of a `Host` header value, without the port
+fn host_name(host: &str) -> &str {
+    if host.starts_with('[') {
+        return host.find(']').map_or(host, |end| &host[..=end]);
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+/// Axum middleware rejecting requests from disallowed origins and hosts
+pub(crate) async fn validate(
+    State(policy): State<Arc<OriginPolicy>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| {
+            request
+                .uri()
+                .authority()
+                .map(|authority| authority.as_str())
+        });
+    if !host.is_some_and(|host| policy.allows_host(host)) {
+        warn!(
+            "Rejected request to {} for host {:?}",
+            request.uri(),
+            host.unwrap_or_default()
+        );
+        return (StatusCode::FORBIDDEN, "Host not allowed").into_response();
+    }
+
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let origin = origin.to_str().unwrap_or_default();
+        if !policy.allows_origin(origin) {
+            warn!(
+                "Rejected request to {} from origin {}",
+                request.uri(),
+                origin
+            );
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_allows_loopback_only() {
+        let policy = OriginPolicy::new();
+        for origin in [
+            "http://localhost:3000",
+            "https://127.0.0.1",
+            "http://[::1]:8080",
+        ] {
+            assert!(policy.allows_origin(origin), "{origin}");
+        }
+        for origin in [
+            "https://evil.example.com",
+            "null",
+            "http://localhost.evil.com",
+        ] {
+            assert!(!policy.allows_origin(origin), "{origin}");
+        }
+
+        for host in ["localhost", "127.0.0.1:8080", "[::1]:8080", "LOCALHOST:1"] {
+            assert!(policy.allows_host(host), "{host}");
+        }
+        for host in ["evil.example.com", "evil.example.com:8080", "10.0.0.1:8080"] {
+            assert!(!policy.allows_host(host), "{host}");
+        }
+    }
+
+    #[test]
+    fn test_allowed_origins_and_hosts() {
+        let policy = OriginPolicy::new()
+            .with_allowed_origins(["https://app.example.com/", "not a url"])
+            .with_allowed_hosts(["mcp.example.com", "api.example.com:8443"]);
+
+        assert!(policy.allows_origin("https://app.example.com"));
+        assert!(!policy.allows_origin("http://app.example.com"));
+        assert!(!policy.allows_origin("https://app.example.com:444"));
+
+        assert!(policy.allows_host("mcp.example.com"));
+        assert!(policy.allows_host("MCP.example.com:9000"));
+        assert!(policy.allows_host("api.example.com:8443"));
+        assert!(!policy.allows_host("api.example.com"));
+
+        let policy = OriginPolicy::new()
+            .with_allowed_origin("*")
+            .with_allowed_host("*");
+        assert!(policy.allows_origin("https://anywhere.example.com"));
+        assert!(policy.allows_host("anywhere.example.com"));
+    }
+
+    #[test]
+    fn test_loopback_origins_can_be_refused() {
+        let policy = OriginPolicy::new()
+            .with_allowed_origin("http://localhost:3000")
+            .with_loopback_origins(false);
+
+        assert!(policy.allows_origin("http://localhost:3000"));
+        assert!(!policy.allows_origin("http://localhost:8080"));
+        assert!(!policy.allows_origin("http://127.0.0.1:3000"));
+        assert!(policy.allows_host("localhost:8080"));
+    }
+}
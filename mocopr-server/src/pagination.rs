@@ -0,0 +1,219 @@
+//! Cursor-based pagination for list operations
+//!
+//! List results are ordered by a stable key (tool name, prompt name, resource
+//! URI) and paginated by key rather than by position. A cursor records the last
+//! key returned on the previous page, so items added or removed between calls
+//! never cause duplicates or skipped entries.
+//!
+//! Cursors are opaque to clients: the key is base64url-encoded and signed with
+//! HMAC-SHA256 together with the name of the list it belongs to. A cursor that
+//! was modified, forged, or taken from a different list is rejected with an
+//! invalid params error.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use mocopr_core::prelude::*;
+use rand::RngCore;
+use sha2::Sha256;
+use std::sync::Arc;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default number of items returned per page
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Produces and validates signed pagination cursors
+#[derive(Clone)]
+pub struct Paginator {
+    page_size: usize,
+    secret: Arc<[u8]>,
+}
+
+impl Paginator {
+    /// Create a paginator with the given page size and a random signing secret
+    pub fn new(page_size: usize) -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        Self {
+            page_size: page_size.max(1),
+            secret: Arc::from(secret.as_slice()),
+        }
+    }
+
+    /// Use a fixed signing secret
+    ///
+    /// Servers running as several replicas behind a load balancer should share
+    /// a secret so that a cursor issued by one replica is accepted by another.
+    pub fn with_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secret = Arc::from(secret.as_ref());
+        self
+    }
+
+    /// Get the page size
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Encode a cursor pointing after `key` in the list named `scope`
+    pub fn encode_cursor(&self, scope: &str, key: &str) -> String {
+        let tag = self.sign(scope, key.as_bytes());
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(key),
+            URL_SAFE_NO_PAD.encode(tag)
+        )
+    }
+
+    /// Decode and verify a cursor for the list named `scope`
+    pub fn decode_cursor(&self, scope: &str, cursor: &str) -> Result<String> {
+        let invalid = || Error::invalid_params(format!("Invalid {} cursor", scope));
+
+        let (key, tag) = cursor.split_once('.').ok_or_else(invalid)?;
+        let key = URL_SAFE_NO_PAD.decode(key).map_err(|_| invalid())?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).map_err(|_| invalid())?;
+
+        self.mac(scope, &key)
+            .verify_slice(&tag)
+            .map_err(|_| invalid())?;
+
+        String::from_utf8(key).map_err(|_| invalid())
+    }
+
+    /// Return the page of `items` that follows `cursor`
+    ///
+    /// Items are sorted by key; the returned cursor is `None` on the last page.
+    pub fn paginate<T>(
+        &self,
+        scope: &str,
+        mut items: Vec<(String, T)>,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<T>, Option<String>)> {
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let start = match cursor {
+            Some(cursor) => {
+                let after = self.decode_cursor(scope, cursor)?;
+                items.partition_point(|(key, _)| key.as_str() <= after.as_str())
+            }
+            None => 0,
+        };
+
+        let end = (start + self.page_size).min(items.len());
+        let next_cursor = if end < items.len() {
+            Some(self.encode_cursor(scope, &items[end - 1].0))
+        } else {
+            None
+        };
+
+        let page = items
+            .into_iter()
+            .skip(start)
+            .take(end - start)
+            .map(|(_, item)| item)
+            .collect();
+
+        Ok((page, next_cursor))
+    }
+
+    fn mac(&self, scope: &str, key: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(scope.as_bytes());
+        mac.update(&[0]);
+        mac.update(key);
+        mac
+    }
+
+    fn sign(&self, scope: &str, key: &[u8]) -> Vec<u8> {
+        self.mac(scope, key).finalize().into_bytes().to_vec()
+    }
+}
+
+impl Default for Paginator {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAGE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(names: &[&str]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|n| (n.to_string(), n.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_pages_are_ordered_and_complete() {
+        let paginator = Paginator::new(2);
+        let all = items(&["d", "b", "e", "a", "c"]);
+
+        let (first, cursor) = paginator.paginate("tools", all.clone(), None).unwrap();
+        assert_eq!(first, vec!["a", "b"]);
+
+        let (second, cursor) = paginator
+            .paginate("tools", all.clone(), cursor.as_deref())
+            .unwrap();
+        assert_eq!(second, vec!["c", "d"]);
+
+        let (third, cursor) = paginator.paginate("tools", all, cursor.as_deref()).unwrap();
+        assert_eq!(third, vec!["e"]);
+        assert!(cursor.is_none());
+    }
+
+    #[test]
+    fn test_changes_between_pages() {
+        let paginator = Paginator::new(2);
+
+        let (_, cursor) = paginator
+            .paginate("tools", items(&["a", "b", "c", "d"]), None)
+            .unwrap();
+
+        // "b" removed and "aa" inserted before the cursor; "bb" inserted after it
+        let (page, _) = paginator
+            .paginate(
+                "tools",
+                items(&["a", "aa", "bb", "c", "d"]),
+                cursor.as_deref(),
+            )
+            .unwrap();
+        assert_eq!(page, vec!["bb", "c"]);
+    }
+
+    #[test]
+    fn test_rejects_tampered_cursors() {
+        let paginator = Paginator::new(1);
+        let (_, cursor) = paginator
+            .paginate("tools", items(&["a", "b"]), None)
+            .unwrap();
+        let cursor = cursor.unwrap();
+
+        let forged = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode("z"),
+            cursor.split_once('.').unwrap().1
+        );
+        for bad in ["1", "not-a-cursor", forged.as_str()] {
+            let err = paginator.paginate("tools", items(&["a", "b"]), Some(bad));
+            assert!(matches!(err, Err(Error::InvalidParams(_))));
+        }
+
+        // Cursors are bound to the list that issued them
+        assert!(paginator.decode_cursor("prompts", &cursor).is_err());
+        // and to the signing secret
+        assert!(Paginator::new(1).decode_cursor("tools", &cursor).is_err());
+    }
+
+    #[test]
+    fn test_shared_secret() {
+        let a = Paginator::new(1).with_secret("shared");
+        let b = Paginator::new(1).with_secret("shared");
+        let cursor = a.encode_cursor("resources", "file:///a");
+        assert_eq!(b.decode_cursor("resources", &cursor).unwrap(), "file:///a");
+    }
+}
@@ -0,0 +1,538 @@
+//! Directory-backed prompt library
+//!
+//! [`DirectoryPromptProvider`] serves every `.md` file in a directory as a
+//! prompt, so prompts can be written and edited without recompiling the
+//! server. Each file starts with YAML front matter declaring the prompt and
+//! its arguments, followed by the template:
+//!
+//! ```markdown
+//! ---
+//! name: code_review
+//! description: Review a piece of code
+//! arguments:
+//!   - name: code
+//!     description: The code to review
+//!     required: true
+//!   - name: focus
+//! ---
+//! ## system
+//! You are a careful code reviewer.
+//!
+//! ## user
+//! Review this code{% if focus %}, focusing on {{ focus }}{% endif %}:
+//!
+//! {{ code }}
+//! ```
+//!
+//! The body is rendered by [`TemplatePromptHandler`], so it uses Jinja
+//! syntax. `## system`, `## user` and `## assistant` headings split the body
+//! into messages with those roles; a body without role headings is a single
+//! user message. The name defaults to the file name without its extension.
+//!
+//! When watching is enabled, the directory is reloaded when Markdown files
+//! change and `notifications/prompts/list_changed` is sent. A burst of
+//! changes, such as an editor saving or a checkout rewriting many files, is
+//! reloaded once, after no file has changed for the debounce period. Files that
+//! fail to parse are skipped with a warning so one broken prompt does not
+//! take the others down.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let server = McpServerBuilder::new()
+//!     .with_info("Prompt Library", "1.0.0")
+//!     .with_prompts()
+//!     .with_prompt_provider(DirectoryPromptProvider::new("./prompts")?)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::handlers::{PromptHandler, PromptProvider, TemplatePromptHandler};
+use crate::notifications::NotificationSender;
+use async_trait::async_trait;
+use mocopr_core::error::ProtocolError;
+use mocopr_core::prelude::*;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default time without file changes before prompts are reloaded
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+type PromptMap = HashMap<String, Arc<TemplatePromptHandler>>;
+
+/// Prompt provider that loads prompts from Markdown files in a directory
+pub struct DirectoryPromptProvider {
+    root: PathBuf,
+    prompts: Arc<RwLock<PromptMap>>,
+    watch_enabled: bool,
+    debounce: Duration,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+/// YAML front matter of a prompt file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    arguments: Vec<PromptArgument>,
+}
+
+impl DirectoryPromptProvider {
+    /// Create a provider for the directory at `root` and load its prompts.
+    ///
+    /// The directory must exist. It is watched for changes by default.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self> {
+        let root = std::fs::canonicalize(root.as_ref()).map_err(|e| {
+            Error::resource_access(format!(
+                "Failed to open prompt directory '{}': {}",
+                root.as_ref().display(),
+                e
+            ))
+        })?;
+
+        if !root.is_dir() {
+            return Err(Error::resource_access(format!(
+                "'{}' is not a directory",
+                root.display()
+            )));
+        }
+
+        let prompts = load_prompts(&root)?;
+        Ok(Self {
+            root,
+            prompts: Arc::new(RwLock::new(prompts)),
+            watch_enabled: true,
+            debounce: DEFAULT_DEBOUNCE,
+            watcher: Mutex::new(None),
+        })
+    }
+
+    /// Enable or disable reloading when files change
+    pub fn with_watching(mut self, enabled: bool) -> Self {
+        self.watch_enabled = enabled;
+        self
+    }
+
+    /// Set how long no file may change before prompts are reloaded
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Get the directory prompts are loaded from
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the names of the loaded prompts, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Load the prompts from disk again, replacing the current set
+    pub fn reload(&self) -> Result<()> {
+        reload(&self.root, &self.prompts)
+    }
+
+    fn get(&self, name: &str) -> Option<Arc<TemplatePromptHandler>> {
+        self.read().get(name).cloned()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, PromptMap> {
+        self.prompts.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl PromptProvider for DirectoryPromptProvider {
+    async fn list(&self) -> Result<Vec<Prompt>> {
+        let mut handlers: Vec<_> = self.read().values().cloned().collect();
+        handlers.sort_by(|a, b| a.name().cmp(b.name()));
+
+        let mut prompts = Vec::with_capacity(handlers.len());
+        for handler in handlers {
+            prompts.push(handler.prompt().await);
+        }
+        Ok(prompts)
+    }
+
+    fn handles(&self, name: &str) -> bool {
+        self.read().contains_key(name)
+    }
+
+    async fn generate(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, String>>,
+    ) -> Result<PromptsGetResponse> {
+        let handler = self
+            .get(name)
+            .ok_or_else(|| Error::Protocol(ProtocolError::PromptNotFound(name.to_string())))?;
+        handler.generate(arguments).await
+    }
+
+    fn watch(&self, notifications: NotificationSender) -> Result<()> {
+        if !self.watch_enabled {
+            return Ok(());
+        }
+
+        let root = self.root.clone();
+        let (changed, changes) = mpsc::channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("Prompt watcher error for '{}': {}", root.display(), e);
+                        return;
+                    }
+                };
+
+                if !event.paths.iter().any(|path| is_prompt_file(path)) || event.kind.is_access() {
+                    return;
+                }
+
+                debug!("Prompt file event {:?} for {:?}", event.kind, event.paths);
+                let _ = changed.send(());
+            })
+            .map_err(|e| Error::internal(format!("Failed to create prompt watcher: {}", e)))?;
+
+        watcher
+            .watch(&self.root, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                Error::internal(format!(
+                    "Failed to watch prompt directory '{}': {}",
+                    self.root.display(),
+                    e
+                ))
+            })?;
+
+        let root = self.root.clone();
+        let prompts = self.prompts.clone();
+        let debounce = self.debounce;
+        std::thread::Builder::new()
+            .name("prompt-reload".to_string())
+            .spawn(move || reload_on_change(&root, &prompts, &changes, debounce, &notifications))
+            .map_err(|e| Error::internal(format!("Failed to start prompt reloader: {}", e)))?;
+
+        *self
+            .watcher
+            .lock()
+            .map_err(|_| Error::internal("Watcher lock poisoned"))? = Some(watcher);
+
+        Ok(())
+    }
+}
+
+/// Reload the prompts once no change has arrived for `debounce`
+///
+/// Runs until the watcher sending the changes is dropped.
+fn reload_on_change(
+    root: &Path,
+    prompts: &RwLock<PromptMap>,
+    changes: &mpsc::Receiver<()>,
+    debounce: Duration,
+    notifications: &NotificationSender,
+) {
+    while changes.recv().is_ok() {
+        loop {
+            match changes.recv_timeout(debounce) {
+                Ok(()) => continue,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        match reload(root, prompts) {
+            Ok(()) => notifications.prompt_list_changed(),
+            Err(e) => warn!("Failed to reload prompts from '{}': {}", root.display(), e),
+        }
+    }
+}
+
+/// Check whether a path names a visible Markdown file
+fn is_prompt_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+        && !path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+fn reload(root: &Path, prompts: &RwLock<PromptMap>) -> Result<()> {
+    let loaded = load_prompts(root)?;
+    *prompts.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+    Ok(())
+}
+
+/// Load every prompt file in `root`, skipping files that fail to parse
+fn load_prompts(root: &Path) -> Result<PromptMap> {
+    let entries = std::fs::read_dir(root).map_err(|e| {
+        Error::resource_access(format!(
+            "Failed to read prompt directory '{}': {}",
+            root.display(),
+            e
+        ))
+    })?;
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_prompt_file(path))
+        .collect();
+    paths.sort();
+
+    let mut prompts = PromptMap::new();
+    for path in paths {
+        let handler = std::fs::read_to_string(&path)
+            .map_err(|e| Error::resource_access(e.to_string()))
+            .and_then(|source| parse_prompt(&path, &source));
+        match handler {
+            Ok(handler) => match prompts.entry(handler.name().to_string()) {
+                Entry::Occupied(entry) => warn!(
+                    "Skipping prompt file '{}': prompt '{}' is already defined",
+                    path.display(),
+                    entry.key()
+                ),
+                Entry::Vacant(entry) => {
+                    entry.insert(Arc::new(handler));
+                }
+            },
+            Err(e) => warn!("Skipping prompt file '{}': {}", path.display(), e),
+        }
+    }
+    Ok(prompts)
+}
+
+/// Parse a prompt file into a template handler
+fn parse_prompt(path: &Path, source: &str) -> Result<TemplatePromptHandler> {
+    let source = source.replace("\r\n", "\n");
+    let (front_matter, body) = split_front_matter(&source)
+        .ok_or_else(|| Error::InvalidParams("Missing YAML front matter".to_string()))?;
+    let front_matter: FrontMatter = serde_yaml::from_str(front_matter)
+        .map_err(|e| Error::InvalidParams(format!("Invalid front matter: {}", e)))?;
+
+    let name = match front_matter.name {
+        Some(name) => name,
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::InvalidParams("Prompt has no name".to_string()))?,
+    };
+
+    let mut handler = TemplatePromptHandler::conversation(
+        name,
+        front_matter.description.unwrap_or_default(),
+        front_matter.arguments,
+    );
+    for (role, template) in split_messages(body) {
+        handler = handler.with_message(role, template);
+    }
+    handler.validate()?;
+    Ok(handler)
+}
+
+/// Split `source` into its front matter and body
+fn split_front_matter(source: &str) -> Option<(&str, &str)> {
+    let rest = source.strip_prefix("---\n")?;
+    if let Some(body) = rest.strip_prefix("---\n") {
+        return Some(("", body));
+    }
+    let end = rest.find("\n---\n").map(|end| (end, end + 5)).or_else(|| {
+        rest.strip_suffix("\n---")
+            .map(|front| (front.len(), rest.len()))
+    })?;
+    Some((&rest[..end.0], &rest[end.1..]))
+}
+
+/// Split a prompt body into messages at `## <role>` headings
+///
+/// Headings inside fenced code blocks are left alone.
+fn split_messages(body: &str) -> Vec<(MessageRole, String)> {
+    let mut messages = Vec::new();
+    let mut role = MessageRole::User;
+    let mut text = String::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if let Some(next) = (!in_fence).then(|| role_heading(line)).flatten() {
+            push_message(&mut messages, role, &text);
+            role = next;
+            text.clear();
+            continue;
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    push_message(&mut messages, role, &text);
+    messages
+}
+
+fn push_message(messages: &mut Vec<(MessageRole, String)>, role: MessageRole, text: &str) {
+    let text = text.trim();
+    if !text.is_empty() {
+        messages.push((role, text.to_string()));
+    }
+}
+
+fn role_heading(line: &str) -> Option<MessageRole> {
+    let heading = line.strip_prefix("##")?.trim();
+    match heading.to_ascii_lowercase().as_str() {
+        "system" => Some(MessageRole::System),
+        "user" => Some(MessageRole::User),
+        "assistant" => Some(MessageRole::Assistant),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const REVIEW: &str = "---
+name: code_review
+description: Review code
+arguments:
+  - name: code
+    required: true
+  - name: focus
+---
+## System
+You review code.
+
+## user
+Review{% if focus %} for {{ focus }}{% endif %}:
+
+```
+## not a heading
+{{ code }}
+```
+";
+
+    fn setup() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("review.md"), REVIEW).unwrap();
+        std::fs::write(
+            dir.path().join("greet.md"),
+            "---\narguments: [{name: who}]\n---\nHello {{ who | default(\"world\") }}!\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.md"), "no front matter").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "---\n---\nignored").unwrap();
+        dir
+    }
+
+    fn text(content: &Content) -> &str {
+        match content {
+            Content::Text(text) => &text.text,
+            other => panic!("unexpected content: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loads_prompts_and_role_sections() {
+        let dir = setup();
+        let provider = DirectoryPromptProvider::new(dir.path()).unwrap();
+        assert_eq!(provider.names(), ["code_review", "greet"]);
+
+        let prompts = provider.list().await.unwrap();
+        assert_eq!(prompts[0].description.as_deref(), Some("Review code"));
+        assert_eq!(prompts[0].arguments.as_ref().unwrap().len(), 2);
+
+        let arguments = HashMap::from([
+            ("code".to_string(), "x = 1".to_string()),
+            ("focus".to_string(), "style".to_string()),
+        ]);
+        let response = provider
+            .generate("code_review", Some(arguments))
+            .await
+            .unwrap();
+        assert_eq!(response.messages.len(), 2);
+        assert!(matches!(response.messages[0].role, MessageRole::System));
+        assert_eq!(text(&response.messages[0].content), "You review code.");
+        assert_eq!(
+            text(&response.messages[1].content),
+            "Review for style:\n\n```\n## not a heading\nx = 1\n```"
+        );
+
+        let response = provider.generate("greet", None).await.unwrap();
+        assert!(matches!(response.messages[0].role, MessageRole::User));
+        assert_eq!(text(&response.messages[0].content), "Hello world!");
+
+        let missing = provider.generate("code_review", None).await.unwrap_err();
+        assert!(matches!(missing, Error::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn test_watch_reloads_and_notifies() {
+        let dir = setup();
+        let provider = DirectoryPromptProvider::new(dir.path()).unwrap();
+
+        let notifications = NotificationSender::new();
+        let mut receiver = notifications.subscribe();
+        provider.watch(notifications).unwrap();
+
+        std::fs::write(
+            provider.root().join("farewell.md"),
+            "---\ndescription: Say goodbye\n---\nBye!\n",
+        )
+        .unwrap();
+
+        let notification = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no list_changed notification")
+            .unwrap();
+        assert_eq!(
+            notification.method,
+            crate::notifications::PROMPTS_LIST_CHANGED
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !provider.handles("farewell") {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("prompt was not reloaded");
+    }
+
+    #[tokio::test]
+    async fn test_server_relays_list_changed() {
+        let dir = setup();
+        let server = crate::McpServerBuilder::new()
+            .with_info("Prompt Library", "1.0.0")
+            .with_prompts()
+            .with_prompt_provider(DirectoryPromptProvider::new(dir.path()).unwrap())
+            .build()
+            .unwrap();
+        let mut receiver = server.notifications().subscribe();
+
+        std::fs::remove_file(dir.path().join("greet.md")).unwrap();
+
+        let notification = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no list_changed notification")
+            .unwrap();
+        assert_eq!(
+            notification.method,
+            crate::notifications::PROMPTS_LIST_CHANGED
+        );
+    }
+}
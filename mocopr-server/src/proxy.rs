@@ -0,0 +1,849 @@
+//! Aggregating proxy for upstream MCP servers
+//!
+//! An [`McpProxy`] fronts any number of upstream servers, each reached through
+//! an [`McpClient`], behind a single [`McpServer`](crate::McpServer). Tools and
+//! prompts are exposed under the namespace of their upstream, so the `search`
+//! tool of the upstream registered as `github` is offered as `github.search`.
+//! Namespaces must not overlap: registering both `git` and `git.hub` fails when
+//! the server is built.
+//!
+//! Resources and resource templates keep their URIs. A read is routed to the
+//! upstream that lists the resource or, failing that, to the upstream offering
+//! the most specific template the URI matches.
+//!
+//! Calls are forwarded to the owning upstream. Progress notifications for a
+//! forwarded call are relayed to the client that made it, and `list_changed`
+//! notifications from an upstream are passed on to every connected client. A
+//! request cancelled by a WebSocket client is cancelled upstream as well; stdio
+//! sessions handle one request at a time, so their cancellations arrive only
+//! after the call has finished.
+//!
+//! When the connection to an upstream drops, the upstream is marked unhealthy:
+//! its tools, prompts and resources disappear from the lists and calls to it
+//! fail. Upstreams added with [`McpProxy::with_reconnecting_upstream`] are
+//! reconnected in the background and come back once the connection succeeds.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_client::McpClient;
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::proxy::McpProxy;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let client_info = Implementation {
+//!         name: "My Proxy".to_string(),
+//!         version: "1.0.0".to_string(),
+//!     };
+//!     let connect_github = {
+//!         let client_info = client_info.clone();
+//!         move || {
+//!             McpClient::connect_websocket(
+//!                 "ws://localhost:9001/mcp",
+//!                 client_info.clone(),
+//!                 ClientCapabilities::default(),
+//!             )
+//!         }
+//!     };
+//!     let github = connect_github().await?;
+//!     let jira = McpClient::connect_stdio(
+//!         "jira-mcp",
+//!         &[],
+//!         client_info,
+//!         ClientCapabilities::default(),
+//!     )
+//!     .await?;
+//!
+//!     let proxy = McpProxy::new()
+//!         .with_reconnecting_upstream("github", github, connect_github)
+//!         .with_upstream("jira", jira);
+//!
+//!     let server = McpServerBuilder::new()
+//!         .with_info("My Proxy", "1.0.0")
+//!         .with_proxy(proxy)?
+//!         .build()?;
+//!
+//!     server.run_stdio().await
+//! }
+//! ```
+
+use crate::context::RequestContext;
+use crate::handlers::{PromptProvider, ResourceProvider, ToolProvider};
+use crate::notifications::{
+    NotificationSender, PROMPTS_LIST_CHANGED, RESOURCES_LIST_CHANGED, RESOURCES_UPDATED,
+    TOOLS_LIST_CHANGED,
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use mocopr_client::McpClient;
+use mocopr_core::error::ProtocolError;
+use mocopr_core::monitoring::{HealthCheck, HealthCheckResult, HealthStatus};
+use mocopr_core::prelude::*;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Separator placed between an upstream namespace and a tool or prompt name
+pub const DEFAULT_NAMESPACE_SEPARATOR: &str = ".";
+
+/// Delay before the first attempt to reconnect to an upstream
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between attempts to reconnect to an upstream
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Opens a new connection to an upstream server
+type Connector = Box<dyn Fn() -> BoxFuture<'static, Result<McpClient>> + Send + Sync>;
+
+/// Proxy that aggregates several upstream MCP servers
+///
+/// Register it on a server with
+/// [`McpServerBuilder::with_proxy`](crate::McpServerBuilder::with_proxy).
+/// Clones share the same upstream connections.
+#[derive(Clone)]
+pub struct McpProxy {
+    upstreams: Vec<Arc<Upstream>>,
+    separator: String,
+    resource_routes: Arc<RwLock<ResourceRoutes>>,
+    watching: Arc<AtomicBool>,
+}
+
+/// An upstream server and what is known about it
+struct Upstream {
+    namespace: String,
+    client: RwLock<Arc<McpClient>>,
+    connect: Option<Connector>,
+    healthy: AtomicBool,
+    tools: tokio::sync::RwLock<Option<Vec<Tool>>>,
+    prompts: tokio::sync::RwLock<Option<Vec<Prompt>>>,
+    resources: tokio::sync::RwLock<Option<Vec<Resource>>>,
+    templates: tokio::sync::RwLock<Option<Vec<ResourceTemplate>>>,
+}
+
+/// The upstreams that resource URIs are routed to
+#[derive(Default)]
+struct ResourceRoutes {
+    /// Resource URI to the namespace of the upstream listing it
+    uris: HashMap<String, String>,
+    /// URI template and the namespace of the upstream offering it
+    templates: Vec<(String, String)>,
+}
+
+impl McpProxy {
+    /// Create a proxy without upstreams
+    pub fn new() -> Self {
+        Self {
+            upstreams: Vec::new(),
+            separator: DEFAULT_NAMESPACE_SEPARATOR.to_string(),
+            resource_routes: Arc::new(RwLock::new(ResourceRoutes::default())),
+            watching: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Set the separator between namespace and name, `.` by default
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Add an upstream server whose tools and prompts are exposed under `namespace`
+    ///
+    /// The upstream stays unavailable once its connection drops.
+    pub fn with_upstream(self, namespace: impl Into<String>, client: McpClient) -> Self {
+        self.add_upstream(namespace.into(), client, None)
+    }
+
+    /// Add an upstream server that is reconnected with `connect` when its connection drops
+    ///
+    /// Failed attempts are retried with exponential backoff, from half a second
+    /// up to 30 seconds. Once an attempt succeeds the upstream is healthy again
+    /// and clients are told that its lists changed.
+    pub fn with_reconnecting_upstream<F, Fut>(
+        self,
+        namespace: impl Into<String>,
+        client: McpClient,
+        connect: F,
+    ) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<McpClient>> + Send + 'static,
+    {
+        let connect: Connector = Box::new(move || Box::pin(connect()));
+        self.add_upstream(namespace.into(), client, Some(connect))
+    }
+
+    fn add_upstream(
+        mut self,
+        namespace: String,
+        client: McpClient,
+        connect: Option<Connector>,
+    ) -> Self {
+        self.upstreams.push(Arc::new(Upstream {
+            namespace,
+            client: RwLock::new(Arc::new(client)),
+            connect,
+            healthy: AtomicBool::new(true),
+            tools: tokio::sync::RwLock::new(None),
+            prompts: tokio::sync::RwLock::new(None),
+            resources: tokio::sync::RwLock::new(None),
+            templates: tokio::sync::RwLock::new(None),
+        }));
+        self
+    }
+
+    /// Check that every namespaced name belongs to exactly one upstream
+    ///
+    /// Fails if two upstreams share a namespace, or if one namespace followed
+    /// by the separator begins another, as `git` and `git.hub` do with the
+    /// default separator. Called by
+    /// [`McpServerBuilder::with_proxy`](crate::McpServerBuilder::with_proxy).
+    pub fn validate(&self) -> Result<()> {
+        for (index, upstream) in self.upstreams.iter().enumerate() {
+            let prefix = self.namespaced(upstream, "");
+            for other in &self.upstreams[index + 1..] {
+                let other_prefix = self.namespaced(other, "");
+                if prefix.starts_with(&other_prefix) || other_prefix.starts_with(&prefix) {
+                    return Err(Error::Configuration(format!(
+                        "Upstream namespaces '{}' and '{}' overlap",
+                        upstream.namespace, other.namespace
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the health of every upstream by namespace
+    pub fn upstream_health(&self) -> HashMap<String, bool> {
+        self.upstreams
+            .iter()
+            .map(|upstream| (upstream.namespace.clone(), upstream.is_healthy()))
+            .collect()
+    }
+
+    /// Create a health check that reports unhealthy upstreams
+    ///
+    /// Register it with [`MonitoringSystem::register_health_check`](mocopr_core::monitoring::MonitoringSystem::register_health_check)
+    /// to have upstream outages show up on the health endpoint.
+    pub fn health_check(&self) -> Box<dyn HealthCheck> {
+        Box::new(ProxyHealthCheck {
+            proxy: self.clone(),
+        })
+    }
+
+    /// Start relaying notifications from every upstream to `notifications`
+    ///
+    /// Only the first call has an effect. This is done automatically when the
+    /// proxy is registered as a resource provider.
+    pub fn watch(&self, notifications: NotificationSender) -> Result<()> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|_| Error::internal("Watching upstreams requires a Tokio runtime"))?;
+        if self.watching.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        for upstream in &self.upstreams {
+            runtime.spawn(
+                upstream
+                    .clone()
+                    .relay(self.resource_routes.clone(), notifications.clone()),
+            );
+        }
+        Ok(())
+    }
+
+    /// Split a namespaced name into its upstream and the upstream's own name
+    fn route<'a>(&self, name: &'a str) -> Option<(&Arc<Upstream>, &'a str)> {
+        self.upstreams.iter().find_map(|upstream| {
+            name.strip_prefix(upstream.namespace.as_str())
+                .and_then(|rest| rest.strip_prefix(self.separator.as_str()))
+                .map(|name| (upstream, name))
+        })
+    }
+
+    fn namespaced(&self, upstream: &Upstream, name: &str) -> String {
+        format!("{}{}{}", upstream.namespace, self.separator, name)
+    }
+
+    fn resource_upstream(&self, uri: &url::Url) -> Option<&Arc<Upstream>> {
+        let routes = self.resource_routes.read().ok()?;
+        let namespace = routes.route(uri.as_str())?;
+        self.upstreams
+            .iter()
+            .find(|upstream| upstream.namespace == namespace)
+    }
+}
+
+impl Default for McpProxy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceRoutes {
+    /// Replace the routes to the upstream registered as `namespace`
+    fn set(&mut self, namespace: &str, resources: &[Resource], templates: &[ResourceTemplate]) {
+        self.uris.retain(|_, owner| owner != namespace);
+        self.templates.retain(|(_, owner)| owner != namespace);
+        for resource in resources {
+            self.uris
+                .insert(resource.uri.to_string(), namespace.to_string());
+        }
+        for template in templates {
+            self.templates
+                .push((template.uri_template.clone(), namespace.to_string()));
+        }
+    }
+
+    /// Get the namespace of the upstream serving `uri`
+    ///
+    /// A listed resource wins over templates. Among matching templates the one
+    /// with the longest literal prefix wins, then the one registered first.
+    fn route(&self, uri: &str) -> Option<&str> {
+        if let Some(namespace) = self.uris.get(uri) {
+            return Some(namespace);
+        }
+        self.templates
+            .iter()
+            .rev()
+            .filter(|(template, _)| template_matches(template, uri))
+            .max_by_key(|(template, _)| template.find('{').unwrap_or(template.len()))
+            .map(|(_, namespace)| namespace.as_str())
+    }
+}
+
+/// Check whether `uri` could be an expansion of the RFC 6570 `template`
+///
+/// Only the literal parts of the template are compared, in order; each
+/// expression matches any text.
+fn template_matches(template: &str, uri: &str) -> bool {
+    let mut literals = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        literals.push(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => return false,
+        }
+    }
+    let Some((first, others)) = literals.split_first() else {
+        return template == uri;
+    };
+
+    let Some(mut remaining) = uri.strip_prefix(first) else {
+        return false;
+    };
+    for literal in others {
+        match remaining.find(literal) {
+            Some(index) => remaining = &remaining[index + literal.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(rest)
+}
+
+impl Upstream {
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Acquire)
+    }
+
+    /// Get the current connection to the upstream
+    fn client(&self) -> Arc<McpClient> {
+        self.client
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Fail fast when the upstream is known to be down
+    fn ensure_healthy(&self) -> Result<()> {
+        if self.is_healthy() {
+            Ok(())
+        } else {
+            Err(Error::Server(format!(
+                "Upstream server '{}' is unavailable",
+                self.namespace
+            )))
+        }
+    }
+
+    /// Mark the upstream unhealthy if a call failed because the connection dropped
+    fn check_connection<T>(&self, result: Result<T>) -> Result<T> {
+        if let Err(Error::ConnectionClosed) = &result {
+            self.mark_unhealthy();
+        }
+        result
+    }
+
+    fn mark_unhealthy(&self) {
+        if self.healthy.swap(false, Ordering::AcqRel) {
+            warn!("Upstream server '{}' is unavailable", self.namespace);
+        }
+    }
+
+    async fn tools(&self) -> Result<Vec<Tool>> {
+        if let Some(tools) = self.tools.read().await.as_ref() {
+            return Ok(tools.clone());
+        }
+        let tools = self.check_connection(self.client().list_all_tools().await)?;
+        *self.tools.write().await = Some(tools.clone());
+        Ok(tools)
+    }
+
+    async fn prompts(&self) -> Result<Vec<Prompt>> {
+        if let Some(prompts) = self.prompts.read().await.as_ref() {
+            return Ok(prompts.clone());
+        }
+        let prompts = self.check_connection(self.client().list_all_prompts().await)?;
+        *self.prompts.write().await = Some(prompts.clone());
+        Ok(prompts)
+    }
+
+    async fn resources(&self) -> Result<Vec<Resource>> {
+        if let Some(resources) = self.resources.read().await.as_ref() {
+            return Ok(resources.clone());
+        }
+        let resources = self.check_connection(self.client().list_all_resources().await)?;
+        *self.resources.write().await = Some(resources.clone());
+        Ok(resources)
+    }
+
+    /// Get the resource templates, treating an upstream without templates as offering none
+    async fn templates(&self) -> Result<Vec<ResourceTemplate>> {
+        if let Some(templates) = self.templates.read().await.as_ref() {
+            return Ok(templates.clone());
+        }
+        let templates = match self.client().list_all_resource_templates().await {
+            Ok(templates) => templates,
+            Err(Error::ConnectionClosed) => {
+                self.mark_unhealthy();
+                return Err(Error::ConnectionClosed);
+            }
+            Err(e) => {
+                debug!(
+                    "Upstream '{}' offers no resource templates: {}",
+                    self.namespace, e
+                );
+                Vec::new()
+            }
+        };
+        *self.templates.write().await = Some(templates.clone());
+        Ok(templates)
+    }
+
+    /// Get the resources and templates of the upstream and route their URIs to it
+    async fn load_resources(
+        &self,
+        routes: &RwLock<ResourceRoutes>,
+    ) -> Result<(Vec<Resource>, Vec<ResourceTemplate>)> {
+        let resources = self.resources().await?;
+        let templates = self.templates().await?;
+        if let Ok(mut routes) = routes.write() {
+            routes.set(&self.namespace, &resources, &templates);
+        }
+        Ok((resources, templates))
+    }
+
+    async fn invalidate(&self) {
+        *self.tools.write().await = None;
+        *self.prompts.write().await = None;
+        *self.resources.write().await = None;
+        *self.templates.write().await = None;
+    }
+
+    /// Relay notifications from the upstream, reconnecting if it can, until
+    /// its connection is lost for good
+    async fn relay(
+        self: Arc<Self>,
+        routes: Arc<RwLock<ResourceRoutes>>,
+        notifications: NotificationSender,
+    ) {
+        loop {
+            // Route resource reads without waiting for a client to list them
+            if let Err(e) = self.load_resources(&routes).await {
+                debug!(
+                    "Failed to list resources of upstream '{}': {}",
+                    self.namespace, e
+                );
+            }
+
+            self.relay_connection(&routes, &notifications).await;
+            self.mark_unhealthy();
+            self.invalidate().await;
+            notify_all_lists_changed(&notifications);
+
+            let Some(connect) = &self.connect else {
+                return;
+            };
+            let client = self.reconnect(connect).await;
+            *self
+                .client
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(client);
+            self.healthy.store(true, Ordering::Release);
+            info!("Reconnected to upstream server '{}'", self.namespace);
+            notify_all_lists_changed(&notifications);
+        }
+    }
+
+    /// Relay notifications from the current connection until it closes
+    async fn relay_connection(
+        &self,
+        routes: &RwLock<ResourceRoutes>,
+        notifications: &NotificationSender,
+    ) {
+        let client = self.client();
+        let mut upstream_notifications = client.subscribe_notifications();
+
+        loop {
+            let notification = tokio::select! {
+                notification = upstream_notifications.recv() => notification,
+                _ = client.closed() => return,
+            };
+
+            match notification {
+                Ok(notification) => match notification.method.as_str() {
+                    TOOLS_LIST_CHANGED => {
+                        *self.tools.write().await = None;
+                        notifications.tool_list_changed();
+                    }
+                    PROMPTS_LIST_CHANGED => {
+                        *self.prompts.write().await = None;
+                        notifications.prompt_list_changed();
+                    }
+                    RESOURCES_LIST_CHANGED => {
+                        *self.resources.write().await = None;
+                        *self.templates.write().await = None;
+                        if let Err(e) = self.load_resources(routes).await {
+                            debug!(
+                                "Failed to list resources of upstream '{}': {}",
+                                self.namespace, e
+                            );
+                        }
+                        notifications.resource_list_changed();
+                    }
+                    RESOURCES_UPDATED => notifications.send(notification),
+                    method => debug!(
+                        "Not relaying '{}' from upstream '{}'",
+                        method, self.namespace
+                    ),
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // Some list changes may have been missed
+                    warn!(
+                        "Missed {} notifications from upstream '{}'",
+                        skipped, self.namespace
+                    );
+                    self.invalidate().await;
+                    notify_all_lists_changed(notifications);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Connect to the upstream again, retrying with backoff until it succeeds
+    async fn reconnect(&self, connect: &Connector) -> McpClient {
+        let mut delay = RECONNECT_DELAY;
+        loop {
+            tokio::time::sleep(delay).await;
+            match connect().await {
+                Ok(client) => return client,
+                Err(e) => {
+                    debug!(
+                        "Failed to reconnect to upstream '{}': {}",
+                        self.namespace, e
+                    );
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+}
+
+fn notify_all_lists_changed(notifications: &NotificationSender) {
+    notifications.tool_list_changed();
+    notifications.prompt_list_changed();
+    notifications.resource_list_changed();
+}
+
+#[async_trait]
+impl ToolProvider for McpProxy {
+    async fn list(&self) -> Result<Vec<Tool>> {
+        let mut tools = Vec::new();
+        for upstream in self.upstreams.iter().filter(|u| u.is_healthy()) {
+            match upstream.tools().await {
+                Ok(upstream_tools) => tools.extend(upstream_tools.into_iter().map(|mut tool| {
+                    tool.name = self.namespaced(upstream, &tool.name);
+                    tool
+                })),
+                Err(e) => warn!(
+                    "Failed to list tools of upstream '{}': {}",
+                    upstream.namespace, e
+                ),
+            }
+        }
+        Ok(tools)
+    }
+
+    fn handles(&self, name: &str) -> bool {
+        self.route(name).is_some()
+    }
+
+    async fn call(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<ToolsCallResponse> {
+        let (upstream, tool) = self
+            .route(name)
+            .ok_or_else(|| Error::Protocol(ProtocolError::ToolNotFound(name.to_string())))?;
+        upstream.ensure_healthy()?;
+
+        // Relay progress only when the downstream client asked for it
+        let context = RequestContext::current().filter(|c| c.progress_token().is_some());
+        let result = match context {
+            Some(context) => {
+                upstream
+                    .client()
+                    .call_tool_with_progress(tool.to_string(), arguments, |progress| {
+                        let _ = context.send_progress(
+                            progress.progress,
+                            progress.total,
+                            progress.message,
+                        );
+                    })
+                    .await
+            }
+            None => {
+                upstream
+                    .client()
+                    .call_tool(tool.to_string(), arguments)
+                    .await
+            }
+        };
+        upstream.check_connection(result)
+    }
+}
+
+#[async_trait]
+impl PromptProvider for McpProxy {
+    async fn list(&self) -> Result<Vec<Prompt>> {
+        let mut prompts = Vec::new();
+        for upstream in self.upstreams.iter().filter(|u| u.is_healthy()) {
+            match upstream.prompts().await {
+                Ok(upstream_prompts) => {
+                    prompts.extend(upstream_prompts.into_iter().map(|mut prompt| {
+                        prompt.name = self.namespaced(upstream, &prompt.name);
+                        prompt
+                    }))
+                }
+                Err(e) => warn!(
+                    "Failed to list prompts of upstream '{}': {}",
+                    upstream.namespace, e
+                ),
+            }
+        }
+        Ok(prompts)
+    }
+
+    fn handles(&self, name: &str) -> bool {
+        self.route(name).is_some()
+    }
+
+    async fn generate(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, String>>,
+    ) -> Result<PromptsGetResponse> {
+        let (upstream, prompt) = self
+            .route(name)
+            .ok_or_else(|| Error::Protocol(ProtocolError::PromptNotFound(name.to_string())))?;
+        upstream.ensure_healthy()?;
+
+        let result = upstream
+            .client()
+            .get_prompt(prompt.to_string(), arguments)
+            .await;
+        upstream.check_connection(result)
+    }
+}
+
+#[async_trait]
+impl ResourceProvider for McpProxy {
+    async fn list(&self) -> Result<Vec<Resource>> {
+        let mut resources = Vec::new();
+        for upstream in self.upstreams.iter().filter(|u| u.is_healthy()) {
+            match upstream.load_resources(&self.resource_routes).await {
+                Ok((upstream_resources, _)) => resources.extend(upstream_resources),
+                Err(e) => warn!(
+                    "Failed to list resources of upstream '{}': {}",
+                    upstream.namespace, e
+                ),
+            }
+        }
+        Ok(resources)
+    }
+
+    async fn templates(&self) -> Vec<ResourceTemplate> {
+        let mut templates = Vec::new();
+        for upstream in self.upstreams.iter().filter(|u| u.is_healthy()) {
+            match upstream.load_resources(&self.resource_routes).await {
+                Ok((_, upstream_templates)) => templates.extend(upstream_templates),
+                Err(e) => warn!(
+                    "Failed to list resource templates of upstream '{}': {}",
+                    upstream.namespace, e
+                ),
+            }
+        }
+        templates
+    }
+
+    fn handles(&self, uri: &url::Url) -> bool {
+        self.resource_upstream(uri).is_some()
+    }
+
+    async fn read(&self, uri: &url::Url) -> Result<Vec<ResourceContent>> {
+        let upstream = self
+            .resource_upstream(uri)
+            .ok_or_else(|| Error::Protocol(ProtocolError::ResourceNotFound(uri.to_string())))?;
+        upstream.ensure_healthy()?;
+
+        let result = upstream.client().read_resource(uri.clone()).await;
+        upstream
+            .check_connection(result)
+            .map(|response| response.contents)
+    }
+
+    fn supports_subscription(&self) -> bool {
+        true
+    }
+
+    async fn subscribe(&self, uri: &url::Url) -> Result<()> {
+        let upstream = self
+            .resource_upstream(uri)
+            .ok_or_else(|| Error::Protocol(ProtocolError::ResourceNotFound(uri.to_string())))?;
+        upstream.ensure_healthy()?;
+
+        let result = upstream.client().subscribe_resource(uri.clone()).await;
+        upstream.check_connection(result).map(|_| ())
+    }
+
+    async fn unsubscribe(&self, uri: &url::Url) -> Result<()> {
+        let upstream = self
+            .resource_upstream(uri)
+            .ok_or_else(|| Error::Protocol(ProtocolError::ResourceNotFound(uri.to_string())))?;
+        upstream.ensure_healthy()?;
+
+        let result = upstream.client().unsubscribe_resource(uri.clone()).await;
+        upstream.check_connection(result).map(|_| ())
+    }
+
+    fn watch(&self, notifications: NotificationSender) -> Result<()> {
+        McpProxy::watch(self, notifications)
+    }
+}
+
+/// Health check reporting the upstreams of a proxy
+struct ProxyHealthCheck {
+    proxy: McpProxy,
+}
+
+#[async_trait]
+impl HealthCheck for ProxyHealthCheck {
+    fn name(&self) -> &str {
+        "proxy_upstreams"
+    }
+
+    async fn check(&self) -> HealthCheckResult {
+        let start = Instant::now();
+        let mut unhealthy: Vec<_> = self
+            .proxy
+            .upstream_health()
+            .into_iter()
+            .filter(|(_, healthy)| !healthy)
+            .map(|(namespace, _)| namespace)
+            .collect();
+        unhealthy.sort();
+
+        let (status, message) = if unhealthy.is_empty() {
+            (HealthStatus::Healthy, None)
+        } else if unhealthy.len() == self.proxy.upstreams.len() {
+            (
+                HealthStatus::Unhealthy,
+                Some("All upstream servers are unavailable".to_string()),
+            )
+        } else {
+            (
+                HealthStatus::Degraded,
+                Some(format!("Unavailable upstreams: {}", unhealthy.join(", "))),
+            )
+        };
+
+        HealthCheckResult {
+            name: self.name().to_string(),
+            status,
+            message,
+            timestamp: SystemTime::now(),
+            duration: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_matches() {
+        assert!(template_matches(
+            "file:///docs/{path}",
+            "file:///docs/a/b.md"
+        ));
+        assert!(template_matches(
+            "repo://{owner}/{name}/readme",
+            "repo://rust-lang/rust/readme"
+        ));
+        assert!(template_matches(
+            "file:///docs/readme",
+            "file:///docs/readme"
+        ));
+        assert!(!template_matches(
+            "file:///docs/{path}",
+            "file:///etc/passwd"
+        ));
+        assert!(!template_matches(
+            "repo://{owner}/{name}/readme",
+            "repo://rust-lang/rust/license"
+        ));
+        assert!(!template_matches("file:///docs/{path", "file:///docs/a"));
+    }
+
+    #[test]
+    fn test_resource_routes_prefer_listed_then_specific() {
+        let mut routes = ResourceRoutes::default();
+        routes.set(
+            "all",
+            &[],
+            &[ResourceTemplate::new("file:///{path}", "Files")],
+        );
+        routes.set(
+            "docs",
+            &[],
+            &[ResourceTemplate::new("file:///docs/{path}", "Docs")],
+        );
+        assert_eq!(routes.route("file:///docs/a.md"), Some("docs"));
+        assert_eq!(routes.route("file:///etc/hosts"), Some("all"));
+        assert_eq!(routes.route("http://example.com/"), None);
+
+        let listed = Resource::new(url::Url::parse("file:///docs/a.md").unwrap(), "A");
+        routes.set("all", &[listed], &[]);
+        assert_eq!(routes.route("file:///docs/a.md"), Some("all"));
+        assert_eq!(routes.route("file:///etc/hosts"), None);
+    }
+}
@@ -0,0 +1,780 @@
+//! Keyed rate limiting and quotas
+//!
+//! [`RateLimitMiddleware`] keeps a token bucket per client, so one busy client
+//! cannot use up the capacity of everyone else. Buckets allow short bursts up
+//! to their capacity and refill at a steady rate. Clients are told apart by
+//! the [`RateLimitKey`] the middleware is created with.
+//!
+//! Besides the limit applied to every request, methods and tools can have
+//! limits of their own; a request has to pass all limits that apply to it.
+//! Daily and monthly [`Quota`]s cap the total number of requests per period
+//! and are counted in a [`QuotaStore`], which [`FileQuotaStore`] persists.
+//! A request is only counted when it passes all of them, so rejected requests
+//! use up neither tokens nor quota.
+//!
+//! Rejected requests fail with `-32005` (rate limited). The `data` of the
+//! error holds `retryAfter`, the whole seconds until the request can succeed,
+//! `retryAfterMs`, the same in milliseconds, and `limit`, the limit that was
+//! hit.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::rate_limit::{Quota, RateLimit, RateLimitKey, RateLimitMiddleware};
+//!
+//! # fn main() -> Result<()> {
+//! let rate_limit = RateLimitMiddleware::keyed(RateLimitKey::Subject)
+//!     .with_limit(RateLimit::per_minute(120).with_burst(20))
+//!     .with_method_limit("resources/read", RateLimit::per_second(10))
+//!     .with_tool_limit("search", RateLimit::per_minute(10))
+//!     .with_quota(Quota::daily(10_000))
+//!     .with_tool_quota("translate", Quota::monthly(500));
+//!
+//! let server = McpServerBuilder::new()
+//!     .with_info("Limited Server", "1.0.0")
+//!     .with_middleware(rate_limit)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::context::{ClientAddr, Principal};
+use crate::middleware::Middleware;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, Utc};
+use mocopr_core::error::ProtocolError;
+use mocopr_core::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Number of buckets above which idle buckets are dropped
+const BUCKET_PRUNE_THRESHOLD: usize = 10_000;
+
+/// Default time between writes of a [`FileQuotaStore`]
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How requests are attributed to clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// All requests share one bucket
+    #[default]
+    Global,
+    /// Each session has its own bucket
+    Session,
+    /// Each authenticated [`Principal`] has its own bucket
+    ///
+    /// Sessions without a principal are limited per session.
+    Subject,
+    /// Each client IP address has its own bucket
+    ///
+    /// WebSocket sessions know their client's address, and sessions served
+    /// with [`McpServer::run_transport_from`](crate::McpServer::run_transport_from)
+    /// are given one. Sessions without a known address, such as stdio
+    /// sessions, are limited per session, with a warning when first limited.
+    ClientIp,
+}
+
+/// Identifier minted for sessions that need one for rate limiting
+#[derive(Debug, Clone)]
+struct SessionKey(String);
+
+impl RateLimitKey {
+    /// Get the key of the session whose request is being handled
+    fn resolve(self) -> String {
+        let extensions = Extensions::current();
+        let session = |unknown_addr: bool| match &extensions {
+            Some(extensions) => {
+                let key = extensions.get_or_insert_with(|| {
+                    if unknown_addr {
+                        warn!("Client address of session unknown, limiting it per session");
+                    }
+                    SessionKey(uuid::Uuid::new_v4().to_string())
+                });
+                format!("session:{}", key.0)
+            }
+            None => "session:unknown".to_string(),
+        };
+
+        match self {
+            Self::Global => "global".to_string(),
+            Self::Session => session(false),
+            Self::Subject => match extensions.as_ref().and_then(|e| e.get::<Principal>()) {
+                Some(principal) => format!("subject:{}", principal.subject()),
+                None => session(false),
+            },
+            Self::ClientIp => match extensions.as_ref().and_then(|e| e.get::<ClientAddr>()) {
+                Some(ClientAddr(addr)) => format!("ip:{}", addr.ip()),
+                None => session(true),
+            },
+        }
+    }
+}
+
+/// A token bucket limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    capacity: f64,
+    per_second: f64,
+}
+
+impl RateLimit {
+    /// Allow `requests` per `period`, with bursts of up to `requests`
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Self {
+            capacity: f64::from(requests.max(1)),
+            per_second: f64::from(requests) / period.as_secs_f64().max(f64::EPSILON),
+        }
+    }
+
+    /// Allow `requests` per second
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Allow `requests` per minute
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Allow `requests` per hour
+    pub fn per_hour(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(3600))
+    }
+
+    /// Set the number of requests that may be made at once
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.capacity = f64::from(burst.max(1));
+        self
+    }
+}
+
+/// State of one token bucket
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.capacity);
+        self.updated = now;
+    }
+
+    /// Time until a token is available, zero if one is
+    fn wait(&self, limit: &RateLimit) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else if limit.per_second <= 0.0 {
+            Duration::MAX
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / limit.per_second)
+        }
+    }
+}
+
+/// Length of a quota period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    /// A calendar day in UTC
+    Daily,
+    /// A calendar month in UTC
+    Monthly,
+}
+
+impl QuotaPeriod {
+    /// Identify the period containing `now`, e.g. `2025-06-18` or `2025-06`
+    fn id(self, now: DateTime<Utc>) -> String {
+        match self {
+            Self::Daily => now.format("%Y-%m-%d").to_string(),
+            Self::Monthly => now.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// Time from `now` until the next period starts
+    fn remaining(self, now: DateTime<Utc>) -> Duration {
+        let today = now.date_naive();
+        let next = match self {
+            Self::Daily => today + ChronoDuration::days(1),
+            Self::Monthly => {
+                let (year, month) = match today.month() {
+                    12 => (today.year() + 1, 1),
+                    month => (today.year(), month + 1),
+                };
+                NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(today)
+            }
+        };
+        let start = next.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        (start - now).to_std().unwrap_or_default()
+    }
+}
+
+/// A cap on the number of requests per day or month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    limit: u64,
+    period: QuotaPeriod,
+}
+
+impl Quota {
+    /// Allow `limit` requests per period
+    pub fn new(limit: u64, period: QuotaPeriod) -> Self {
+        Self { limit, period }
+    }
+
+    /// Allow `limit` requests per day
+    pub fn daily(limit: u64) -> Self {
+        Self::new(limit, QuotaPeriod::Daily)
+    }
+
+    /// Allow `limit` requests per month
+    pub fn monthly(limit: u64) -> Self {
+        Self::new(limit, QuotaPeriod::Monthly)
+    }
+}
+
+/// Storage for quota counters
+///
+/// A counter holds the number of uses in one period; counting in a new period
+/// starts again from zero and discards the count of the earlier one.
+#[async_trait]
+pub trait QuotaStore: Send + Sync {
+    /// Count one use of `counter` in `period` unless `limit` uses were already counted
+    ///
+    /// Returns whether the use was counted.
+    async fn consume(&self, counter: &str, period: &str, limit: u64) -> Result<bool>;
+
+    /// Get the number of uses of `counter` counted in `period`
+    async fn usage(&self, counter: &str, period: &str) -> Result<u64>;
+
+    /// Count one use of each `(counter, period, limit)`, or of none if any
+    /// counter is used up
+    ///
+    /// Returns the index of the first counter that is used up. The default
+    /// implementation checks [`QuotaStore::usage`] before counting, which
+    /// concurrent requests can race; stores should check and count at once.
+    async fn consume_all(&self, uses: &[(&str, &str, u64)]) -> Result<Option<usize>> {
+        for (index, (counter, period, limit)) in uses.iter().enumerate() {
+            if self.usage(counter, period).await? >= *limit {
+                return Ok(Some(index));
+            }
+        }
+        for (index, (counter, period, limit)) in uses.iter().enumerate() {
+            if !self.consume(counter, period, *limit).await? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Quota counters kept in memory
+#[derive(Debug, Default)]
+pub struct MemoryQuotaStore {
+    counters: Mutex<HashMap<String, (String, u64)>>,
+}
+
+impl MemoryQuotaStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Count a use in a map of counters, as shared by the stores
+fn consume_counter(
+    counters: &mut HashMap<String, (String, u64)>,
+    counter: &str,
+    period: &str,
+    limit: u64,
+) -> bool {
+    let entry = counters
+        .entry(counter.to_string())
+        .or_insert_with(|| (period.to_string(), 0));
+    if entry.0 != period {
+        *entry = (period.to_string(), 0);
+    }
+    if entry.1 >= limit {
+        return false;
+    }
+    entry.1 += 1;
+    true
+}
+
+fn counter_usage(counters: &HashMap<String, (String, u64)>, counter: &str, period: &str) -> u64 {
+    match counters.get(counter) {
+        Some((counted, count)) if counted == period => *count,
+        _ => 0,
+    }
+}
+
+/// Count a use of every counter in a map of counters, or of none
+fn consume_counters(
+    counters: &mut HashMap<String, (String, u64)>,
+    uses: &[(&str, &str, u64)],
+) -> Option<usize> {
+    if let Some(exhausted) = uses
+        .iter()
+        .position(|(counter, period, limit)| counter_usage(counters, counter, period) >= *limit)
+    {
+        return Some(exhausted);
+    }
+    for (counter, period, limit) in uses {
+        consume_counter(counters, counter, period, *limit);
+    }
+    None
+}
+
+#[async_trait]
+impl QuotaStore for MemoryQuotaStore {
+    async fn consume(&self, counter: &str, period: &str, limit: u64) -> Result<bool> {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(consume_counter(&mut counters, counter, period, limit))
+    }
+
+    async fn usage(&self, counter: &str, period: &str) -> Result<u64> {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(counter_usage(&counters, counter, period))
+    }
+
+    async fn consume_all(&self, uses: &[(&str, &str, u64)]) -> Result<Option<usize>> {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(consume_counters(&mut counters, uses))
+    }
+}
+
+/// Quota counters persisted to a JSON file
+///
+/// Counted uses are written in the background at most once per flush
+/// interval, one second by default, and when the store is dropped. A crash
+/// loses at most the uses of the last interval. Only one server should use a
+/// file at a time.
+#[derive(Debug)]
+pub struct FileQuotaStore {
+    file: Arc<QuotaFile>,
+    flush_interval: Duration,
+    flusher: Once,
+}
+
+/// Counters of a [`FileQuotaStore`] and the file they are written to
+#[derive(Debug)]
+struct QuotaFile {
+    path: PathBuf,
+    counters: Mutex<HashMap<String, (String, u64)>>,
+    /// Whether the counters changed since they were last written
+    dirty: AtomicBool,
+    /// Held while writing, so writes do not overlap
+    writing: tokio::sync::Mutex<()>,
+}
+
+impl QuotaFile {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (String, u64)>> {
+        self.counters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serialize the counters if they changed since the last write
+    fn snapshot(&self) -> Result<Option<Vec<u8>>> {
+        let counters = self.lock();
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::to_vec_pretty(&*counters)?))
+    }
+
+    /// Write the counters if they changed, replacing the file in one step
+    async fn save(&self) -> Result<()> {
+        let _writing = self.writing.lock().await;
+        let Some(contents) = self.snapshot()? else {
+            return Ok(());
+        };
+        let temporary = self.path.with_extension("tmp");
+        let written = async {
+            tokio::fs::write(&temporary, contents).await?;
+            tokio::fs::rename(&temporary, &self.path).await
+        };
+        if let Err(e) = written.await {
+            self.dirty.store(true, Ordering::Release);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Write the counters from outside the runtime
+    fn save_blocking(&self) -> Result<()> {
+        let Some(contents) = self.snapshot()? else {
+            return Ok(());
+        };
+        let temporary = self.path.with_extension("tmp");
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for QuotaFile {
+    fn drop(&mut self) {
+        if let Err(e) = self.save_blocking() {
+            warn!("Failed to write quota file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl FileQuotaStore {
+    /// Open the store at `path`, starting empty if the file does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let counters = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source).map_err(|e| {
+                Error::Configuration(format!("Invalid quota file {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            file: Arc::new(QuotaFile {
+                path,
+                counters: Mutex::new(counters),
+                dirty: AtomicBool::new(false),
+                writing: tokio::sync::Mutex::new(()),
+            }),
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            flusher: Once::new(),
+        })
+    }
+
+    /// Set the time between writes of counted uses
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    /// Write counted uses now instead of at the next flush
+    pub async fn flush(&self) -> Result<()> {
+        self.file.save().await
+    }
+
+    /// Mark the counters as changed, starting the background writer on first use
+    fn changed(&self) {
+        self.file.dirty.store(true, Ordering::Release);
+        self.flusher.call_once(|| {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn(flush_periodically(
+                    Arc::downgrade(&self.file),
+                    self.flush_interval,
+                ));
+            }
+        });
+    }
+}
+
+/// Write the counters of `file` every `interval` until the store is dropped
+async fn flush_periodically(file: Weak<QuotaFile>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(file) = file.upgrade() else {
+            return;
+        };
+        if let Err(e) = file.save().await {
+            warn!("Failed to write quota file {}: {}", file.path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl QuotaStore for FileQuotaStore {
+    async fn consume(&self, counter: &str, period: &str, limit: u64) -> Result<bool> {
+        if !consume_counter(&mut self.file.lock(), counter, period, limit) {
+            return Ok(false);
+        }
+        self.changed();
+        Ok(true)
+    }
+
+    async fn usage(&self, counter: &str, period: &str) -> Result<u64> {
+        Ok(counter_usage(&self.file.lock(), counter, period))
+    }
+
+    async fn consume_all(&self, uses: &[(&str, &str, u64)]) -> Result<Option<usize>> {
+        let exhausted = consume_counters(&mut self.file.lock(), uses);
+        if exhausted.is_none() && !uses.is_empty() {
+            self.changed();
+        }
+        Ok(exhausted)
+    }
+}
+
+/// Rate limiting middleware
+pub struct RateLimitMiddleware {
+    key: RateLimitKey,
+    limit: Option<RateLimit>,
+    method_limits: HashMap<String, RateLimit>,
+    tool_limits: HashMap<String, RateLimit>,
+    quota: Option<Quota>,
+    tool_quotas: HashMap<String, Quota>,
+    store: Arc<dyn QuotaStore>,
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+}
+
+impl RateLimitMiddleware {
+    /// Allow `max_requests` per `window_duration` across all clients
+    pub fn new(max_requests: u32, window_duration: Duration) -> Self {
+        Self::keyed(RateLimitKey::Global).with_limit(RateLimit::new(max_requests, window_duration))
+    }
+
+    /// Create a middleware without limits that tells clients apart by `key`
+    pub fn keyed(key: RateLimitKey) -> Self {
+        Self {
+            key,
+            limit: None,
+            method_limits: HashMap::new(),
+            tool_limits: HashMap::new(),
+            quota: None,
+            tool_quotas: HashMap::new(),
+            store: Arc::new(MemoryQuotaStore::new()),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limit every request
+    pub fn with_limit(mut self, limit: RateLimit) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Limit requests for one method, e.g. `resources/read`
+    pub fn with_method_limit(mut self, method: impl Into<String>, limit: RateLimit) -> Self {
+        self.method_limits.insert(method.into(), limit);
+        self
+    }
+
+    /// Limit calls of one tool
+    pub fn with_tool_limit(mut self, tool: impl Into<String>, limit: RateLimit) -> Self {
+        self.tool_limits.insert(tool.into(), limit);
+        self
+    }
+
+    /// Cap the number of requests per period
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Cap the number of calls of one tool per period
+    pub fn with_tool_quota(mut self, tool: impl Into<String>, quota: Quota) -> Self {
+        self.tool_quotas.insert(tool.into(), quota);
+        self
+    }
+
+    /// Count quotas in `store` instead of in memory
+    pub fn with_quota_store(mut self, store: impl QuotaStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Take a token from every bucket that applies, or from none
+    fn take_tokens(&self, key: &str, limits: &[(String, RateLimit)]) -> Result<()> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > BUCKET_PRUNE_THRESHOLD {
+            // A bucket that has refilled completely is the same as a new one
+            buckets.retain(|(scope, _), bucket| {
+                let limit = self.limit_for(scope);
+                limit.is_some_and(|limit| {
+                    bucket.refill(&limit, now);
+                    bucket.tokens < limit.capacity
+                })
+            });
+        }
+
+        let mut wait = Duration::ZERO;
+        let mut exceeded = None;
+        for (scope, limit) in limits {
+            let bucket = buckets
+                .entry((scope.clone(), key.to_string()))
+                .or_insert_with(|| Bucket::full(limit, now));
+            bucket.refill(limit, now);
+            let bucket_wait = bucket.wait(limit);
+            if bucket_wait > wait {
+                wait = bucket_wait;
+                exceeded = Some(scope.as_str());
+            }
+        }
+
+        if let Some(scope) = exceeded {
+            return Err(rate_limited(scope, wait));
+        }
+        for (scope, _) in limits {
+            if let Some(bucket) = buckets.get_mut(&(scope.clone(), key.to_string())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Give back the tokens taken by [`RateLimitMiddleware::take_tokens`]
+    fn return_tokens(&self, key: &str, limits: &[(String, RateLimit)]) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        for (scope, limit) in limits {
+            if let Some(bucket) = buckets.get_mut(&(scope.clone(), key.to_string())) {
+                bucket.tokens = (bucket.tokens + 1.0).min(limit.capacity);
+            }
+        }
+    }
+
+    /// Find the limit of a bucket scope
+    fn limit_for(&self, scope: &str) -> Option<RateLimit> {
+        match scope.split_once(':') {
+            Some(("method", method)) => self.method_limits.get(method).copied(),
+            Some(("tool", tool)) => self.tool_limits.get(tool).copied(),
+            _ => self.limit,
+        }
+    }
+}
+
+/// Build the error for a request rejected by `limit`
+fn rate_limited(limit: &str, wait: Duration) -> Error {
+    let millis = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+    Error::Protocol(ProtocolError::RateLimitExceeded).with_data(json!({
+        "retryAfter": millis.div_ceil(1000),
+        "retryAfterMs": millis,
+        "limit": limit,
+    }))
+}
+
+#[async_trait]
+impl Middleware for RateLimitMiddleware {
+    async fn before_request(&self, request: &JsonRpcRequest) -> Result<()> {
+        let tool = (request.method == "tools/call")
+            .then(|| {
+                request
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("name"))
+                    .and_then(|name| name.as_str())
+            })
+            .flatten();
+
+        let mut limits = Vec::new();
+        if let Some(limit) = self.limit {
+            limits.push(("default".to_string(), limit));
+        }
+        if let Some(limit) = self.method_limits.get(&request.method) {
+            limits.push((format!("method:{}", request.method), *limit));
+        }
+        if let Some(tool) = tool
+            && let Some(limit) = self.tool_limits.get(tool)
+        {
+            limits.push((format!("tool:{}", tool), *limit));
+        }
+
+        let mut quotas = Vec::new();
+        if let Some(quota) = self.quota {
+            quotas.push(("quota".to_string(), quota));
+        }
+        if let Some(tool) = tool
+            && let Some(quota) = self.tool_quotas.get(tool)
+        {
+            quotas.push((format!("quota:tool:{}", tool), *quota));
+        }
+
+        if limits.is_empty() && quotas.is_empty() {
+            return Ok(());
+        }
+
+        let key = self.key.resolve();
+        self.take_tokens(&key, &limits)?;
+        if quotas.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let counters: Vec<_> = quotas
+            .iter()
+            .map(|(scope, quota)| (format!("{}|{}", scope, key), quota.period.id(now)))
+            .collect();
+        let uses: Vec<_> = counters
+            .iter()
+            .zip(&quotas)
+            .map(|((counter, period), (_, quota))| (counter.as_str(), period.as_str(), quota.limit))
+            .collect();
+
+        let exhausted = match self.store.consume_all(&uses).await {
+            Ok(exhausted) => exhausted,
+            Err(e) => {
+                self.return_tokens(&key, &limits);
+                return Err(e);
+            }
+        };
+        if let Some(index) = exhausted {
+            self.return_tokens(&key, &limits);
+            let (scope, quota) = &quotas[index];
+            return Err(rate_limited(scope, quota.period.remaining(now)));
+        }
+        Ok(())
+    }
+
+    async fn after_response(
+        &self,
+        _request: &JsonRpcRequest,
+        _response: &JsonRpcResponse,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_error(&self, _request: &JsonRpcRequest, _error: &Error) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_up_to_capacity() {
+        let limit = RateLimit::per_second(2).with_burst(3);
+        let start = Instant::now();
+        let mut bucket = Bucket::full(&limit, start);
+
+        bucket.tokens = 0.0;
+        assert_eq!(bucket.wait(&limit), Duration::from_millis(500));
+
+        bucket.refill(&limit, start + Duration::from_secs(10));
+        assert_eq!(bucket.tokens, 3.0);
+        assert_eq!(bucket.wait(&limit), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_quota_periods() {
+        let now = "2025-12-31T23:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(QuotaPeriod::Daily.id(now), "2025-12-31");
+        assert_eq!(QuotaPeriod::Monthly.id(now), "2025-12");
+        assert_eq!(QuotaPeriod::Daily.remaining(now), Duration::from_secs(3600));
+        assert_eq!(
+            QuotaPeriod::Monthly.remaining(now),
+            Duration::from_secs(3600)
+        );
+    }
+}
@@ -1,22 +1,83 @@
 //! Registry for managing server capabilities
 
+use crate::cache::ResourceCache;
+use crate::execution::{ToolExecutionPolicy, ToolLimiter};
 use crate::handlers::*;
+use crate::notifications::NotificationSender;
+use crate::pagination::Paginator;
+use crate::schema::SchemaValidation;
+use mocopr_core::monitoring::MonitoringSystem;
 use mocopr_core::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::warn;
 
 /// Registry for resource handlers
 #[derive(Clone)]
 pub struct ResourceRegistry {
     handlers: Arc<RwLock<HashMap<String, Box<dyn ResourceHandler>>>>,
+    providers: Arc<RwLock<Vec<Box<dyn ResourceProvider>>>>,
+    notifications: NotificationSender,
+    paginator: Paginator,
+    cache: Option<Arc<ResourceCache>>,
 }
 
 impl ResourceRegistry {
     pub fn new() -> Self {
+        Self::with_notifications(NotificationSender::new())
+    }
+
+    /// Create a registry whose providers publish change notifications through `notifications`
+    pub fn with_notifications(notifications: NotificationSender) -> Self {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
+            providers: Arc::new(RwLock::new(Vec::new())),
+            notifications,
+            paginator: Paginator::default(),
+            cache: None,
+        }
+    }
+
+    /// Set the paginator used for list operations
+    pub fn set_paginator(&mut self, paginator: Paginator) {
+        self.paginator = paginator;
+    }
+
+    /// Cache resource reads
+    ///
+    /// Entries are dropped when a change is announced through this registry's
+    /// notification sender.
+    pub fn set_cache(&mut self, cache: ResourceCache) {
+        cache.listen(&self.notifications);
+        self.cache = Some(Arc::new(cache));
+    }
+
+    /// Get the cache of resource reads, if one is set
+    pub fn cache(&self) -> Option<&ResourceCache> {
+        self.cache.as_deref()
+    }
+
+    /// Get the notification sender used by this registry
+    pub fn notifications(&self) -> &NotificationSender {
+        &self.notifications
+    }
+
+    /// Register a resource provider
+    ///
+    /// The provider is asked to start watching for changes immediately; a
+    /// provider that fails to start watching is still registered and served.
+    pub fn register_provider(&mut self, provider: Box<dyn ResourceProvider>) {
+        if let Err(e) = provider.watch(self.notifications.clone()) {
+            warn!(
+                "Resource provider could not start watching for changes: {}",
+                e
+            );
         }
+
+        futures::executor::block_on(async {
+            self.providers.write().await.push(provider);
+        });
     }
 
     /// Register a resource handler
@@ -37,28 +98,58 @@ impl ResourceRegistry {
         let mut resources = Vec::new();
 
         for handler in handlers.values() {
-            resources.push(handler.resource().await);
+            let resource = handler.resource().await;
+            resources.push((resource.uri.to_string(), resource));
         }
 
-        // Apply pagination if cursor is provided
-        let start_index = if let Some(cursor) = &request.pagination.cursor {
-            cursor.parse::<usize>().unwrap_or(0)
-        } else {
-            0
-        };
-
-        let page_size = 50; // Default page size
-        let end_index = (start_index + page_size).min(resources.len());
-        let page_resources = resources[start_index..end_index].to_vec();
+        for provider in self.providers.read().await.iter() {
+            resources.extend(
+                provider
+                    .list()
+                    .await?
+                    .into_iter()
+                    .map(|resource| (resource.uri.to_string(), resource)),
+            );
+        }
 
-        let next_cursor = if end_index < resources.len() {
-            Some(end_index.to_string())
-        } else {
-            None
-        };
+        let (resources, next_cursor) = self.paginator.paginate(
+            "resources",
+            resources,
+            request.pagination.cursor.as_deref(),
+        )?;
 
         Ok(ResourcesListResponse {
-            resources: page_resources,
+            resources,
+            next_cursor,
+            meta: ResponseMetadata { _meta: None },
+        })
+    }
+
+    /// List all resource templates
+    pub async fn list_templates(
+        &self,
+        request: ResourcesTemplatesListRequest,
+    ) -> Result<ResourcesTemplatesListResponse> {
+        let mut resource_templates = Vec::new();
+
+        for provider in self.providers.read().await.iter() {
+            resource_templates.extend(
+                provider
+                    .templates()
+                    .await
+                    .into_iter()
+                    .map(|template| (template.uri_template.clone(), template)),
+            );
+        }
+
+        let (resource_templates, next_cursor) = self.paginator.paginate(
+            "resource templates",
+            resource_templates,
+            request.pagination.cursor.as_deref(),
+        )?;
+
+        Ok(ResourcesTemplatesListResponse {
+            resource_templates,
             next_cursor,
             meta: ResponseMetadata { _meta: None },
         })
@@ -73,16 +164,33 @@ impl ResourceRegistry {
         let uri_str = request.uri.to_string();
 
         if let Some(handler) = handlers.get(&uri_str) {
-            let contents = handler.read().await?;
-            Ok(ResourcesReadResponse {
+            let contents = match &self.cache {
+                Some(cache) => cache.read_handler(&uri_str, handler.as_ref()).await?,
+                None => handler.read().await?,
+            };
+            return Ok(ResourcesReadResponse {
                 contents,
                 meta: ResponseMetadata { _meta: None },
-            })
-        } else {
-            Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
-            ))
+            });
         }
+
+        for provider in self.providers.read().await.iter() {
+            if provider.handles(&request.uri) {
+                let read = provider.read(&request.uri);
+                let contents = match &self.cache {
+                    Some(cache) => cache.read_with(&uri_str, read).await?,
+                    None => read.await?,
+                };
+                return Ok(ResourcesReadResponse {
+                    contents,
+                    meta: ResponseMetadata { _meta: None },
+                });
+            }
+        }
+
+        Err(Error::Protocol(
+            mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
+        ))
     }
 
     /// Subscribe to resource updates
@@ -96,19 +204,33 @@ impl ResourceRegistry {
         if let Some(handler) = handlers.get(&uri_str) {
             if handler.supports_subscription() {
                 handler.subscribe().await?;
-                Ok(ResourcesSubscribeResponse {
+                return Ok(ResourcesSubscribeResponse {
                     meta: ResponseMetadata { _meta: None },
-                })
+                });
             } else {
-                Err(Error::InvalidRequest(
+                return Err(Error::InvalidRequest(
                     "Resource does not support subscription".to_string(),
-                ))
+                ));
+            }
+        }
+
+        for provider in self.providers.read().await.iter() {
+            if provider.handles(&request.uri) {
+                if !provider.supports_subscription() {
+                    return Err(Error::InvalidRequest(
+                        "Resource does not support subscription".to_string(),
+                    ));
+                }
+                provider.subscribe(&request.uri).await?;
+                return Ok(ResourcesSubscribeResponse {
+                    meta: ResponseMetadata { _meta: None },
+                });
             }
-        } else {
-            Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
-            ))
         }
+
+        Err(Error::Protocol(
+            mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
+        ))
     }
 
     /// Unsubscribe from resource updates
@@ -121,14 +243,23 @@ impl ResourceRegistry {
 
         if let Some(handler) = handlers.get(&uri_str) {
             handler.unsubscribe().await?;
-            Ok(ResourcesUnsubscribeResponse {
+            return Ok(ResourcesUnsubscribeResponse {
                 meta: ResponseMetadata { _meta: None },
-            })
-        } else {
-            Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
-            ))
+            });
         }
+
+        for provider in self.providers.read().await.iter() {
+            if provider.handles(&request.uri) {
+                provider.unsubscribe(&request.uri).await?;
+                return Ok(ResourcesUnsubscribeResponse {
+                    meta: ResponseMetadata { _meta: None },
+                });
+            }
+        }
+
+        Err(Error::Protocol(
+            mocopr_core::error::ProtocolError::ResourceNotFound(uri_str),
+        ))
     }
 }
 
@@ -142,52 +273,99 @@ impl Default for ResourceRegistry {
 #[derive(Clone)]
 pub struct ToolRegistry {
     handlers: Arc<RwLock<HashMap<String, Box<dyn ToolHandler>>>>,
+    providers: Arc<RwLock<Vec<Box<dyn ToolProvider>>>>,
+    paginator: Paginator,
+    schema_validation: Arc<SchemaValidation>,
+    policies: Arc<std::sync::RwLock<HashMap<String, ToolExecutionPolicy>>>,
+    limiters: Arc<std::sync::Mutex<HashMap<String, Arc<ToolLimiter>>>>,
+    monitoring: Option<MonitoringSystem>,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
+            providers: Arc::new(RwLock::new(Vec::new())),
+            paginator: Paginator::default(),
+            schema_validation: Arc::new(SchemaValidation::default()),
+            policies: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            limiters: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            monitoring: None,
+        }
+    }
+
+    /// Set the paginator used for list operations
+    pub fn set_paginator(&mut self, paginator: Paginator) {
+        self.paginator = paginator;
+    }
+
+    /// Set how tool arguments are validated against input schemas
+    pub fn set_schema_validation(&mut self, validation: SchemaValidation) {
+        self.schema_validation = Arc::new(validation);
+    }
+
+    /// Set the monitoring system that receives tool execution counters
+    pub fn set_monitoring(&mut self, monitoring: MonitoringSystem) {
+        self.monitoring = Some(monitoring);
+    }
+
+    /// Set the execution policy for a tool, overriding the handler's own policy
+    pub fn set_policy(&self, tool: impl Into<String>, policy: ToolExecutionPolicy) {
+        let tool = tool.into();
+        if let Ok(mut limiters) = self.limiters.lock() {
+            limiters.remove(&tool);
+        }
+        if let Ok(mut policies) = self.policies.write() {
+            policies.insert(tool, policy);
         }
     }
 
     /// Register a tool handler
     pub fn register(&mut self, handler: Box<dyn ToolHandler>) {
         let name = futures::executor::block_on(async { handler.tool().await.name });
+        self.schema_validation.invalidate(&name);
+        if let Ok(mut limiters) = self.limiters.lock() {
+            limiters.remove(&name);
+        }
 
         futures::executor::block_on(async {
             self.handlers.write().await.insert(name, handler);
         });
     }
 
+    /// Register a tool provider
+    pub fn register_provider(&mut self, provider: Box<dyn ToolProvider>) {
+        futures::executor::block_on(async {
+            self.providers.write().await.push(provider);
+        });
+    }
+
     /// List all tools
     pub async fn list_tools(&self, request: ToolsListRequest) -> Result<ToolsListResponse> {
         let handlers = self.handlers.read().await;
         let mut tools = Vec::new();
 
         for handler in handlers.values() {
-            tools.push(handler.tool().await);
+            let tool = handler.tool().await;
+            tools.push((tool.name.clone(), tool));
         }
 
-        // Apply pagination if cursor is provided
-        let start_index = if let Some(cursor) = &request.pagination.cursor {
-            cursor.parse::<usize>().unwrap_or(0)
-        } else {
-            0
-        };
-
-        let page_size = 50; // Default page size
-        let end_index = (start_index + page_size).min(tools.len());
-        let page_tools = tools[start_index..end_index].to_vec();
+        for provider in self.providers.read().await.iter() {
+            tools.extend(
+                provider
+                    .list()
+                    .await?
+                    .into_iter()
+                    .map(|tool| (tool.name.clone(), tool)),
+            );
+        }
 
-        let next_cursor = if end_index < tools.len() {
-            Some(end_index.to_string())
-        } else {
-            None
-        };
+        let (tools, next_cursor) =
+            self.paginator
+                .paginate("tools", tools, request.pagination.cursor.as_deref())?;
 
         Ok(ToolsListResponse {
-            tools: page_tools,
+            tools,
             next_cursor,
             meta: ResponseMetadata { _meta: None },
         })
@@ -198,12 +376,61 @@ impl ToolRegistry {
         let handlers = self.handlers.read().await;
 
         if let Some(handler) = handlers.get(&request.name) {
-            handler.call(request.arguments).await
-        } else {
-            Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::ToolNotFound(request.name),
-            ))
+            if self.schema_validation.is_enabled_for(&request.name) {
+                let tool = handler.tool().await;
+                self.schema_validation
+                    .validate(&tool, request.arguments.as_ref())?;
+            }
+            return match self.limiter(&request.name, Some(handler.as_ref())) {
+                Some(limiter) => {
+                    limiter
+                        .run(self.monitoring.as_ref(), handler.call(request.arguments))
+                        .await
+                }
+                None => handler.call(request.arguments).await,
+            };
         }
+
+        for provider in self.providers.read().await.iter() {
+            if provider.handles(&request.name) {
+                if self.schema_validation.is_enabled_for(&request.name)
+                    && let Some(tool) = provider.tool(&request.name).await?
+                {
+                    self.schema_validation
+                        .validate(&tool, request.arguments.as_ref())?;
+                }
+                let call = provider.call(&request.name, request.arguments);
+                return match self.limiter(&request.name, None) {
+                    Some(limiter) => limiter.run(self.monitoring.as_ref(), call).await,
+                    None => call.await,
+                };
+            }
+        }
+
+        Err(Error::Protocol(
+            mocopr_core::error::ProtocolError::ToolNotFound(request.name),
+        ))
+    }
+}
+
+impl ToolRegistry {
+    /// Get the limiter enforcing the execution policy of a tool, if it has one
+    fn limiter(&self, name: &str, handler: Option<&dyn ToolHandler>) -> Option<Arc<ToolLimiter>> {
+        let mut limiters = self.limiters.lock().ok()?;
+        if let Some(limiter) = limiters.get(name) {
+            return Some(limiter.clone());
+        }
+
+        let policy = self
+            .policies
+            .read()
+            .ok()
+            .and_then(|policies| policies.get(name).cloned())
+            .or_else(|| handler.and_then(|handler| handler.execution_policy()))?;
+
+        let limiter = Arc::new(ToolLimiter::new(name, policy));
+        limiters.insert(name.to_string(), limiter.clone());
+        Some(limiter)
     }
 }
 
@@ -217,15 +444,31 @@ impl Default for ToolRegistry {
 #[derive(Clone)]
 pub struct PromptRegistry {
     handlers: Arc<RwLock<HashMap<String, Box<dyn PromptHandler>>>>,
+    providers: Arc<RwLock<Vec<Box<dyn PromptProvider>>>>,
+    notifications: NotificationSender,
+    paginator: Paginator,
 }
 
 impl PromptRegistry {
     pub fn new() -> Self {
+        Self::with_notifications(NotificationSender::new())
+    }
+
+    /// Create a registry whose providers publish change notifications through `notifications`
+    pub fn with_notifications(notifications: NotificationSender) -> Self {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
+            providers: Arc::new(RwLock::new(Vec::new())),
+            notifications,
+            paginator: Paginator::default(),
         }
     }
 
+    /// Set the paginator used for list operations
+    pub fn set_paginator(&mut self, paginator: Paginator) {
+        self.paginator = paginator;
+    }
+
     /// Register a prompt handler
     pub fn register(&mut self, handler: Box<dyn PromptHandler>) {
         let name = futures::executor::block_on(async { handler.prompt().await.name });
@@ -235,34 +478,49 @@ impl PromptRegistry {
         });
     }
 
+    /// Register a prompt provider
+    ///
+    /// The provider is asked to start watching for changes immediately; a
+    /// provider that fails to start watching is still registered and served.
+    pub fn register_provider(&mut self, provider: Box<dyn PromptProvider>) {
+        if let Err(e) = provider.watch(self.notifications.clone()) {
+            warn!(
+                "Prompt provider could not start watching for changes: {}",
+                e
+            );
+        }
+
+        futures::executor::block_on(async {
+            self.providers.write().await.push(provider);
+        });
+    }
+
     /// List all prompts
     pub async fn list_prompts(&self, request: PromptsListRequest) -> Result<PromptsListResponse> {
         let handlers = self.handlers.read().await;
         let mut prompts = Vec::new();
 
         for handler in handlers.values() {
-            prompts.push(handler.prompt().await);
+            let prompt = handler.prompt().await;
+            prompts.push((prompt.name.clone(), prompt));
         }
 
-        // Apply pagination if cursor is provided
-        let start_index = if let Some(cursor) = &request.pagination.cursor {
-            cursor.parse::<usize>().unwrap_or(0)
-        } else {
-            0
-        };
-
-        let page_size = 50; // Default page size
-        let end_index = (start_index + page_size).min(prompts.len());
-        let page_prompts = prompts[start_index..end_index].to_vec();
+        for provider in self.providers.read().await.iter() {
+            prompts.extend(
+                provider
+                    .list()
+                    .await?
+                    .into_iter()
+                    .map(|prompt| (prompt.name.clone(), prompt)),
+            );
+        }
 
-        let next_cursor = if end_index < prompts.len() {
-            Some(end_index.to_string())
-        } else {
-            None
-        };
+        let (prompts, next_cursor) =
+            self.paginator
+                .paginate("prompts", prompts, request.pagination.cursor.as_deref())?;
 
         Ok(PromptsListResponse {
-            prompts: page_prompts,
+            prompts,
             next_cursor,
             meta: ResponseMetadata { _meta: None },
         })
@@ -273,12 +531,18 @@ impl PromptRegistry {
         let handlers = self.handlers.read().await;
 
         if let Some(handler) = handlers.get(&request.name) {
-            handler.generate(request.arguments).await
-        } else {
-            Err(Error::Protocol(
-                mocopr_core::error::ProtocolError::PromptNotFound(request.name),
-            ))
+            return handler.generate(request.arguments).await;
         }
+
+        for provider in self.providers.read().await.iter() {
+            if provider.handles(&request.name) {
+                return provider.generate(&request.name, request.arguments).await;
+            }
+        }
+
+        Err(Error::Protocol(
+            mocopr_core::error::ProtocolError::PromptNotFound(request.name),
+        ))
     }
 }
 
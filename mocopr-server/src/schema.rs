@@ -0,0 +1,226 @@
+//! JSON Schema validation of tool arguments
+//!
+//! Before a `tools/call` request reaches its [`ToolHandler`](crate::handlers::ToolHandler),
+//! the arguments are checked against the tool's `input_schema` using JSON Schema
+//! draft 2020-12. Invalid arguments are rejected with `-32602` and the list of
+//! violations in `error.data`:
+//!
+//! ```json
+//! {
+//!   "code": -32602,
+//!   "message": "Invalid parameters: Invalid arguments for tool 'add'",
+//!   "data": {
+//!     "tool": "add",
+//!     "violations": [
+//!       { "path": "/a", "schemaPath": "/properties/a/type", "message": "\"one\" is not of type \"number\"" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Validation is enabled for all tools by default and can be switched off
+//! globally or per tool. It applies to tools of a
+//! [`ToolProvider`](crate::handlers::ToolProvider) too, using the schema the
+//! provider advertises. Compiled schemas are cached per tool name and
+//! recompiled when the schema changes.
+
+use mocopr_core::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Validates tool arguments against tool input schemas
+pub struct SchemaValidation {
+    enabled: bool,
+    overrides: HashMap<String, bool>,
+    cache: RwLock<HashMap<String, CompiledSchema>>,
+}
+
+/// A tool's input schema and its compiled form, `None` if it is invalid
+type CompiledSchema = (serde_json::Value, Option<Arc<jsonschema::Validator>>);
+
+impl SchemaValidation {
+    /// Create a validation policy, enabled or disabled for all tools
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            overrides: HashMap::new(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Enable or disable validation for tools without a per-tool setting
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Enable or disable validation for a single tool, overriding the global setting
+    pub fn set_tool_enabled(&mut self, tool: impl Into<String>, enabled: bool) {
+        self.overrides.insert(tool.into(), enabled);
+    }
+
+    /// Check whether arguments of the given tool are validated
+    pub fn is_enabled_for(&self, tool: &str) -> bool {
+        self.overrides.get(tool).copied().unwrap_or(self.enabled)
+    }
+
+    /// Drop the cached compiled schema of a tool
+    pub fn invalidate(&self, tool: &str) {
+        if let Ok(mut cache) = self.cache.write() {
+            cache.remove(tool);
+        }
+    }
+
+    /// Validate arguments against the tool's input schema
+    ///
+    /// Missing arguments are validated as an empty object. Tools whose schema
+    /// cannot be compiled are logged once and not validated.
+    pub fn validate(&self, tool: &Tool, arguments: Option<&serde_json::Value>) -> Result<()> {
+        if !self.is_enabled_for(&tool.name) {
+            return Ok(());
+        }
+
+        let Some(validator) = self.compiled(tool) else {
+            return Ok(());
+        };
+
+        let empty = json!({});
+        let instance = arguments.unwrap_or(&empty);
+
+        let violations: Vec<serde_json::Value> = validator
+            .iter_errors(instance)
+            .map(|error| {
+                json!({
+                    "path": error.instance_path().to_string(),
+                    "schemaPath": error.schema_path().to_string(),
+                    "message": error.to_string(),
+                })
+            })
+            .collect();
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(
+            Error::invalid_params(format!("Invalid arguments for tool '{}'", tool.name)).with_data(
+                json!({
+                    "tool": tool.name,
+                    "violations": violations,
+                }),
+            ),
+        )
+    }
+
+    fn compiled(&self, tool: &Tool) -> Option<Arc<jsonschema::Validator>> {
+        if let Some(compiled) = self.cache.read().ok().and_then(|cache| {
+            cache
+                .get(&tool.name)
+                .filter(|(schema, _)| *schema == tool.input_schema)
+                .map(|(_, compiled)| compiled.clone())
+        }) {
+            return compiled;
+        }
+
+        let compiled = match jsonschema::draft202012::new(&tool.input_schema) {
+            Ok(validator) => Some(Arc::new(validator)),
+            Err(e) => {
+                warn!(
+                    "Input schema of tool '{}' is invalid, arguments will not be validated: {}",
+                    tool.name, e
+                );
+                None
+            }
+        };
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(
+                tool.name.clone(),
+                (tool.input_schema.clone(), compiled.clone()),
+            );
+        }
+
+        compiled
+    }
+}
+
+impl Default for SchemaValidation {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool() -> Tool {
+        Tool::new(
+            "add",
+            json!({
+                "type": "object",
+                "properties": {
+                    "a": {"type": "number"},
+                    "b": {"type": "number"}
+                },
+                "required": ["a", "b"]
+            }),
+        )
+    }
+
+    #[test]
+    fn test_valid_arguments() {
+        let validation = SchemaValidation::default();
+        assert!(
+            validation
+                .validate(&tool(), Some(&json!({"a": 1, "b": 2.5})))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_violations_in_error_data() {
+        let validation = SchemaValidation::default();
+        let error = validation
+            .validate(&tool(), Some(&json!({"a": "one"})))
+            .unwrap_err();
+
+        assert_eq!(error.json_rpc_code(), -32602);
+        let violations = error.data().unwrap()["violations"].as_array().unwrap();
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|v| v["path"] == "/a"));
+
+        let error = validation.validate(&tool(), None).unwrap_err();
+        assert!(matches!(error.inner(), Error::InvalidParams(_)));
+    }
+
+    #[test]
+    fn test_per_tool_override() {
+        let mut validation = SchemaValidation::new(false);
+        assert!(validation.validate(&tool(), None).is_ok());
+
+        validation.set_tool_enabled("add", true);
+        assert!(validation.validate(&tool(), None).is_err());
+
+        let mut validation = SchemaValidation::new(true);
+        validation.set_tool_enabled("add", false);
+        assert!(validation.validate(&tool(), None).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_schema_is_skipped() {
+        let validation = SchemaValidation::default();
+        let tool = Tool::new("broken", json!({"type": 12}));
+        assert!(validation.validate(&tool, Some(&json!("anything"))).is_ok());
+    }
+
+    #[test]
+    fn test_changed_schema_is_recompiled() {
+        let validation = SchemaValidation::default();
+        assert!(validation.validate(&tool(), None).is_err());
+
+        let relaxed = Tool::new("add", json!({"type": "object"}));
+        assert!(validation.validate(&relaxed, None).is_ok());
+    }
+}
@@ -1,12 +1,33 @@
 //! High-level MCP server implementation
 
+use crate::admin::{AdminApi, CancelRequest, ClientHello, RegisteredSession, SessionRegistry};
+use crate::auth::Authenticator;
+use crate::context::{ClientAddr, Principal, RequestContext};
+use crate::metrics::MonitoringEndpoints;
 use crate::middleware::Middleware;
+use crate::notifications::NotificationSender;
+use crate::oauth::ProtectedResource;
+use crate::origin::OriginPolicy;
 use crate::registry::*;
-use axum::extract::ws::WebSocket;
-use mocopr_core::monitoring::MonitoringSystem;
+use crate::shutdown::ShutdownHandle;
+use crate::tls::TlsConfig;
+use axum::Extension;
+use axum::extract::ConnectInfo;
+use axum::extract::ws::{WebSocket, WebSocketUpgrade};
+use futures::FutureExt;
+use mocopr_core::monitoring::{MonitoringSystem, RequestMetrics};
 use mocopr_core::prelude::*;
 use serde_json::json;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_rustls::TlsAcceptor;
 use tracing::{debug, error, info, warn};
 
 /// High-level MCP server
@@ -14,12 +35,18 @@ pub struct McpServer {
     info: Implementation,
     capabilities: ServerCapabilities,
     handler: Arc<ServerMessageHandler>,
-    middleware_stack: Vec<Box<dyn Middleware>>,
     monitoring_system: Option<MonitoringSystem>,
     bind_address: String,
     port: u16,
     enable_http: bool,
     enable_websocket: bool,
+    shutdown: ShutdownHandle,
+    monitoring_endpoints: MonitoringEndpoints,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    protected_resource: Option<ProtectedResource>,
+    origin_policy: Arc<OriginPolicy>,
+    tls: Option<(TlsConfig, TlsAcceptor)>,
+    admin: Option<AdminApi>,
 }
 
 impl McpServer {
@@ -29,6 +56,7 @@ impl McpServer {
     }
 
     /// Create a new MCP server
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         info: Implementation,
         capabilities: ServerCapabilities,
@@ -42,24 +70,78 @@ impl McpServer {
         enable_http: bool,
         enable_websocket: bool,
     ) -> Self {
-        let handler = Arc::new(ServerMessageHandler::new(
+        let mut handler = ServerMessageHandler::new(
             info.clone(),
             capabilities.clone(),
             resource_registry,
             tool_registry,
             prompt_registry,
-        ));
+        );
+        handler.monitoring = monitoring_system.clone();
+        handler.middleware = middleware_stack;
+        let handler = Arc::new(handler);
 
         Self {
             info,
             capabilities,
             handler,
-            middleware_stack,
             monitoring_system,
             bind_address,
             port,
             enable_http,
             enable_websocket,
+            shutdown: ShutdownHandle::default(),
+            monitoring_endpoints: MonitoringEndpoints::default(),
+            authenticator: None,
+            protected_resource: None,
+            origin_policy: Arc::new(OriginPolicy::default()),
+            tls: None,
+            admin: None,
+        }
+    }
+
+    /// Replace the shutdown handle, e.g. to change the grace period
+    pub(crate) fn set_shutdown_handle(&mut self, shutdown: ShutdownHandle) {
+        self.shutdown = shutdown;
+    }
+
+    /// Replace the paths of the health, readiness and metrics endpoints
+    pub(crate) fn set_monitoring_endpoints(&mut self, endpoints: MonitoringEndpoints) {
+        self.monitoring_endpoints = endpoints;
+    }
+
+    /// Authenticate HTTP and WebSocket clients at the transport
+    pub(crate) fn set_authenticator(&mut self, authenticator: Arc<dyn Authenticator>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    /// Serve OAuth protected resource metadata and validate access tokens
+    pub(crate) fn set_protected_resource(&mut self, resource: ProtectedResource) {
+        self.authenticator = Some(Arc::new(resource.clone()));
+        self.protected_resource = Some(resource);
+    }
+
+    /// Restrict the origins and hosts HTTP and WebSocket clients may use
+    pub(crate) fn set_origin_policy(&mut self, policy: OriginPolicy) {
+        self.origin_policy = Arc::new(policy);
+    }
+
+    /// Serve HTTP and WebSocket clients over TLS
+    pub(crate) fn set_tls(&mut self, config: TlsConfig, acceptor: TlsAcceptor) {
+        self.tls = Some((config, acceptor));
+    }
+
+    /// Serve the admin endpoints next to the MCP endpoint
+    pub(crate) fn set_admin_api(&mut self, admin: AdminApi) {
+        self.admin = Some(admin);
+    }
+
+    /// Answer requests whose handler panics with `message`
+    ///
+    /// Only takes effect before the server starts running.
+    pub(crate) fn set_panic_message(&mut self, message: String) {
+        if let Some(handler) = Arc::get_mut(&mut self.handler) {
+            handler.panic_message = message;
         }
     }
 
@@ -75,7 +157,7 @@ impl McpServer {
 
     /// Get the middleware stack
     pub fn middleware(&self) -> &Vec<Box<dyn Middleware>> {
-        &self.middleware_stack
+        &self.handler.middleware
     }
 
     /// Get the monitoring system (if enabled)
@@ -83,6 +165,31 @@ impl McpServer {
         self.monitoring_system.as_ref()
     }
 
+    /// Get the connected sessions
+    pub fn sessions(&self) -> &SessionRegistry {
+        &self.handler.sessions
+    }
+
+    /// Get the admin API settings, if the admin endpoints are served
+    pub fn admin_api(&self) -> Option<&AdminApi> {
+        self.admin.as_ref()
+    }
+
+    /// Get the paths of the health, readiness and metrics endpoints
+    pub fn monitoring_endpoints(&self) -> &MonitoringEndpoints {
+        &self.monitoring_endpoints
+    }
+
+    /// Get the origins and hosts HTTP and WebSocket clients may use
+    pub fn origin_policy(&self) -> &OriginPolicy {
+        &self.origin_policy
+    }
+
+    /// Get the TLS settings, if HTTP and WebSocket clients are served over TLS
+    pub fn tls_config(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref().map(|(config, _)| config)
+    }
+
     /// Get the configured bind address
     pub fn bind_address(&self) -> &str {
         &self.bind_address
@@ -103,13 +210,98 @@ impl McpServer {
         self.enable_websocket
     }
 
+    /// Get a handle that can be used to shut the server down gracefully
+    ///
+    /// Calling [`ShutdownHandle::shutdown`] stops accepting new connections,
+    /// lets in-flight requests finish within the grace period configured with
+    /// [`McpServerBuilder::with_shutdown_timeout`](crate::builder::McpServerBuilder::with_shutdown_timeout),
+    /// flushes pending notifications and closes all sessions. The `run*`
+    /// methods return once this is done.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Run the server with configured transports until `signal` completes
+    ///
+    /// When the signal fires a graceful shutdown is started and this method
+    /// returns once it is complete. Use [`shutdown_signal`](crate::shutdown::shutdown_signal)
+    /// to stop on ctrl-c or SIGTERM.
+    pub async fn run_until<F>(&self, signal: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        let run = self.run();
+        tokio::pin!(run);
+
+        tokio::select! {
+            result = &mut run => return result,
+            _ = signal => self.shutdown.shutdown(),
+        }
+
+        run.await
+    }
+
     /// Run the server using stdio transport
     pub async fn run_stdio(&self) -> Result<()> {
         info!("Starting MCP server with stdio transport");
 
         let transport = mocopr_core::transport::stdio::StdioTransport::current_process();
-        let (session, mut events) =
-            mocopr_core::protocol::Session::new(Box::new(transport), self.handler.clone());
+        self.run_transport(Box::new(transport)).await
+    }
+
+    /// Serve a single client connected through `transport`
+    ///
+    /// Requests go through the same middleware, metrics and panic isolation
+    /// as WebSocket requests. This is what [`McpServer::run_stdio`] does with
+    /// the process's standard input and output.
+    ///
+    /// On shutdown the session stops reading and the request being handled
+    /// may finish until the deadline; after that it is answered with an
+    /// internal error. Notifications published meanwhile are delivered before
+    /// the transport is closed.
+    pub async fn run_transport(&self, transport: Box<dyn Transport>) -> Result<()> {
+        self.serve_transport(transport, None).await
+    }
+
+    /// Serve a single client at `client` connected through `transport`
+    ///
+    /// Like [`McpServer::run_transport`], but the session knows the client's
+    /// address as [`ClientAddr`], as WebSocket sessions do, so that for
+    /// example rate limits per client IP apply to it.
+    pub async fn run_transport_from(
+        &self,
+        transport: Box<dyn Transport>,
+        client: SocketAddr,
+    ) -> Result<()> {
+        self.serve_transport(transport, Some(client)).await
+    }
+
+    async fn serve_transport(
+        &self,
+        transport: Box<dyn Transport>,
+        client: Option<SocketAddr>,
+    ) -> Result<()> {
+        let transport_type = transport.transport_type();
+        let outbound = Arc::new(OnceLock::new());
+        let (session, mut events) = mocopr_core::protocol::Session::new(
+            transport,
+            Arc::new(SessionHandler {
+                handler: self.handler.clone(),
+                outbound: outbound.clone(),
+            }),
+        );
+        let _ = outbound.set(session.outbound());
+        let session = session.with_panic_message(self.handler.panic_message.clone());
+        if let Some(client) = client {
+            session.extensions().insert(ClientAddr(client));
+        }
+        let registered =
+            self.handler
+                .sessions
+                .register(transport_type, session.extensions().clone(), None);
+        let monitoring = self.monitoring_system.clone();
+        let sessions = self.handler.sessions.clone();
+        let session_id = registered.id().to_string();
 
         // Handle session events in the background
         let session_events = tokio::spawn(async move {
@@ -128,13 +320,90 @@ impl McpServer {
                     mocopr_core::protocol::SessionEvent::Error { error } => {
                         error!("Session error: {}", error);
                     }
-                    _ => {}
+                    mocopr_core::protocol::SessionEvent::MessageReceived { message } => {
+                        sessions.touch(&session_id);
+                        // Requests are handled one at a time, in the order received
+                        if let Some((request_id, request)) = stdio_request(&message) {
+                            sessions.request_started(&session_id, request_id, &request, None);
+                        }
+                    }
+                    mocopr_core::protocol::SessionEvent::MessageSent { message } => {
+                        if let Ok(JsonRpcMessage::Response(response)) =
+                            Protocol::parse_message(&message)
+                            && let Some(request_id) = response.id
+                        {
+                            sessions.request_finished(&session_id, &request_id);
+                        }
+                    }
+                    mocopr_core::protocol::SessionEvent::Panicked { method, .. } => {
+                        if let Some(monitoring) = &monitoring {
+                            monitoring.record_panic(metric_method(&method)).await;
+                        }
+                    }
                 }
             }
         });
 
+        // Forward server notifications to the client while the session runs
+        let mut notifications = self.notifications().subscribe();
+        let forward_notifications = async {
+            loop {
+                match notifications.recv().await {
+                    Ok(notification) => {
+                        if let Err(e) = session.send_notification(notification).await {
+                            warn!("Failed to send notification: {}", e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} notifications for slow client", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        std::future::pending::<()>().await;
+                    }
+                }
+            }
+        };
+
+        // Stop the session from the outside without dropping its loop, which
+        // answers any request it abandons and hands the transport back
+        let control = async {
+            tokio::select! {
+                _ = forward_notifications => {}
+                _ = async {
+                    // Stop reading, letting the current request finish until the deadline
+                    self.shutdown.requested().await;
+                    session.stop();
+                    self.shutdown.deadline().await;
+                    warn!("Shutdown deadline reached, cancelling in-flight request");
+                    session.abort();
+                } => {}
+                _ = registered.disconnected() => {
+                    info!("Session disconnected by the server");
+                    session.abort();
+                }
+            }
+            std::future::pending::<()>().await
+        };
+
         // Run the session
-        let session_result = session.run().await;
+        let session_result = tokio::select! {
+            result = session.run() => result,
+            _ = control => Ok(()),
+        };
+
+        if self.shutdown.is_shutting_down() {
+            // Deliver notifications published while draining
+            while let Ok(notification) = notifications.try_recv() {
+                if let Err(e) = session.send_notification(notification).await {
+                    warn!("Failed to send notification: {}", e);
+                    break;
+                }
+            }
+
+            if let Err(e) = session.close().await {
+                warn!("Failed to close session: {}", e);
+            }
+        }
 
         // Wait for event handler to finish
         let _ = session_events.await;
@@ -171,20 +440,22 @@ impl McpServer {
         info!("Starting MCP server with HTTP transport on {}", addr);
 
         use axum::{Router, routing::post};
-        use tower_http::cors::CorsLayer;
 
         let handler = self.handler.clone();
 
         let app = Router::new()
             .route("/mcp", post(handle_http_request))
-            .layer(CorsLayer::permissive())
             .with_state(handler);
+        let app = self
+            .secured(app)
+            .merge(self.monitoring_routes())
+            .merge(self.metadata_routes())
+            .merge(self.admin_routes());
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
         info!("HTTP server listening on {}", addr);
 
-        axum::serve(listener, app).await?;
-        Ok(())
+        self.serve(listener, app).await
     }
 
     /// Run the server with both HTTP and WebSocket support
@@ -194,56 +465,157 @@ impl McpServer {
             addr
         );
 
-        use axum::{
-            Router,
-            extract::ws::WebSocketUpgrade,
-            routing::{get, post},
-        };
-        use tower_http::cors::CorsLayer;
+        use axum::{Router, routing::post};
 
         let handler = self.handler.clone();
-        let ws_handler = handler.clone();
+        let shutdown = self.shutdown.clone();
 
         let app = Router::new()
             .route("/mcp", post(handle_http_request))
-            .route(
-                "/mcp/ws",
-                get(move |ws: WebSocketUpgrade| async move {
-                    ws.on_upgrade(move |socket| handle_websocket(socket, ws_handler))
-                }),
-            )
-            .layer(CorsLayer::permissive())
+            .route("/mcp/ws", websocket_route(handler.clone(), shutdown))
             .with_state(handler);
+        let app = self
+            .secured(app)
+            .merge(self.monitoring_routes())
+            .merge(self.metadata_routes())
+            .merge(self.admin_routes());
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
         info!("HTTP+WebSocket server listening on {}", addr);
 
-        axum::serve(listener, app).await?;
-        Ok(())
+        self.serve(listener, app).await
     }
 
     /// Run the server using WebSocket transport
     pub async fn run_websocket(&self, addr: &str) -> Result<()> {
         info!("Starting MCP server with WebSocket transport on {}", addr);
 
-        use axum::{Router, extract::ws::WebSocketUpgrade, routing::get};
-        use tower_http::cors::CorsLayer;
-
-        let handler = self.handler.clone();
+        use axum::Router;
 
-        let app = Router::new()
-            .route(
-                "/mcp",
-                get(move |ws: WebSocketUpgrade| async move {
-                    ws.on_upgrade(move |socket| handle_websocket(socket, handler))
-                }),
-            )
-            .layer(CorsLayer::permissive());
+        let app = Router::new().route(
+            "/mcp",
+            websocket_route(self.handler.clone(), self.shutdown.clone()),
+        );
+        let app = self
+            .secured(app)
+            .merge(self.monitoring_routes())
+            .merge(self.metadata_routes())
+            .merge(self.admin_routes());
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
         info!("WebSocket server listening on {}", addr);
 
-        axum::serve(listener, app).await?;
+        self.serve(listener, app).await
+    }
+
+    /// Apply transport authentication, if an authenticator is set, and the
+    /// origin policy with its CORS rules to `app`
+    fn secured(&self, app: axum::Router) -> axum::Router {
+        let app = match &self.authenticator {
+            Some(authenticator) => app.layer(axum::middleware::from_fn_with_state(
+                authenticator.clone(),
+                crate::auth::authenticate,
+            )),
+            None => app,
+        };
+        self.origin_checked(app)
+            .layer(self.origin_policy.cors_layer())
+    }
+
+    /// OAuth protected resource metadata routes, behind the origin policy with
+    /// its CORS rules, empty without a protected resource
+    fn metadata_routes(&self) -> axum::Router {
+        let mut routes = axum::Router::new();
+        if let Some(resource) = &self.protected_resource {
+            let metadata = resource.metadata().clone();
+            for path in resource.metadata_paths() {
+                let metadata = metadata.clone();
+                routes = routes.route(
+                    &path,
+                    axum::routing::get(move || async move { axum::Json(metadata) }),
+                );
+            }
+        }
+        self.origin_checked(routes)
+            .layer(self.origin_policy.cors_layer())
+    }
+
+    /// Admin API routes, behind the origin policy, empty without an admin API
+    fn admin_routes(&self) -> axum::Router {
+        match &self.admin {
+            Some(admin) => self.origin_checked(admin.routes(self.handler.sessions.clone())),
+            None => axum::Router::new(),
+        }
+    }
+
+    /// Health, readiness and metrics routes, behind the origin policy, empty
+    /// when monitoring is disabled
+    fn monitoring_routes(&self) -> axum::Router {
+        match &self.monitoring_system {
+            Some(monitoring) => self.origin_checked(crate::metrics::routes(
+                monitoring.clone(),
+                &self.monitoring_endpoints,
+                self.shutdown.clone(),
+                self.handler.sessions.clone(),
+            )),
+            None => axum::Router::new(),
+        }
+    }
+
+    /// Reject requests to `routes` from disallowed origins and hosts
+    fn origin_checked(&self, routes: axum::Router) -> axum::Router {
+        routes.layer(axum::middleware::from_fn_with_state(
+            self.origin_policy.clone(),
+            crate::origin::validate,
+        ))
+    }
+
+    /// Serve an axum app until shutdown has drained all connections
+    async fn serve(&self, listener: tokio::net::TcpListener, app: axum::Router) -> Result<()> {
+        let addr = listener.local_addr()?;
+        if addr.ip().is_unspecified() {
+            warn!(
+                "SECURITY WARNING: listening on {} exposes this MCP server to every network \
+                 interface. Anyone who can reach this machine can connect to it. Bind to \
+                 127.0.0.1 unless the server must be remote, and then require \
+                 authentication and list the host names clients use with \
+                 OriginPolicy::with_allowed_host; requests for other hosts are rejected",
+                addr
+            );
+        }
+
+        let shutdown = self.shutdown.clone();
+        let signal = async move { shutdown.requested().await };
+        let server: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>> = match &self.tls {
+            Some((_, acceptor)) => {
+                Box::pin(crate::tls::serve(listener, app, acceptor.clone(), signal))
+            }
+            None => Box::pin(
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(signal)
+                .into_future(),
+            ),
+        };
+
+        tokio::select! {
+            result = server => result?,
+            _ = self.shutdown.deadline() => {
+                warn!("Shutdown deadline reached, dropping remaining HTTP connections");
+            }
+        }
+
+        // Upgraded WebSocket connections are not tracked by axum
+        tokio::select! {
+            _ = self.shutdown.wait_idle() => {}
+            _ = self.shutdown.deadline() => {
+                warn!("Shutdown deadline reached with {} active sessions", self.shutdown.active());
+            }
+        }
+
+        info!("Server shut down");
         Ok(())
     }
 
@@ -261,12 +633,219 @@ impl McpServer {
     pub fn prompts(&self) -> &PromptRegistry {
         &self.handler.prompts
     }
+
+    /// Get the sender used to publish notifications to connected clients
+    pub fn notifications(&self) -> &NotificationSender {
+        self.handler.resources.notifications()
+    }
 }
 
-/// Route MCP method calls to appropriate handlers
+/// Route MCP method calls to appropriate handlers, recording request metrics
+///
+/// A handler that panics does not take the connection down with it: the
+/// request is answered with an internal error instead.
 async fn handle_mcp_method(
     handler: &Arc<ServerMessageHandler>,
     json_msg: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let start_time = std::time::Instant::now();
+    let response = match AssertUnwindSafe(apply_middleware(handler, json_msg))
+        .catch_unwind()
+        .await
+    {
+        Ok(response) => response,
+        Err(payload) => {
+            recover_from_panic(handler, json_msg, Utils::panic_message(payload.as_ref())).await
+        }
+    };
+
+    let Some(monitoring) = &handler.monitoring else {
+        return response;
+    };
+    let error_message = response
+        .as_ref()
+        .and_then(|r| r.get("error"))
+        .map(|e| e["message"].as_str().unwrap_or_default().to_string());
+
+    monitoring
+        .record_request(RequestMetrics {
+            start_time,
+            method: metric_method(
+                json_msg
+                    .get("method")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("unknown"),
+            )
+            .to_string(),
+            success: error_message.is_none(),
+            response_time: start_time.elapsed(),
+            error_message,
+        })
+        .await;
+
+    response
+}
+
+/// Methods recorded under their own name in request metrics
+const METRIC_METHODS: &[&str] = &[
+    "initialize",
+    "ping",
+    "resources/list",
+    "resources/templates/list",
+    "resources/read",
+    "resources/subscribe",
+    "resources/unsubscribe",
+    "tools/list",
+    "tools/call",
+    "prompts/list",
+    "prompts/get",
+    "logging/setLevel",
+    "notifications/initialized",
+    "notifications/cancelled",
+];
+
+/// Get the metrics label of `method`
+///
+/// Methods the server does not route are all recorded as `other`, so clients
+/// cannot add labels to the metrics by making up method names.
+fn metric_method(method: &str) -> &str {
+    if METRIC_METHODS.contains(&method) {
+        method
+    } else {
+        "other"
+    }
+}
+
+/// Log a panic raised while handling a request and build the response to it
+///
+/// The panic message is only logged; the client gets the server's redacted
+/// panic message. Middleware hear of the failure through `on_error`.
+async fn recover_from_panic(
+    handler: &Arc<ServerMessageHandler>,
+    json_msg: &serde_json::Value,
+    message: String,
+) -> Option<serde_json::Value> {
+    let method = json_msg
+        .get("method")
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown");
+    let id = json_msg.get("id");
+    error!(
+        method = %method,
+        request_id = %id.unwrap_or(&serde_json::Value::Null),
+        principal = Principal::current().as_ref().map(|p| p.subject()).unwrap_or("anonymous"),
+        "Handler panicked: {}",
+        message
+    );
+
+    if let Some(monitoring) = &handler.monitoring {
+        monitoring.record_panic(metric_method(method)).await;
+    }
+    if let Ok(request) = serde_json::from_value::<JsonRpcRequest>(json_msg.clone()) {
+        let error = Error::internal(format!("Handler panicked: {}", message));
+        for middleware in &handler.middleware {
+            let _ = middleware.on_error(&request, &error).await;
+        }
+    }
+
+    // Notifications get no response
+    id?;
+    Some(json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": mocopr_core::protocol::error_codes::INTERNAL_ERROR,
+            "message": handler.panic_message
+        },
+        "id": id
+    }))
+}
+
+/// Run a request through the middleware stack around routing it
+///
+/// A middleware rejecting the request in `before_request` stops it from
+/// reaching the handler; its error becomes the response.
+async fn apply_middleware(
+    handler: &Arc<ServerMessageHandler>,
+    json_msg: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    if handler.middleware.is_empty() {
+        return route_mcp_method(handler, json_msg).await;
+    }
+
+    // Malformed requests get their error response from the router
+    let Ok(request) = serde_json::from_value::<JsonRpcRequest>(json_msg.clone()) else {
+        return route_mcp_method(handler, json_msg).await;
+    };
+
+    let mut cancel_guard = CancelGuard {
+        handler: handler.clone(),
+        request: Some(request.clone()),
+    };
+
+    for middleware in &handler.middleware {
+        if let Err(e) = middleware.before_request(&request).await {
+            cancel_guard.request = None;
+            for middleware in &handler.middleware {
+                let _ = middleware.on_error(&request, &e).await;
+            }
+            return Some(error_response(json_msg.get("id"), &e));
+        }
+    }
+
+    let response = route_mcp_method(handler, json_msg).await;
+
+    if let Some(response) = &response
+        && let Ok(response) = serde_json::from_value::<JsonRpcResponse>(response.clone())
+    {
+        for middleware in &handler.middleware {
+            if let Err(e) = middleware.after_response(&request, &response).await {
+                warn!("Middleware failed after {}: {}", request.method, e);
+            }
+        }
+    }
+    cancel_guard.request = None;
+
+    response
+}
+
+/// Tells middleware about a request dropped before it ended, e.g. when its
+/// task is aborted because the client cancelled it or disconnected
+///
+/// Panics are reported through `on_error` by [`recover_from_panic`] instead.
+struct CancelGuard {
+    handler: Arc<ServerMessageHandler>,
+    request: Option<JsonRpcRequest>,
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        let Some(request) = self.request.take() else {
+            return;
+        };
+        if std::thread::panicking() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let handler = self.handler.clone();
+        let extensions = Extensions::current().unwrap_or_default();
+        runtime.spawn(extensions.scope(async move {
+            for middleware in &handler.middleware {
+                if let Err(e) = middleware.on_cancel(&request).await {
+                    warn!(
+                        "Middleware failed after cancelling {}: {}",
+                        request.method, e
+                    );
+                }
+            }
+        }));
+    }
+}
+
+async fn route_mcp_method(
+    handler: &Arc<ServerMessageHandler>,
+    json_msg: &serde_json::Value,
 ) -> Option<serde_json::Value> {
     let method = match json_msg.get("method").and_then(|m| m.as_str()) {
         Some(method) => method,
@@ -317,6 +896,20 @@ async fn handle_mcp_method(
             }
         }
 
+        "resources/templates/list" => {
+            let request = match params {
+                Some(p) => serde_json::from_value::<ResourcesTemplatesListRequest>(p.clone()),
+                None => Ok(ResourcesTemplatesListRequest::new()),
+            };
+            match request {
+                Ok(req) => handler
+                    .handle_resources_templates_list(req)
+                    .await
+                    .map(|r| serde_json::to_value(r).unwrap()),
+                Err(e) => Err(mocopr_core::Error::InvalidRequest(e.to_string())),
+            }
+        }
+
         "resources/read" => {
             let request = match params
                 .and_then(|p| serde_json::from_value::<ResourcesReadRequest>(p.clone()).ok())
@@ -506,29 +1099,177 @@ async fn handle_mcp_method(
             "result": value,
             "id": id
         })),
-        Err(e) => Some(json!({
-            "jsonrpc": "2.0",
-            "error": {
-                "code": match &e {
-                    mocopr_core::Error::MethodNotFound(_) => -32601,
-                    mocopr_core::Error::InvalidRequest(_) => -32602,
-                    _ => -32603,
-                },
-                "message": e.to_string()
-            },
-            "id": id
-        })),
+        Err(e) => Some(error_response(id, &e)),
+    }
+}
+
+/// Build the JSON-RPC error response for a failed request
+fn error_response(id: Option<&serde_json::Value>, e: &Error) -> serde_json::Value {
+    use mocopr_core::error::ProtocolError;
+    use mocopr_core::protocol::error_codes;
+
+    let mut error = json!({
+        "code": match e.inner() {
+            mocopr_core::Error::MethodNotFound(_) => error_codes::METHOD_NOT_FOUND,
+            mocopr_core::Error::InvalidRequest(_) => error_codes::INVALID_PARAMS,
+            mocopr_core::Error::InvalidParams(_) => error_codes::INVALID_PARAMS,
+            mocopr_core::Error::Protocol(ProtocolError::PermissionDenied) => {
+                error_codes::PERMISSION_DENIED
+            }
+            mocopr_core::Error::Protocol(ProtocolError::RateLimitExceeded) => {
+                error_codes::RATE_LIMITED
+            }
+            mocopr_core::Error::Protocol(ProtocolError::ServerBusy(_)) => error_codes::SERVER_BUSY,
+            mocopr_core::Error::Remote { code, .. } => *code,
+            _ => error_codes::INTERNAL_ERROR,
+        },
+        "message": e.to_string()
+    });
+    if let Some(data) = e.data() {
+        error["data"] = data.clone();
     }
+    json!({
+        "jsonrpc": "2.0",
+        "error": error,
+        "id": id
+    })
+}
+
+/// Route upgrading requests to WebSocket sessions
+///
+/// The session starts out knowing the client's address and, when the upgrade
+/// request was authenticated, its [`Principal`].
+fn websocket_route<S>(
+    handler: Arc<ServerMessageHandler>,
+    shutdown: ShutdownHandle,
+) -> axum::routing::MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    axum::routing::get(
+        move |ConnectInfo(client): ConnectInfo<SocketAddr>,
+              principal: Option<Extension<Principal>>,
+              ws: WebSocketUpgrade| async move {
+            let extensions = Extensions::new();
+            extensions.insert(ClientAddr(client));
+            if let Some(Extension(principal)) = principal {
+                extensions.insert(principal);
+            }
+            ws.on_upgrade(move |socket| handle_websocket(socket, handler, shutdown, extensions))
+        },
+    )
 }
 
 /// Handle WebSocket connections
-async fn handle_websocket(mut socket: WebSocket, handler: Arc<ServerMessageHandler>) {
+///
+/// Requests after initialization run concurrently, each in its own task with a
+/// [`RequestContext`]. Their responses and per-request notifications share one
+/// outgoing queue, so a progress notification always reaches the client before
+/// the response to its request.
+async fn handle_websocket(
+    mut socket: WebSocket,
+    handler: Arc<ServerMessageHandler>,
+    shutdown: ShutdownHandle,
+    extensions: Extensions,
+) {
+    if shutdown.is_shutting_down() {
+        let _ = socket.send(axum::extract::ws::Message::Close(None)).await;
+        return;
+    }
+
+    let _active = shutdown.track();
     info!("WebSocket client connected");
 
     // Handle the MCP initialization handshake
     let mut initialized = false;
 
-    while let Some(result) = socket.recv().await {
+    let mut notifications = handler.resources.notifications().subscribe();
+    let mut notifications_open = true;
+
+    let (outgoing, mut outgoing_messages) = mpsc::unbounded_channel::<String>();
+    let (cancel, mut cancels) = mpsc::unbounded_channel::<CancelRequest>();
+    let session = handler
+        .sessions
+        .register("websocket", extensions.clone(), Some(cancel));
+    let mut requests = JoinSet::new();
+    let mut in_flight: HashMap<RequestId, InFlightRequest> = HashMap::new();
+
+    loop {
+        let result = tokio::select! {
+            result = socket.recv() => match result {
+                Some(result) => result,
+                None => break,
+            },
+            Some(text) = outgoing_messages.recv() => {
+                if let Err(e) = socket.send(axum::extract::ws::Message::Text(text)).await {
+                    error!("Failed to send WebSocket response: {}", e);
+                    break;
+                }
+                continue;
+            }
+            Some(finished) = requests.join_next_with_id() => {
+                finish_request(&mut in_flight, finished);
+                continue;
+            }
+            Some((request_id, reply)) = cancels.recv() => {
+                let cancelled = cancel_request(
+                    &mut in_flight,
+                    &session,
+                    &request_id,
+                    Some(&outgoing),
+                );
+                let _ = reply.send(cancelled);
+                continue;
+            }
+            notification = notifications.recv(), if initialized && notifications_open => {
+                match notification {
+                    Ok(notification) => {
+                        let text = serde_json::to_string(&notification).unwrap_or_default();
+                        if let Err(e) = socket.send(axum::extract::ws::Message::Text(text)).await {
+                            error!("Failed to send WebSocket notification: {}", e);
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} notifications for slow WebSocket client", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => notifications_open = false,
+                }
+                continue;
+            }
+            _ = session.disconnected() => {
+                info!("Disconnecting WebSocket client at the server's request");
+                requests.abort_all();
+                while let Ok(text) = outgoing_messages.try_recv() {
+                    if socket.send(axum::extract::ws::Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                let _ = socket.send(axum::extract::ws::Message::Close(None)).await;
+                break;
+            }
+            _ = shutdown.requested() => {
+                drain_websocket(
+                    &mut socket,
+                    &shutdown,
+                    &mut requests,
+                    &mut in_flight,
+                    &mut outgoing_messages,
+                ).await;
+
+                // Deliver notifications published while draining, then close
+                while initialized && let Ok(notification) = notifications.try_recv() {
+                    let text = serde_json::to_string(&notification).unwrap_or_default();
+                    if socket.send(axum::extract::ws::Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                let _ = socket.send(axum::extract::ws::Message::Close(None)).await;
+                break;
+            }
+        };
+
+        session.touch();
         match result {
             Ok(msg) => {
                 if let Ok(text) = msg.to_text() {
@@ -545,10 +1286,13 @@ async fn handle_websocket(mut socket: WebSocket, handler: Arc<ServerMessageHandl
                                     if method == "initialize" {
                                         // Parse the initialize request
                                         match serde_json::from_value::<InitializeRequest>(
-                                            json_msg.clone(),
+                                            json_msg.get("params").cloned().unwrap_or_default(),
                                         ) {
                                             Ok(init_request) => {
-                                                match handler.handle_initialize(init_request).await
+                                                match extensions
+                                                    .clone()
+                                                    .scope(handler.handle_initialize(init_request))
+                                                    .await
                                                 {
                                                     Ok(init_response) => {
                                                         initialized = true;
@@ -598,9 +1342,29 @@ async fn handle_websocket(mut socket: WebSocket, handler: Arc<ServerMessageHandl
                                         "id": json_msg.get("id")
                                     }))
                                 }
+                            } else if json_msg.get("id").is_none_or(|id| id.is_null()) {
+                                // Notifications never get a response
+                                handle_websocket_notification(
+                                    &handler,
+                                    &json_msg,
+                                    &session,
+                                    &mut in_flight,
+                                )
+                                .await;
+                                None
                             } else {
-                                // Handle regular MCP messages after initialization
-                                handle_mcp_method(&handler, &json_msg).await
+                                // Handle regular MCP requests after initialization
+                                // concurrently with the rest of the session
+                                spawn_request(
+                                    &handler,
+                                    json_msg,
+                                    &session,
+                                    &extensions,
+                                    &outgoing,
+                                    &mut requests,
+                                    &mut in_flight,
+                                );
+                                None
                             };
 
                             // Send response if there is one
@@ -656,9 +1420,224 @@ async fn handle_websocket(mut socket: WebSocket, handler: Arc<ServerMessageHandl
         }
     }
 
+    // Requests still running have nobody left to answer to
+    requests.abort_all();
+    extensions.clear();
     info!("WebSocket client disconnected");
 }
 
+/// Get the ID and JSON of a request received over stdio
+fn stdio_request(message: &str) -> Option<(RequestId, serde_json::Value)> {
+    let json_msg: serde_json::Value = serde_json::from_str(message).ok()?;
+    json_msg.get("method")?;
+    let request_id = serde_json::from_value(json_msg.get("id")?.clone()).ok()?;
+    Some((request_id, json_msg))
+}
+
+/// Run a request in its own task, sending the response through `outgoing`
+fn spawn_request(
+    handler: &Arc<ServerMessageHandler>,
+    json_msg: serde_json::Value,
+    session: &RegisteredSession,
+    extensions: &Extensions,
+    outgoing: &mpsc::UnboundedSender<String>,
+    requests: &mut JoinSet<()>,
+    in_flight: &mut HashMap<RequestId, InFlightRequest>,
+) {
+    let id = json_msg["id"].clone();
+    let request_id = serde_json::from_value::<RequestId>(id.clone()).ok();
+    let context = RequestContext::new(
+        json_msg["method"].as_str().unwrap_or_default(),
+        request_id.clone(),
+        json_msg
+            .pointer("/params/_meta/progressToken")
+            .and_then(|token| serde_json::from_value(token.clone()).ok()),
+        extensions.clone(),
+        outgoing.clone(),
+    );
+
+    let handler = handler.clone();
+    let outgoing = outgoing.clone();
+    let request = json_msg.clone();
+    let answered = Arc::new(AtomicBool::new(false));
+    let task = requests.spawn(context.scope({
+        let answered = answered.clone();
+        async move {
+            if let Some(response) = handle_mcp_method(&handler, &json_msg).await
+                && !answered.swap(true, Ordering::SeqCst)
+            {
+                let _ = outgoing.send(response.to_string());
+            }
+        }
+    }));
+
+    if let Some(request_id) = request_id {
+        session.request_started(request_id.clone(), &request, task.clone());
+        in_flight.insert(request_id, InFlightRequest { id, task, answered });
+    }
+}
+
+/// A request of a WebSocket session whose task is running
+struct InFlightRequest {
+    /// ID of the request as the client sent it
+    id: serde_json::Value,
+    task: AbortHandle,
+    /// Set by whoever sends the one response the request gets
+    answered: Arc<AtomicBool>,
+}
+
+impl InFlightRequest {
+    /// Stop the request unless it has been answered, returning whether it was
+    fn cancel(&self) -> bool {
+        if self.answered.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.task.abort();
+        true
+    }
+}
+
+/// Cancel a request in flight, for a `notifications/cancelled` from the client
+/// or at the server's request
+///
+/// With `outgoing`, the request is answered with an error; a client that
+/// cancelled the request itself expects no response. Returns `false` if the
+/// request was not in flight or had already been answered.
+fn cancel_request(
+    in_flight: &mut HashMap<RequestId, InFlightRequest>,
+    session: &RegisteredSession,
+    request_id: &RequestId,
+    outgoing: Option<&mpsc::UnboundedSender<String>>,
+) -> bool {
+    let Some(request) = in_flight.remove(request_id) else {
+        return false;
+    };
+    session.request_finished(request_id);
+    if !request.cancel() {
+        return false;
+    }
+    if let Some(outgoing) = outgoing {
+        let response = json!({
+            "jsonrpc": "2.0",
+            "error": {
+                "code": mocopr_core::protocol::error_codes::INTERNAL_ERROR,
+                "message": "Request cancelled by the server"
+            },
+            "id": request.id
+        });
+        let _ = outgoing.send(response.to_string());
+    }
+    true
+}
+
+/// Handle a notification from a WebSocket client
+async fn handle_websocket_notification(
+    handler: &Arc<ServerMessageHandler>,
+    json_msg: &serde_json::Value,
+    session: &RegisteredSession,
+    in_flight: &mut HashMap<RequestId, InFlightRequest>,
+) {
+    match json_msg.get("method").and_then(|m| m.as_str()) {
+        Some("notifications/cancelled") => {
+            let cancelled = json_msg
+                .get("params")
+                .and_then(|p| serde_json::from_value::<CancelledNotification>(p.clone()).ok());
+            if let Some(cancelled) = cancelled
+                && cancel_request(in_flight, session, &cancelled.request_id, None)
+            {
+                debug!(
+                    "Cancelled request {}: {}",
+                    cancelled.request_id,
+                    cancelled.reason.as_deref().unwrap_or("no reason given")
+                );
+            }
+        }
+        Some(_) => {
+            let routed = AssertUnwindSafe(route_mcp_method(handler, json_msg))
+                .catch_unwind()
+                .await;
+            if let Err(payload) = routed {
+                recover_from_panic(handler, json_msg, Utils::panic_message(payload.as_ref())).await;
+            }
+        }
+        None => warn!("Ignoring WebSocket message without a method"),
+    }
+}
+
+/// Forget a finished request task
+fn finish_request(
+    in_flight: &mut HashMap<RequestId, InFlightRequest>,
+    finished: std::result::Result<(tokio::task::Id, ()), tokio::task::JoinError>,
+) {
+    let task_id = match finished {
+        Ok((task_id, ())) => task_id,
+        Err(e) => {
+            if e.is_panic() {
+                error!("Request handler panicked: {}", e);
+            }
+            e.id()
+        }
+    };
+    in_flight.retain(|_, request| request.task.id() != task_id);
+}
+
+/// Let in-flight requests finish until the shutdown deadline, then cancel the rest
+async fn drain_websocket(
+    socket: &mut WebSocket,
+    shutdown: &ShutdownHandle,
+    requests: &mut JoinSet<()>,
+    in_flight: &mut HashMap<RequestId, InFlightRequest>,
+    outgoing_messages: &mut mpsc::UnboundedReceiver<String>,
+) {
+    let deadline = shutdown.deadline();
+    tokio::pin!(deadline);
+
+    while !requests.is_empty() {
+        tokio::select! {
+            Some(text) = outgoing_messages.recv() => {
+                if socket.send(axum::extract::ws::Message::Text(text)).await.is_err() {
+                    requests.abort_all();
+                    return;
+                }
+            }
+            Some(finished) = requests.join_next_with_id() => finish_request(in_flight, finished),
+            _ = &mut deadline => {
+                warn!("Cancelling {} in-flight requests at shutdown deadline", in_flight.len());
+                requests.abort_all();
+                for (_, request) in in_flight.drain() {
+                    if !request.cancel() {
+                        continue;
+                    }
+                    let id = request.id;
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "error": {
+                            "code": -32603,
+                            "message": "Request cancelled: server is shutting down"
+                        },
+                        "id": id
+                    });
+                    let _ = socket
+                        .send(axum::extract::ws::Message::Text(response.to_string()))
+                        .await;
+                }
+                break;
+            }
+        }
+    }
+
+    // Deliver responses queued by requests that finished last
+    while let Ok(text) = outgoing_messages.try_recv() {
+        if socket
+            .send(axum::extract::ws::Message::Text(text))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
 /// Server message handler that implements the MCP protocol
 pub struct ServerMessageHandler {
     pub info: Implementation,
@@ -666,6 +1645,12 @@ pub struct ServerMessageHandler {
     pub resources: ResourceRegistry,
     pub tools: ToolRegistry,
     pub prompts: PromptRegistry,
+    pub monitoring: Option<MonitoringSystem>,
+    pub middleware: Vec<Box<dyn Middleware>>,
+    /// Error message sent for requests whose handler panicked
+    pub panic_message: String,
+    /// Sessions connected to the server
+    pub sessions: SessionRegistry,
 }
 
 impl ServerMessageHandler {
@@ -682,6 +1667,10 @@ impl ServerMessageHandler {
             resources,
             tools,
             prompts,
+            monitoring: None,
+            middleware: Vec::new(),
+            panic_message: mocopr_core::protocol::DEFAULT_PANIC_MESSAGE.to_string(),
+            sessions: SessionRegistry::default(),
         }
     }
 }
@@ -702,8 +1691,17 @@ impl MessageHandler for ServerMessageHandler {
             request.client_info.name, request.client_info.version
         );
 
+        let protocol_version = Protocol::latest_version().to_string();
+        if let Some(extensions) = Extensions::current() {
+            extensions.insert(ClientHello {
+                client_info: request.client_info,
+                protocol_version: protocol_version.clone(),
+                capabilities: request.capabilities,
+            });
+        }
+
         Ok(InitializeResponse {
-            protocol_version: Protocol::latest_version().to_string(),
+            protocol_version,
             capabilities: self.capabilities.clone(),
             server_info: self.info.clone(),
             instructions: None,
@@ -717,6 +1715,13 @@ impl MessageHandler for ServerMessageHandler {
         self.resources.list_resources(request).await
     }
 
+    async fn handle_resources_templates_list(
+        &self,
+        request: ResourcesTemplatesListRequest,
+    ) -> Result<ResourcesTemplatesListResponse> {
+        self.resources.list_templates(request).await
+    }
+
     async fn handle_resources_read(
         &self,
         request: ResourcesReadRequest,
@@ -758,6 +1763,61 @@ impl MessageHandler for ServerMessageHandler {
     }
 }
 
+/// Handles the messages of a [`Session`](mocopr_core::protocol::Session),
+/// running its requests through the same pipeline as WebSocket requests
+struct SessionHandler {
+    handler: Arc<ServerMessageHandler>,
+    /// Outbound queue of the session, for notifications sent through a
+    /// request's [`RequestContext`]
+    outbound: Arc<OnceLock<mpsc::UnboundedSender<String>>>,
+}
+
+#[async_trait::async_trait]
+impl MessageHandler for SessionHandler {
+    async fn handle_request(&self, request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+        // Initialization is answered without middleware, as on WebSocket
+        if request.method == "initialize" {
+            return None;
+        }
+
+        let mut json_msg = serde_json::to_value(request).ok()?;
+        if json_msg["params"].is_null()
+            && let Some(fields) = json_msg.as_object_mut()
+        {
+            fields.remove("params");
+        }
+        let outbound = self.outbound.get()?.clone();
+        let context = RequestContext::new(
+            request.method.as_str(),
+            request.id.clone(),
+            json_msg
+                .pointer("/params/_meta/progressToken")
+                .and_then(|token| serde_json::from_value(token.clone()).ok()),
+            Extensions::current().unwrap_or_default(),
+            outbound,
+        );
+        let response = context
+            .scope(handle_mcp_method(&self.handler, &json_msg))
+            .await
+            .unwrap_or_else(|| json!({"jsonrpc": "2.0", "result": {}, "id": request.id}));
+        Some(serde_json::from_value(response).unwrap_or_else(|e| {
+            Protocol::create_response(
+                request.id.clone(),
+                None,
+                Some(Protocol::create_error(
+                    mocopr_core::protocol::error_codes::INTERNAL_ERROR,
+                    &e.to_string(),
+                    None,
+                )),
+            )
+        }))
+    }
+
+    async fn handle_initialize(&self, request: InitializeRequest) -> Result<InitializeResponse> {
+        self.handler.handle_initialize(request).await
+    }
+}
+
 /// HTTP request handler for MCP over HTTP
 async fn handle_http_request(
     axum::extract::State(_handler): axum::extract::State<Arc<ServerMessageHandler>>,
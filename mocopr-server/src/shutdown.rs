@@ -0,0 +1,241 @@
+//! Graceful shutdown support
+//!
+//! A [`ShutdownHandle`] coordinates stopping a running [`McpServer`](crate::McpServer).
+//! Once shutdown is requested the server stops accepting connections and new
+//! requests, lets in-flight requests finish for up to the configured grace
+//! period, cancels whatever is still running after that, flushes pending
+//! notifications and closes every session.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::shutdown::shutdown_signal;
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let server = McpServerBuilder::new()
+//!         .with_info("My Server", "1.0.0")
+//!         .with_websocket_transport()
+//!         .with_shutdown_timeout(Duration::from_secs(20))
+//!         .build()?;
+//!
+//!     // Stops on ctrl-c or SIGTERM
+//!     server.run_until(shutdown_signal()).await
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, watch};
+use tokio::time::Instant;
+use tracing::info;
+
+/// Default time in-flight requests get to finish after shutdown is requested
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Handle used to request and observe a graceful shutdown
+///
+/// Clones share the same state, so a handle can be moved into a signal
+/// handler or another task while the server keeps running.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    requested_at: Arc<watch::Sender<Option<Instant>>>,
+    grace_period: Duration,
+    active: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl ShutdownHandle {
+    /// Create a handle with the given grace period for in-flight work
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            requested_at: Arc::new(watch::channel(None).0),
+            grace_period,
+            active: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Request a graceful shutdown
+    ///
+    /// Calling this more than once has no further effect.
+    pub fn shutdown(&self) {
+        self.requested_at.send_if_modified(|requested_at| {
+            if requested_at.is_some() {
+                return false;
+            }
+            info!(
+                "Shutdown requested, draining for up to {:?}",
+                self.grace_period
+            );
+            *requested_at = Some(Instant::now());
+            true
+        });
+    }
+
+    /// Check whether shutdown has been requested
+    pub fn is_shutting_down(&self) -> bool {
+        self.requested_at.borrow().is_some()
+    }
+
+    /// Get the grace period for in-flight work
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Wait until shutdown is requested
+    pub async fn requested(&self) {
+        let mut receiver = self.requested_at.subscribe();
+        let _ = receiver.wait_for(Option::is_some).await;
+    }
+
+    /// Wait until the grace period after the shutdown request has elapsed
+    ///
+    /// Work still running at this point should be cancelled.
+    pub async fn deadline(&self) {
+        let mut receiver = self.requested_at.subscribe();
+        let requested_at = receiver
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|requested_at| *requested_at);
+
+        match requested_at {
+            Some(requested_at) => tokio::time::sleep_until(requested_at + self.grace_period).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Get the number of active sessions and requests being tracked
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Wait until no tracked sessions or requests remain
+    pub async fn wait_idle(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.active() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Track a session or request until the returned guard is dropped
+    pub fn track(&self) -> ActiveGuard {
+        self.active.fetch_add(1, Ordering::AcqRel);
+        ActiveGuard {
+            active: self.active.clone(),
+            idle: self.idle.clone(),
+        }
+    }
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHUTDOWN_TIMEOUT)
+    }
+}
+
+/// Keeps a session or request counted as active while alive
+pub struct ActiveGuard {
+    active: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        if self.active.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+/// Wait for ctrl-c or, on Unix, SIGTERM
+///
+/// Pass this to [`McpServer::run_until`](crate::McpServer::run_until) to shut
+/// down cleanly when the process is stopped, for example during a rolling
+/// deployment.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received ctrl-c"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_deadline_follows_request() {
+        let handle = ShutdownHandle::new(Duration::from_millis(50));
+        assert!(!handle.is_shutting_down());
+
+        let deadline = tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                handle.deadline().await;
+                Instant::now()
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!deadline.is_finished());
+
+        let requested_at = Instant::now();
+        handle.shutdown();
+        handle.shutdown();
+        handle.requested().await;
+        assert!(handle.is_shutting_down());
+
+        let reached = deadline.await.unwrap();
+        assert!(reached - requested_at >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle() {
+        let handle = ShutdownHandle::default();
+        handle.wait_idle().await;
+
+        let first = handle.track();
+        let second = handle.track();
+        assert_eq!(handle.active(), 2);
+
+        let waiter = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.wait_idle().await }
+        });
+
+        drop(first);
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        drop(second);
+        waiter.await.unwrap();
+        assert_eq!(handle.active(), 0);
+    }
+}
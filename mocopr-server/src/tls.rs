@@ -0,0 +1,341 @@
+//! TLS termination for HTTP and WebSocket transports
+//!
+//! A server configured with a [`TlsConfig`] serves `https://` and `wss://`
+//! instead of plain HTTP, using rustls with a certificate chain and private
+//! key read from PEM files.
+//!
+//! With [`TlsConfig::with_client_ca`] the server also asks clients for a
+//! certificate (mutual TLS) and only accepts certificates issued by the given
+//! authorities. The subject of a verified client certificate, such as
+//! `CN=alice, O=Example`, becomes the [`Principal`] of the client's session,
+//! so RBAC and handlers see who connected without any further credentials.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mocopr_server::prelude::*;
+//! use mocopr_server::tls::TlsConfig;
+//!
+//! let builder = McpServerBuilder::new()
+//!     .with_info("Secure Server", "1.0.0")
+//!     .with_tls_config(
+//!         TlsConfig::new("certs/server.pem", "certs/server.key")
+//!             .with_client_ca("certs/clients-ca.pem"),
+//!     )
+//!     .with_websocket_transport();
+//! ```
+
+use crate::context::Principal;
+use axum::extract::ConnectInfo;
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use mocopr_core::prelude::*;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::{debug, warn};
+
+/// How long a client may take to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after an accept error that is not about a single connection, such
+/// as running out of file descriptors, as `axum::serve` does
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Certificate, key and client authentication settings for TLS
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    client_certificate_optional: bool,
+}
+
+impl TlsConfig {
+    /// Serve TLS with the certificate chain in `cert` and the private key in
+    /// `key`, both PEM files
+    ///
+    /// The chain starts with the server's certificate, followed by any
+    /// intermediates. The key may be PKCS#8, PKCS#1 (RSA) or SEC1 (EC).
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+            client_ca: None,
+            client_certificate_optional: false,
+        }
+    }
+
+    /// Require clients to present a certificate issued by one of the
+    /// authorities in the PEM file `ca`
+    ///
+    /// The subject of the client's certificate becomes the [`Principal`] of
+    /// its session, and the server's authenticator, if any, is not consulted
+    /// for it.
+    pub fn with_client_ca(mut self, ca: impl Into<PathBuf>) -> Self {
+        self.client_ca = Some(ca.into());
+        self
+    }
+
+    /// Also accept clients that present no certificate
+    ///
+    /// Such clients must then pass the server's authenticator, if one is
+    /// set. Certificates that are presented are still verified. Has no
+    /// effect without [`with_client_ca`](Self::with_client_ca).
+    pub fn allow_clients_without_certificate(mut self) -> Self {
+        self.client_certificate_optional = true;
+        self
+    }
+
+    /// Get the path of the certificate chain
+    pub fn cert_path(&self) -> &Path {
+        &self.cert
+    }
+
+    /// Get the path of the private key
+    pub fn key_path(&self) -> &Path {
+        &self.key
+    }
+
+    /// Get the path of the client certificate authorities, if clients are
+    /// authenticated by certificate
+    pub fn client_ca_path(&self) -> Option<&Path> {
+        self.client_ca.as_deref()
+    }
+
+    /// Read the PEM files and create the rustls acceptor
+    pub(crate) fn acceptor(&self) -> Result<TlsAcceptor> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::Configuration(format!("Invalid TLS settings: {}", e)))?;
+
+        let builder = match &self.client_ca {
+            Some(ca) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certificates(ca)? {
+                    roots.add(cert).map_err(|e| {
+                        Error::Configuration(format!(
+                            "Invalid client CA certificate in '{}': {}",
+                            ca.display(),
+                            e
+                        ))
+                    })?;
+                }
+                let mut verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+                if self.client_certificate_optional {
+                    verifier = verifier.allow_unauthenticated();
+                }
+                let verifier = verifier.build().map_err(|e| {
+                    Error::Configuration(format!("Invalid client CA '{}': {}", ca.display(), e))
+                })?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder
+            .with_single_cert(load_certificates(&self.cert)?, load_private_key(&self.key)?)
+            .map_err(|e| {
+                Error::Configuration(format!(
+                    "Invalid TLS certificate '{}' or key '{}': {}",
+                    self.cert.display(),
+                    self.key.display(),
+                    e
+                ))
+            })?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// Read every certificate in the PEM file at `path`
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|e| {
+            Error::Configuration(format!(
+                "Failed to read certificates from '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+    if certs.is_empty() {
+        return Err(Error::Configuration(format!(
+            "No certificates found in '{}'",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+/// Read the first private key in the PEM file at `path`
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path).map_err(|e| {
+        Error::Configuration(format!(
+            "Failed to read private key from '{}': {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Principal named by the subject of a verified client certificate
+fn client_principal(cert: &CertificateDer<'_>) -> Option<Principal> {
+    match x509_parser::parse_x509_certificate(cert) {
+        Ok((_, cert)) => Some(Principal::new(cert.subject().to_string())),
+        Err(e) => {
+            warn!("Failed to parse client certificate: {}", e);
+            None
+        }
+    }
+}
+
+/// Serve `app` over TLS until `signal` completes and open connections close
+///
+/// Each request carries the client's address as [`ConnectInfo`] and, when
+/// the client presented a verified certificate, its [`Principal`].
+pub(crate) async fn serve<F>(
+    listener: TcpListener,
+    app: axum::Router,
+    acceptor: TlsAcceptor,
+    signal: F,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(signal);
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) if is_connection_error(&e) => continue,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                        _ = &mut signal => break,
+                    }
+                }
+            },
+            _ = &mut signal => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let watcher = graceful.watcher();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!("TLS handshake with {} failed: {}", addr, e);
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {} timed out", addr);
+                        return;
+                    }
+                };
+
+            let principal = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(client_principal);
+            if let Some(principal) = &principal {
+                debug!(
+                    "Client {} authenticated by certificate as {}",
+                    addr,
+                    principal.subject()
+                );
+            }
+
+            let service =
+                hyper::service::service_fn(move |mut request: axum::http::Request<Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(addr));
+                    if let Some(principal) = &principal {
+                        request.extensions_mut().insert(principal.clone());
+                    }
+                    // Routers are always ready to take a request
+                    app.clone().call(request)
+                });
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                debug!("Connection from {} closed with error: {}", addr, e);
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+    Ok(())
+}
+
+/// Check whether an accept error only concerns the connection being accepted
+fn is_connection_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionReset
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_connection_errors_are_retried_at_once() {
+        let error = |kind| std::io::Error::from(kind);
+        assert!(is_connection_error(&error(
+            std::io::ErrorKind::ConnectionAborted
+        )));
+        // EMFILE and ENFILE surface as other errors and back off
+        assert!(!is_connection_error(&std::io::Error::from_raw_os_error(24)));
+        assert!(!is_connection_error(&error(
+            std::io::ErrorKind::OutOfMemory
+        )));
+    }
+
+    #[test]
+    fn test_client_principal_from_certificate_subject() {
+        let mut params = rcgen::CertificateParams::new(vec!["client".to_string()]).unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "alice");
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+
+        let principal = client_principal(cert.der()).unwrap();
+        assert_eq!(principal.subject(), "CN=alice");
+
+        assert!(client_principal(&CertificateDer::from(vec![1, 2, 3])).is_none());
+    }
+
+    #[test]
+    fn test_missing_files_are_configuration_errors() {
+        let error = TlsConfig::new("/nonexistent/cert.pem", "/nonexistent/key.pem")
+            .acceptor()
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+        assert!(error.to_string().contains("/nonexistent/cert.pem"));
+    }
+}
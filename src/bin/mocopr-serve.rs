@@ -0,0 +1,52 @@
+//! Run an MCP server described by a manifest file
+//!
+//! ```text
+//! mocopr-serve server.toml
+//! ```
+//!
+//! See `mocopr_server::manifest` for the manifest format. Logs go to
+//! standard error so that standard output stays free for the stdio
+//! transport.
+
+use mocopr_server::shutdown_signal;
+use std::process::ExitCode;
+use tracing::{Level, error};
+
+const USAGE: &str = "Usage: mocopr-serve <manifest.toml|manifest.json>";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (Some(manifest), None) = (args.next(), args.next()) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    if manifest == "-h" || manifest == "--help" {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let server = match mocopr_rbac::manifest::builder_from_manifest(&manifest)
+        .await
+        .and_then(|builder| builder.build())
+    {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Failed to load manifest '{}': {}", manifest, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match server.run_until(shutdown_signal()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("Server error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Integration tests for the admin API over live WebSocket sessions
+
+mod common;
+
+use common::{Socket, connect_socket, free_port, next_json};
+use futures::{SinkExt, StreamExt};
+use mocopr_core::Result;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::admin::AdminApi;
+use mocopr_server::auth::ApiKeyAuthenticator;
+use mocopr_server::handlers::ToolHandler;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+struct SleepTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for SleepTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("sleep", json!({"type": "object"}))
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let millis = arguments
+            .and_then(|args| args["millis"].as_u64())
+            .unwrap_or(0);
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+}
+
+/// Start a WebSocket server with the admin API, returning its port
+fn start_server() -> anyhow::Result<u16> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Observable Server", "1.0.0")
+        .with_tools()
+        .with_tool(SleepTool)
+        .with_admin_api(AdminApi::new(
+            ApiKeyAuthenticator::new().with_key("admin-secret", "operator"),
+        ))
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+    Ok(port)
+}
+
+async fn connect(port: u16) -> Socket {
+    connect_socket(
+        port,
+        json!({"name": "inspected", "version": "2.1.0"}),
+        json!({"roots": {"listChanged": true}}),
+    )
+    .await
+}
+
+async fn call_sleep(socket: &mut Socket, id: i64, millis: u64) {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": {"name": "sleep", "arguments": {"millis": millis}}
+    });
+    socket
+        .send(Message::Text(request.to_string()))
+        .await
+        .unwrap();
+}
+
+async fn sessions(port: u16) -> anyhow::Result<Vec<Value>> {
+    let response = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{port}/admin/sessions"))
+        .bearer_auth("admin-secret")
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    Ok(response.json().await?)
+}
+
+async fn admin_delete(port: u16, path: &str) -> anyhow::Result<u16> {
+    let response = reqwest::Client::new()
+        .delete(format!("http://127.0.0.1:{port}/admin/sessions/{path}"))
+        .bearer_auth("admin-secret")
+        .send()
+        .await?;
+    Ok(response.status().as_u16())
+}
+
+#[tokio::test]
+async fn test_list_sessions_and_cancel_requests() -> anyhow::Result<()> {
+    let port = start_server()?;
+    let mut socket = connect(port).await;
+    call_sleep(&mut socket, 5, 10_000).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let listed = sessions(port).await?;
+    assert_eq!(listed.len(), 1);
+    let session = &listed[0];
+    assert_eq!(session["transport"], json!("websocket"));
+    assert_eq!(session["client_info"]["name"], json!("inspected"));
+    assert_eq!(session["client_info"]["version"], json!("2.1.0"));
+    assert_eq!(
+        session["protocol_version"],
+        json!(mocopr_core::protocol::PROTOCOL_VERSION)
+    );
+    assert_eq!(session["capabilities"]["roots"]["listChanged"], json!(true));
+    assert!(
+        session["client_addr"]
+            .as_str()
+            .unwrap()
+            .starts_with("127.0.0.1:")
+    );
+    assert!(session["last_activity"].is_string());
+    let requests = session["requests"].as_array().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["id"], json!(5));
+    assert_eq!(requests[0]["method"], json!("tools/call"));
+    assert_eq!(requests[0]["tool"], json!("sleep"));
+    assert!(requests[0]["duration_ms"].as_u64().unwrap() >= 50);
+
+    let id = session["id"].as_str().unwrap();
+    assert_eq!(admin_delete(port, &format!("{id}/requests/5")).await?, 204);
+    let response = next_json(&mut socket).await.unwrap();
+    assert_eq!(response["id"], json!(5));
+    assert!(response.get("error").is_some(), "{response}");
+    assert_eq!(admin_delete(port, &format!("{id}/requests/5")).await?, 404);
+    assert!(
+        sessions(port).await?[0]["requests"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+
+    // The session keeps serving requests
+    call_sleep(&mut socket, 6, 0).await;
+    let response = next_json(&mut socket).await.unwrap();
+    assert_eq!(response["id"], json!(6));
+    assert!(response.get("result").is_some(), "{response}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_disconnect_session() -> anyhow::Result<()> {
+    let port = start_server()?;
+    let mut socket = connect(port).await;
+    let id = sessions(port).await?[0]["id"].as_str().unwrap().to_string();
+
+    assert_eq!(admin_delete(port, &id).await?, 204);
+    assert!(matches!(
+        tokio::time::timeout(Duration::from_secs(5), socket.next()).await?,
+        Some(Ok(Message::Close(_))) | None
+    ));
+
+    for _ in 0..50 {
+        if sessions(port).await?.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(sessions(port).await?.is_empty());
+    assert_eq!(admin_delete(port, &id).await?, 404);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_api_requires_its_own_credentials() -> anyhow::Result<()> {
+    let port = start_server()?;
+    let _socket = connect(port).await;
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{port}/admin/sessions");
+
+    let response = client.get(&url).send().await?;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = client.get(&url).bearer_auth("wrong").send().await?;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = client
+        .delete(format!("{url}/anything"))
+        .header("x-api-key", "wrong")
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(&url)
+        .header("x-api-key", "admin-secret")
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Integration tests for audit records written by the RBAC middleware
+
+mod common;
+
+use anyhow::Result;
+use mocopr_core::prelude::*;
+use mocopr_core::protocol::Extensions;
+use mocopr_rbac::RbacMiddleware;
+use mocopr_server::McpServerBuilder;
+use mocopr_server::audit::{
+    AuditDecision, AuditOutcome, JsonlAuditSink, MemoryAuditSink, hash_arguments, verify_audit_logs,
+};
+use mocopr_server::context::Principal;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::middleware::Middleware;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn tool_call(id: i64, tool: &str) -> JsonRpcRequest {
+    JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "tools/call".to_string(),
+        params: Some(json!({
+            "name": tool,
+            "arguments": {"query": "status"},
+        })),
+        id: Some(RequestId::Number(id)),
+    }
+}
+
+/// State of a session authenticated as `subject`
+fn session(subject: &str) -> Extensions {
+    let extensions = Extensions::new();
+    extensions.insert(Principal::new(subject));
+    extensions
+}
+
+fn response(id: i64, error: Option<&str>) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Some(RequestId::Number(id)),
+        result: error.is_none().then(|| json!({"content": []})),
+        error: error.map(|message| JsonRpcError {
+            code: -32603,
+            message: message.to_string(),
+            data: None,
+        }),
+    }
+}
+
+async fn middleware(sink: Arc<dyn mocopr_server::audit::AuditSink>) -> Result<RbacMiddleware> {
+    Ok(RbacMiddleware::builder()
+        .with_role("analyst", &["call:tools"])
+        .with_assignment("alice", "analyst")
+        .with_audit_sink(sink)
+        .build()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_decisions_and_outcomes_are_recorded() -> Result<()> {
+    let sink = Arc::new(MemoryAuditSink::new());
+    let rbac = middleware(sink.clone()).await?;
+
+    let alice = session("alice");
+    let allowed = tool_call(1, "report");
+    alice.clone().scope(rbac.before_request(&allowed)).await?;
+    assert!(sink.records().is_empty(), "recorded once the request ends");
+    alice
+        .clone()
+        .scope(rbac.after_response(&allowed, &response(1, None)))
+        .await?;
+
+    let failed = tool_call(2, "report");
+    alice.clone().scope(rbac.before_request(&failed)).await?;
+    alice
+        .scope(rbac.after_response(&failed, &response(2, Some("report unavailable"))))
+        .await?;
+
+    let denied = tool_call(3, "report");
+    let mallory = session("mallory");
+    assert!(mallory.scope(rbac.before_request(&denied)).await.is_err());
+
+    let records = sink.records();
+    assert_eq!(records.len(), 3);
+
+    assert_eq!(records[0].principal, "alice");
+    assert_eq!(records[0].method, "tools/call");
+    assert_eq!(records[0].target.as_deref(), Some("report"));
+    assert_eq!(records[0].request_id, Some(RequestId::Number(1)));
+    assert_eq!(
+        records[0].arguments_hash.as_deref(),
+        Some(hash_arguments(allowed.params.as_ref().unwrap())).as_deref()
+    );
+    assert_eq!(records[0].decision, AuditDecision::Allowed);
+    assert_eq!(records[0].outcome, AuditOutcome::Success);
+
+    assert_eq!(records[1].decision, AuditDecision::Allowed);
+    assert_eq!(records[1].outcome, AuditOutcome::Error);
+    assert_eq!(records[1].error.as_deref(), Some("report unavailable"));
+
+    assert_eq!(records[2].principal, "mallory");
+    assert_eq!(records[2].decision, AuditDecision::Denied);
+    assert_eq!(records[2].outcome, AuditOutcome::Rejected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failure_in_a_later_middleware_is_recorded() -> Result<()> {
+    let sink = Arc::new(MemoryAuditSink::new());
+    let rbac = middleware(sink.clone()).await?;
+
+    let alice = session("alice");
+    let request = tool_call(1, "report");
+    alice.clone().scope(rbac.before_request(&request)).await?;
+    alice
+        .scope(rbac.on_error(&request, &Error::internal("rate limited")))
+        .await?;
+
+    let records = sink.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].decision, AuditDecision::Allowed);
+    assert_eq!(records[0].outcome, AuditOutcome::Error);
+    assert!(
+        records[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("rate limited")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_claimed_subjects_are_not_recorded_as_the_principal() -> Result<()> {
+    let sink = Arc::new(MemoryAuditSink::new());
+    let rbac = RbacMiddleware::builder()
+        .with_role("analyst", &["call:tools"])
+        .with_assignment("alice", "analyst")
+        .with_audit_sink(sink.clone())
+        .with_message_subjects(true)
+        .build()
+        .await?;
+
+    // A stdio client claiming to be alice, as allowed by the opt-in
+    let mut request = tool_call(1, "report");
+    request.params.as_mut().unwrap()["auth"] = json!({"subject_id": "alice"});
+    rbac.before_request(&request).await?;
+    rbac.after_response(&request, &response(1, None)).await?;
+
+    // An authenticated client claiming to be someone else
+    let mut request = tool_call(2, "report");
+    request.params.as_mut().unwrap()["auth"] = json!({"subject_id": "bob"});
+    let alice = session("alice");
+    alice.clone().scope(rbac.before_request(&request)).await?;
+    alice
+        .scope(rbac.after_response(&request, &response(2, None)))
+        .await?;
+
+    let records = sink.records();
+    assert_eq!(records[0].principal, "anonymous");
+    assert_eq!(records[0].claimed_subject.as_deref(), Some("alice"));
+    assert_eq!(records[1].principal, "alice");
+    assert_eq!(records[1].claimed_subject.as_deref(), Some("bob"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jsonl_log_is_chained_across_rotations() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("audit.jsonl");
+    let sink = Arc::new(JsonlAuditSink::open(&path)?.with_max_bytes(512));
+    let rbac = middleware(sink.clone()).await?;
+
+    for id in 1..=6 {
+        let subject = if id % 2 == 0 { "alice" } else { "mallory" };
+        let request = tool_call(id, "report");
+        let session = session(subject);
+        if session
+            .clone()
+            .scope(rbac.before_request(&request))
+            .await
+            .is_ok()
+        {
+            session
+                .scope(rbac.after_response(&request, &response(id, None)))
+                .await?;
+        }
+    }
+
+    let files = sink.files()?;
+    assert!(files.len() > 1, "log should have rotated: {files:?}");
+    let verification = verify_audit_logs(&files, None)?;
+    assert_eq!(verification.records, 6);
+    assert_eq!(verification.last_sequence, 6);
+
+    // Reopening continues the chain
+    let sink = Arc::new(JsonlAuditSink::open(&path)?.with_max_bytes(512));
+    let rbac = middleware(sink.clone()).await?;
+    assert!(
+        session("mallory")
+            .scope(rbac.before_request(&tool_call(7, "report")))
+            .await
+            .is_err()
+    );
+    assert_eq!(verify_audit_logs(&sink.files()?, None)?.records, 7);
+    Ok(())
+}
+
+/// Never finishes
+struct HangTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for HangTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("hang", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> mocopr_core::Result<ToolsCallResponse> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_request_is_recorded() -> Result<()> {
+    let sink = Arc::new(MemoryAuditSink::new());
+    let rbac = RbacMiddleware::builder()
+        .with_role("analyst", &["call:tools"])
+        .with_assignment("anonymous", "analyst")
+        .with_audit_sink(sink.clone())
+        .build()
+        .await?;
+
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Audited Server", "1.0.0")
+        .with_tools()
+        .with_tool(HangTool)
+        .with_middleware(rbac)
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    let client = common::connect(port).await;
+
+    // Dropping the call sends notifications/cancelled
+    let call = client.call_tool("hang".to_string(), None);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), call)
+            .await
+            .is_err()
+    );
+
+    let record = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(record) = sink.records().pop() {
+                return record;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("cancelled request was not recorded");
+    assert_eq!(record.target.as_deref(), Some("hang"));
+    assert_eq!(record.decision, AuditDecision::Allowed);
+    assert_eq!(record.outcome, AuditOutcome::Cancelled);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_truncation_is_detected_with_a_known_head() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("audit.jsonl");
+    let sink = Arc::new(JsonlAuditSink::open(&path)?);
+    let rbac = middleware(sink.clone()).await?;
+
+    for id in 1..=3 {
+        assert!(
+            session("mallory")
+                .scope(rbac.before_request(&tool_call(id, "report")))
+                .await
+                .is_err()
+        );
+    }
+    let head = verify_audit_logs(&sink.files()?, None)?.last_hash;
+    let original = std::fs::read_to_string(&path)?;
+
+    // Cutting the last record leaves a valid chain that lacks the known head
+    let lines: Vec<&str> = original.lines().collect();
+    std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[1]))?;
+    assert!(verify_audit_logs(&sink.files()?, None).is_ok());
+    assert!(verify_audit_logs(&sink.files()?, Some(&head)).is_err());
+
+    // Records appended after the known head are fine
+    std::fs::write(&path, &original)?;
+    let sink = Arc::new(JsonlAuditSink::open(&path)?);
+    let rbac = middleware(sink.clone()).await?;
+    assert!(
+        session("mallory")
+            .scope(rbac.before_request(&tool_call(4, "report")))
+            .await
+            .is_err()
+    );
+    assert_eq!(verify_audit_logs(&sink.files()?, Some(&head))?.records, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampering_is_detected() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("audit.jsonl");
+    let sink = Arc::new(JsonlAuditSink::open(&path)?);
+    let rbac = middleware(sink.clone()).await?;
+
+    for id in 1..=3 {
+        assert!(
+            session("mallory")
+                .scope(rbac.before_request(&tool_call(id, "report")))
+                .await
+                .is_err()
+        );
+    }
+    verify_audit_logs(&sink.files()?, None)?;
+    let original = std::fs::read_to_string(&path)?;
+
+    // Rewriting a decision
+    std::fs::write(&path, original.replacen("\"denied\"", "\"allowed\"", 1))?;
+    let error = verify_audit_logs(&sink.files()?, None).unwrap_err();
+    assert_eq!(error.data().unwrap()["line"], json!(1));
+
+    // Dropping a record
+    let lines: Vec<&str> = original.lines().collect();
+    std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2]))?;
+    let error = verify_audit_logs(&sink.files()?, None).unwrap_err();
+    assert_eq!(error.data().unwrap()["line"], json!(2));
+
+    // An edited record with a forged hash
+    let mut entry: Value = serde_json::from_str(lines[1])?;
+    entry["principal"] = json!("alice");
+    entry["hash"] = json!("f".repeat(64));
+    std::fs::write(&path, format!("{}\n{}\n{}\n", lines[0], entry, lines[2]))?;
+    assert!(verify_audit_logs(&sink.files()?, None).is_err());
+    Ok(())
+}
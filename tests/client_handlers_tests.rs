@@ -0,0 +1,164 @@
+//! Integration tests for client handlers of requests sent by the server
+
+use futures::{SinkExt, StreamExt};
+use mocopr_client::McpClientBuilder;
+use mocopr_client::handlers::SamplingHandler;
+use mocopr_core::prelude::*;
+use serde_json::{Value, json};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Answers with the reversed text of the last message
+struct ReversingModel;
+
+#[async_trait::async_trait]
+impl SamplingHandler for ReversingModel {
+    async fn create_message(&self, request: CreateMessageRequest) -> Result<CreateMessageResponse> {
+        let Some(Content::Text(text)) = request.messages.last().map(|m| &m.content) else {
+            return Err(Error::InvalidRequest("Expected a text message".to_string()));
+        };
+        Ok(CreateMessageResponse {
+            content: Content::Text(TextContent::new(
+                text.text.chars().rev().collect::<String>(),
+            )),
+            model: "reverse-1".to_string(),
+            stop_reason: Some(StopReason::EndTurn),
+            role: MessageRole::Assistant,
+            meta: ResponseMetadata { _meta: None },
+        })
+    }
+}
+
+/// Accept one client and complete its handshake, returning the socket and
+/// the capabilities the client advertised
+async fn accept(listener: TcpListener) -> (WebSocketStream<TcpStream>, Value) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+    let initialize = next_json(&mut socket).await;
+    assert_eq!(initialize["method"], json!("initialize"));
+    let response = json!({
+        "jsonrpc": "2.0",
+        "id": initialize["id"],
+        "result": {
+            "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+            "capabilities": {},
+            "serverInfo": {"name": "Scripted Server", "version": "1.0.0"}
+        }
+    });
+    socket
+        .send(Message::Text(response.to_string()))
+        .await
+        .unwrap();
+    (socket, initialize["params"]["capabilities"].clone())
+}
+
+async fn next_json(socket: &mut WebSocketStream<TcpStream>) -> Value {
+    loop {
+        if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+/// Send a request to the client and wait for its response
+async fn request(
+    socket: &mut WebSocketStream<TcpStream>,
+    id: &str,
+    method: &str,
+    params: Value,
+) -> Value {
+    let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+    socket
+        .send(Message::Text(request.to_string()))
+        .await
+        .unwrap();
+    loop {
+        let message = next_json(socket).await;
+        if message["id"] == json!(id) {
+            return message;
+        }
+    }
+}
+
+async fn listen() -> anyhow::Result<(TcpListener, String)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("ws://{}", listener.local_addr()?);
+    Ok((listener, url))
+}
+
+#[tokio::test]
+async fn test_sampling_and_roots_requests_are_answered() -> anyhow::Result<()> {
+    let (listener, url) = listen().await?;
+    let server = tokio::spawn(accept(listener));
+
+    let _client = McpClientBuilder::new()
+        .with_info("Sampling Client".to_string(), "1.0.0".to_string())
+        .with_sampling_handler(ReversingModel)
+        .with_roots(true)
+        .with_roots_provider(vec![Root {
+            uri: url::Url::parse("file:///workspace/project")?,
+            name: Some("project".to_string()),
+        }])
+        .connect_websocket(&url)
+        .await?;
+    let (mut socket, capabilities) = server.await?;
+    assert_eq!(capabilities["sampling"], json!({}));
+    assert_eq!(capabilities["roots"]["listChanged"], json!(true));
+
+    let response = request(
+        &mut socket,
+        "sample-1",
+        "sampling/createMessage",
+        json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "hello"}}],
+            "maxTokens": 16
+        }),
+    )
+    .await;
+    let result = &response["result"];
+    assert_eq!(result["content"]["text"], json!("olleh"), "{response}");
+    assert_eq!(result["model"], json!("reverse-1"));
+    assert_eq!(result["role"], json!("assistant"));
+
+    // Errors from the handler are sent back to the server
+    let response = request(
+        &mut socket,
+        "sample-2",
+        "sampling/createMessage",
+        json!({"messages": []}),
+    )
+    .await;
+    assert!(response.get("error").is_some(), "{response}");
+
+    let response = request(&mut socket, "roots-1", "roots/list", json!({})).await;
+    assert_eq!(
+        response["result"]["roots"],
+        json!([{"uri": "file:///workspace/project", "name": "project"}]),
+        "{response}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_requests_without_handlers_are_rejected() -> anyhow::Result<()> {
+    let (listener, url) = listen().await?;
+    let server = tokio::spawn(accept(listener));
+
+    let _client = McpClientBuilder::new()
+        .with_info("Plain Client".to_string(), "1.0.0".to_string())
+        .connect_websocket(&url)
+        .await?;
+    let (mut socket, capabilities) = server.await?;
+    assert!(capabilities.get("sampling").is_none());
+
+    for (id, method) in [("1", "sampling/createMessage"), ("2", "roots/list")] {
+        let response = request(&mut socket, id, method, json!({"messages": []})).await;
+        assert_eq!(
+            response["error"]["code"],
+            json!(mocopr_core::protocol::error_codes::METHOD_NOT_FOUND),
+            "{response}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Integration tests for the typed notification streams of the client
+
+mod common;
+
+use futures::StreamExt;
+use mocopr_client::McpClientBuilder;
+use mocopr_client::notifications::{NotificationKind, ServerNotification};
+use mocopr_core::prelude::*;
+use mocopr_server::McpServerBuilder;
+use mocopr_server::notifications::NotificationSender;
+use serde_json::json;
+use std::time::Duration;
+
+async fn connect() -> anyhow::Result<(mocopr_client::McpClient, NotificationSender)> {
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Chatty Server", "1.0.0")
+        .with_tools()
+        .with_resources()
+        .with_prompts()
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    let notifications = server.notifications().clone();
+    tokio::spawn(async move { server.run().await });
+
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+    let client = common::retry(|| {
+        McpClientBuilder::new()
+            .with_info("Listening Client".to_string(), "1.0.0".to_string())
+            .connect_websocket(&url)
+    })
+    .await;
+    Ok((client, notifications))
+}
+
+#[tokio::test]
+async fn test_typed_notifications_reach_every_subscriber() -> anyhow::Result<()> {
+    let (client, server) = connect().await?;
+    let mut first = client.notifications();
+    let mut second = client.notifications();
+    let mut tools = client.notifications_of([NotificationKind::ToolsListChanged]);
+
+    let uri = url::Url::parse("file:///reports/daily.txt")?;
+    server.resource_updated(&uri);
+    server.resource_list_changed();
+    server.prompt_list_changed();
+    server.send(Protocol::create_notification(
+        "notifications/message",
+        Some(json!({"level": "warning", "data": "disk almost full", "logger": "storage"})),
+    ));
+    server.send(Protocol::create_notification(
+        "notifications/custom",
+        Some(json!({"anything": true})),
+    ));
+    for _ in 0..100 {
+        server.tool_list_changed();
+    }
+
+    for stream in [&mut first, &mut second] {
+        let received: Vec<ServerNotification> = stream.by_ref().take(105).collect().await;
+        match &received[0] {
+            ServerNotification::ResourceUpdated(updated) => assert_eq!(updated.uri, uri),
+            other => panic!("unexpected notification: {other:?}"),
+        }
+        assert!(matches!(
+            received[1],
+            ServerNotification::ResourcesListChanged
+        ));
+        assert!(matches!(
+            received[2],
+            ServerNotification::PromptsListChanged
+        ));
+        match &received[3] {
+            ServerNotification::LogMessage(log) => {
+                assert!(matches!(log.level, LogLevel::Warning));
+                assert_eq!(log.data, json!("disk almost full"));
+                assert_eq!(log.logger.as_deref(), Some("storage"));
+            }
+            other => panic!("unexpected notification: {other:?}"),
+        }
+        match &received[4] {
+            ServerNotification::Other(notification) => {
+                assert_eq!(notification.method, "notifications/custom")
+            }
+            other => panic!("unexpected notification: {other:?}"),
+        }
+        assert!(
+            received[5..]
+                .iter()
+                .all(|n| n.kind() == NotificationKind::ToolsListChanged)
+        );
+    }
+
+    let received: Vec<ServerNotification> = tools.by_ref().take(100).collect().await;
+    assert_eq!(received.len(), 100);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streams_end_when_the_connection_closes() -> anyhow::Result<()> {
+    let (client, server) = connect().await?;
+    let mut notifications = client.notifications();
+    let mut prompts = client.notifications_of([NotificationKind::PromptsListChanged]);
+
+    server.tool_list_changed();
+    assert!(matches!(
+        notifications.next().await,
+        Some(ServerNotification::ToolsListChanged)
+    ));
+
+    client.close().await?;
+    let ended = tokio::time::timeout(Duration::from_secs(5), async {
+        (notifications.next().await, prompts.next().await)
+    })
+    .await?;
+    assert!(matches!(ended, (None, None)));
+    Ok(())
+}
@@ -0,0 +1,291 @@
+//! Integration tests for the command tool handler
+
+mod common;
+
+use mocopr_core::Error;
+use mocopr_core::security::SecurityValidator;
+use mocopr_core::types::Content;
+use mocopr_server::McpServerBuilder;
+use mocopr_server::handlers::{CommandToolHandler, ToolHandler};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn text(content: &Content) -> &str {
+    match content {
+        Content::Text(text) => &text.text,
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_command_result_and_environment() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let tool = CommandToolHandler::new(
+        "report",
+        "sh",
+        [
+            "-c",
+            "echo \"$GREETING ${HOME:-no home} $(pwd)\"; echo oops >&2; exit {{ code }}",
+        ],
+    )
+    .with_working_dir(dir.path())
+    .with_env("GREETING", "hi");
+
+    let response = tool.call(Some(json!({"code": 3}))).await?;
+    assert_eq!(response.is_error, Some(true));
+
+    // Only PATH and the explicitly set variables reach the program
+    let cwd = std::fs::canonicalize(dir.path())?;
+    let stdout = format!("hi no home {}\n", cwd.display());
+    assert_eq!(text(&response.content[0]), stdout);
+    assert_eq!(text(&response.content[1]), "oops\n");
+    assert!(text(&response.content[2]).contains("failed"));
+
+    let structured = response.structured_content.expect("structured content");
+    assert_eq!(structured["stdout"], stdout);
+    assert_eq!(structured["stderr"], "oops\n");
+    assert_eq!(structured["exitCode"], 3);
+    assert_eq!(structured["timedOut"], false);
+    assert_eq!(structured["truncated"], false);
+
+    let response = tool.call(Some(json!({"code": 0}))).await?;
+    assert_eq!(response.is_error, Some(false));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_timeout_and_output_cap() -> anyhow::Result<()> {
+    let tool = CommandToolHandler::new("slow", "sh", ["-c", "echo started; sleep 10"])
+        .with_timeout(Duration::from_millis(300));
+
+    let start = Instant::now();
+    let response = tool.call(None).await?;
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(response.is_error, Some(true));
+    let structured = response.structured_content.expect("structured content");
+    assert_eq!(structured["timedOut"], true);
+    assert_eq!(structured["exitCode"], json!(null));
+    // Output written before the timeout is kept
+    assert_eq!(structured["stdout"], "started\n");
+
+    let tool = CommandToolHandler::new("count", "seq", ["1", "100000"]).with_max_output(100);
+    let response = tool.call(None).await?;
+    assert_eq!(response.is_error, Some(false));
+    let structured = response.structured_content.expect("structured content");
+    assert_eq!(structured["truncated"], true);
+    assert_eq!(structured["stdout"].as_str().unwrap().len(), 100);
+    assert!(structured["stdout"].as_str().unwrap().starts_with("1\n2\n"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_path_arguments() -> anyhow::Result<()> {
+    let root = TempDir::new()?;
+    std::fs::write(root.path().join("notes.txt"), "inside")?;
+    let outside = TempDir::new()?;
+    std::fs::write(outside.path().join("secret.txt"), "outside")?;
+
+    let tool = CommandToolHandler::new("cat", "cat", ["{{ file }}"])
+        .with_working_dir(root.path())
+        .with_path_argument("file")
+        .with_security_validator(
+            SecurityValidator::new()
+                .with_root_directory(root.path().to_path_buf())
+                .with_allowed_extensions(vec!["txt".to_string()]),
+        );
+
+    let response = tool.call(Some(json!({"file": "notes.txt"}))).await?;
+    assert_eq!(text(&response.content[0]), "inside");
+
+    let escape = outside.path().join("secret.txt");
+    for file in [
+        json!(escape.to_string_lossy()),
+        json!("../secret.txt"),
+        json!(["notes.txt", escape.to_string_lossy()]),
+    ] {
+        let result = tool.call(Some(json!({ "file": file }))).await;
+        assert!(matches!(result, Err(Error::Security(_))), "{result:?}");
+    }
+
+    let result = tool.call(Some(json!({"file": 7}))).await;
+    assert!(matches!(result, Err(Error::InvalidParams(_))), "{result:?}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_paths_stay_in_working_dir() -> anyhow::Result<()> {
+    let parent = TempDir::new()?;
+    let root = parent.path().join("root");
+    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    std::fs::create_dir_all(parent.path().join("data"))?;
+    std::fs::write(parent.path().join("data/file.txt"), "outside")?;
+    // A sibling with the same name as the escaped target must not be used
+    std::fs::create_dir_all(root.join("data"))?;
+    std::fs::write(root.join("data/file.txt"), "inside")?;
+
+    let tool = CommandToolHandler::new("echo", "echo", ["{{ path }}"])
+        .with_working_dir(&root)
+        .with_path_argument("path");
+    let canonical = std::fs::canonicalize(&root)?;
+
+    // Traversal is rejected rather than normalized away
+    for path in [
+        "../data/file.txt",
+        "src/../../data/file.txt",
+        "../../data/file.txt",
+    ] {
+        let result = tool.call(Some(json!({ "path": path }))).await;
+        assert!(
+            matches!(result, Err(Error::Security(_))),
+            "{path}: {result:?}"
+        );
+    }
+
+    // Any file type, directories and not yet existing outputs are accepted,
+    // and the program receives the resolved path
+    for (path, resolved) in [
+        ("src/main.rs", canonical.join("src/main.rs")),
+        ("src", canonical.join("src")),
+        (".", canonical.clone()),
+        ("out/report.json", canonical.join("out/report.json")),
+    ] {
+        let response = tool.call(Some(json!({ "path": path }))).await?;
+        assert_eq!(
+            text(&response.content[0]).trim_end(),
+            resolved.to_string_lossy(),
+            "{path}"
+        );
+    }
+
+    // Symbolic links leading out of the working directory are followed
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(parent.path().join("data"), root.join("link"))?;
+        let result = tool.call(Some(json!({"path": "link/file.txt"}))).await;
+        assert!(matches!(result, Err(Error::Security(_))), "{result:?}");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_command_timeout_kills_process_group() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let marker = dir.path().join("marker");
+    // The background child would create the marker after the timeout
+    let script = format!("(sleep 1; touch '{}') & wait", marker.display());
+    let tool = CommandToolHandler::new("spawner", "sh", ["-c", script.as_str()])
+        .with_timeout(Duration::from_millis(200));
+
+    let response = tool.call(None).await?;
+    let structured = response.structured_content.expect("structured content");
+    assert_eq!(structured["timedOut"], true);
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!marker.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_arrays_expand_and_options_are_rejected() -> anyhow::Result<()> {
+    let root = TempDir::new()?;
+    let canonical = std::fs::canonicalize(root.path())?;
+    let tool = CommandToolHandler::new("show", "printf", ["%s\\n", "{{ words }}", "{{ files }}"])
+        .with_working_dir(root.path())
+        .with_path_argument("files");
+
+    // Each item becomes its own program argument, spaces included
+    let response = tool
+        .call(Some(
+            json!({"words": ["a b", "c"], "files": ["x.txt", "y.txt"]}),
+        ))
+        .await?;
+    let expected = format!(
+        "a b\nc\n{}\n{}\n",
+        canonical.join("x.txt").display(),
+        canonical.join("y.txt").display()
+    );
+    assert_eq!(text(&response.content[0]), expected);
+
+    // Values cannot smuggle in options, whether alone or in arrays
+    for words in [json!("-v"), json!(["ok", "--help"])] {
+        let result = tool
+            .call(Some(json!({"words": words, "files": "x.txt"})))
+            .await;
+        assert!(matches!(result, Err(Error::InvalidParams(_))), "{result:?}");
+    }
+
+    // Dashes are fine where the template itself starts an option
+    let tool = CommandToolHandler::new("echo", "echo", ["--x={{ value }}"]);
+    let response = tool.call(Some(json!({"value": "-1"}))).await?;
+    assert_eq!(text(&response.content[0]), "--x=-1\n");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_command_background_children_end_with_the_call() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let marker = dir.path().join("marker");
+    // The program exits at once, leaving a child that would create the marker
+    let script = format!("(sleep 1; touch '{}') >/dev/null 2>&1 &", marker.display());
+    let tool = CommandToolHandler::new("detach", "sh", ["-c", script.as_str()]);
+
+    let response = tool.call(None).await?;
+    assert_eq!(response.is_error, Some(false));
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!marker.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_streams_progress() -> anyhow::Result<()> {
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Command Server", "1.0.0")
+        .with_tools()
+        .with_tool(
+            CommandToolHandler::new("lines", "printf", ["first\\nsecond\\nthird\\n"])
+                .with_progress_interval(Duration::ZERO),
+        )
+        .with_tool(CommandToolHandler::new("count", "seq", ["1", "1000"]))
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    let client = common::connect(port).await;
+
+    let mut progress = Vec::new();
+    let response = client
+        .call_tool_with_progress("lines".to_string(), None, |notification| {
+            progress.push((notification.progress, notification.message));
+        })
+        .await?;
+
+    assert_eq!(
+        progress,
+        [
+            (1.0, Some("first".to_string())),
+            (2.0, Some("second".to_string())),
+            (3.0, Some("third".to_string())),
+        ]
+    );
+    let structured = response.structured_content.expect("structured content");
+    assert_eq!(structured["stdout"], "first\nsecond\nthird\n");
+
+    // Fast output is throttled, but the last line is always reported
+    let mut progress = Vec::new();
+    client
+        .call_tool_with_progress("count".to_string(), None, |notification| {
+            progress.push((notification.progress, notification.message));
+        })
+        .await?;
+    assert!(progress.len() < 100, "{} notifications", progress.len());
+    assert_eq!(progress[0], (1.0, Some("1".to_string())));
+    assert_eq!(progress.last(), Some(&(1000.0, Some("1000".to_string()))));
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test file includes this module with `mod common;` and uses only part
+//! of it.
+
+#![allow(dead_code)]
+
+use mocopr_client::McpClient;
+use mocopr_core::types::{ClientCapabilities, Implementation};
+use std::future::Future;
+use std::time::Duration;
+
+/// A raw WebSocket connection to a test server
+pub type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Get a local port that nothing listens on
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Implementation info sent by test clients
+pub fn client_info() -> Implementation {
+    Implementation {
+        name: "test".to_string(),
+        version: "1.0.0".to_string(),
+    }
+}
+
+/// Retry `attempt` until it succeeds, for a server that is still starting
+///
+/// Panics if it has not succeeded after about a second.
+pub async fn retry<T, E, F, Fut>(mut attempt: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    for _ in 0..50 {
+        if let Ok(value) = attempt().await {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("server did not start");
+}
+
+/// Connect an MCP client to the WebSocket server on `port`
+pub async fn connect(port: u16) -> McpClient {
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+    retry(|| McpClient::connect_websocket(&url, client_info(), ClientCapabilities::default())).await
+}
+
+/// Open a raw WebSocket to the server on `port`, without initializing a session
+pub async fn open_socket(port: u16) -> Socket {
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+    retry(|| tokio_tungstenite::connect_async(url.as_str()))
+        .await
+        .0
+}
+
+/// Open a WebSocket to the server on `port` and initialize an MCP session
+pub async fn connect_socket(
+    port: u16,
+    client_info: serde_json::Value,
+    capabilities: serde_json::Value,
+) -> Socket {
+    use futures::SinkExt;
+
+    let mut socket = open_socket(port).await;
+    let initialize = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+            "capabilities": capabilities,
+            "clientInfo": client_info
+        }
+    });
+    socket
+        .send(tokio_tungstenite::tungstenite::Message::Text(
+            initialize.to_string(),
+        ))
+        .await
+        .unwrap();
+    let response = next_json(&mut socket).await.unwrap();
+    assert!(response.get("result").is_some(), "{response}");
+    socket
+}
+
+/// Read the next text message from `socket` as JSON
+pub async fn next_json(socket: &mut Socket) -> Option<serde_json::Value> {
+    use futures::StreamExt;
+
+    match socket.next().await? {
+        Ok(tokio_tungstenite::tungstenite::Message::Text(text)) => serde_json::from_str(&text).ok(),
+        _ => None,
+    }
+}
+
+/// The server end of an in-memory connection, served with
+/// `McpServer::run_transport` in place of stdio
+pub struct ChannelTransport {
+    sender: tokio::sync::mpsc::UnboundedSender<String>,
+    receiver: tokio::sync::mpsc::UnboundedReceiver<String>,
+}
+
+#[async_trait::async_trait]
+impl mocopr_core::Transport for ChannelTransport {
+    async fn send(&mut self, message: &str) -> mocopr_core::Result<()> {
+        self.sender
+            .send(message.to_string())
+            .map_err(|_| mocopr_core::Error::ConnectionClosed)
+    }
+
+    async fn receive(&mut self) -> mocopr_core::Result<Option<String>> {
+        Ok(self.receiver.recv().await)
+    }
+
+    async fn close(&mut self) -> mocopr_core::Result<()> {
+        self.receiver.close();
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    fn transport_type(&self) -> &'static str {
+        "stdio"
+    }
+}
+
+/// The client end of an in-memory connection, speaking raw JSON-RPC
+pub struct ChannelPeer {
+    sender: tokio::sync::mpsc::UnboundedSender<String>,
+    receiver: tokio::sync::mpsc::UnboundedReceiver<String>,
+    next_id: i64,
+}
+
+impl ChannelPeer {
+    /// Send a request without waiting for its response, returning its ID
+    pub fn send_request(&mut self, method: &str, params: serde_json::Value) -> i64 {
+        self.next_id += 1;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params
+        });
+        self.sender.send(request.to_string()).unwrap();
+        self.next_id
+    }
+
+    /// Send a request and wait for its response, skipping notifications
+    pub async fn request(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let id = self.send_request(method, params);
+        loop {
+            let message = self.next_message().await.expect("connection closed");
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    /// Initialize an MCP session
+    pub async fn initialize(&mut self) {
+        let response = self
+            .request(
+                "initialize",
+                serde_json::json!({
+                    "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "1.0.0"}
+                }),
+            )
+            .await;
+        assert!(response.get("result").is_some(), "{response}");
+    }
+
+    /// Wait up to five seconds for the next message from the server
+    pub async fn next_message(&mut self) -> Option<serde_json::Value> {
+        let text = tokio::time::timeout(Duration::from_secs(5), self.receiver.recv())
+            .await
+            .ok()??;
+        Some(serde_json::from_str(&text).unwrap())
+    }
+}
+
+/// Create an in-memory connection between a server and a test client
+pub fn channel() -> (ChannelTransport, ChannelPeer) {
+    let (to_server, from_client) = tokio::sync::mpsc::unbounded_channel();
+    let (to_client, from_server) = tokio::sync::mpsc::unbounded_channel();
+    (
+        ChannelTransport {
+            sender: to_client,
+            receiver: from_client,
+        },
+        ChannelPeer {
+            sender: to_server,
+            receiver: from_server,
+            next_id: 0,
+        },
+    )
+}
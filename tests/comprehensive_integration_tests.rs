@@ -266,6 +266,9 @@ async fn verify_resource_response_structure(response: &ResourcesReadResponse) ->
                     assert!(!image_content.data.is_empty());
                     println!("Image data size: {} bytes", image_content.data.len());
                 }
+                Content::Resource(resource) => {
+                    println!("Embedded resource: {}", resource.resource.uri);
+                }
             }
         }
     }
@@ -0,0 +1,161 @@
+//! Integration tests for graceful server shutdown
+
+mod common;
+
+use common::{Socket, channel, connect_socket, free_port, next_json};
+use futures::{SinkExt, StreamExt};
+use mocopr_core::Result;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::handlers::ToolHandler;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+struct SleepTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for SleepTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("sleep", json!({"type": "object"}))
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let millis = arguments
+            .and_then(|args| args["millis"].as_u64())
+            .unwrap_or(0);
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+}
+
+async fn connect(port: u16) -> Socket {
+    connect_socket(port, json!({"name": "test", "version": "1.0.0"}), json!({})).await
+}
+
+fn call_sleep(millis: u64) -> Message {
+    Message::Text(
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"millis": millis}}
+        })
+        .to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_in_flight_request_finishes_before_shutdown() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Shutdown Server", "1.0.0")
+        .with_tools()
+        .with_tool(SleepTool)
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .with_shutdown_timeout(Duration::from_secs(5))
+        .build()?;
+    let shutdown = server.shutdown_handle();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let running = tokio::spawn(async move {
+        server
+            .run_until(async {
+                let _ = stopped.await;
+            })
+            .await
+    });
+
+    let mut socket = connect(port).await;
+    socket.send(call_sleep(200)).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stop.send(()).unwrap();
+
+    let response = next_json(&mut socket).await.expect("response");
+    assert_eq!(response["id"], 1);
+    assert!(response.get("result").is_some(), "{response}");
+    assert!(matches!(
+        socket.next().await,
+        Some(Ok(Message::Close(_))) | None
+    ));
+
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    assert!(shutdown.is_shutting_down());
+    assert_eq!(shutdown.active(), 0);
+
+    // No longer accepting connections
+    assert!(
+        tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{port}/mcp"))
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_deadline_cancels_request() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Shutdown Server", "1.0.0")
+        .with_tools()
+        .with_tool(SleepTool)
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .with_shutdown_timeout(Duration::from_millis(100))
+        .build()?;
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(async move { server.run().await });
+
+    let mut socket = connect(port).await;
+    socket.send(call_sleep(10_000)).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown.shutdown();
+
+    let response = next_json(&mut socket).await.expect("response");
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["error"]["code"], -32603);
+
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_shutdown_deadline_answers_and_flushes() -> anyhow::Result<()> {
+    let server = McpServerBuilder::new()
+        .with_info("Shutdown Server", "1.0.0")
+        .with_tools()
+        .with_tool(SleepTool)
+        .with_shutdown_timeout(Duration::from_millis(100))
+        .build()?;
+    let shutdown = server.shutdown_handle();
+    let notifications = server.notifications().clone();
+    let (transport, mut client) = channel();
+    let running = tokio::spawn(async move { server.run_transport(Box::new(transport)).await });
+    client.initialize().await;
+
+    let id = client.send_request(
+        "tools/call",
+        json!({"name": "sleep", "arguments": {"millis": 10_000}}),
+    );
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown.shutdown();
+    // Published while the request drains
+    notifications.tool_list_changed();
+
+    let mut methods = Vec::new();
+    let response = loop {
+        let message = client.next_message().await.expect("response");
+        match message.get("method") {
+            Some(method) => methods.push(method.clone()),
+            None => break message,
+        }
+    };
+    assert_eq!(response["id"], id);
+    assert_eq!(response["error"]["code"], -32603, "{response}");
+    assert_eq!(methods, ["notifications/tools/list_changed"]);
+
+    // The session stopped cleanly instead of being dropped mid-request
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    assert_eq!(shutdown.active(), 0);
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! Integration tests for declarative server manifests
+
+mod common;
+
+use common::{connect, free_port};
+use mocopr_client::McpClient;
+use mocopr_core::Error;
+use mocopr_core::types::{ClientCapabilities, Content};
+use mocopr_rbac::config::RbacConfig;
+use mocopr_server::McpServerBuilder;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_manifest(dir: &Path, port: u16) -> std::path::PathBuf {
+    std::fs::write(dir.join("notes.txt"), "remember the milk").unwrap();
+    std::fs::create_dir(dir.join("prompts")).unwrap();
+    std::fs::write(
+        dir.join("prompts/greet.md"),
+        "---\narguments: [{name: who, required: true}]\n---\nHello {{ who }}!\n",
+    )
+    .unwrap();
+
+    let manifest = dir.join("server.toml");
+    std::fs::write(
+        &manifest,
+        format!(
+            r#"
+[server]
+name = "Manifest Server"
+version = "2.0.0"
+
+[[resources]]
+type = "file"
+uri = "file:///notes.txt"
+name = "Notes"
+path = "notes.txt"
+mime_type = "text/plain"
+
+[[prompts]]
+type = "template"
+name = "summarize"
+description = "Summarize text"
+arguments = [{{ name = "text", required = true }}]
+messages = [
+    {{ role = "system", template = "You summarize." }},
+    {{ role = "user", template = "Summarize: {{{{ text }}}}" }},
+]
+
+[[prompts]]
+type = "directory"
+path = "prompts"
+
+[[tools]]
+type = "command"
+name = "echo"
+description = "Echo text"
+command = ["echo", "{{{{ text }}}}"]
+input_schema = {{ type = "object", properties = {{ text = {{ type = "string" }} }} }}
+
+[middleware]
+logging = true
+rate_limit = {{ max_requests = 1000, window_secs = 60 }}
+
+[transport]
+websocket = true
+bind = "127.0.0.1"
+port = {port}
+"#
+        ),
+    )
+    .unwrap();
+    manifest
+}
+
+fn text(content: &Content) -> &str {
+    match content {
+        Content::Text(text) => &text.text,
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_server_from_toml_manifest() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let port = free_port();
+    let server = McpServerBuilder::from_manifest(write_manifest(dir.path(), port))?.build()?;
+    tokio::spawn(async move { server.run().await });
+    let client = connect(port).await;
+
+    let response = client
+        .call_tool("echo".to_string(), Some(json!({"text": "hello; rm -rf /"})))
+        .await?;
+    assert_eq!(text(&response.content[0]), "hello; rm -rf /\n");
+
+    let resources = client.list_all_resources().await?;
+    assert_eq!(resources[0].name, "Notes");
+    let contents = client.read_resource(resources[0].uri.clone()).await?;
+    assert_eq!(text(&contents.contents[0].contents[0]), "remember the milk");
+
+    let mut prompts: Vec<_> = client
+        .list_all_prompts()
+        .await?
+        .into_iter()
+        .map(|prompt| prompt.name)
+        .collect();
+    prompts.sort();
+    assert_eq!(prompts, ["greet", "summarize"]);
+
+    let arguments = HashMap::from([("text".to_string(), "a long story".to_string())]);
+    let prompt = client
+        .get_prompt("summarize".to_string(), Some(arguments))
+        .await?;
+    assert_eq!(prompt.messages.len(), 2);
+    assert_eq!(text(&prompt.messages[1].content), "Summarize: a long story");
+
+    let arguments = HashMap::from([("who".to_string(), "ops".to_string())]);
+    let prompt = client
+        .get_prompt("greet".to_string(), Some(arguments))
+        .await?;
+    assert_eq!(text(&prompt.messages[0].content), "Hello ops!");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_manifest_and_errors() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+
+    let manifest = dir.path().join("server.json");
+    std::fs::write(
+        &manifest,
+        json!({
+            "server": {"name": "JSON Server", "version": "1.0.0"},
+            "tools": [{"type": "command", "name": "date", "command": ["date"]}]
+        })
+        .to_string(),
+    )?;
+    let server = McpServerBuilder::from_manifest(&manifest)?.build()?;
+    assert!(server.capabilities().tools.is_some());
+
+    let unknown = dir.path().join("unknown.toml");
+    std::fs::write(
+        &unknown,
+        "[server]\nname = \"x\"\nversion = \"1\"\ncolour = \"blue\"\n",
+    )?;
+    let Err(error) = McpServerBuilder::from_manifest(&unknown) else {
+        panic!("unknown field was accepted");
+    };
+    assert!(matches!(error, Error::Configuration(_)), "{error}");
+
+    let yaml = dir.path().join("server.yaml");
+    std::fs::write(&yaml, "server: {}")?;
+    assert!(McpServerBuilder::from_manifest(&yaml).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rbac_manifest_needs_rbac_loader() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    RbacConfig::development().to_file(&dir.path().join("rbac.json").to_string_lossy())?;
+
+    let manifest = dir.path().join("server.toml");
+    std::fs::write(
+        &manifest,
+        "[server]\nname = \"Secure\"\nversion = \"1.0.0\"\n\n[middleware.rbac]\nconfig = \"rbac.json\"\n",
+    )?;
+
+    // Silently serving without the configured access control would be unsafe
+    let Err(error) = McpServerBuilder::from_manifest(&manifest) else {
+        panic!("RBAC manifest was accepted without RBAC");
+    };
+    assert!(error.to_string().contains("mocopr-rbac"), "{error}");
+
+    let server = mocopr_rbac::manifest::builder_from_manifest(&manifest)
+        .await?
+        .build()?;
+    assert_eq!(server.middleware().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rbac_manifest_with_audit_log() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    RbacConfig::development().to_file(&dir.path().join("rbac.json").to_string_lossy())?;
+
+    let manifest = dir.path().join("server.toml");
+    std::fs::write(
+        &manifest,
+        "[server]\nname = \"Secure\"\nversion = \"1.0.0\"\n\n[middleware.rbac]\nconfig = \"rbac.json\"\naudit_log = \"audit/access.jsonl\"\n",
+    )?;
+    std::fs::create_dir(dir.path().join("audit"))?;
+
+    let server = mocopr_rbac::manifest::builder_from_manifest(&manifest)
+        .await?
+        .build()?;
+    let request = mocopr_core::types::JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "tools/call".to_string(),
+        params: Some(json!({"name": "shutdown", "auth": {"subject_id": "mallory"}})),
+        id: Some(mocopr_core::types::RequestId::Number(1)),
+    };
+    assert!(
+        server.middleware()[0]
+            .before_request(&request)
+            .await
+            .is_err()
+    );
+
+    let log = dir.path().join("audit/access.jsonl");
+    let verification = mocopr_server::audit::verify_audit_log(&log)?;
+    assert_eq!(verification.records, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_manifest_enforces_rbac() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let marker = dir.path().join("ran");
+    std::fs::write(
+        dir.path().join("rbac.json"),
+        json!({
+            "default_roles": false,
+            "audit_enabled": false,
+            "cache_config": {"enabled": false, "ttl_seconds": 60, "max_entries": 100},
+            "roles": [{
+                "name": "operator",
+                "description": null,
+                "permissions": ["call:tools:echo"],
+                "conditional_permissions": [],
+                "inherits_from": []
+            }],
+            "assignments": [{
+                "subject_id": "ops",
+                "subject_type": "user",
+                "roles": ["operator"],
+                "elevation": null
+            }]
+        })
+        .to_string(),
+    )?;
+    let manifest = dir.path().join("server.json");
+    std::fs::write(
+        &manifest,
+        json!({
+            "server": {"name": "Stdio Server", "version": "1.0.0"},
+            "tools": [{
+                "type": "command",
+                "name": "wipe",
+                "command": ["touch", marker.to_string_lossy()]
+            }],
+            "middleware": {"rbac": {"config": "rbac.json"}}
+        })
+        .to_string(),
+    )?;
+
+    // Without http or websocket transports the manifest is served over stdio
+    let client = McpClient::connect_stdio(
+        env!("CARGO_BIN_EXE_mocopr-serve"),
+        &[&manifest.to_string_lossy()],
+        common::client_info(),
+        ClientCapabilities::default(),
+    )
+    .await?;
+
+    let error = client
+        .call_tool("wipe".to_string(), None)
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Protocol error: Permission denied");
+    assert!(!marker.exists(), "denied command was run");
+    client.close().await?;
+    Ok(())
+}
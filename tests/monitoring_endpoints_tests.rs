@@ -0,0 +1,204 @@
+//! Integration tests for the health, readiness and metrics endpoints
+
+mod common;
+
+use common::{channel, free_port, retry};
+use futures::{SinkExt, StreamExt};
+use mocopr_server::{McpServerBuilder, MonitoringEndpoints};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+async fn send(
+    socket: &mut tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    message: Value,
+) -> Value {
+    socket
+        .send(Message::Text(message.to_string()))
+        .await
+        .unwrap();
+    match socket.next().await {
+        Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_monitoring_endpoints() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Monitored Server", "1.0.0")
+        .with_tools()
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .with_monitoring_endpoints(MonitoringEndpoints::default().with_metrics_path("/prom"))
+        .build()?;
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(async move { server.run().await });
+
+    let base = format!("http://127.0.0.1:{port}");
+    let client = reqwest::Client::new();
+    let ready = retry(|| client.get(format!("{base}/readyz")).send()).await;
+    assert_eq!(ready.status(), 200);
+
+    let health = client.get(format!("{base}/healthz")).send().await?;
+    assert_eq!(health.status(), 200);
+    assert_eq!(health.json::<Value>().await?["status"], "Healthy");
+
+    let (mut socket, _) =
+        tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{port}/mcp")).await?;
+    let initialized = send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0.0"}
+            }
+        }),
+    )
+    .await;
+    assert!(initialized.get("result").is_some());
+    send(
+        &mut socket,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+    )
+    .await;
+    send(
+        &mut socket,
+        json!({"jsonrpc": "2.0", "id": 2, "method": "no/such"}),
+    )
+    .await;
+
+    let metrics = client.get(format!("{base}/prom")).send().await?;
+    assert_eq!(metrics.status(), 200);
+    assert!(
+        metrics.headers()["content-type"]
+            .to_str()?
+            .starts_with("text/plain; version=0.0.4")
+    );
+    let text = metrics.text().await?;
+    assert!(
+        text.contains("mcp_requests_total{method=\"ping\"} 1"),
+        "{text}"
+    );
+    assert!(text.contains("mcp_request_errors_total{method=\"other\"} 1"));
+    assert!(text.contains("mcp_request_duration_seconds_count{method=\"ping\"} 1"));
+    assert!(text.contains("mcp_active_sessions 1"));
+
+    // Default path is no longer served once replaced
+    assert_eq!(
+        client.get(format!("{base}/metrics")).send().await?.status(),
+        404
+    );
+
+    drop(socket);
+    shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_count_stdio_sessions_and_requests() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = Arc::new(
+        McpServerBuilder::new()
+            .with_info("Monitored Server", "1.0.0")
+            .with_tools()
+            .with_bind_address("127.0.0.1", port)
+            .with_websocket_transport()
+            .with_monitoring_endpoints(MonitoringEndpoints::default())
+            .build()?,
+    );
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn({
+        let server = server.clone();
+        async move { server.run().await }
+    });
+
+    let (transport, mut peer) = channel();
+    let stdio = tokio::spawn({
+        let server = server.clone();
+        async move { server.run_transport(Box::new(transport)).await }
+    });
+    peer.initialize().await;
+    let pong = peer.request("ping", json!({})).await;
+    assert!(pong.get("result").is_some(), "{pong}");
+
+    let base = format!("http://127.0.0.1:{port}");
+    let client = reqwest::Client::new();
+    let text = retry(|| client.get(format!("{base}/metrics")).send())
+        .await
+        .text()
+        .await?;
+    assert!(text.contains("mcp_active_sessions 1"), "{text}");
+    assert!(
+        text.contains("mcp_requests_total{method=\"ping\"} 1"),
+        "{text}"
+    );
+
+    drop(peer);
+    tokio::time::timeout(Duration::from_secs(2), stdio).await???;
+    let text = client
+        .get(format!("{base}/metrics"))
+        .send()
+        .await?
+        .text()
+        .await?;
+    assert!(text.contains("mcp_active_sessions 0"), "{text}");
+
+    shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_methods_share_one_label() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = Arc::new(
+        McpServerBuilder::new()
+            .with_info("Monitored Server", "1.0.0")
+            .with_bind_address("127.0.0.1", port)
+            .with_websocket_transport()
+            .with_monitoring_endpoints(MonitoringEndpoints::default())
+            .build()?,
+    );
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn({
+        let server = server.clone();
+        async move { server.run().await }
+    });
+
+    let (transport, mut peer) = channel();
+    tokio::spawn({
+        let server = server.clone();
+        async move { server.run_transport(Box::new(transport)).await }
+    });
+    peer.initialize().await;
+    for index in 0..20 {
+        let response = peer.request(&format!("made/up/{index}"), json!({})).await;
+        assert!(response.get("error").is_some(), "{response}");
+    }
+
+    let base = format!("http://127.0.0.1:{port}");
+    let client = reqwest::Client::new();
+    let text = retry(|| client.get(format!("{base}/metrics")).send())
+        .await
+        .text()
+        .await?;
+    assert!(
+        text.contains("mcp_requests_total{method=\"other\"} 20"),
+        "{text}"
+    );
+    assert!(!text.contains("made/up"), "{text}");
+
+    shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(2), running).await???;
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! Integration tests for tools generated from OpenAPI documents
+
+mod common;
+
+use axum::extract::{Path as UrlPath, RawQuery};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use mocopr_core::Error;
+use mocopr_core::types::Content;
+use mocopr_server::McpServerBuilder;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::openapi::{OpenApiBridge, OpenApiTool};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use url::Url;
+
+const DOCUMENT: &str = r##"
+openapi: 3.0.3
+info: {title: Pets, version: 1.0.0}
+servers: [{url: /api}]
+components:
+  schemas:
+    NewPet:
+      type: object
+      required: [name]
+      properties:
+        name: {type: string}
+        tag: {type: string}
+  parameters:
+    Limit: {name: limit, in: query, description: Page size, schema: {type: integer}}
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      tags: [pets]
+      summary: List pets
+      parameters:
+        - $ref: '#/components/parameters/Limit'
+        - {name: tags, in: query, schema: {type: array, items: {type: string}}}
+    post:
+      operationId: createPet
+      tags: [pets]
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: {$ref: '#/components/schemas/NewPet'}
+  /pets/{petId}:
+    parameters:
+      - {name: petId, in: path, required: true, schema: {type: string}}
+    get:
+      operationId: getPet
+      tags: [pets]
+      parameters:
+        - {name: X-Request-Id, in: header, schema: {type: string}}
+    delete:
+      operationId: deletePet
+      tags: [pets]
+  /upload:
+    post:
+      operationId: upload
+      requestBody:
+        content:
+          multipart/form-data:
+            schema: {type: object}
+  /health:
+    get:
+      summary: Health check
+"##;
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get("authorization")
+        .is_some_and(|value| value == "Bearer secret")
+}
+
+async fn guarded(headers: HeaderMap, response: Response) -> Response {
+    if authorized(&headers) {
+        response
+    } else {
+        (StatusCode::UNAUTHORIZED, "missing token").into_response()
+    }
+}
+
+/// Start a stub of the pets service and return its base URL
+async fn start_stub() -> anyhow::Result<Url> {
+    let app = Router::new()
+        .route(
+            "/api/pets",
+            get(|headers: HeaderMap, RawQuery(query): RawQuery| async move {
+                guarded(headers, Json(json!({ "query": query })).into_response()).await
+            })
+            .post(|headers: HeaderMap, Json(pet): Json<Value>| async move {
+                let created = (StatusCode::CREATED, Json(json!({"id": 7, "pet": pet})));
+                guarded(headers, created.into_response()).await
+            }),
+        )
+        .route(
+            "/api/pets/:pet_id",
+            get(
+                |headers: HeaderMap, UrlPath(pet_id): UrlPath<String>| async move {
+                    let request_id = headers
+                        .get("x-request-id")
+                        .map(|value| value.to_str().unwrap().to_string());
+                    let pet = Json(json!({"id": pet_id, "requestId": request_id}));
+                    guarded(headers, pet.into_response()).await
+                },
+            ),
+        )
+        .route("/api/health", get(|| async { "ok" }));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok(Url::parse(&format!("http://{address}/api"))?)
+}
+
+fn write_document(dir: &Path) -> PathBuf {
+    let path = dir.join("pets.yaml");
+    std::fs::write(&path, DOCUMENT).unwrap();
+    path
+}
+
+async fn find<'a>(tools: &'a [OpenApiTool], name: &str) -> &'a OpenApiTool {
+    for tool in tools {
+        if tool.tool().await.name == name {
+            return tool;
+        }
+    }
+    panic!("no tool named {name}");
+}
+
+async fn names(tools: &[OpenApiTool]) -> Vec<String> {
+    let mut names = Vec::new();
+    for tool in tools {
+        names.push(tool.tool().await.name);
+    }
+    names.sort();
+    names
+}
+
+fn text(content: &Content) -> &str {
+    match content {
+        Content::Text(text) => &text.text,
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_tools_and_schemas_from_document() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let base_url = Url::parse("http://localhost:1/api")?;
+    let bridge = OpenApiBridge::from_file(write_document(dir.path()))?.with_base_url(base_url);
+
+    // The multipart upload cannot be sent and is left out
+    let tools = bridge.tools()?;
+    assert_eq!(
+        names(&tools).await,
+        ["createPet", "deletePet", "getPet", "get_health", "listPets"]
+    );
+
+    let get_pet = find(&tools, "getPet").await;
+    assert_eq!((get_pet.method(), get_pet.path()), ("GET", "/pets/{petId}"));
+    let schema = get_pet.tool().await.input_schema;
+    assert_eq!(schema["properties"]["petId"]["type"], "string");
+    assert_eq!(schema["properties"]["X-Request-Id"]["type"], "string");
+    assert_eq!(schema["required"], json!(["petId"]));
+
+    let schema = find(&tools, "listPets").await.tool().await.input_schema;
+    assert_eq!(
+        schema["properties"]["limit"],
+        json!({"type": "integer", "description": "Page size"})
+    );
+    assert!(schema.get("required").is_none());
+
+    // References are inlined so the schema stands on its own
+    let schema = find(&tools, "createPet").await.tool().await.input_schema;
+    assert_eq!(schema["properties"]["body"]["required"], json!(["name"]));
+    assert_eq!(schema["required"], json!(["body"]));
+
+    let health = find(&tools, "get_health").await.tool().await;
+    assert_eq!(health.description.as_deref(), Some("Health check"));
+
+    let filtered = bridge
+        .clone()
+        .with_tags(["pets"])
+        .without_operations(["deletePet"])
+        .tools()?;
+    assert_eq!(names(&filtered).await, ["createPet", "getPet", "listPets"]);
+    let selected = bridge.with_operations(["get_health", "upload"]).tools()?;
+    assert_eq!(names(&selected).await, ["get_health"]);
+
+    // Relative server URLs need an explicit base URL
+    let Err(error) = OpenApiBridge::from_file(write_document(dir.path()))?.tools() else {
+        panic!("relative server URL was accepted");
+    };
+    assert!(matches!(error, Error::Configuration(_)), "{error}");
+    assert!(OpenApiBridge::from_value(json!({"swagger": "2.0"})).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_calls_are_translated_to_requests() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let base_url = start_stub().await?;
+    let tools = OpenApiBridge::from_file(write_document(dir.path()))?
+        .with_base_url(base_url.clone())
+        .with_bearer_token("secret")
+        .tools()?;
+
+    // Path values are encoded, so they cannot add segments
+    let response = find(&tools, "getPet")
+        .await
+        .call(Some(json!({"petId": "a/b c", "X-Request-Id": "r1"})))
+        .await?;
+    assert_eq!(response.is_error, Some(false));
+    assert_eq!(
+        response.structured_content,
+        Some(json!({"id": "a/b c", "requestId": "r1"}))
+    );
+
+    let response = find(&tools, "listPets")
+        .await
+        .call(Some(json!({"limit": 2, "tags": ["cat", "dog"]})))
+        .await?;
+    assert_eq!(
+        response.structured_content.unwrap()["query"],
+        "limit=2&tags=cat&tags=dog"
+    );
+
+    let response = find(&tools, "createPet")
+        .await
+        .call(Some(json!({"body": {"name": "Rex"}})))
+        .await?;
+    let created: Value = serde_json::from_str(text(&response.content[0]))?;
+    assert_eq!(created, json!({"id": 7, "pet": {"name": "Rex"}}));
+
+    let response = find(&tools, "get_health").await.call(None).await?;
+    assert_eq!(text(&response.content[0]), "ok");
+    assert!(response.structured_content.is_none());
+
+    let result = find(&tools, "createPet").await.call(Some(json!({}))).await;
+    assert!(matches!(result, Err(Error::InvalidParams(_))), "{result:?}");
+
+    // Without the auth header the service rejects the call
+    let tools = OpenApiBridge::from_file(write_document(dir.path()))?
+        .with_base_url(base_url)
+        .tools()?;
+    let response = find(&tools, "listPets").await.call(None).await?;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text(&response.content[0]).contains("401"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_path_values_cannot_move_up_the_path() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let tools = OpenApiBridge::from_file(write_document(dir.path()))?
+        .with_base_url(start_stub().await?)
+        .with_bearer_token("secret")
+        .tools()?;
+
+    // DELETE /pets/.. would otherwise hit /api/pets
+    for pet_id in ["..", ".", ""] {
+        let result = find(&tools, "deletePet")
+            .await
+            .call(Some(json!({"petId": pet_id})))
+            .await;
+        assert!(matches!(result, Err(Error::InvalidParams(_))), "{result:?}");
+    }
+
+    // Dots within a value are sent as they are
+    let response = find(&tools, "getPet")
+        .await
+        .call(Some(json!({"petId": "..rex"})))
+        .await?;
+    assert_eq!(response.structured_content.unwrap()["id"], "..rex");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_large_responses_are_rejected() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let tools = OpenApiBridge::from_file(write_document(dir.path()))?
+        .with_base_url(start_stub().await?)
+        .with_bearer_token("secret")
+        .with_max_response_bytes(8)
+        .tools()?;
+
+    let response = find(&tools, "listPets").await.call(None).await?;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text(&response.content[0]).contains("exceeds 8 bytes"));
+
+    // Small bodies still come through
+    let response = find(&tools, "get_health").await.call(None).await?;
+    assert_eq!(text(&response.content[0]), "ok");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_with_openapi_tools() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let bridge = OpenApiBridge::from_file(write_document(dir.path()))?
+        .with_base_url(start_stub().await?)
+        .with_header("Authorization", "Bearer secret")
+        .with_operations(["getPet"]);
+
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Pets", "1.0.0")
+        .with_openapi(bridge)?
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    assert!(server.capabilities().tools.is_some());
+    tokio::spawn(async move { server.run().await });
+
+    let client = common::connect(port).await;
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tools.len(), 1);
+    let response = client
+        .call_tool("getPet".to_string(), Some(json!({"petId": "9"})))
+        .await?;
+    assert_eq!(
+        response.structured_content,
+        Some(json!({"id": "9", "requestId": null}))
+    );
+    Ok(())
+}
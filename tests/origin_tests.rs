@@ -0,0 +1,207 @@
+//! Integration tests for origin and Host validation
+
+mod common;
+
+use common::{free_port, retry};
+use mocopr_server::oauth::ProtectedResource;
+use mocopr_server::origin::OriginPolicy;
+use mocopr_server::{McpServerBuilder, MonitoringEndpoints};
+use serde_json::json;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, http::StatusCode};
+use url::Url;
+
+/// Start a server with HTTP and WebSocket transports, returning its port
+async fn start_server(policy: Option<OriginPolicy>) -> anyhow::Result<u16> {
+    let port = free_port();
+    let mut builder = McpServerBuilder::new()
+        .with_info("Local Server", "1.0.0")
+        .with_bind_address("127.0.0.1", port)
+        .with_http_transport()
+        .with_websocket_transport();
+    if let Some(policy) = policy {
+        builder = builder.with_origin_policy(policy);
+    }
+    let server = builder.build()?;
+    tokio::spawn(async move { server.run().await });
+
+    retry(|| tokio::net::TcpStream::connect(("127.0.0.1", port))).await;
+    Ok(port)
+}
+
+/// Open a WebSocket with the given headers, returning the upgrade status
+async fn upgrade(port: u16, headers: &[(&str, &str)]) -> anyhow::Result<StatusCode> {
+    let mut request = format!("ws://127.0.0.1:{port}/mcp/ws").into_client_request()?;
+    for (name, value) in headers {
+        let name = tungstenite::http::HeaderName::from_bytes(name.as_bytes())?;
+        request.headers_mut().insert(name, value.parse()?);
+    }
+    match tokio_tungstenite::connect_async(request).await {
+        Ok((_, response)) => Ok(response.status()),
+        Err(tungstenite::Error::Http(response)) => Ok(response.status()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// POST a ping with the given headers, returning the response status
+async fn post(port: u16, headers: &[(&str, &str)]) -> anyhow::Result<u16> {
+    let mut request = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}/mcp"))
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    Ok(request.send().await?.status().as_u16())
+}
+
+#[tokio::test]
+async fn test_default_policy_allows_local_clients_only() -> anyhow::Result<()> {
+    let port = start_server(None).await?;
+
+    // Non-browser clients send no Origin
+    assert_eq!(upgrade(port, &[]).await?, StatusCode::SWITCHING_PROTOCOLS);
+    assert!((200..300).contains(&post(port, &[]).await?));
+
+    // Pages served from the same machine
+    let local = [("origin", "http://localhost:3000")];
+    assert_eq!(
+        upgrade(port, &local).await?,
+        StatusCode::SWITCHING_PROTOCOLS
+    );
+
+    // Pages from elsewhere, and the opaque origin of sandboxed pages
+    for origin in ["https://evil.example.com", "null"] {
+        let headers = [("origin", origin)];
+        assert_eq!(upgrade(port, &headers).await?, StatusCode::FORBIDDEN);
+        assert_eq!(post(port, &headers).await?, 403);
+    }
+
+    // DNS rebinding: the attacker's domain resolves to 127.0.0.1
+    let rebound = format!("rebind.evil.example.com:{port}");
+    let headers = [("host", rebound.as_str())];
+    assert_eq!(upgrade(port, &headers).await?, StatusCode::FORBIDDEN);
+    assert_eq!(post(port, &headers).await?, 403);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_configured_origins_and_hosts() -> anyhow::Result<()> {
+    let policy = OriginPolicy::new()
+        .with_allowed_origin("https://app.example.com")
+        .with_allowed_host("mcp.example.com");
+    let port = start_server(Some(policy)).await?;
+
+    let host = format!("mcp.example.com:{port}");
+    let headers = [
+        ("host", host.as_str()),
+        ("origin", "https://app.example.com"),
+    ];
+    assert_eq!(
+        upgrade(port, &headers).await?,
+        StatusCode::SWITCHING_PROTOCOLS
+    );
+    assert!((200..300).contains(&post(port, &headers).await?));
+
+    let headers = [("host", "other.example.com")];
+    assert_eq!(post(port, &headers).await?, 403);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cors_preflight_follows_the_policy() -> anyhow::Result<()> {
+    let policy = OriginPolicy::new()
+        .with_allowed_origin("https://app.example.com")
+        .with_cors_header("x-trace-id")
+        .with_cors_max_age(Duration::from_secs(600));
+    let port = start_server(Some(policy)).await?;
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{port}/mcp");
+
+    let response = client
+        .request(reqwest::Method::OPTIONS, &url)
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "authorization,x-trace-id")
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    let allowed = headers["access-control-allow-headers"].to_str()?;
+    assert!(allowed.contains("authorization") && allowed.contains("x-trace-id"));
+    assert_eq!(headers["access-control-max-age"], "600");
+
+    let response = client
+        .request(reqwest::Method::OPTIONS, &url)
+        .header("origin", "https://evil.example.com")
+        .header("access-control-request-method", "POST")
+        .send()
+        .await?;
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_monitoring_and_metadata_routes_follow_the_policy() -> anyhow::Result<()> {
+    let port = free_port();
+    let resource = ProtectedResource::new(Url::parse(&format!("http://127.0.0.1:{port}/mcp"))?)
+        .with_authorization_server(Url::parse("https://auth.example.com/")?);
+    let server = McpServerBuilder::new()
+        .with_info("Local Server", "1.0.0")
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .with_monitoring_endpoints(MonitoringEndpoints::default())
+        .with_protected_resource(resource)
+        .with_origin_policy(OriginPolicy::new().with_allowed_origin("https://app.example.com"))
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+    retry(|| tokio::net::TcpStream::connect(("127.0.0.1", port))).await;
+
+    let client = reqwest::Client::new();
+    let rebound = format!("rebind.evil.example.com:{port}");
+    for path in [
+        "/healthz",
+        "/metrics",
+        "/.well-known/oauth-protected-resource",
+    ] {
+        let url = format!("http://127.0.0.1:{port}{path}");
+        assert_eq!(client.get(&url).send().await?.status(), 200, "{path}");
+
+        let response = client
+            .get(&url)
+            .header("origin", "https://app.example.com")
+            .send()
+            .await?;
+        assert_eq!(response.status(), 200, "{path}");
+
+        for (name, value) in [
+            ("origin", "https://evil.example.com"),
+            ("host", rebound.as_str()),
+        ] {
+            let response = client.get(&url).header(name, value).send().await?;
+            assert_eq!(response.status(), 403, "{path} with {name} {value}");
+        }
+    }
+
+    // Metadata CORS only allows the allowed origins
+    let url = format!("http://127.0.0.1:{port}/.well-known/oauth-protected-resource");
+    let response = client
+        .get(&url)
+        .header("origin", "https://app.example.com")
+        .send()
+        .await?;
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    Ok(())
+}
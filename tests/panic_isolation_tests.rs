@@ -0,0 +1,138 @@
+//! Integration tests for isolating panics in request handlers
+
+mod common;
+
+use common::{channel, free_port, retry};
+use mocopr_client::McpClientBuilder;
+use mocopr_core::prelude::*;
+use mocopr_core::protocol::{DEFAULT_PANIC_MESSAGE, Session, SessionEvent};
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::handlers::ToolHandler;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Panics when asked to, answers otherwise
+struct FragileTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for FragileTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("fragile", json!({"type": "object"}))
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        if arguments.is_some_and(|args| args["explode"] == json!(true)) {
+            panic!("secret internal state");
+        }
+        Ok(ToolsCallResponse::success(vec![Content::Text(
+            TextContent::new("still standing"),
+        )]))
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_session_survives_handler_panic() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = Arc::new(
+        McpServerBuilder::new()
+            .with_info("Fragile Server", "1.0.0")
+            .with_tools()
+            .with_tool(FragileTool)
+            .with_monitoring()
+            .with_panic_message("Something went wrong, please retry")
+            .with_bind_address("127.0.0.1", port)
+            .with_websocket_transport()
+            .build()?,
+    );
+    let running = server.clone();
+    tokio::spawn(async move { running.run().await });
+
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+    let client = retry(|| {
+        McpClientBuilder::new()
+            .with_info("Test Client".to_string(), "1.0.0".to_string())
+            .connect_websocket(&url)
+    })
+    .await;
+
+    let error = client
+        .call_tool("fragile".to_string(), Some(json!({"explode": true})))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Something went wrong, please retry"),
+        "{error}"
+    );
+    assert!(!error.contains("secret internal state"), "{error}");
+
+    // The connection still serves requests
+    let response = client.call_tool("fragile".to_string(), None).await?;
+    assert_eq!(response.is_error, Some(false));
+
+    let monitoring = server.monitoring().unwrap();
+    let metrics = monitoring.get_method_metrics().await;
+    assert_eq!(metrics["tools/call"].panics, 1);
+    assert_eq!(metrics["tools/call"].errors, 1);
+    Ok(())
+}
+
+/// Handler whose tool calls panic
+struct PanickingHandler;
+
+#[async_trait::async_trait]
+impl MessageHandler for PanickingHandler {
+    async fn handle_initialize(&self, _request: InitializeRequest) -> Result<InitializeResponse> {
+        unreachable!("not initialized in this test")
+    }
+
+    async fn handle_tools_call(&self, _request: ToolsCallRequest) -> Result<ToolsCallResponse> {
+        panic!("handler bug");
+    }
+}
+
+#[tokio::test]
+async fn test_session_answers_panicking_requests() -> anyhow::Result<()> {
+    let (transport, mut peer) = channel();
+    let (session, mut events) = Session::new(Box::new(transport), Arc::new(PanickingHandler));
+    let session = Arc::new(session);
+    let running = session.clone();
+    let run = tokio::spawn(async move { running.run().await });
+
+    let response = peer
+        .request("tools/call", json!({"name": "anything"}))
+        .await;
+    assert_eq!(response["id"], json!(1));
+    assert_eq!(response["error"]["code"], json!(-32603));
+    assert_eq!(response["error"]["message"], json!(DEFAULT_PANIC_MESSAGE));
+
+    // The session keeps processing messages
+    let response = peer.request("ping", json!({})).await;
+    assert_eq!(response["id"], json!(2));
+    assert!(response.get("result").is_some(), "{response}");
+
+    let mut panicked = None;
+    while let Ok(event) = events.try_recv() {
+        if let SessionEvent::Panicked {
+            method,
+            request_id,
+            message,
+        } = event
+        {
+            panicked = Some((method, request_id, message));
+        }
+    }
+    assert_eq!(
+        panicked,
+        Some((
+            "tools/call".to_string(),
+            Some(RequestId::Number(1)),
+            "handler bug".to_string()
+        ))
+    );
+
+    drop(peer);
+    run.await??;
+    Ok(())
+}
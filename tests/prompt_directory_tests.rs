@@ -0,0 +1,66 @@
+//! Integration tests for prompts loaded from a watched directory
+
+mod common;
+
+use common::channel;
+use mocopr_server::McpServerBuilder;
+use mocopr_server::prompt_directory::DirectoryPromptProvider;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_bursts_of_changes_reload_once() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    std::fs::write(dir.path().join("greet.md"), "---\n---\nHello!\n")?;
+
+    let server = Arc::new(
+        McpServerBuilder::new()
+            .with_info("Prompt Library", "1.0.0")
+            .with_prompts()
+            .with_prompt_provider(
+                DirectoryPromptProvider::new(dir.path())?.with_debounce(Duration::from_millis(300)),
+            )
+            .build()?,
+    );
+    let (transport, mut peer) = channel();
+    tokio::spawn({
+        let server = server.clone();
+        async move { server.run_transport(Box::new(transport)).await }
+    });
+    peer.initialize().await;
+
+    // Written one after the other, as a checkout or an editor would
+    for index in 0..5 {
+        std::fs::write(
+            dir.path().join(format!("prompt_{index}.md")),
+            format!("---\ndescription: Prompt {index}\n---\nBody {index}\n"),
+        )?;
+        std::fs::write(
+            dir.path().join("greet.md"),
+            format!("---\n---\nHello {index}!\n"),
+        )?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let notification = peer.next_message().await.expect("no list_changed");
+    assert_eq!(notification["method"], "notifications/prompts/list_changed");
+    let quiet = tokio::time::timeout(Duration::from_secs(1), peer.next_message()).await;
+    assert!(quiet.is_err(), "reloaded more than once: {quiet:?}");
+
+    let listed = peer.request("prompts/list", json!({})).await;
+    let names: Vec<_> = listed["result"]["prompts"]
+        .as_array()
+        .expect("prompts")
+        .iter()
+        .map(|prompt| prompt["name"].as_str().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "greet", "prompt_0", "prompt_1", "prompt_2", "prompt_3", "prompt_4"
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! Integration tests for templated prompts
+
+mod common;
+
+use common::{free_port, open_socket};
+use futures::{SinkExt, StreamExt};
+use mocopr_core::types::{Content, MessageRole, PromptArgument};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::handlers::{PromptHandler, TemplatePromptHandler};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+fn review_prompt() -> TemplatePromptHandler {
+    TemplatePromptHandler::conversation(
+        "review",
+        "Review code",
+        vec![
+            PromptArgument::new("code").required(true),
+            PromptArgument::new("language"),
+            PromptArgument::new("focus"),
+            PromptArgument::new("rules"),
+        ],
+    )
+    .with_system_message("You review {{ language | default(\"code\") }}.")
+    .with_user_message(
+        "{% if focus %}Focus on {{ focus }}.\n{% endif %}\
+         {% for rule in rules | default(\"\") | split(\",\") if rule %}- {{ rule | trim }}\n{% endfor %}\
+         Use {{ \"{{\" }} literally.",
+    )
+    .with_assistant_message("{% if focus %}Noted, {{ focus }} first.{% endif %}")
+    .with_resource_message(
+        MessageRole::User,
+        "file:///review/{{ language | default(\"code\") }}.txt",
+        "{{ code }}",
+        Some("text/plain".to_string()),
+    )
+}
+
+fn arguments(pairs: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+    Some(
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+fn text(content: &Content) -> &str {
+    match content {
+        Content::Text(text) => &text.text,
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_template_renders_conversation() -> anyhow::Result<()> {
+    let response = review_prompt()
+        .generate(arguments(&[
+            ("code", "fn main() {}"),
+            ("language", "rust"),
+            ("focus", "safety"),
+            ("rules", "no unwrap, no panics"),
+        ]))
+        .await?;
+
+    let roles: Vec<_> = response
+        .messages
+        .iter()
+        .map(|message| serde_json::to_value(&message.role).unwrap())
+        .collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+
+    assert_eq!(text(&response.messages[0].content), "You review rust.");
+    assert_eq!(
+        text(&response.messages[1].content),
+        "Focus on safety.\n- no unwrap\n- no panics\nUse {{ literally."
+    );
+    assert_eq!(text(&response.messages[2].content), "Noted, safety first.");
+
+    match &response.messages[3].content {
+        Content::Resource(resource) => {
+            assert_eq!(resource.resource.uri.as_str(), "file:///review/rust.txt");
+            assert_eq!(resource.resource.text.as_deref(), Some("fn main() {}"));
+            assert_eq!(resource.resource.mime_type.as_deref(), Some("text/plain"));
+        }
+        other => panic!("unexpected content: {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_template_defaults_and_empty_messages() -> anyhow::Result<()> {
+    let response = review_prompt()
+        .generate(arguments(&[("code", "x = 1")]))
+        .await?;
+
+    // The assistant message renders empty without a focus and is left out
+    assert_eq!(response.messages.len(), 3);
+    assert_eq!(text(&response.messages[0].content), "You review code.");
+    assert_eq!(text(&response.messages[1].content), "Use {{ literally.");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_message_template() -> anyhow::Result<()> {
+    let prompt = TemplatePromptHandler::new(
+        "summarize",
+        "Summarize text",
+        "Summarize: {{ text }}",
+        vec![PromptArgument::new("text").required(true)],
+    );
+    let response = prompt.generate(arguments(&[("text", "hello")])).await?;
+
+    assert_eq!(response.messages.len(), 1);
+    assert!(matches!(response.messages[0].role, MessageRole::User));
+    assert_eq!(text(&response.messages[0].content), "Summarize: hello");
+    Ok(())
+}
+
+#[test]
+fn test_old_placeholders_are_rejected() {
+    let argument = || vec![PromptArgument::new("text").required(true)];
+    let old = TemplatePromptHandler::new(
+        "summarize",
+        "Summarize text",
+        "Summarize: {text}",
+        argument(),
+    );
+    let error = old.validate().unwrap_err();
+    assert!(error.to_string().contains("{{ text }}"), "{error}");
+
+    let resource = TemplatePromptHandler::conversation("attach", "Attach a file", argument())
+        .with_resource_message(MessageRole::User, "file:///{text}", "Attached", None);
+    assert!(resource.validate().is_err());
+
+    // Braces that do not name an argument are plain text
+    assert!(review_prompt().validate().is_ok());
+    let json = TemplatePromptHandler::new(
+        "json",
+        "Reply in JSON",
+        "Reply with {\"ok\": true} or {other}",
+        argument(),
+    );
+    assert!(json.validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_missing_required_argument_is_invalid_params() -> anyhow::Result<()> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Prompt Server", "1.0.0")
+        .with_prompts()
+        .with_prompt(review_prompt())
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    let mut socket = open_socket(port).await;
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "prompt-test", "version": "1.0.0"}
+        }
+    });
+    socket.send(Message::Text(initialize.to_string())).await?;
+    socket.next().await.expect("connection closed")?;
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "prompts/get",
+        "params": {"name": "review", "arguments": {"language": "rust"}}
+    });
+    socket.send(Message::Text(request.to_string())).await?;
+
+    let reply = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await?
+        .expect("connection closed")?;
+    let reply: Value = serde_json::from_str(reply.to_text()?)?;
+    assert_eq!(reply["error"]["code"], -32602);
+    assert!(
+        reply["error"]["message"].as_str().unwrap().contains("code"),
+        "{reply}"
+    );
+    Ok(())
+}
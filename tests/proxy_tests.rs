@@ -0,0 +1,514 @@
+//! Integration tests for the aggregating proxy
+
+mod common;
+
+use common::{channel, client_info, connect, free_port};
+use mocopr_client::McpClient;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_core::types::{Content, TextContent};
+use mocopr_core::{Error, Result};
+use mocopr_server::context::RequestContext;
+use mocopr_server::directory::DirectoryResourceProvider;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::notifications::NotificationSender;
+use mocopr_server::proxy::McpProxy;
+use mocopr_server::shutdown::ShutdownHandle;
+use mocopr_server::{McpServer, McpServerBuilder};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Replies with its own name and the `text` argument
+struct EchoTool {
+    server: &'static str,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for EchoTool {
+    async fn tool(&self) -> Tool {
+        Tool::new(
+            "echo",
+            json!({"type": "object", "properties": {"text": {"type": "string"}}}),
+        )
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let text = arguments
+            .and_then(|args| args["text"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        Ok(ToolsCallResponse::success(vec![Content::Text(
+            TextContent::new(format!("{}: {}", self.server, text)),
+        )]))
+    }
+}
+
+/// Reports three progress steps before finishing
+struct CountTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for CountTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("count", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let context = RequestContext::current().expect("request context");
+        for step in 1..=3 {
+            context.send_progress(step as f64, Some(3.0), Some(format!("step {step}")))?;
+        }
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+}
+
+/// Always fails with invalid params and structured details
+struct RejectTool;
+
+#[async_trait::async_trait]
+impl ToolHandler for RejectTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("reject", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        Err(Error::invalid_params("no widget given").with_data(json!({"field": "widget"})))
+    }
+}
+
+/// Never finishes, and signals when it is cancelled
+struct HangTool {
+    cancelled: Arc<Notify>,
+}
+
+struct NotifyOnDrop(Arc<Notify>);
+
+impl Drop for NotifyOnDrop {
+    fn drop(&mut self) {
+        self.0.notify_one();
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for HangTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("hang", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let _guard = NotifyOnDrop(self.cancelled.clone());
+        std::future::pending().await
+    }
+}
+
+struct Upstreams {
+    alpha: NotificationSender,
+    beta: ShutdownHandle,
+    cancelled: Arc<Notify>,
+    proxy: McpProxy,
+    client: McpClient,
+}
+
+/// Start two upstream servers, a proxy in front of them and a client of the proxy
+async fn start() -> anyhow::Result<Upstreams> {
+    let cancelled = Arc::new(Notify::new());
+
+    let alpha_port = free_port();
+    let alpha = McpServerBuilder::new()
+        .with_info("Alpha", "1.0.0")
+        .with_tools()
+        // The proxy validates arguments against the schemas it advertises
+        .with_schema_validation(false)
+        .with_tool(EchoTool { server: "alpha" })
+        .with_tool(CountTool)
+        .with_tool(RejectTool)
+        .with_tool(HangTool {
+            cancelled: cancelled.clone(),
+        })
+        .with_bind_address("127.0.0.1", alpha_port)
+        .with_websocket_transport()
+        .build()?;
+    let alpha_notifications = alpha.notifications().clone();
+    tokio::spawn(async move { alpha.run().await });
+
+    let beta_port = free_port();
+    let beta = McpServerBuilder::new()
+        .with_info("Beta", "1.0.0")
+        .with_tools()
+        .with_tool(EchoTool { server: "beta" })
+        .with_bind_address("127.0.0.1", beta_port)
+        .with_websocket_transport()
+        .with_shutdown_timeout(Duration::from_millis(100))
+        .build()?;
+    let beta_shutdown = beta.shutdown_handle();
+    tokio::spawn(async move { beta.run().await });
+
+    let proxy = McpProxy::new()
+        .with_upstream("alpha", connect(alpha_port).await)
+        .with_upstream("beta", connect(beta_port).await);
+
+    let proxy_port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Proxy", "1.0.0")
+        .with_proxy(proxy.clone())?
+        .with_bind_address("127.0.0.1", proxy_port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    Ok(Upstreams {
+        alpha: alpha_notifications,
+        beta: beta_shutdown,
+        cancelled,
+        proxy,
+        client: connect(proxy_port).await,
+    })
+}
+
+fn text(response: &ToolsCallResponse) -> &str {
+    match &response.content[0] {
+        Content::Text(text) => &text.text,
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_proxy_namespaces_and_forwards_calls() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+
+    let mut names: Vec<_> = upstreams
+        .client
+        .list_all_tools()
+        .await?
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "alpha.count",
+            "alpha.echo",
+            "alpha.hang",
+            "alpha.reject",
+            "beta.echo"
+        ]
+    );
+
+    let response = upstreams
+        .client
+        .call_tool("beta.echo".to_string(), Some(json!({"text": "hi"})))
+        .await?;
+    assert_eq!(text(&response), "beta: hi");
+
+    let missing = upstreams
+        .client
+        .call_tool("gamma.echo".to_string(), None)
+        .await;
+    assert!(missing.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_validates_arguments_against_upstream_schemas() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+
+    let error = upstreams
+        .client
+        .call_tool("alpha.echo".to_string(), Some(json!({"text": 5})))
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Invalid arguments for tool 'alpha.echo'"),
+        "{error}"
+    );
+
+    let response = upstreams
+        .client
+        .call_tool("alpha.echo".to_string(), Some(json!({"text": "fine"})))
+        .await?;
+    assert_eq!(text(&response), "alpha: fine");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_relays_upstream_error_codes() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+
+    let error = upstreams
+        .client
+        .call_tool("alpha.reject".to_string(), None)
+        .await
+        .unwrap_err();
+    assert_eq!(error.json_rpc_code(), -32602);
+    assert_eq!(error.to_string(), "Invalid parameters: no widget given");
+    assert_eq!(error.data(), Some(&json!({"field": "widget"})));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_relays_progress() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+
+    let mut progress = Vec::new();
+    upstreams
+        .client
+        .call_tool_with_progress("alpha.count".to_string(), None, |notification| {
+            progress.push((notification.progress, notification.message));
+        })
+        .await?;
+
+    assert_eq!(
+        progress,
+        [
+            (1.0, Some("step 1".to_string())),
+            (2.0, Some("step 2".to_string())),
+            (3.0, Some("step 3".to_string())),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_relays_cancellation() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+
+    let call = upstreams.client.call_tool("alpha.hang".to_string(), None);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), call)
+            .await
+            .is_err()
+    );
+
+    // Dropping the call cancels it at the proxy, which cancels it upstream
+    tokio::time::timeout(Duration::from_secs(5), upstreams.cancelled.notified())
+        .await
+        .expect("upstream request was not cancelled");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_relays_list_changed_and_marks_upstream_unhealthy() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+    let mut notifications = upstreams.client.subscribe_notifications();
+    // The proxy answers once it is relaying
+    upstreams.client.list_all_tools().await?;
+
+    upstreams.alpha.tool_list_changed();
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await?
+        .unwrap();
+    assert_eq!(notification.method, "notifications/tools/list_changed");
+
+    upstreams.beta.shutdown();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while upstreams.proxy.upstream_health()["beta"] {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("upstream was not marked unhealthy");
+    assert!(upstreams.proxy.upstream_health()["alpha"]);
+
+    let names: Vec<_> = upstreams
+        .client
+        .list_all_tools()
+        .await?
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    assert!(
+        names.iter().all(|name| name.starts_with("alpha.")),
+        "{names:?}"
+    );
+
+    let unavailable = upstreams
+        .client
+        .call_tool("beta.echo".to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        unavailable.to_string().contains("unavailable"),
+        "{unavailable}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_relays_progress_over_stdio() -> anyhow::Result<()> {
+    let upstreams = start().await?;
+    let server = McpServerBuilder::new()
+        .with_info("Proxy", "1.0.0")
+        .with_proxy(upstreams.proxy.clone())?
+        .build()?;
+    let (transport, mut peer) = channel();
+    tokio::spawn(async move { server.run_transport(Box::new(transport)).await });
+    peer.initialize().await;
+
+    let id = peer.send_request(
+        "tools/call",
+        json!({"name": "alpha.count", "_meta": {"progressToken": "count"}}),
+    );
+    let mut progress = Vec::new();
+    loop {
+        let message = peer.next_message().await.expect("no response");
+        if message["id"] == id {
+            assert!(message.get("result").is_some(), "{message}");
+            break;
+        }
+        if message["method"] == "notifications/progress" {
+            assert_eq!(message["params"]["progressToken"], "count");
+            progress.push(message["params"]["progress"].as_f64().unwrap());
+        }
+    }
+    assert_eq!(progress, [1.0, 2.0, 3.0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_rejects_overlapping_namespaces() -> anyhow::Result<()> {
+    let port = free_port();
+    let upstream = McpServerBuilder::new()
+        .with_info("Upstream", "1.0.0")
+        .with_tools()
+        .with_tool(EchoTool { server: "upstream" })
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { upstream.run().await });
+
+    for (first, second) in [("git", "git.hub"), ("git", "git")] {
+        let proxy = McpProxy::new()
+            .with_upstream(first, connect(port).await)
+            .with_upstream(second, connect(port).await);
+        let error = McpServerBuilder::new()
+            .with_info("Proxy", "1.0.0")
+            .with_proxy(proxy)
+            .err()
+            .expect("overlapping namespaces were accepted");
+        assert!(error.to_string().contains("overlap"), "{error}");
+    }
+
+    let proxy = McpProxy::new()
+        .with_upstream("git", connect(port).await)
+        .with_upstream("github", connect(port).await);
+    assert!(
+        McpServerBuilder::new()
+            .with_info("Proxy", "1.0.0")
+            .with_proxy(proxy)
+            .is_ok()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_routes_reads_through_resource_templates() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(dir.path().join("notes.txt"), "remember the milk")?;
+    let docs = DirectoryResourceProvider::new(dir.path(), "Docs")?
+        .as_template()
+        .with_watching(false);
+    let template = docs.uri_template();
+
+    let docs_port = free_port();
+    let upstream = McpServerBuilder::new()
+        .with_info("Docs", "1.0.0")
+        .with_resources()
+        .with_resource_provider(docs)
+        .with_bind_address("127.0.0.1", docs_port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { upstream.run().await });
+
+    let proxy = McpProxy::new().with_upstream("docs", connect(docs_port).await);
+    let proxy_port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Proxy", "1.0.0")
+        .with_proxy(proxy)?
+        .with_bind_address("127.0.0.1", proxy_port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+    let client = connect(proxy_port).await;
+
+    let templates = client.list_all_resource_templates().await?;
+    assert_eq!(templates.len(), 1, "{templates:?}");
+    assert_eq!(templates[0].uri_template, template);
+
+    let uri = url::Url::parse(&template.replace("{path}", "notes.txt"))?;
+    let response = client.read_resource(uri).await?;
+    assert_eq!(response.contents.len(), 1);
+    assert!(
+        serde_json::to_string(&response.contents[0])?.contains("remember the milk"),
+        "{:?}",
+        response.contents
+    );
+
+    let outside = url::Url::parse("file:///etc/hostname")?;
+    assert!(client.read_resource(outside).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy_reconnects_upstreams() -> anyhow::Result<()> {
+    fn gamma(port: u16) -> Result<McpServer> {
+        McpServerBuilder::new()
+            .with_info("Gamma", "1.0.0")
+            .with_tools()
+            .with_tool(EchoTool { server: "gamma" })
+            .with_bind_address("127.0.0.1", port)
+            .with_websocket_transport()
+            .with_shutdown_timeout(Duration::from_millis(100))
+            .build()
+    }
+
+    let port = free_port();
+    let first = gamma(port)?;
+    let shutdown = first.shutdown_handle();
+    let running = tokio::spawn(async move { first.run().await });
+
+    let proxy = McpProxy::new().with_reconnecting_upstream(
+        "gamma",
+        connect(port).await,
+        move || async move {
+            let url = format!("ws://127.0.0.1:{port}/mcp");
+            McpClient::connect_websocket(&url, client_info(), Default::default()).await
+        },
+    );
+    let proxy_port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Proxy", "1.0.0")
+        .with_proxy(proxy.clone())?
+        .with_bind_address("127.0.0.1", proxy_port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+    let client = connect(proxy_port).await;
+
+    shutdown.shutdown();
+    let _ = running.await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while proxy.upstream_health()["gamma"] {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("upstream was not marked unhealthy");
+
+    let second = gamma(port)?;
+    tokio::spawn(async move { second.run().await });
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !proxy.upstream_health()["gamma"] {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("upstream was not reconnected");
+
+    let response = client
+        .call_tool("gamma.echo".to_string(), Some(json!({"text": "back"})))
+        .await?;
+    assert_eq!(text(&response), "gamma: back");
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! Integration tests for keyed rate limiting and quotas
+
+mod common;
+
+use mocopr_core::protocol::{Extensions, error_codes};
+use mocopr_core::types::JsonRpcRequest;
+use mocopr_core::{Error, Result};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::context::Principal;
+use mocopr_server::middleware::Middleware;
+use mocopr_server::rate_limit::{
+    FileQuotaStore, Quota, QuotaStore, RateLimit, RateLimitKey, RateLimitMiddleware,
+};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn request(method: &str, params: Option<Value>) -> JsonRpcRequest {
+    JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: None,
+        method: method.to_string(),
+        params,
+    }
+}
+
+fn call_tool(name: &str) -> JsonRpcRequest {
+    request("tools/call", Some(json!({"name": name})))
+}
+
+/// Run the middleware for a request made in the session of `extensions`
+async fn check(
+    middleware: &RateLimitMiddleware,
+    extensions: &Extensions,
+    request: &JsonRpcRequest,
+) -> Result<()> {
+    extensions
+        .clone()
+        .scope(middleware.before_request(request))
+        .await
+}
+
+fn retry_after(result: Result<()>) -> Value {
+    let error = result.expect_err("request was not limited");
+    assert!(
+        matches!(error.inner(), Error::Protocol(_)),
+        "unexpected error: {error}"
+    );
+    error.data().cloned().expect("error data")
+}
+
+#[tokio::test]
+async fn test_sessions_have_separate_buckets() -> anyhow::Result<()> {
+    let middleware = RateLimitMiddleware::keyed(RateLimitKey::Session)
+        .with_limit(RateLimit::per_minute(60).with_burst(2));
+    let (first, second) = (Extensions::new(), Extensions::new());
+    let ping = request("ping", None);
+
+    check(&middleware, &first, &ping).await?;
+    check(&middleware, &first, &ping).await?;
+    let data = retry_after(check(&middleware, &first, &ping).await);
+    assert_eq!(data["limit"], "default");
+    assert_eq!(data["retryAfter"], 1);
+    assert!(data["retryAfterMs"].as_u64().unwrap() <= 1000);
+
+    // Another session still has its full burst
+    check(&middleware, &second, &ping).await?;
+    check(&middleware, &second, &ping).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sessions_of_one_subject_share_a_bucket() -> anyhow::Result<()> {
+    let middleware =
+        RateLimitMiddleware::keyed(RateLimitKey::Subject).with_limit(RateLimit::per_hour(1));
+    let alice = Extensions::new();
+    alice.insert(Principal::new("alice"));
+    let alice_again = Extensions::new();
+    alice_again.insert(Principal::new("alice"));
+    let bob = Extensions::new();
+    bob.insert(Principal::new("bob"));
+    let ping = request("ping", None);
+
+    check(&middleware, &alice, &ping).await?;
+    let data = retry_after(check(&middleware, &alice_again, &ping).await);
+    assert!(data["retryAfter"].as_u64().unwrap() > 3500);
+    check(&middleware, &bob, &ping).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_method_and_tool_limits() -> anyhow::Result<()> {
+    let middleware = RateLimitMiddleware::keyed(RateLimitKey::Session)
+        .with_method_limit("resources/read", RateLimit::per_minute(1))
+        .with_tool_limit("search", RateLimit::per_minute(1));
+    let session = Extensions::new();
+
+    check(&middleware, &session, &request("resources/read", None)).await?;
+    let data = retry_after(check(&middleware, &session, &request("resources/read", None)).await);
+    assert_eq!(data["limit"], "method:resources/read");
+
+    check(&middleware, &session, &call_tool("search")).await?;
+    let data = retry_after(check(&middleware, &session, &call_tool("search")).await);
+    assert_eq!(data["limit"], "tool:search");
+
+    // Other methods and tools are not limited
+    for _ in 0..5 {
+        check(&middleware, &session, &call_tool("echo")).await?;
+        check(&middleware, &session, &request("tools/list", None)).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_quotas_persist_across_restarts() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("quotas.json");
+    let session = Extensions::new();
+    session.insert(Principal::new("alice"));
+
+    let limited = || -> Result<RateLimitMiddleware> {
+        Ok(RateLimitMiddleware::keyed(RateLimitKey::Subject)
+            .with_quota(Quota::daily(100))
+            .with_tool_quota("translate", Quota::monthly(2))
+            .with_quota_store(FileQuotaStore::open(&path)?))
+    };
+
+    let middleware = limited()?;
+    check(&middleware, &session, &call_tool("translate")).await?;
+    check(&middleware, &session, &call_tool("translate")).await?;
+    let data = retry_after(check(&middleware, &session, &call_tool("translate")).await);
+    assert_eq!(data["limit"], "quota:tool:translate");
+    assert!(data["retryAfter"].as_u64().unwrap() > 0);
+    drop(middleware);
+
+    // A new middleware reading the same file keeps counting
+    let middleware = limited()?;
+    let data = retry_after(check(&middleware, &session, &call_tool("translate")).await);
+    assert_eq!(data["limit"], "quota:tool:translate");
+    check(&middleware, &session, &request("ping", None)).await?;
+    drop(middleware);
+
+    let store = FileQuotaStore::open(&path)?;
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    // Rejected requests use up none of the daily quota
+    assert_eq!(store.usage("quota|subject:alice", &today).await?, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_requests_use_up_no_quota_or_tokens() -> anyhow::Result<()> {
+    let middleware = RateLimitMiddleware::keyed(RateLimitKey::Session)
+        .with_limit(RateLimit::per_hour(3))
+        .with_quota(Quota::daily(3))
+        .with_tool_quota("translate", Quota::daily(1));
+    let session = Extensions::new();
+
+    check(&middleware, &session, &call_tool("translate")).await?;
+    for _ in 0..3 {
+        let data = retry_after(check(&middleware, &session, &call_tool("translate")).await);
+        assert_eq!(data["limit"], "quota:tool:translate");
+    }
+
+    // Neither the bucket nor the daily quota paid for the rejected calls
+    check(&middleware, &session, &request("ping", None)).await?;
+    check(&middleware, &session, &request("ping", None)).await?;
+    let data = retry_after(check(&middleware, &session, &request("ping", None)).await);
+    assert_eq!(data["limit"], "default");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_file_quota_store_writes_in_the_background() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("quotas.json");
+    let store = FileQuotaStore::open(&path)?.with_flush_interval(Duration::from_millis(50));
+
+    for _ in 0..10 {
+        assert!(store.consume("counter", "2025-06", 100).await?);
+    }
+    assert!(!path.exists());
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        FileQuotaStore::open(&path)?
+            .usage("counter", "2025-06")
+            .await?,
+        10
+    );
+
+    assert_eq!(
+        store.consume_all(&[("counter", "2025-06", 10)]).await?,
+        Some(0)
+    );
+    assert!(store.consume("counter", "2025-06", 100).await?);
+    store.flush().await?;
+    assert_eq!(
+        FileQuotaStore::open(&path)?
+            .usage("counter", "2025-06")
+            .await?,
+        11
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transport_sessions_with_an_address_are_limited_by_it() -> anyhow::Result<()> {
+    let server = std::sync::Arc::new(
+        McpServerBuilder::new()
+            .with_info("Limited Server", "1.0.0")
+            .with_middleware(
+                RateLimitMiddleware::keyed(RateLimitKey::ClientIp)
+                    .with_limit(RateLimit::per_minute(60).with_burst(1)),
+            )
+            .build()?,
+    );
+    let client: std::net::SocketAddr = "192.0.2.7:4000".parse()?;
+
+    let mut peers = Vec::new();
+    for port in [4000, 4001] {
+        let (transport, mut peer) = common::channel();
+        let server = server.clone();
+        let client = std::net::SocketAddr::new(client.ip(), port);
+        tokio::spawn(async move { server.run_transport_from(Box::new(transport), client).await });
+        peer.initialize().await;
+        peers.push(peer);
+    }
+
+    let first = peers[0].request("ping", json!({})).await;
+    assert!(first.get("result").is_some(), "{first}");
+    // The second session comes from the same address and shares its bucket
+    let second = peers[1].request("ping", json!({})).await;
+    assert_eq!(second["error"]["code"], error_codes::RATE_LIMITED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_websocket_clients_are_limited_by_address() -> anyhow::Result<()> {
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Limited Server", "1.0.0")
+        .with_middleware(
+            RateLimitMiddleware::keyed(RateLimitKey::ClientIp)
+                .with_limit(RateLimit::per_minute(60).with_burst(1)),
+        )
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    let client = common::connect(port).await;
+    client.ping(None).await?;
+    let error = client.ping(None).await.unwrap_err();
+    assert_eq!(error.json_rpc_code(), error_codes::RATE_LIMITED);
+    let data = error.data().expect("error data");
+    assert_eq!(data["retryAfter"], 1);
+    assert_eq!(data["limit"], "default");
+
+    // A new connection from the same address shares the bucket
+    let client = common::connect(port).await;
+    let error = client.ping(None).await.unwrap_err();
+    assert_eq!(error.json_rpc_code(), error_codes::RATE_LIMITED);
+    Ok(())
+}
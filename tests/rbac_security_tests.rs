@@ -582,3 +582,59 @@ async fn test_dos_resistance() -> Result<()> {
 
     Ok(())
 }
+
+/// Run `before_request` as a session authenticated as `principal`
+async fn authorize_as(
+    rbac: &RbacMiddleware,
+    principal: mocopr_server::context::Principal,
+    request: &JsonRpcRequest,
+) -> mocopr_core::Result<()> {
+    let extensions = Extensions::new();
+    extensions.insert(principal);
+    extensions.scope(rbac.before_request(request)).await
+}
+
+/// Test that role names cannot be claimed as subject IDs
+#[tokio::test]
+async fn test_role_subject_spoofing_is_denied() -> Result<()> {
+    let rbac = RbacMiddleware::builder()
+        .with_default_roles()
+        .with_role("analyst", &["call:tools:report*"])
+        .with_message_subjects(true)
+        .build()
+        .await?;
+    let call = |tool: &str, subject: Option<&str>| {
+        create_test_request("tools/call", Some(json!({"name": tool})), subject, None)
+    };
+
+    // Claimed by an unauthenticated client
+    for subject in ["role:admin", "role:analyst"] {
+        assert!(
+            rbac.before_request(&call("report_daily", Some(subject)))
+                .await
+                .is_err(),
+            "{subject} must be denied"
+        );
+    }
+
+    // Claimed as the subject of a token
+    let spoofed = mocopr_server::context::Principal::new("role:admin");
+    assert!(
+        authorize_as(&rbac, spoofed, &call("anything", None))
+            .await
+            .is_err()
+    );
+
+    // Roles granted by a token still apply, through inheritance and patterns
+    let admin = mocopr_server::context::Principal::new("alice").with_roles(["admin"]);
+    authorize_as(&rbac, admin, &call("anything", None)).await?;
+    let analyst = mocopr_server::context::Principal::new("bob").with_roles(["analyst"]);
+    authorize_as(&rbac, analyst.clone(), &call("report_daily", None)).await?;
+    assert!(
+        authorize_as(&rbac, analyst, &call("shutdown", None))
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
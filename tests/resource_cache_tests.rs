@@ -0,0 +1,225 @@
+//! Integration tests for caching of resource reads
+
+mod common;
+
+use async_trait::async_trait;
+use mocopr_core::Result;
+use mocopr_core::protocol::Extensions;
+use mocopr_core::types::{Content, Resource, ResourceContent, ResourcesReadRequest};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::cache::ResourceCache;
+use mocopr_server::context::Principal;
+use mocopr_server::handlers::{FileResourceHandler, ResourceHandler};
+use mocopr_server::notifications::NotificationSender;
+use mocopr_server::registry::ResourceRegistry;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use url::Url;
+
+/// Counts reads and serves the current version as its entity tag
+struct ReportHandler {
+    uri: Url,
+    reads: Arc<AtomicUsize>,
+    version: Arc<AtomicUsize>,
+    ttl: Option<Duration>,
+}
+
+impl ReportHandler {
+    fn new(uri: &str, ttl: Option<Duration>) -> Self {
+        Self {
+            uri: Url::parse(uri).unwrap(),
+            reads: Arc::new(AtomicUsize::new(0)),
+            version: Arc::new(AtomicUsize::new(1)),
+            ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceHandler for ReportHandler {
+    async fn resource(&self) -> Resource {
+        Resource::new(self.uri.clone(), "Report")
+    }
+
+    async fn read(&self) -> Result<Vec<ResourceContent>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        let text = format!("report v{}", self.version.load(Ordering::SeqCst));
+        Ok(vec![ResourceContent {
+            uri: self.uri.clone(),
+            mime_type: None,
+            contents: vec![Content::from(text)],
+        }])
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    async fn etag(&self) -> Result<Option<String>> {
+        Ok(Some(self.version.load(Ordering::SeqCst).to_string()))
+    }
+}
+
+async fn read(registry: &ResourceRegistry, uri: &str) -> Result<String> {
+    let response = registry
+        .read_resource(ResourcesReadRequest {
+            uri: Url::parse(uri).unwrap(),
+        })
+        .await?;
+    match &response.contents[0].contents[0] {
+        Content::Text(text) => Ok(text.text.clone()),
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_reads_are_cached_until_updated() -> anyhow::Result<()> {
+    let notifications = NotificationSender::new();
+    let mut registry = ResourceRegistry::with_notifications(notifications.clone());
+    registry.set_cache(ResourceCache::new());
+
+    let cached = ReportHandler::new("report://daily", Some(Duration::from_secs(60)));
+    let (reads, version) = (cached.reads.clone(), cached.version.clone());
+    registry.register(Box::new(cached));
+
+    // Without a TTL nothing is cached
+    let uncached = ReportHandler::new("report://live", None);
+    let live_reads = uncached.reads.clone();
+    registry.register(Box::new(uncached));
+
+    for _ in 0..3 {
+        assert_eq!(read(&registry, "report://daily").await?, "report v1");
+        read(&registry, "report://live").await?;
+    }
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+    assert_eq!(live_reads.load(Ordering::SeqCst), 3);
+
+    // An update notification drops the entry even though the TTL has not expired
+    version.store(2, Ordering::SeqCst);
+    assert_eq!(read(&registry, "report://daily").await?, "report v1");
+    notifications.resource_updated(&Url::parse("report://daily")?);
+    assert_eq!(read(&registry, "report://daily").await?, "report v2");
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+
+    notifications.resource_list_changed();
+    assert!(registry.cache().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_entries_are_kept_per_principal() -> anyhow::Result<()> {
+    let session = |subject: &str| {
+        let extensions = Extensions::new();
+        extensions.insert(Principal::new(subject));
+        extensions
+    };
+
+    for shared in [false, true] {
+        let mut registry = ResourceRegistry::new();
+        registry.set_cache(ResourceCache::new().with_shared_entries(shared));
+        let handler = ReportHandler::new("report://payroll", Some(Duration::from_secs(60)));
+        let reads = handler.reads.clone();
+        registry.register(Box::new(handler));
+
+        for subject in ["alice", "alice", "bob"] {
+            session(subject)
+                .scope(read(&registry, "report://payroll"))
+                .await?;
+        }
+        read(&registry, "report://payroll").await?;
+
+        let expected = if shared { 1 } else { 3 };
+        assert_eq!(reads.load(Ordering::SeqCst), expected, "shared: {shared}");
+
+        // Invalidating a resource drops it for every principal
+        registry.cache().unwrap().invalidate("report://payroll");
+        assert!(registry.cache().unwrap().is_empty());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_entries_are_revalidated() -> anyhow::Result<()> {
+    let mut registry = ResourceRegistry::new();
+    // The cache TTL for this URI overrides the handler's own
+    registry.set_cache(ResourceCache::new().with_ttl("report://daily", Duration::from_millis(50)));
+
+    let handler = ReportHandler::new("report://daily", Some(Duration::from_secs(60)));
+    let (reads, version) = (handler.reads.clone(), handler.version.clone());
+    registry.register(Box::new(handler));
+
+    assert_eq!(read(&registry, "report://daily").await?, "report v1");
+    tokio::time::sleep(Duration::from_millis(80)).await;
+
+    // The entity tag is unchanged, so the entry is renewed without a read
+    assert_eq!(read(&registry, "report://daily").await?, "report v1");
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    version.store(2, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    assert_eq!(read(&registry, "report://daily").await?, "report v2");
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_file_resources_revalidate_by_modification_time() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "first")?;
+
+    let mut registry = ResourceRegistry::new();
+    // A zero TTL checks the modification time on every read
+    registry.set_cache(ResourceCache::new().with_default_ttl(Duration::ZERO));
+    registry.register(Box::new(FileResourceHandler::new(
+        Url::parse("file:///notes.txt")?,
+        "Notes",
+        &path,
+    )));
+
+    assert_eq!(read(&registry, "file:///notes.txt").await?, "first");
+    assert_eq!(registry.cache().unwrap().len(), 1);
+
+    std::fs::write(&path, "second")?;
+    let file = std::fs::File::options().write(true).open(&path)?;
+    file.set_modified(SystemTime::now() + Duration::from_secs(10))?;
+    assert_eq!(read(&registry, "file:///notes.txt").await?, "second");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_cache_follows_notifications() -> anyhow::Result<()> {
+    let handler = ReportHandler::new("report://daily", None);
+    let (reads, version) = (handler.reads.clone(), handler.version.clone());
+
+    let port = common::free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Reports", "1.0.0")
+        .with_resources()
+        .with_resource(handler)
+        .with_resource_cache(ResourceCache::new().with_default_ttl(Duration::from_secs(60)))
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    let notifications = server.notifications().clone();
+    tokio::spawn(async move { server.run().await });
+
+    let client = common::connect(port).await;
+
+    let uri = Url::parse("report://daily")?;
+    client.read_resource(uri.clone()).await?;
+    client.read_resource(uri.clone()).await?;
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    version.store(2, Ordering::SeqCst);
+    notifications.resource_updated(&uri);
+    let response = client.read_resource(uri).await?;
+    match &response.contents[0].contents[0] {
+        Content::Text(text) => assert_eq!(text.text, "report v2"),
+        other => panic!("unexpected content: {other:?}"),
+    }
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+    Ok(())
+}
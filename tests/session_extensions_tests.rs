@@ -0,0 +1,168 @@
+//! Integration tests for per-session extensions
+
+mod common;
+
+use common::{channel, connect, free_port};
+use mocopr_client::McpClient;
+use mocopr_core::Result;
+use mocopr_core::protocol::Extensions;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_core::types::{Content, JsonRpcRequest, JsonRpcResponse, TextContent};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::context::RequestContext;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::middleware::Middleware;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+#[derive(Clone)]
+struct LoginToken(String);
+
+/// Number of requests the session has made, kept by [`CountingMiddleware`]
+struct RequestCount(u32);
+
+/// Signals when the session state holding it is dropped
+struct DropSignal(Arc<Notify>);
+
+impl Drop for DropSignal {
+    fn drop(&mut self) {
+        self.0.notify_one();
+    }
+}
+
+struct LoginTool {
+    dropped: Arc<Notify>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for LoginTool {
+    async fn tool(&self) -> Tool {
+        Tool::new("login", json!({"type": "object"}))
+    }
+
+    async fn call(&self, arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let user = arguments
+            .and_then(|args| args["user"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        let context = RequestContext::current().expect("request context");
+        context.extensions().insert(LoginToken(user));
+        context
+            .extensions()
+            .insert(DropSignal(self.dropped.clone()));
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+}
+
+struct WhoAmITool;
+
+#[async_trait::async_trait]
+impl ToolHandler for WhoAmITool {
+    async fn tool(&self) -> Tool {
+        Tool::new("whoami", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let extensions = Extensions::current().expect("session extensions");
+        let user = extensions
+            .get::<LoginToken>()
+            .map(|token| token.0)
+            .unwrap_or_else(|| "anonymous".to_string());
+        let requests = extensions.with(|count: &RequestCount| count.0).unwrap_or(0);
+        Ok(ToolsCallResponse::success(vec![Content::Text(
+            TextContent::new(format!("{user} after {requests} requests")),
+        )]))
+    }
+}
+
+struct CountingMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for CountingMiddleware {
+    async fn before_request(&self, _request: &JsonRpcRequest) -> Result<()> {
+        if let Some(extensions) = Extensions::current() {
+            extensions.with_mut_or_insert_with(|| RequestCount(0), |count| count.0 += 1);
+        }
+        Ok(())
+    }
+
+    async fn after_response(
+        &self,
+        _request: &JsonRpcRequest,
+        _response: &JsonRpcResponse,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_error(&self, _request: &JsonRpcRequest, _error: &mocopr_core::Error) -> Result<()> {
+        Ok(())
+    }
+}
+
+async fn whoami(client: &McpClient) -> anyhow::Result<String> {
+    let response = client.call_tool("whoami".to_string(), None).await?;
+    match &response.content[0] {
+        Content::Text(text) => Ok(text.text.clone()),
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_session_state_is_isolated_and_dropped() -> anyhow::Result<()> {
+    let dropped = Arc::new(Notify::new());
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Extensions Server", "1.0.0")
+        .with_tools()
+        .with_tool(LoginTool {
+            dropped: dropped.clone(),
+        })
+        .with_tool(WhoAmITool)
+        .with_middleware(CountingMiddleware)
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    let alice = connect(port).await;
+    let bob = connect(port).await;
+
+    alice
+        .call_tool("login".to_string(), Some(json!({"user": "alice"})))
+        .await?;
+    assert_eq!(whoami(&alice).await?, "alice after 2 requests");
+    assert_eq!(whoami(&bob).await?, "anonymous after 1 requests");
+
+    // Disconnecting drops the state of that session only
+    alice.close().await?;
+    tokio::time::timeout(Duration::from_secs(5), dropped.notified())
+        .await
+        .expect("session state was not dropped");
+    assert_eq!(whoami(&bob).await?, "anonymous after 2 requests");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_middleware_runs_on_stdio_sessions() -> anyhow::Result<()> {
+    let server = McpServerBuilder::new()
+        .with_info("Extensions Server", "1.0.0")
+        .with_tools()
+        .with_tool(WhoAmITool)
+        .with_middleware(CountingMiddleware)
+        .build()?;
+    let (transport, mut client) = channel();
+    tokio::spawn(async move { server.run_transport(Box::new(transport)).await });
+    client.initialize().await;
+
+    for requests in 1..=2 {
+        let response = client
+            .request("tools/call", json!({"name": "whoami"}))
+            .await;
+        assert_eq!(
+            response["result"]["content"][0]["text"],
+            format!("anonymous after {requests} requests")
+        );
+    }
+    Ok(())
+}
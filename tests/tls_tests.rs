@@ -0,0 +1,219 @@
+//! Integration tests for TLS and mutual TLS on the WebSocket and HTTP transports
+
+mod common;
+
+use common::{free_port, retry};
+use mocopr_client::McpClientBuilder;
+use mocopr_core::Result;
+use mocopr_core::transport::websocket::WebSocketOptions;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_core::types::{Content, TextContent};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::context::Principal;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::tls::TlsConfig;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose,
+};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Answers with the subject of the session's principal
+struct WhoAmITool;
+
+#[async_trait::async_trait]
+impl ToolHandler for WhoAmITool {
+    async fn tool(&self) -> Tool {
+        Tool::new("whoami", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let subject = Principal::current()
+            .map(|principal| principal.subject().to_string())
+            .unwrap_or_else(|| "nobody".to_string());
+        Ok(ToolsCallResponse::success(vec![Content::Text(
+            TextContent::new(subject),
+        )]))
+    }
+}
+
+/// A certificate authority with a server and a client certificate, as PEM files
+struct Pki {
+    dir: TempDir,
+}
+
+impl Pki {
+    fn new() -> anyhow::Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let (ca, ca_key) = authority("Test CA")?;
+        write_pem(dir.path(), "ca", &ca, &ca_key)?;
+
+        let mut server = CertificateParams::new(vec!["localhost".into(), "127.0.0.1".into()])?;
+        server.distinguished_name.push(DnType::CommonName, "server");
+        server.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let server_key = KeyPair::generate()?;
+        let server = server.signed_by(&server_key, &ca, &ca_key)?;
+        write_pem(dir.path(), "server", &server, &server_key)?;
+
+        let mut client = CertificateParams::new(Vec::<String>::new())?;
+        client.distinguished_name.push(DnType::CommonName, "alice");
+        client.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client_key = KeyPair::generate()?;
+        let client = client.signed_by(&client_key, &ca, &ca_key)?;
+        write_pem(dir.path(), "client", &client, &client_key)?;
+
+        let (rogue, rogue_key) = authority("Rogue CA")?;
+        write_pem(dir.path(), "rogue", &rogue, &rogue_key)?;
+        Ok(Self { dir })
+    }
+
+    fn cert(&self, name: &str) -> PathBuf {
+        self.dir.path().join(format!("{name}.pem"))
+    }
+
+    fn key(&self, name: &str) -> PathBuf {
+        self.dir.path().join(format!("{name}.key"))
+    }
+}
+
+fn authority(name: &str) -> anyhow::Result<(Certificate, KeyPair)> {
+    let mut params = CertificateParams::new(Vec::<String>::new())?;
+    params.distinguished_name.push(DnType::CommonName, name);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    let key = KeyPair::generate()?;
+    let cert = params.self_signed(&key)?;
+    Ok((cert, key))
+}
+
+fn write_pem(dir: &Path, name: &str, cert: &Certificate, key: &KeyPair) -> anyhow::Result<()> {
+    std::fs::write(dir.join(format!("{name}.pem")), cert.pem())?;
+    std::fs::write(dir.join(format!("{name}.key")), key.serialize_pem())?;
+    Ok(())
+}
+
+/// Start a TLS server with HTTP and WebSocket transports, returning its port
+async fn start_server(tls: TlsConfig) -> anyhow::Result<u16> {
+    let port = free_port();
+    let server = McpServerBuilder::new()
+        .with_info("Secure Server", "1.0.0")
+        .with_tools()
+        .with_tool(WhoAmITool)
+        .with_tls_config(tls)
+        .with_bind_address("127.0.0.1", port)
+        .with_http_transport()
+        .with_websocket_transport()
+        .build()?;
+    tokio::spawn(async move { server.run().await });
+
+    retry(|| tokio::net::TcpStream::connect(("127.0.0.1", port))).await;
+    Ok(port)
+}
+
+/// Connect with `options` and ask the server who the session belongs to
+async fn whoami(url: &str, options: WebSocketOptions) -> Result<String> {
+    let client = McpClientBuilder::new()
+        .with_info("Test Client".to_string(), "1.0.0".to_string())
+        .with_websocket_options(options)
+        .connect_websocket(url)
+        .await?;
+    let response = client.call_tool("whoami".to_string(), None).await?;
+    client.close().await?;
+    match &response.content[0] {
+        Content::Text(text) => Ok(text.text.clone()),
+        other => panic!("unexpected content: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_wss_with_custom_root_certificates() -> anyhow::Result<()> {
+    let pki = Pki::new()?;
+    let port = start_server(TlsConfig::new(pki.cert("server"), pki.key("server"))).await?;
+    let url = format!("wss://localhost:{port}/mcp/ws");
+
+    let options = WebSocketOptions::new().with_root_certificates(pki.cert("ca"));
+    assert_eq!(whoami(&url, options).await?, "nobody");
+
+    // The bundled authorities do not know the test CA
+    assert!(whoami(&url, WebSocketOptions::new()).await.is_err());
+    let options = WebSocketOptions::new().with_root_certificates(pki.cert("rogue"));
+    assert!(whoami(&url, options).await.is_err());
+
+    // Plain WebSocket is not served
+    let plain = format!("ws://localhost:{port}/mcp/ws");
+    assert!(whoami(&plain, WebSocketOptions::new()).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_https_requests() -> anyhow::Result<()> {
+    let pki = Pki::new()?;
+    let port = start_server(TlsConfig::new(pki.cert("server"), pki.key("server"))).await?;
+
+    let ca = reqwest::Certificate::from_pem(&std::fs::read(pki.cert("ca"))?)?;
+    let client = reqwest::Client::builder()
+        .add_root_certificate(ca)
+        .build()?;
+    let response = client
+        .post(format!("https://localhost:{port}/mcp"))
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+
+    let response = client
+        .post(format!("https://localhost:{port}/mcp"))
+        .header("origin", "https://evil.example.com")
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 403);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_certificate_subject_is_the_principal() -> anyhow::Result<()> {
+    let pki = Pki::new()?;
+    let tls = TlsConfig::new(pki.cert("server"), pki.key("server")).with_client_ca(pki.cert("ca"));
+    let port = start_server(tls).await?;
+    let url = format!("wss://localhost:{port}/mcp/ws");
+    let options = WebSocketOptions::new().with_root_certificates(pki.cert("ca"));
+
+    let with_certificate = options
+        .clone()
+        .with_client_certificate(pki.cert("client"), pki.key("client"));
+    assert_eq!(whoami(&url, with_certificate).await?, "CN=alice");
+
+    // No certificate, or one from an authority the server does not trust
+    assert!(whoami(&url, options.clone()).await.is_err());
+    let rogue = options.with_client_certificate(pki.cert("rogue"), pki.key("rogue"));
+    assert!(whoami(&url, rogue).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optional_client_certificates() -> anyhow::Result<()> {
+    let pki = Pki::new()?;
+    let tls = TlsConfig::new(pki.cert("server"), pki.key("server"))
+        .with_client_ca(pki.cert("ca"))
+        .allow_clients_without_certificate();
+    let port = start_server(tls).await?;
+    let url = format!("wss://localhost:{port}/mcp/ws");
+    let options = WebSocketOptions::new().with_root_certificates(pki.cert("ca"));
+
+    assert_eq!(whoami(&url, options.clone()).await?, "nobody");
+    let with_certificate = options.with_client_certificate(pki.cert("client"), pki.key("client"));
+    assert_eq!(whoami(&url, with_certificate).await?, "CN=alice");
+    Ok(())
+}
+
+#[test]
+fn test_unreadable_certificate_fails_the_build() {
+    let result = McpServerBuilder::new()
+        .with_info("Secure Server", "1.0.0")
+        .with_tls("/nonexistent/server.pem", "/nonexistent/server.key")
+        .build();
+    assert!(result.is_err());
+}
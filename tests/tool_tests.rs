@@ -1,6 +1,7 @@
 //! Integration tests for MCP tools functionality
 
 use mocopr_core::Result;
+use mocopr_core::ToolExecutor;
 use mocopr_core::types::tools::{Tool, ToolsCallResponse};
 use mocopr_server::handlers::ToolHandler;
 use serde_json::json;
@@ -40,6 +41,7 @@ impl ToolHandler for TestTool {
         Ok(ToolsCallResponse {
             content,
             is_error: None,
+            structured_content: None,
             meta: Default::default(),
         })
     }
@@ -68,3 +70,55 @@ async fn test_tool_creation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// Tool declaring execution limits through the derive macro
+#[derive(mocopr_macros::Tool)]
+#[tool(
+    name = "slow_tool",
+    description = "A tool that never finishes in time",
+    timeout_ms = 20,
+    max_concurrency = 2,
+    max_queue = 4
+)]
+struct SlowTool;
+
+#[async_trait::async_trait]
+impl ToolExecutor for SlowTool {
+    async fn execute(&self, _arguments: Option<serde_json::Value>) -> Result<ToolsCallResponse> {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        Ok(ToolsCallResponse::success(vec![]))
+    }
+}
+
+#[tokio::test]
+async fn test_tool_execution_policy() -> anyhow::Result<()> {
+    use mocopr_core::types::tools::ToolsCallRequest;
+    use mocopr_server::{McpServerBuilder, ToolExecutionPolicy};
+
+    let policy = SlowTool.execution_policy().expect("policy from attributes");
+    assert_eq!(
+        policy,
+        ToolExecutionPolicy::new()
+            .with_timeout(std::time::Duration::from_millis(20))
+            .with_max_concurrency(2)
+            .with_max_queue(4)
+    );
+
+    let server = McpServerBuilder::new()
+        .with_info("Policy Server", "1.0.0")
+        .with_tools()
+        .with_monitoring()
+        .with_tool(SlowTool)
+        .build()?;
+
+    let response = server
+        .tools()
+        .call_tool(ToolsCallRequest::new("slow_tool"))
+        .await?;
+    assert_eq!(response.is_error, Some(true));
+
+    let metrics = server.monitoring().unwrap().get_tool_metrics().await;
+    assert_eq!(metrics["slow_tool"].timeouts, 1);
+
+    Ok(())
+}
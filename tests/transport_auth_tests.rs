@@ -0,0 +1,219 @@
+//! Integration tests for transport-level authentication
+
+mod common;
+
+use common::{Socket, free_port, retry};
+use futures::{SinkExt, StreamExt};
+use mocopr_core::Result;
+use mocopr_core::protocol::Extensions;
+use mocopr_core::types::tools::{Tool, ToolsCallResponse};
+use mocopr_core::types::{Content, TextContent};
+use mocopr_core::types::{JsonRpcRequest, RequestId};
+use mocopr_server::McpServerBuilder;
+use mocopr_server::auth::ApiKeyAuthenticator;
+use mocopr_server::context::Principal;
+use mocopr_server::handlers::ToolHandler;
+use mocopr_server::middleware::{AuthMiddleware, Middleware};
+use serde_json::{Value, json};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Answers with the subject of the session's principal
+struct WhoAmITool;
+
+#[async_trait::async_trait]
+impl ToolHandler for WhoAmITool {
+    async fn tool(&self) -> Tool {
+        Tool::new("whoami", json!({"type": "object"}))
+    }
+
+    async fn call(&self, _arguments: Option<Value>) -> Result<ToolsCallResponse> {
+        let subject = Principal::current()
+            .map(|principal| principal.subject().to_string())
+            .unwrap_or_else(|| "nobody".to_string());
+        Ok(ToolsCallResponse::success(vec![Content::Text(
+            TextContent::new(subject),
+        )]))
+    }
+}
+
+/// Start a server requiring the key `k1` for `alice`
+fn start_server(port: u16, http: bool) -> anyhow::Result<()> {
+    let mut builder = McpServerBuilder::new()
+        .with_info("Private Server", "1.0.0")
+        .with_tools()
+        .with_tool(WhoAmITool)
+        .with_authenticator(ApiKeyAuthenticator::new().with_key("k1", "alice"))
+        // Sessions authenticated at the transport need no key in the body
+        .with_middleware(AuthMiddleware::new().with_api_key("k1".to_string()))
+        .with_monitoring()
+        .with_bind_address("127.0.0.1", port)
+        .with_websocket_transport();
+    if http {
+        builder = builder.with_http_transport();
+    }
+    let server = builder.build()?;
+    tokio::spawn(async move { server.run().await });
+    Ok(())
+}
+
+async fn connect(
+    url: &str,
+    authorization: Option<&str>,
+) -> std::result::Result<Socket, tungstenite::Error> {
+    // Retry until the server listens, returning its answer to the upgrade
+    retry(|| async {
+        let mut request = url.into_client_request().unwrap();
+        if let Some(authorization) = authorization {
+            request
+                .headers_mut()
+                .insert("authorization", authorization.parse().unwrap());
+        }
+        match tokio_tungstenite::connect_async(request).await {
+            Err(tungstenite::Error::Io(e)) => Err(e),
+            result => Ok(result.map(|(socket, _)| socket)),
+        }
+    })
+    .await
+}
+
+async fn send(socket: &mut Socket, message: Value) -> anyhow::Result<Value> {
+    socket.send(Message::Text(message.to_string())).await?;
+    match socket.next().await {
+        Some(Ok(Message::Text(text))) => Ok(serde_json::from_str(&text)?),
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_sessions_carry_the_principal() -> anyhow::Result<()> {
+    let port = free_port();
+    start_server(port, false)?;
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+
+    let mut socket = connect(&url, Some("Bearer k1")).await?;
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": mocopr_core::protocol::PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "test", "version": "1.0.0"}
+        }
+    });
+    let response = send(&mut socket, initialize).await?;
+    assert!(response.get("result").is_some(), "{response}");
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "whoami"}
+    });
+    let response = send(&mut socket, call).await?;
+    assert_eq!(
+        response["result"]["content"][0]["text"], "alice",
+        "{response}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unauthenticated_upgrades_are_rejected() -> anyhow::Result<()> {
+    let port = free_port();
+    start_server(port, false)?;
+    let url = format!("ws://127.0.0.1:{port}/mcp");
+
+    for authorization in [None, Some("Bearer wrong"), Some("Basic azE=")] {
+        match connect(&url, authorization).await {
+            Err(tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), 401);
+                assert_eq!(response.headers()["www-authenticate"], "Bearer");
+            }
+            other => panic!("{authorization:?} was not rejected: {other:?}"),
+        }
+    }
+
+    // Monitoring endpoints stay reachable without credentials
+    let health = reqwest::get(format!("http://127.0.0.1:{port}/healthz")).await?;
+    assert!(health.status().is_success());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_requests_require_credentials() -> anyhow::Result<()> {
+    let port = free_port();
+    start_server(port, true)?;
+    let url = format!("http://127.0.0.1:{port}/mcp");
+    let client = reqwest::Client::new();
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+
+    let response = retry(|| client.post(&url).json(&ping).send()).await;
+    assert_eq!(response.status(), 401);
+
+    let response = client
+        .post(&url)
+        .header("x-api-key", "k1")
+        .json(&ping)
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+
+    // The WebSocket endpoint moves to /mcp/ws but is protected the same way
+    let ws_url = format!("ws://127.0.0.1:{port}/mcp/ws");
+    assert!(connect(&ws_url, None).await.is_err());
+    connect(&ws_url, Some("ApiKey k1")).await?;
+    Ok(())
+}
+
+/// Run `middleware` on a tool call with `params` from a session of `principal`
+async fn check(middleware: &AuthMiddleware, principal: Principal, params: Value) -> Result<()> {
+    let extensions = Extensions::new();
+    extensions.insert(principal);
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "tools/call".to_string(),
+        params: Some(params),
+        id: Some(RequestId::Number(1)),
+    };
+    extensions
+        .scope(async move { middleware.before_request(&request).await })
+        .await
+}
+
+#[tokio::test]
+async fn test_anonymous_principals_still_need_a_key() -> anyhow::Result<()> {
+    let call = json!({"name": "whoami"});
+    let with_key = json!({"name": "whoami", "auth": {"api_key": "k1"}});
+    let middleware = AuthMiddleware::new().with_api_key("k1".to_string());
+    assert!(
+        check(&middleware, Principal::anonymous(), call.clone())
+            .await
+            .is_err()
+    );
+    // Keys in the request body are ignored unless enabled
+    assert!(
+        check(&middleware, Principal::anonymous(), with_key.clone())
+            .await
+            .is_err()
+    );
+    assert!(
+        check(&middleware, Principal::new("alice"), call.clone())
+            .await
+            .is_ok()
+    );
+
+    let middleware = middleware.with_message_keys(true);
+    assert!(
+        check(&middleware, Principal::anonymous(), call)
+            .await
+            .is_err()
+    );
+    assert!(
+        check(&middleware, Principal::anonymous(), with_key)
+            .await
+            .is_ok()
+    );
+    Ok(())
+}